@@ -36,6 +36,11 @@ pub mod tests;
 mod verifier;
 pub use self::verifier::*;
 
+#[cfg(feature = "std")]
+mod solidity;
+#[cfg(feature = "std")]
+pub use self::solidity::verifying_key_json;
+
 #[derive(Clone, Debug)]
 pub struct Proof<E: Engine> {
     pub a: E::G1Affine,
@@ -128,29 +133,26 @@ impl<E: Engine> Serialize for PreparedVerifyingKey<E> {
         where S: Serializer
     {
         let mut writer = vec![];
-        self.write(&mut &mut writer[..]).expect("Faild to serialize PreparedVerifyingKey.");
+        self.write(&mut writer).expect("Failed to serialize PreparedVerifyingKey.");
         bytes::serialize(&writer[..], serializer)
     }
 }
 
 #[cfg(feature = "std")]
 impl<'de, E: Engine> Deserialize<'de> for PreparedVerifyingKey<E> {
-    fn deserialize<D>(_deserializer: D) -> Result<Self, D::Error>
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
         where D: Deserializer<'de>
     {
-        // Never called
-        unimplemented!();
+        let b = bytes::deserialize(deserializer)?;
+        PreparedVerifyingKey::<E>::read(&mut &b[..])
+            .map_err(serde::de::Error::custom)
     }
 }
 
 impl<E: Engine> Encode for PreparedVerifyingKey<E> {
     fn using_encoded<R, F: FnOnce(&[u8]) -> R>(&self, f: F) -> R {
         let mut writer = vec![];
-
-        #[cfg(feature = "std")]
-        self.write(&mut &mut writer).expect("Faild to write PreparedVerifyingKey");
-        #[cfg(not(feature = "std"))]
-        self.write(&mut &mut writer[..]).expect("Faild to write PreparedVerifyingKey");
+        self.write(&mut writer).expect("Failed to write PreparedVerifyingKey");
 
         writer.using_encoded(f)
     }
@@ -159,15 +161,14 @@ impl<E: Engine> Encode for PreparedVerifyingKey<E> {
 impl<E: Engine> Decode for PreparedVerifyingKey<E> {
     fn decode<I: Input>(input: &mut I) -> Option<Self> {
         <Vec<u8> as Decode>::decode(input)
-            .map(|b| PreparedVerifyingKey::<E>::read(&mut &b[..])
-                .expect("Faild to read PreparedVerifyingKey"))
+            .and_then(|b| PreparedVerifyingKey::<E>::read(&mut &b[..]).ok())
     }
 }
 
 impl<E: Engine> Default for PreparedVerifyingKey<E> {
     fn default() -> Self {
         PreparedVerifyingKey::<E>::read(&mut &vec![0u8][..])
-            .expect("Faild to read PreparedVerifyingKey")
+            .expect("Failed to read PreparedVerifyingKey")
     }
 }
 
@@ -277,82 +278,137 @@ impl<E: Engine> PartialEq for VerifyingKey<E> {
     }
 }
 
-// impl<E: Engine> VerifyingKey<E> {
-//     pub fn write<W: io::Write>(
-//         &self,
-//         writer: &mut W
-//     ) -> io::Result<()>
-//     {
-//         use byteorder::BigEndian;
-
-//         writer.write(self.alpha_g1.into_uncompressed().as_ref())?;
-//         writer.write(self.beta_g1.into_uncompressed().as_ref())?;
-//         writer.write(self.beta_g2.into_uncompressed().as_ref())?;
-//         writer.write(self.gamma_g2.into_uncompressed().as_ref())?;
-//         writer.write(self.delta_g1.into_uncompressed().as_ref())?;
-//         writer.write(self.delta_g2.into_uncompressed().as_ref())?;
-//         BigEndian::write_u32(writer, self.ic.len() as u32)?;
-//         for ic in &self.ic {
-//             writer.write(ic.into_uncompressed().as_ref())?;
-//         }
-
-//         Ok(())
-//     }
-
-//     pub fn read<R: io::Read>(
-//         reader: &mut R
-//     ) -> io::Result<Self>
-//     {
-//         let mut g1_repr = <E::G1Affine as CurveAffine>::Uncompressed::empty();
-//         let mut g2_repr = <E::G2Affine as CurveAffine>::Uncompressed::empty();
-
-//         reader.read(g1_repr.as_mut())?;
-//         let alpha_g1 = g1_repr.into_affine().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-
-//         reader.read(g1_repr.as_mut())?;
-//         let beta_g1 = g1_repr.into_affine().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-
-//         reader.read(g2_repr.as_mut())?;
-//         let beta_g2 = g2_repr.into_affine().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-
-//         reader.read(g2_repr.as_mut())?;
-//         let gamma_g2 = g2_repr.into_affine().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-
-//         reader.read(g1_repr.as_mut())?;
-//         let delta_g1 = g1_repr.into_affine().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-
-//         reader.read(g2_repr.as_mut())?;
-//         let delta_g2 = g2_repr.into_affine().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-
-//         let ic_len = reader.read_u32::<BigEndian>()? as usize;
-
-//         let mut ic = vec![];
-
-//         for _ in 0..ic_len {
-//             reader.read(g1_repr.as_mut())?;
-//             let g1 = g1_repr
-//                      .into_affine()
-//                      .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
-//                      .and_then(|e| if e.is_zero() {
-//                          Err(io::Error::new(io::ErrorKind::InvalidData, "point at infinity"))
-//                      } else {
-//                          Ok(e)
-//                      })?;
-
-//             ic.push(g1);
-//         }
-
-//         Ok(VerifyingKey {
-//             alpha_g1: alpha_g1,
-//             beta_g1: beta_g1,
-//             beta_g2: beta_g2,
-//             gamma_g2: gamma_g2,
-//             delta_g1: delta_g1,
-//             delta_g2: delta_g2,
-//             ic: ic
-//         })
-//     }
-// }
+#[cfg(feature = "std")]
+impl<E: Engine> Serialize for VerifyingKey<E> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        let mut writer = vec![];
+        self.write(&mut writer).expect("Failed to serialize VerifyingKey.");
+        bytes::serialize(&writer[..], serializer)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'de, E: Engine> Deserialize<'de> for VerifyingKey<E> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: Deserializer<'de>
+    {
+        let b = bytes::deserialize(deserializer)?;
+        VerifyingKey::<E>::from_slice(&b[..])
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl<E: Engine> Encode for VerifyingKey<E> {
+    fn using_encoded<R, F: FnOnce(&[u8]) -> R>(&self, f: F) -> R {
+        let mut writer = vec![];
+        self.write(&mut writer).expect("Failed to write VerifyingKey");
+
+        writer.using_encoded(f)
+    }
+}
+
+impl<E: Engine> Decode for VerifyingKey<E> {
+    fn decode<I: Input>(input: &mut I) -> Option<Self> {
+        <Vec<u8> as Decode>::decode(input)
+            .and_then(|b| VerifyingKey::<E>::from_slice(&b[..]).ok())
+    }
+}
+
+impl<E: Engine> VerifyingKey<E> {
+    /// Writes the key in the same layout as bellman's `VerifyingKey::write`:
+    /// the uncompressed alpha_g1, beta_g1, beta_g2, gamma_g2, delta_g1 and
+    /// delta_g2, followed by the number of IC elements as a big-endian u32
+    /// and the uncompressed IC elements.
+    pub fn write<W: io::Write>(
+        &self,
+        writer: &mut W
+    ) -> io::Result<()>
+    {
+        use byteorder::{ByteOrder, BigEndian};
+
+        writer.write(self.alpha_g1.into_uncompressed().as_ref())?;
+        writer.write(self.beta_g1.into_uncompressed().as_ref())?;
+        writer.write(self.beta_g2.into_uncompressed().as_ref())?;
+        writer.write(self.gamma_g2.into_uncompressed().as_ref())?;
+        writer.write(self.delta_g1.into_uncompressed().as_ref())?;
+        writer.write(self.delta_g2.into_uncompressed().as_ref())?;
+
+        let mut buf = [0u8; 4];
+        BigEndian::write_u32(&mut buf, self.ic.len() as u32);
+        writer.write(&buf)?;
+
+        for ic in &self.ic {
+            writer.write(ic.into_uncompressed().as_ref())?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads a key written by `write`. Every point must be on the curve, in
+    /// the prime order subgroup and not the point at infinity.
+    pub fn read<R: io::Read>(
+        reader: &mut R
+    ) -> io::Result<Self>
+    {
+        use byteorder::{ByteOrder, BigEndian};
+
+        let alpha_g1 = read_point::<E::G1Affine, _>(reader)?;
+        let beta_g1 = read_point::<E::G1Affine, _>(reader)?;
+        let beta_g2 = read_point::<E::G2Affine, _>(reader)?;
+        let gamma_g2 = read_point::<E::G2Affine, _>(reader)?;
+        let delta_g1 = read_point::<E::G1Affine, _>(reader)?;
+        let delta_g2 = read_point::<E::G2Affine, _>(reader)?;
+
+        let mut buf = [0u8; 4];
+        reader.read(&mut buf)?;
+
+        let ic_len = BigEndian::read_u32(&buf) as usize;
+
+        let mut ic = vec![];
+
+        for _ in 0..ic_len {
+            ic.push(read_point::<E::G1Affine, _>(reader)?);
+        }
+
+        Ok(VerifyingKey {
+            alpha_g1: alpha_g1,
+            beta_g1: beta_g1,
+            beta_g2: beta_g2,
+            gamma_g2: gamma_g2,
+            delta_g1: delta_g1,
+            delta_g2: delta_g2,
+            ic: ic
+        })
+    }
+
+    /// Reads a key that must span the whole slice, so each key has exactly
+    /// one encoding.
+    pub fn from_slice(mut bytes: &[u8]) -> io::Result<Self> {
+        let vk = Self::read(&mut bytes)?;
+
+        if !bytes.is_empty() {
+            return Err(io::Error::TrailingData);
+        }
+
+        Ok(vk)
+    }
+}
+
+fn read_point<G: CurveAffine, R: io::Read>(reader: &mut R) -> io::Result<G> {
+    let mut repr = G::Uncompressed::empty();
+    reader.read(repr.as_mut())?;
+
+    repr
+        .into_affine()
+        .map_err(|_| io::Error::InvalidData)
+        .and_then(|e| if e.is_zero() {
+            Err(io::Error::PointInfinity)
+        } else {
+            Ok(e)
+        })
+}
 
 /// This is an error that could occur during circuit synthesis contexts,
 /// such as CRS generation, proving or verification.
@@ -444,4 +500,99 @@ mod test_proof_write_read {
 
         assert!(prepared_vk_a == prepared_vk_b);
     }
+
+    fn random_vk(ic_len: usize) -> VerifyingKey<Bls12> {
+        use rand::{Rand, SeedableRng, XorShiftRng};
+        use pairing::CurveProjective;
+        use pairing::bls12_381::{G1, G2};
+
+        let rng = &mut XorShiftRng::from_seed([0x5dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+        VerifyingKey::<Bls12> {
+            alpha_g1: G1::rand(rng).into_affine(),
+            beta_g1: G1::rand(rng).into_affine(),
+            beta_g2: G2::rand(rng).into_affine(),
+            gamma_g2: G2::rand(rng).into_affine(),
+            delta_g1: G1::rand(rng).into_affine(),
+            delta_g2: G2::rand(rng).into_affine(),
+            ic: (0..ic_len).map(|_| G1::rand(rng).into_affine()).collect(),
+        }
+    }
+
+    #[test]
+    fn vk_read_write() {
+        let vk = random_vk(3);
+
+        let mut v = vec![];
+        vk.write(&mut v).unwrap();
+
+        assert_eq!(v.len(), 96 * 3 + 192 * 3 + 4 + 96 * 3);
+
+        let de_vk = VerifyingKey::<Bls12>::from_slice(&v[..]).unwrap();
+        assert!(vk == de_vk);
+        assert!(prepare_verifying_key(&vk) == prepare_verifying_key(&de_vk));
+
+        // Trailing bytes and truncated keys are rejected.
+        v.push(0);
+        match VerifyingKey::<Bls12>::from_slice(&v[..]) {
+            Err(io::Error::TrailingData) => {},
+            _ => panic!("trailing data should be rejected"),
+        }
+        v.truncate(v.len() - 2);
+        assert!(VerifyingKey::<Bls12>::from_slice(&v[..]).is_err());
+    }
+
+    #[test]
+    fn vk_read_rejects_infinity() {
+        let mut vk = random_vk(1);
+        vk.gamma_g2 = G2Affine::zero();
+
+        let mut v = vec![];
+        vk.write(&mut v).unwrap();
+
+        match VerifyingKey::<Bls12>::from_slice(&v[..]) {
+            Err(io::Error::PointInfinity) => {},
+            _ => panic!("gamma at infinity should be rejected"),
+        }
+    }
+
+    #[test]
+    fn vk_codec() {
+        let vk = random_vk(2);
+
+        let encoded = vk.encode();
+        let decoded = VerifyingKey::<Bls12>::decode(&mut &encoded[..]).unwrap();
+        assert!(vk == decoded);
+
+        let mut corrupted = encoded.clone();
+        let last = corrupted.len() - 1;
+        corrupted[last] ^= 1;
+        assert!(VerifyingKey::<Bls12>::decode(&mut &corrupted[..]).is_none());
+    }
+
+    #[test]
+    fn vk_serde() {
+        let vk = random_vk(2);
+
+        let json = serde_json::to_string(&vk).unwrap();
+        let de_vk: VerifyingKey<Bls12> = serde_json::from_str(&json).unwrap();
+        assert!(vk == de_vk);
+
+        let pvk = prepare_verifying_key(&vk);
+        let json = serde_json::to_string(&pvk).unwrap();
+        let de_pvk: PreparedVerifyingKey<Bls12> = serde_json::from_str(&json).unwrap();
+        assert!(pvk == de_pvk);
+    }
+
+    #[test]
+    fn pvk_codec() {
+        let pvk = prepare_verifying_key(&random_vk(2));
+
+        let encoded = pvk.encode();
+        let decoded = PreparedVerifyingKey::<Bls12>::decode(&mut &encoded[..]).unwrap();
+        assert!(pvk == decoded);
+
+        let malformed = vec![0xffu8; 10].encode();
+        assert!(PreparedVerifyingKey::<Bls12>::decode(&mut &malformed[..]).is_none());
+    }
 }
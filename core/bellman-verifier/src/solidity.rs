@@ -0,0 +1,85 @@
+//! Export of BN254 verifying keys for Groth16 verifiers on Ethereum.
+
+use pairing::bn256::{Bn256, G1Affine, G2Affine};
+use pairing::CurveAffine;
+use crate::VerifyingKey;
+
+/// Formats the verifying key as JSON for a Solidity verifier built on the
+/// EIP-197 pairing precompile.
+///
+/// Coordinates are `0x`-prefixed 32-byte big-endian hex strings, and the point
+/// at infinity is `(0, 0)`. G2 coordinates list the imaginary part first, which
+/// is the order the precompile takes them in.
+pub fn verifying_key_json(vk: &VerifyingKey<Bn256>) -> String {
+    let gamma_abc: Vec<String> = vk.ic.iter().map(g1_json).collect();
+
+    format!(
+        "{{\n  \"alpha\": {},\n  \"beta\": {},\n  \"gamma\": {},\n  \"delta\": {},\n  \"gamma_abc\": [\n    {}\n  ]\n}}",
+        g1_json(&vk.alpha_g1),
+        g2_json(&vk.beta_g2),
+        g2_json(&vk.gamma_g2),
+        g2_json(&vk.delta_g2),
+        gamma_abc.join(",\n    "),
+    )
+}
+
+fn g1_json(p: &G1Affine) -> String {
+    let c = coordinates(p.is_zero(), p.into_uncompressed().as_ref());
+    format!("[{}, {}]", c[0], c[1])
+}
+
+fn g2_json(p: &G2Affine) -> String {
+    // Our uncompressed encoding is already x.c1 || x.c0 || y.c1 || y.c0.
+    let c = coordinates(p.is_zero(), p.into_uncompressed().as_ref());
+    format!("[[{}, {}], [{}, {}]]", c[0], c[1], c[2], c[3])
+}
+
+/// Splits an uncompressed encoding into 32-byte hex words.
+fn coordinates(infinity: bool, encoded: &[u8]) -> Vec<String> {
+    encoded
+        .chunks(32)
+        .map(|word| {
+            let hex: String = word.iter().map(|b| format!("{:02x}", b)).collect();
+            if infinity {
+                format!("\"0x{:064x}\"", 0)
+            } else {
+                format!("\"0x{}\"", hex)
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verifying_key_json() {
+        let vk = VerifyingKey::<Bn256> {
+            alpha_g1: G1Affine::one(),
+            beta_g1: G1Affine::one(),
+            beta_g2: G2Affine::one(),
+            gamma_g2: G2Affine::one(),
+            delta_g1: G1Affine::one(),
+            delta_g2: G2Affine::one(),
+            ic: vec![G1Affine::one(), G1Affine::zero()],
+        };
+
+        let g1 = "[\"0x0000000000000000000000000000000000000000000000000000000000000001\", \
+                   \"0x0000000000000000000000000000000000000000000000000000000000000002\"]";
+        // The generator of G2 from EIP-197
+        let g2 = "[[\"0x198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c2\", \
+                    \"0x1800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed\"], \
+                   [\"0x090689d0585ff075ec9e99ad690c3395bc4b313370b38ef355acdadcd122975b\", \
+                    \"0x12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa\"]]";
+        let zero = "[\"0x0000000000000000000000000000000000000000000000000000000000000000\", \
+                     \"0x0000000000000000000000000000000000000000000000000000000000000000\"]";
+
+        let expected = format!(
+            "{{\n  \"alpha\": {},\n  \"beta\": {},\n  \"gamma\": {},\n  \"delta\": {},\n  \"gamma_abc\": [\n    {},\n    {}\n  ]\n}}",
+            g1, g2, g2, g2, g1, zero
+        );
+
+        assert_eq!(verifying_key_json(&vk), expected);
+    }
+}
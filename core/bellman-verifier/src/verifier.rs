@@ -2,9 +2,15 @@ use pairing::{
     Engine,
     CurveProjective,
     CurveAffine,
-    PrimeField
+    PrimeField,
+    multiexp,
 };
 
+#[cfg(feature = "std")]
+use ::std::vec::Vec;
+#[cfg(not(feature = "std"))]
+use crate::std::vec::Vec;
+
 use super::{
     Proof,
     VerifyingKey,
@@ -39,11 +45,10 @@ pub fn verify_proof<'a, E: Engine>(
         return Err(SynthesisError::MalformedVerifyingKey);
     }
 
-    let mut acc = pvk.ic[0].into_projective();
+    let inputs: Vec<_> = public_inputs.iter().map(|i| i.into_repr()).collect();
 
-    for (i, b) in public_inputs.iter().zip(pvk.ic.iter().skip(1)) {
-        acc.add_assign(&b.mul(i.into_repr()));
-    }
+    let mut acc = pvk.ic[0].into_projective();
+    acc.add_assign(&multiexp(&pvk.ic[1..], &inputs[..]));
 
     // The original verification equation is:
     // A * B = alpha * beta + inputs * gamma + C * delta
@@ -90,4 +95,54 @@ mod tests {
 
         assert!(verify_proof(&pvk, &proof, &pub_inp).unwrap());
     }
+
+    #[test]
+    fn test_verify_bn256() {
+        use pairing::bn256::{Bn256, Fr, G1Affine, G2Affine};
+        use pairing::Field;
+        use rand::{Rand, SeedableRng, XorShiftRng};
+
+        let rng = &mut XorShiftRng::from_seed([0x5dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+        // Build a key and a proof from known trapdoors, so that
+        // a * b = alpha * beta + (ic_0 + x * ic_1) * gamma + c * delta.
+        let (alpha, beta, gamma, delta) =
+            (Fr::rand(rng), Fr::rand(rng), Fr::rand(rng), Fr::rand(rng));
+        let (ic0, ic1, x) = (Fr::rand(rng), Fr::rand(rng), Fr::rand(rng));
+        let (a, b) = (Fr::rand(rng), Fr::rand(rng));
+
+        let mut c = a;
+        c.mul_assign(&b);
+        let mut tmp = alpha;
+        tmp.mul_assign(&beta);
+        c.sub_assign(&tmp);
+        let mut tmp = ic1;
+        tmp.mul_assign(&x);
+        tmp.add_assign(&ic0);
+        tmp.mul_assign(&gamma);
+        c.sub_assign(&tmp);
+        c.mul_assign(&delta.inverse().unwrap());
+
+        let vk = VerifyingKey::<Bn256> {
+            alpha_g1: G1Affine::one().mul(alpha).into_affine(),
+            beta_g1: G1Affine::one().mul(beta).into_affine(),
+            beta_g2: G2Affine::one().mul(beta).into_affine(),
+            gamma_g2: G2Affine::one().mul(gamma).into_affine(),
+            delta_g1: G1Affine::one().mul(delta).into_affine(),
+            delta_g2: G2Affine::one().mul(delta).into_affine(),
+            ic: vec![
+                G1Affine::one().mul(ic0).into_affine(),
+                G1Affine::one().mul(ic1).into_affine(),
+            ],
+        };
+        let proof = Proof::<Bn256> {
+            a: G1Affine::one().mul(a).into_affine(),
+            b: G2Affine::one().mul(b).into_affine(),
+            c: G1Affine::one().mul(c).into_affine(),
+        };
+
+        let pvk = prepare_verifying_key(&vk);
+        assert!(verify_proof(&pvk, &proof, &[x]).unwrap());
+        assert!(!verify_proof(&pvk, &proof, &[Fr::rand(rng)]).unwrap());
+    }
 }
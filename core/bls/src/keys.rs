@@ -0,0 +1,194 @@
+use pairing::{
+    bls12_381::{Fr, G1Affine, G1Compressed, G1, G2, HashToCurve},
+    io, CurveAffine, CurveProjective, EncodedPoint, Field, PrimeField, PrimeFieldRepr,
+};
+use rand::Rng;
+use crate::signature::{Signature, core_verify};
+use crate::{SIG_DST, POP_DST, PUBLIC_KEY_SIZE};
+
+/// A secret key, a nonzero scalar.
+#[derive(Clone, PartialEq)]
+pub struct SecretKey(Fr);
+
+impl SecretKey {
+    pub fn generate<R: Rng>(rng: &mut R) -> Self {
+        loop {
+            let sk: Fr = rng.gen();
+            if !sk.is_zero() {
+                return SecretKey(sk);
+            }
+        }
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey(G1Affine::one().mul(self.0).into_affine())
+    }
+
+    pub fn sign(&self, msg: &[u8]) -> Signature {
+        self.sign_with_dst(msg, SIG_DST)
+    }
+
+    /// Signs the own public key under `POP_DST`, which proves the knowledge
+    /// of the secret key and prevents rogue key attacks on aggregation.
+    pub fn prove_possession(&self) -> ProofOfPossession {
+        let pk = self.public_key().to_bytes();
+        ProofOfPossession(self.sign_with_dst(&pk[..], POP_DST))
+    }
+
+    fn sign_with_dst(&self, msg: &[u8], dst: &[u8]) -> Signature {
+        let mut h = G2::hash_to_curve(msg, dst);
+        h.mul_assign(self.0);
+        Signature(h.into_affine())
+    }
+
+    pub fn read<R: io::Read>(mut reader: R) -> io::Result<Self> {
+        let mut repr = <Fr as PrimeField>::Repr::default();
+        repr.read_le(&mut reader)?;
+
+        match Fr::from_repr(repr) {
+            Ok(sk) if !sk.is_zero() => Ok(SecretKey(sk)),
+            Ok(_) => Err(io::Error::InvalidData),
+            Err(_) => Err(io::Error::NotInField),
+        }
+    }
+
+    pub fn write<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        self.0.into_repr().write_le(&mut writer)
+    }
+}
+
+/// A public key, `sk * g1`.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Clone, Copy, PartialEq)]
+pub struct PublicKey(pub(crate) G1Affine);
+
+impl PublicKey {
+    /// Sums up the public keys of the signers of the same message.
+    /// Returns `InvalidData` if there are no keys.
+    pub fn aggregate(pks: &[PublicKey]) -> io::Result<Self> {
+        if pks.is_empty() {
+            return Err(io::Error::InvalidData);
+        }
+
+        let mut acc = G1::zero();
+        for pk in pks {
+            acc.add_assign_mixed(&pk.0);
+        }
+
+        Ok(PublicKey(acc.into_affine()))
+    }
+
+    pub fn verify(&self, msg: &[u8], sig: &Signature) -> bool {
+        core_verify(&self.0, msg, &sig.0, SIG_DST)
+    }
+
+    pub fn verify_possession(&self, pop: &ProofOfPossession) -> bool {
+        let pk = self.to_bytes();
+        core_verify(&self.0, &pk[..], &(pop.0).0, POP_DST)
+    }
+
+    pub fn to_bytes(&self) -> [u8; PUBLIC_KEY_SIZE] {
+        let mut res = [0u8; PUBLIC_KEY_SIZE];
+        res.copy_from_slice(self.0.into_compressed().as_ref());
+        res
+    }
+
+    /// Reads a compressed public key, checking that it's in the prime order subgroup.
+    /// The identity is rejected with `PointInfinity`.
+    pub fn read<R: io::Read>(mut reader: R) -> io::Result<Self> {
+        let mut repr = G1Compressed::empty();
+        reader.read(repr.as_mut())?;
+
+        let pk = repr.into_affine().map_err(|_| io::Error::NotOnCurve)?;
+        if pk.is_zero() {
+            return Err(io::Error::PointInfinity);
+        }
+
+        Ok(PublicKey(pk))
+    }
+
+    pub fn write<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write(self.0.into_compressed().as_ref())
+    }
+}
+
+/// A proof of possession of the secret key of a public key.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Clone, Copy, PartialEq)]
+pub struct ProofOfPossession(Signature);
+
+impl ProofOfPossession {
+    pub fn read<R: io::Read>(reader: R) -> io::Result<Self> {
+        Signature::read(reader).map(ProofOfPossession)
+    }
+
+    pub fn write<W: io::Write>(&self, writer: W) -> io::Result<()> {
+        self.0.write(writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{SeedableRng, XorShiftRng};
+
+    #[test]
+    fn test_sign_verify() {
+        let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let sk = SecretKey::generate(rng);
+        let pk = sk.public_key();
+
+        let sig = sk.sign(b"Foo bar");
+        assert!(pk.verify(b"Foo bar", &sig));
+        assert!(!pk.verify(b"Foo baz", &sig));
+
+        let other_pk = SecretKey::generate(rng).public_key();
+        assert!(!other_pk.verify(b"Foo bar", &sig));
+    }
+
+    #[test]
+    fn test_proof_of_possession() {
+        let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let sk = SecretKey::generate(rng);
+        let pk = sk.public_key();
+        let pop = sk.prove_possession();
+
+        assert!(pk.verify_possession(&pop));
+        assert!(!SecretKey::generate(rng).public_key().verify_possession(&pop));
+
+        // A proof of possession is not a signature of the public key under `SIG_DST`.
+        assert!(!pk.verify(&pk.to_bytes()[..], &pop.0));
+    }
+
+    #[test]
+    fn test_keys_read_write() {
+        let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let sk = SecretKey::generate(rng);
+        let pk = sk.public_key();
+
+        let mut buf = vec![];
+        sk.write(&mut buf).unwrap();
+        assert!(SecretKey::read(&buf[..]).unwrap() == sk);
+
+        let mut buf = vec![];
+        pk.write(&mut buf).unwrap();
+        assert_eq!(buf.len(), PUBLIC_KEY_SIZE);
+        assert_eq!(PublicKey::read(&buf[..]).unwrap(), pk);
+
+        let mut buf = vec![];
+        sk.prove_possession().write(&mut buf).unwrap();
+        assert!(pk.verify_possession(&ProofOfPossession::read(&buf[..]).unwrap()));
+    }
+
+    #[test]
+    fn test_reject_invalid_keys() {
+        assert!(SecretKey::read(&[0u8; 32][..]).is_err());
+
+        let zero = G1Affine::zero().into_compressed();
+        assert!(PublicKey::read(zero.as_ref()).is_err());
+
+        let mut invalid = [0xffu8; PUBLIC_KEY_SIZE];
+        invalid[0] = 0x9f;
+        assert!(PublicKey::read(&invalid[..]).is_err());
+    }
+}
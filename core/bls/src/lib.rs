@@ -0,0 +1,26 @@
+//! BLS signatures over BLS12-381 in the proof-of-possession scheme.
+//! Public keys are in G1 and signatures are in G2.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+mod keys;
+mod signature;
+
+pub use keys::{SecretKey, PublicKey, ProofOfPossession};
+pub use signature::{Signature, fast_aggregate_verify};
+
+/// Domain separation tag of the signatures,
+/// `BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_` ciphersuite.
+pub const SIG_DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+
+/// Domain separation tag of the proofs of possession.
+pub const POP_DST: &[u8] = b"BLS_POP_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+
+/// Size of a compressed public key in bytes.
+pub const PUBLIC_KEY_SIZE: usize = 48;
+
+/// Size of a compressed signature in bytes.
+pub const SIGNATURE_SIZE: usize = 96;
+
+/// Size of a secret key in bytes.
+pub const SECRET_KEY_SIZE: usize = 32;
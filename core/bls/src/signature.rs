@@ -0,0 +1,113 @@
+use pairing::{
+    bls12_381::{Bls12, Fq12, G1Affine, G2Affine, G2Compressed, G2, HashToCurve},
+    io, CurveAffine, CurveProjective, EncodedPoint, Engine, Field,
+};
+use crate::PublicKey;
+
+/// A signature, `sk * H(msg)`.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Clone, Copy, PartialEq)]
+pub struct Signature(pub(crate) G2Affine);
+
+impl Signature {
+    /// Sums up the signatures into one, which has the same size as a single signature.
+    /// Returns `InvalidData` if there are no signatures.
+    pub fn aggregate(sigs: &[Signature]) -> io::Result<Self> {
+        if sigs.is_empty() {
+            return Err(io::Error::InvalidData);
+        }
+
+        let mut acc = G2::zero();
+        for sig in sigs {
+            acc.add_assign_mixed(&sig.0);
+        }
+
+        Ok(Signature(acc.into_affine()))
+    }
+
+    /// Reads a compressed signature, checking that it's in the prime order subgroup.
+    pub fn read<R: io::Read>(mut reader: R) -> io::Result<Self> {
+        let mut repr = G2Compressed::empty();
+        reader.read(repr.as_mut())?;
+
+        repr.into_affine()
+            .map(Signature)
+            .map_err(|_| io::Error::NotOnCurve)
+    }
+
+    pub fn write<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write(self.0.into_compressed().as_ref())
+    }
+}
+
+/// Verifies an aggregate signature of the same message by all the public keys.
+/// Every public key must have its proof of possession verified beforehand.
+pub fn fast_aggregate_verify(pks: &[PublicKey], msg: &[u8], sig: &Signature) -> bool {
+    match PublicKey::aggregate(pks) {
+        Ok(pk) => pk.verify(msg, sig),
+        Err(_) => false,
+    }
+}
+
+/// Checks `e(pk, H(msg)) == e(g1, sig)`.
+pub(crate) fn core_verify(pk: &G1Affine, msg: &[u8], sig: &G2Affine, dst: &[u8]) -> bool {
+    let h = G2::hash_to_curve(msg, dst).into_affine();
+
+    let mut neg_g1 = G1Affine::one();
+    neg_g1.negate();
+
+    let res = Bls12::miller_loop([
+        (&pk.prepare(), &h.prepare()),
+        (&neg_g1.prepare(), &sig.prepare()),
+    ].iter());
+
+    Bls12::final_exponentiation(&res) == Some(Fq12::one())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SecretKey;
+    use rand::{SeedableRng, XorShiftRng};
+
+    #[test]
+    fn test_fast_aggregate_verify() {
+        let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let msg = b"round 42";
+
+        let sks: Vec<_> = (0..10).map(|_| SecretKey::generate(rng)).collect();
+        let pks: Vec<_> = sks.iter().map(|sk| sk.public_key()).collect();
+        let sigs: Vec<_> = sks.iter().map(|sk| sk.sign(msg)).collect();
+
+        let agg = Signature::aggregate(&sigs).unwrap();
+        assert!(fast_aggregate_verify(&pks, msg, &agg));
+        assert!(!fast_aggregate_verify(&pks, b"round 43", &agg));
+
+        // Every signer must be counted.
+        assert!(!fast_aggregate_verify(&pks[1..], msg, &agg));
+        let agg_partial = Signature::aggregate(&sigs[1..]).unwrap();
+        assert!(!fast_aggregate_verify(&pks, msg, &agg_partial));
+        assert!(fast_aggregate_verify(&pks[1..], msg, &agg_partial));
+
+        assert!(Signature::aggregate(&[]).is_err());
+        assert!(!fast_aggregate_verify(&[], msg, &agg));
+    }
+
+    #[test]
+    fn test_signature_read_write() {
+        let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let sk = SecretKey::generate(rng);
+        let sig = sk.sign(b"Foo bar");
+
+        let mut buf = vec![];
+        sig.write(&mut buf).unwrap();
+        assert_eq!(buf.len(), crate::SIGNATURE_SIZE);
+
+        let sig2 = Signature::read(&buf[..]).unwrap();
+        assert_eq!(sig, sig2);
+        assert!(sk.public_key().verify(b"Foo bar", &sig2));
+
+        buf[10] ^= 0x01;
+        assert!(Signature::read(&buf[..]).map(|s| s != sig).unwrap_or(true));
+    }
+}
@@ -53,8 +53,8 @@ impl<E: JubjubEngine> Ciphertext<E> {
         params: &E::Params
     ) -> Self
     {
-        let right = params.generator(p_g).mul(*randomness, params);
-        let v_point = params.generator(p_g).mul(amount as u64, params);
+        let right = params.mul_by_generator(p_g, *randomness);
+        let v_point = params.mul_by_generator(p_g, amount as u64);
         let r_point = enc_key.0.mul(*randomness, params);
         let left = v_point.add(&r_point, params);
 
@@ -72,8 +72,8 @@ impl<E: JubjubEngine> Ciphertext<E> {
         p_g: FixedGenerators,
         params: &E::Params
     ) -> Self {
-        let right = params.generator(p_g).mul(*randomness, params);
-        let v_point = params.generator(p_g).mul(amount as u64, params).negate();
+        let right = params.mul_by_generator(p_g, *randomness);
+        let v_point = params.mul_by_generator(p_g, amount as u64).negate();
         let r_point = enc_key.0.mul(*randomness, params);
         let left = v_point.add(&r_point, params);
 
@@ -90,6 +90,18 @@ impl<E: JubjubEngine> Ciphertext<E> {
         p_g: FixedGenerators,
         params: &E::Params
     ) -> Option<u32>
+    {
+        self.decrypt_with_range(decryption_key, p_g, params, 1_000_000)
+    }
+
+    /// Decryption of the ciphetext for an amount below `range`
+    pub fn decrypt_with_range(
+        &self,
+        decryption_key: &keys::DecryptionKey<E>,
+        p_g: FixedGenerators,
+        params: &E::Params,
+        range: u32,
+    ) -> Option<u32>
     {
         let sr_point = self.right.mul(decryption_key.0, params);
         let neg_sr_point = sr_point.negate();
@@ -99,7 +111,7 @@ impl<E: JubjubEngine> Ciphertext<E> {
         let mut acc = edwards::Point::<E, PrimeOrder>::zero();
 
         // Brute-force decryption
-        for i in 0..1_000_000 {
+        for i in 0..range {
             if acc == v_point {
                 return Some(i)
             }
@@ -213,6 +225,23 @@ mod tests {
         assert_eq!(amount, decrypted_amount);
     }
 
+    #[test]
+    fn test_elgamal_decrypt_with_range() {
+        let params = &JubjubBls12::new();
+        let p_g = FixedGenerators::Diversifier; // 1
+        let rng = &mut XorShiftRng::from_seed([0xbc4f6d44, 0xd62f276c, 0xb963afd0, 0x5455863d]);
+        let amount = 5;
+
+        let sk_fs = Fs::rand(rng);
+        let r_fs = Fs::rand(rng);
+
+        let enc_key = EncryptionKey(params.generator(p_g).mul(sk_fs, params));
+        let ciphetext = Ciphertext::encrypt(amount, &r_fs, &enc_key, p_g, params);
+
+        assert_eq!(ciphetext.decrypt_with_range(&DecryptionKey(sk_fs), p_g, params, 6), Some(amount));
+        assert_eq!(ciphetext.decrypt_with_range(&DecryptionKey(sk_fs), p_g, params, 5), None);
+    }
+
     #[test]
     fn test_elgamal_enc_dec_ivk() {
         let params = &JubjubBls12::new();
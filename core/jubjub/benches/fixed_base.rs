@@ -0,0 +1,46 @@
+#![feature(test)]
+
+extern crate test;
+
+use rand::{Rand, SeedableRng, XorShiftRng};
+
+use jubjub::curve::{JubjubBls12, JubjubParams, FixedGenerators, fs::Fs};
+
+#[bench]
+fn bench_generator_mul(b: &mut ::test::Bencher) {
+    const SAMPLES: usize = 1000;
+
+    let mut rng = XorShiftRng::from_seed([0x5dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+    let params = &JubjubBls12::new();
+    let p_g = FixedGenerators::Diversifier;
+
+    let v: Vec<Fs> = (0..SAMPLES).map(|_| Fs::rand(&mut rng)).collect();
+
+    let mut count = 0;
+    b.iter(|| {
+        let tmp = params.generator(p_g).mul(v[count], params);
+        count = (count + 1) % SAMPLES;
+        tmp
+    });
+}
+
+#[bench]
+fn bench_mul_by_generator(b: &mut ::test::Bencher) {
+    const SAMPLES: usize = 1000;
+
+    let mut rng = XorShiftRng::from_seed([0x5dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+    let params = &JubjubBls12::new();
+    let p_g = FixedGenerators::Diversifier;
+
+    let v: Vec<Fs> = (0..SAMPLES).map(|_| Fs::rand(&mut rng)).collect();
+
+    // Generate the table outside of the measurement.
+    params.mul_by_generator(p_g, Fs::rand(&mut rng));
+
+    let mut count = 0;
+    b.iter(|| {
+        let tmp = params.mul_by_generator(p_g, v[count]);
+        count = (count + 1) % SAMPLES;
+        tmp
+    });
+}
@@ -41,3 +41,8 @@ pub const NULLIFIER_POSITION_IN_TREE_GENERATOR_PERSONALIZATION: &'static [u8; 8]
 
 pub const DIVERSIFIER_PERSONALIZATION: &'static [u8; 8]
           = b"Zech_ds_";
+
+// BLAKE2b invocation personalizations
+/// BLAKE2b Personalization for deterministic RedJubjub nonces
+pub const REDJUBJUB_NONCE_PERSONALIZATION: &'static [u8; 16]
+          = b"zech_RJ_NonceGen";
@@ -334,6 +334,24 @@ impl<E: JubjubEngine, Subgroup> Point<E, Subgroup> {
         }
     }
 
+    /// The extended coordinates `(X, Y, T, Z)` in their canonical representation,
+    /// for tables which select entries on the representation.
+    pub(crate) fn into_reprs(&self) -> [<E::Fr as PrimeField>::Repr; 4] {
+        [self.x.into_repr(), self.y.into_repr(), self.t.into_repr(), self.z.into_repr()]
+    }
+
+    /// The inverse of `into_reprs`.
+    pub(crate) fn from_reprs(reprs: &[<E::Fr as PrimeField>::Repr; 4]) -> Self {
+        let coordinate = |repr| E::Fr::from_repr(repr).expect("representation of a coordinate is in the field");
+        Point {
+            x: coordinate(reprs[0]),
+            y: coordinate(reprs[1]),
+            t: coordinate(reprs[2]),
+            z: coordinate(reprs[3]),
+            _marker: PhantomData
+        }
+    }
+
     pub fn into_xy(&self) -> (E::Fr, E::Fr)
     {
         let zinv = self.z.inverse().unwrap();
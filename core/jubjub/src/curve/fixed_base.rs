@@ -0,0 +1,123 @@
+//! Precomputed window tables for the scalar multiplication
+//! by the fixed generators outside the circuit.
+
+use pairing::{Engine, PrimeField};
+use spin::Once;
+
+use super::{
+    JubjubEngine,
+    PrimeOrder,
+    edwards
+};
+
+#[cfg(feature = "std")]
+use ::std::vec::Vec;
+#[cfg(not(feature = "std"))]
+use crate::std::vec::Vec;
+
+/// The window size in bits.
+pub const WINDOW_SIZE: usize = 4;
+
+/// The number of windows to cover a whole scalar representation.
+pub const NUM_WINDOWS: usize = 256 / WINDOW_SIZE;
+
+/// Coordinates of a point as stored in the table, see `edwards::Point::into_reprs`.
+type Entry<E> = [<<E as Engine>::Fr as PrimeField>::Repr; 4];
+
+/// A table of `j * 2^(WINDOW_SIZE * i) * G` for every window `i` and every digit `j`,
+/// so that a scalar multiplication needs additions only.
+///
+/// The scalars are secret keys and nonces, so the multiplication must not leak their digits
+/// through the memory access pattern or branches: every entry of a window is read,
+/// the one for the digit is picked with a mask, and the identity is added for zero digits.
+/// The field arithmetic underneath is not constant-time audited.
+pub struct FixedBaseTable<E: JubjubEngine>(Vec<Vec<Entry<E>>>);
+
+impl<E: JubjubEngine> FixedBaseTable<E> {
+    pub fn new(base: &edwards::Point<E, PrimeOrder>, params: &E::Params) -> Self {
+        let mut windows = Vec::with_capacity(NUM_WINDOWS);
+        let mut g = base.clone();
+
+        for _ in 0..NUM_WINDOWS {
+            let mut table = Vec::with_capacity(1 << WINDOW_SIZE);
+            let mut acc = edwards::Point::<E, PrimeOrder>::zero();
+
+            for _ in 0..(1 << WINDOW_SIZE) {
+                table.push(acc.into_reprs());
+                acc = acc.add(&g, params);
+            }
+            windows.push(table);
+
+            // g = g * 2^WINDOW_SIZE
+            g = acc;
+        }
+
+        FixedBaseTable(windows)
+    }
+
+    pub fn mul<S: Into<<E::Fs as PrimeField>::Repr>>(
+        &self,
+        scalar: S,
+        params: &E::Params
+    ) -> edwards::Point<E, PrimeOrder>
+    {
+        let scalar = scalar.into();
+        let mut res = edwards::Point::zero();
+
+        for (i, limb) in scalar.as_ref().iter().enumerate() {
+            for j in 0..(64 / WINDOW_SIZE) {
+                let digit = (limb >> (j * WINDOW_SIZE)) as usize & ((1 << WINDOW_SIZE) - 1);
+                let entry = select::<E>(&self.0[i * (64 / WINDOW_SIZE) + j], digit);
+                res = res.add(&edwards::Point::from_reprs(&entry), params);
+            }
+        }
+
+        res
+    }
+}
+
+/// Read every entry and keep the one at `digit`, without branching on or indexing by it.
+fn select<E: JubjubEngine>(table: &[Entry<E>], digit: usize) -> Entry<E> {
+    let mut res = [<E::Fr as PrimeField>::Repr::default(); 4];
+
+    for (j, entry) in table.iter().enumerate() {
+        // all ones if j == digit, zero otherwise
+        let mask = (((j ^ digit) as u64).wrapping_sub(1) >> 63).wrapping_neg();
+
+        for (r, e) in res.iter_mut().zip(entry.iter()) {
+            for (r, e) in r.as_mut().iter_mut().zip(e.as_ref().iter()) {
+                *r |= e & mask;
+            }
+        }
+    }
+
+    res
+}
+
+/// A `FixedBaseTable` which is generated on first use.
+pub struct LazyFixedBaseTable<E: JubjubEngine>(Once<FixedBaseTable<E>>);
+
+impl<E: JubjubEngine> LazyFixedBaseTable<E> {
+    pub fn new() -> Self {
+        LazyFixedBaseTable(Once::new())
+    }
+
+    pub fn get(&self, base: &edwards::Point<E, PrimeOrder>, params: &E::Params) -> &FixedBaseTable<E> {
+        self.0.call_once(|| FixedBaseTable::new(base, params))
+    }
+}
+
+// The tables are derived from the generators,
+// so they don't take part in comparing parameters.
+impl<E: JubjubEngine> PartialEq for LazyFixedBaseTable<E> {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: JubjubEngine> ::std::fmt::Debug for LazyFixedBaseTable<E> {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        write!(f, "LazyFixedBaseTable")
+    }
+}
@@ -49,6 +49,9 @@ pub mod montgomery;
 /// This is an implementation of the scalar field for Jubjub.
 pub mod fs;
 
+/// Precomputed window tables for the fixed generators.
+pub mod fixed_base;
+
 #[cfg(test)]
 pub mod tests;
 
@@ -131,6 +134,13 @@ pub trait JubjubParams<E: JubjubEngine>: Sized {
     fn fixed_base_chunks_per_generator(&self) -> usize;
     /// Returns a fixed generator.
     fn generator(&self, base: FixedGenerators) -> &edwards::Point<E, PrimeOrder>;
+    /// Multiplies a fixed generator by the scalar with a precomputed window table,
+    /// which is generated on first use.
+    fn mul_by_generator<S: Into<<E::Fs as PrimeField>::Repr>>(
+        &self,
+        base: FixedGenerators,
+        scalar: S
+    ) -> edwards::Point<E, PrimeOrder>;
     /// Returns a window table [0, 1, ..., 8] for different magnitudes of some
     /// fixed generator.
     fn circuit_generators(&self, _: FixedGenerators) -> &[Vec<(E::Fr, E::Fr)>];
@@ -158,6 +168,7 @@ pub struct JubjubBls12 {
 
     fixed_base_generators: Vec<edwards::Point<Bls12, PrimeOrder>>,
     fixed_base_circuit_generators: Vec<Vec<Vec<(Fr, Fr)>>>,
+    fixed_base_tables: Vec<fixed_base::LazyFixedBaseTable<Bls12>>,
 }
 
 impl JubjubParams<Bls12> for JubjubBls12 {
@@ -184,6 +195,16 @@ impl JubjubParams<Bls12> for JubjubBls12 {
     {
         &self.fixed_base_generators[base as usize]
     }
+    fn mul_by_generator<S: Into<<fs::Fs as PrimeField>::Repr>>(
+        &self,
+        base: FixedGenerators,
+        scalar: S
+    ) -> edwards::Point<Bls12, PrimeOrder>
+    {
+        self.fixed_base_tables[base as usize]
+            .get(self.generator(base), self)
+            .mul(scalar, self)
+    }
     fn circuit_generators(&self, base: FixedGenerators) -> &[Vec<(Fr, Fr)>]
     {
         &self.fixed_base_circuit_generators[base as usize][..]
@@ -215,6 +236,9 @@ impl JubjubBls12 {
             pedersen_circuit_generators: vec![],
             fixed_base_generators: vec![],
             fixed_base_circuit_generators: vec![],
+            fixed_base_tables: (0..FixedGenerators::Max as usize)
+                .map(|_| fixed_base::LazyFixedBaseTable::new())
+                .collect(),
         };
 
         fn find_group_hash<E: JubjubEngine>(
@@ -32,6 +32,7 @@ pub fn test_suite<E: JubjubEngine>(params: &E::Params)
     test_addition_associativity::<E>(params);
     test_order::<E>(params);
     test_mul_associativity::<E>(params);
+    test_mul_by_generator::<E>(params);
     test_loworder::<E>(params);
     test_read_write::<E>(params);
 }
@@ -114,6 +115,31 @@ fn test_loworder<E: JubjubEngine>(params: &E::Params) {
     assert!(loworder_points[7] == inf);
 }
 
+fn test_mul_by_generator<E: JubjubEngine>(params: &E::Params) {
+    use super::FixedGenerators;
+    let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+    let bases = [
+        FixedGenerators::ProofGenerationKey,
+        FixedGenerators::Diversifier,
+        FixedGenerators::ElGamal,
+        FixedGenerators::ValueCommitmentValue,
+        FixedGenerators::ValueCommitmentRandomness,
+        FixedGenerators::SpendingKeyGenerator,
+    ];
+
+    for base in bases.iter() {
+        assert!(params.mul_by_generator(*base, E::Fs::zero()) == edwards::Point::zero());
+        assert!(params.mul_by_generator(*base, E::Fs::one()) == *params.generator(*base));
+        assert!(params.mul_by_generator(*base, 10u64) == params.generator(*base).mul(10u64, params));
+
+        for _ in 0..20 {
+            let s = E::Fs::rand(rng);
+            assert!(params.mul_by_generator(*base, s) == params.generator(*base).mul(s, params));
+        }
+    }
+}
+
 fn test_mul_associativity<E: JubjubEngine>(params: &E::Params) {
     use self::edwards::Point;
     let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
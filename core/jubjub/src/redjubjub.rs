@@ -5,6 +5,7 @@ use pairing::{Field, PrimeField, PrimeFieldRepr, io};
 use rand::{Rng, Rand};
 
 use crate::curve::{FixedGenerators, JubjubEngine, JubjubParams, Unknown, edwards::Point};
+use crate::constants::REDJUBJUB_NONCE_PERSONALIZATION;
 use crate::util::{hash_to_scalar};
 
 pub fn read_scalar<E: JubjubEngine, R: io::Read>(mut reader: R) -> io::Result<E::Fs> {
@@ -32,6 +33,16 @@ pub struct Signature {
     pub sbar: [u8; 32],
 }
 
+/// A pre-signature under an adaptor point `T = t . P_G`.
+/// It becomes a valid `Signature` once it is completed with `t`,
+/// and the completed signature reveals `t` to the holder of the pre-signature.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Copy, Clone, PartialEq)]
+pub struct PreSignature {
+    pub rbar: [u8; 32],
+    pub sbar: [u8; 32],
+}
+
 #[cfg_attr(feature = "std", derive(Debug))]
 pub struct PrivateKey<E: JubjubEngine>(pub E::Fs);
 
@@ -54,6 +65,47 @@ impl Signature {
     }
 }
 
+impl PreSignature {
+    pub fn read<R: io::Read>(mut reader: R) -> io::Result<Self> {
+        let mut rbar = [0u8; 32];
+        let mut sbar = [0u8; 32];
+        reader.read(&mut rbar)?;
+        reader.read(&mut sbar)?;
+        Ok(PreSignature { rbar, sbar })
+    }
+
+    pub fn write<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write(&self.rbar)?;
+        writer.write(&self.sbar)
+    }
+
+    /// Complete the pre-signature with the adaptor secret `t`.
+    /// S = S' + t
+    pub fn complete<E: JubjubEngine>(&self, t: &E::Fs) -> io::Result<Signature> {
+        let mut s = read_scalar::<E, &[u8]>(&self.sbar[..])?;
+        s.add_assign(t);
+
+        let mut sbar = [0u8; 32];
+        write_scalar::<E, &mut [u8]>(&s, &mut sbar[..])?;
+
+        Ok(Signature { rbar: self.rbar, sbar })
+    }
+
+    /// Extract the adaptor secret from the completed signature.
+    /// t = S - S'
+    pub fn extract_secret<E: JubjubEngine>(&self, sig: &Signature) -> io::Result<E::Fs> {
+        if sig.rbar != self.rbar {
+            return Err(io::Error::InvalidData);
+        }
+
+        let s_pre = read_scalar::<E, &[u8]>(&self.sbar[..])?;
+        let mut t = read_scalar::<E, &[u8]>(&sig.sbar[..])?;
+        t.sub_assign(&s_pre);
+
+        Ok(t)
+    }
+}
+
 impl<E: JubjubEngine> PrivateKey<E> {
     pub fn randomize(&self, alpha: E::Fs) -> Self {
         let mut tmp = self.0;
@@ -77,16 +129,80 @@ impl<E: JubjubEngine> PrivateKey<E> {
         p_g: FixedGenerators,
         params: &E::Params,
     ) -> Signature {
+        let r = Self::random_nonce(msg, rng);
+
+        // R = r . P_G
+        let r_g = params.mul_by_generator(p_g, r).into();
+        self.sign_with_nonce(msg, r, &r_g)
+    }
+
+    /// Sign without relying on a random number generator.
+    /// The nonce is derived from the private key and the message in the spirit of RFC 6979,
+    /// optionally hedged with extra entropy.
+    pub fn sign_deterministic(
+        &self,
+        msg: &[u8],
+        extra_entropy: Option<&[u8; 32]>,
+        p_g: FixedGenerators,
+        params: &E::Params,
+    ) -> Signature {
+        let r = self.deterministic_nonce(msg, extra_entropy);
+
+        // R = r . P_G
+        let r_g = params.mul_by_generator(p_g, r).into();
+        self.sign_with_nonce(msg, r, &r_g)
+    }
+
+    /// Create a pre-signature under the adaptor point `T`.
+    pub fn pre_sign<R: Rng>(
+        &self,
+        msg: &[u8],
+        adaptor: &Point<E, Unknown>,
+        rng: &mut R,
+        p_g: FixedGenerators,
+        params: &E::Params,
+    ) -> PreSignature {
+        let r = Self::random_nonce(msg, rng);
+
+        // R' = r . P_G + T
+        let r_g: Point<E, Unknown> = params.mul_by_generator(p_g, r).into();
+        let r_g = r_g.add(adaptor, params);
+
+        // S' = r + H*(R'bar || M) . sk
+        let sig = self.sign_with_nonce(msg, r, &r_g);
+        PreSignature { rbar: sig.rbar, sbar: sig.sbar }
+    }
+
+    fn random_nonce<R: Rng>(msg: &[u8], rng: &mut R) -> E::Fs {
         // T = (l_H + 128) bits of randomness
         // For H*, l_H = 512 bits
         let mut t = [0u8; 80];
         rng.fill_bytes(&mut t[..]);
 
         // r = H*(T || M)
-        let r = h_star::<E>(&t[..], msg);
+        h_star::<E>(&t[..], msg)
+    }
 
-        // R = r . P_G
-        let r_g = params.generator(p_g).mul(r, params);
+    fn deterministic_nonce(&self, msg: &[u8], extra_entropy: Option<&[u8; 32]>) -> E::Fs {
+        // K = sk || 0x00 or sk || 0x01 || entropy
+        // The flag keeps the two modes from colliding on the same message.
+        let mut k = [0u8; 65];
+        write_scalar::<E, &mut [u8]>(&self.0, &mut k[..32])
+            .expect("Jubjub scalars should serialize to 32 bytes");
+        let len = match extra_entropy {
+            Some(entropy) => {
+                k[32] = 1;
+                k[33..].copy_from_slice(&entropy[..]);
+                65
+            },
+            None => 33,
+        };
+
+        // r = H(K || M)
+        hash_to_scalar::<E>(REDJUBJUB_NONCE_PERSONALIZATION, &k[..len], msg)
+    }
+
+    fn sign_with_nonce(&self, msg: &[u8], r: E::Fs, r_g: &Point<E, Unknown>) -> Signature {
         let mut rbar = [0u8; 32];
         r_g.write(&mut &mut rbar[..])
             .expect("Jubjub points should serialize to 32 bytes");
@@ -105,12 +221,12 @@ impl<E: JubjubEngine> PrivateKey<E> {
 
 impl<E: JubjubEngine> PublicKey<E> {
     pub fn from_private(privkey: &PrivateKey<E>, p_g: FixedGenerators, params: &E::Params) -> Self {
-        let res = params.generator(p_g).mul(privkey.0, params).into();
+        let res = params.mul_by_generator(p_g, privkey.0).into();
         PublicKey(res)
     }
 
     pub fn randomize(&self, alpha: E::Fs, p_g: FixedGenerators, params: &E::Params) -> Self {
-        let res: Point<E, Unknown> = params.generator(p_g).mul(alpha, params).into();
+        let res: Point<E, Unknown> = params.mul_by_generator(p_g, alpha).into();
         let res = res.add(&self.0, params);
         PublicKey(res)
     }
@@ -149,7 +265,35 @@ impl<E: JubjubEngine> PublicKey<E> {
 
         // 0 = h_G(-S . P_G + R + c . vk)
         self.0.mul(c, params).add(&r, params).add(
-            &params.generator(p_g).mul(s, params).negate().into(),
+            &params.mul_by_generator(p_g, s).negate().into(),
+            params
+        ).mul_by_cofactor(params).eq(&Point::zero())
+    }
+
+    /// Verify a pre-signature under the adaptor point `T`.
+    pub fn verify_pre_signature(
+        &self,
+        msg: &[u8],
+        pre_sig: &PreSignature,
+        adaptor: &Point<E, Unknown>,
+        p_g: FixedGenerators,
+        params: &E::Params,
+    ) -> bool {
+        // c = H*(R'bar || M)
+        let c = h_star::<E>(&pre_sig.rbar[..], msg);
+
+        let r = match Point::read(&mut &pre_sig.rbar[..], params) {
+            Ok(r) => r,
+            Err(_) => return false,
+        };
+        let s = match read_scalar::<E, &[u8]>(&pre_sig.sbar[..]) {
+            Ok(s) => s,
+            Err(_) => return false,
+        };
+
+        // 0 = h_G(-S' . P_G + R' - T + c . vk)
+        self.0.mul(c, params).add(&r, params).add(&adaptor.negate(), params).add(
+            &params.mul_by_generator(p_g, s).negate().into(),
             params
         ).mul_by_cofactor(params).eq(&Point::zero())
     }
@@ -195,7 +339,7 @@ pub fn batch_verify<'a, E: JubjubEngine, R: Rng>(
 
         acc = acc.add(&r, params);
         acc = acc.add(&entry.vk.0.mul(c, params), params);
-        acc = acc.add(&params.generator(p_g).mul(s, params).into(), params);
+        acc = acc.add(&params.mul_by_generator(p_g, s).into(), params);
     }
 
     acc = acc.mul_by_cofactor(params).into();
@@ -342,4 +486,165 @@ mod tests {
             assert!(!rvk.verify(msg2, &sig1, p_g, params));
         }
     }
+
+    #[test]
+    fn adaptor_signatures() {
+        let mut rng = XorShiftRng::from_seed([0xbc4f6d44, 0xd62f276c, 0xb963afd0, 0x5455863d]);
+        let p_g = FixedGenerators::SpendingKeyGenerator;
+        let params = &JubjubBls12::new();
+
+        let sk = PrivateKey::<Bls12>(rng.gen());
+        let vk = PublicKey::from_private(&sk, p_g, params);
+        let t: Fs = rng.gen();
+        let adaptor = params.generator(p_g).mul(t, params).into();
+        let msg = b"Foo bar";
+
+        let pre_sig = sk.pre_sign(msg, &adaptor, &mut rng, p_g, params);
+        assert!(vk.verify_pre_signature(msg, &pre_sig, &adaptor, p_g, params));
+        assert!(!vk.verify_pre_signature(b"Spam eggs", &pre_sig, &adaptor, p_g, params));
+
+        // A pre-signature is not a valid signature by itself.
+        let not_sig = Signature { rbar: pre_sig.rbar, sbar: pre_sig.sbar };
+        assert!(!vk.verify(msg, &not_sig, p_g, params));
+
+        // Nor can it be completed with a wrong secret.
+        let wrong = pre_sig.complete::<Bls12>(&rng.gen()).unwrap();
+        assert!(!vk.verify(msg, &wrong, p_g, params));
+
+        let sig = pre_sig.complete::<Bls12>(&t).unwrap();
+        assert!(vk.verify(msg, &sig, p_g, params));
+        assert_eq!(pre_sig.extract_secret::<Bls12>(&sig).unwrap(), t);
+
+        let mut pre_sig_bytes = [0u8; 64];
+        pre_sig.write(&mut pre_sig_bytes[..]).unwrap();
+        assert_eq!(PreSignature::read(&pre_sig_bytes[..]).unwrap(), pre_sig);
+    }
+
+    #[test]
+    fn atomic_swap() {
+        let mut rng = XorShiftRng::from_seed([0xbc4f6d44, 0xd62f276c, 0xb963afd0, 0x5455863d]);
+        let p_g = FixedGenerators::SpendingKeyGenerator;
+        let params = &JubjubBls12::new();
+
+        let alice_sk = PrivateKey::<Bls12>(rng.gen());
+        let alice_vk = PublicKey::from_private(&alice_sk, p_g, params);
+        let bob_sk = PrivateKey::<Bls12>(rng.gen());
+        let bob_vk = PublicKey::from_private(&bob_sk, p_g, params);
+
+        // Alice pays Bob with asset A, Bob pays Alice with asset B.
+        let tx_a = b"alice -> bob: 10 of asset A";
+        let tx_b = b"bob -> alice: 20 of asset B";
+
+        // Bob picks the swap secret and shares only the adaptor point.
+        let t: Fs = rng.gen();
+        let adaptor: Point<Bls12, Unknown> = params.generator(p_g).mul(t, params).into();
+
+        // Both parties exchange pre-signatures under the same adaptor point.
+        let bob_pre_sig = bob_sk.pre_sign(tx_b, &adaptor, &mut rng, p_g, params);
+        assert!(bob_vk.verify_pre_signature(tx_b, &bob_pre_sig, &adaptor, p_g, params));
+
+        let alice_pre_sig = alice_sk.pre_sign(tx_a, &adaptor, &mut rng, p_g, params);
+        assert!(alice_vk.verify_pre_signature(tx_a, &alice_pre_sig, &adaptor, p_g, params));
+
+        // Bob claims asset A, which publishes Alice's completed signature.
+        let alice_sig = alice_pre_sig.complete::<Bls12>(&t).unwrap();
+        assert!(alice_vk.verify(tx_a, &alice_sig, p_g, params));
+
+        // Alice learns the secret from the published signature and claims asset B.
+        let extracted = alice_pre_sig.extract_secret::<Bls12>(&alice_sig).unwrap();
+        let bob_sig = bob_pre_sig.complete::<Bls12>(&extracted).unwrap();
+        assert!(bob_vk.verify(tx_b, &bob_sig, p_g, params));
+
+        // A signature over another nonce does not reveal anything.
+        let other_sig = alice_sk.sign(tx_a, &mut rng, p_g, params);
+        assert!(alice_pre_sig.extract_secret::<Bls12>(&other_sig).is_err());
+    }
+
+    #[test]
+    fn deterministic_signatures() {
+        let mut rng = XorShiftRng::from_seed([0xbc4f6d44, 0xd62f276c, 0xb963afd0, 0x5455863d]);
+        let p_g = FixedGenerators::Diversifier;
+        let params = &JubjubBls12::new();
+
+        for _ in 0..100 {
+            let sk = PrivateKey::<Bls12>(rng.gen());
+            let vk = PublicKey::from_private(&sk, p_g, params);
+            let entropy: [u8; 32] = rng.gen();
+
+            let msg1 = b"Foo bar";
+            let msg2 = b"Spam eggs";
+
+            let sig1 = sk.sign_deterministic(msg1, None, p_g, params);
+            let sig2 = sk.sign_deterministic(msg2, None, p_g, params);
+            let hedged = sk.sign_deterministic(msg1, Some(&entropy), p_g, params);
+
+            assert!(vk.verify(msg1, &sig1, p_g, params));
+            assert!(vk.verify(msg2, &sig2, p_g, params));
+            assert!(vk.verify(msg1, &hedged, p_g, params));
+            assert!(!vk.verify(msg1, &sig2, p_g, params));
+
+            assert_eq!(sig1, sk.sign_deterministic(msg1, None, p_g, params));
+            assert!(sig1.rbar != sig2.rbar);
+            assert!(sig1.rbar != hedged.rbar);
+        }
+    }
+
+    /// Regression vectors for `sign_deterministic`.
+    /// The nonce derivation is specific to this crate, so there are no reference vectors for it:
+    /// the expected signatures were produced by this implementation and only guard against changes.
+    /// The nonce is checked against the specification with BLAKE2b directly, independently of `deterministic_nonce`.
+    #[test]
+    fn deterministic_test_vectors() {
+        use hex_literal::{hex, hex_impl};
+        use blake2_rfc::blake2b::Blake2b;
+        use crate::curve::ToUniform;
+
+        let p_g = FixedGenerators::Diversifier;
+        let params = &JubjubBls12::new();
+        let sk = PrivateKey::<Bls12>::read(
+            &hex!("dcfd7a3cb8291764a4e1ab41f6831d2e285a98114cdc4a2d361a380de0e3cb07")[..]
+        ).unwrap();
+        let vk = PublicKey::<Bls12>::read(
+            &mut &hex!("791b91fae07feada7b6f6042b1e214bc75759b3921956053936c38a95271a834")[..],
+            params
+        ).unwrap();
+        let msg = b"Foo bar";
+
+        let vectors: [(Option<&[u8; 32]>, [u8; 64]); 2] = [
+            (
+                None,
+                hex!("82f21957cb4a6cecc58153683928b82858433fb846b118227f4d23cca415db93f97f0af004b8258fb7bb3db9447c9e643b50c00729a2c9bc6f402fadc971de0d")
+            ),
+            (
+                Some(&[0x42; 32]),
+                hex!("c52104d4427c9388b74673947225c719dc84a1eb29a0cfe585ded33cd11bd82734cf850361d65ba58c9c557bfbce3da7611e9c8428e76c033cad2512062a7903")
+            ),
+        ];
+
+        for (entropy, expected) in vectors.iter() {
+            let sig = sk.sign_deterministic(msg, *entropy, p_g, params);
+            let mut sig_bytes = [0u8; 64];
+            sig.write(&mut sig_bytes[..]).unwrap();
+
+            assert_eq!(&sig_bytes[..], &expected[..]);
+            assert!(vk.verify(msg, &sig, p_g, params));
+
+            // r = BLAKE2b-512("zech_RJ_NonceGen", sk || 0x00 || M) or (.., sk || 0x01 || entropy || M)
+            let mut hasher = Blake2b::with_params(64, &[], &[], b"zech_RJ_NonceGen");
+            hasher.update(&hex!("dcfd7a3cb8291764a4e1ab41f6831d2e285a98114cdc4a2d361a380de0e3cb07"));
+            match entropy {
+                Some(entropy) => {
+                    hasher.update(&[1]);
+                    hasher.update(&entropy[..]);
+                },
+                None => hasher.update(&[0]),
+            }
+            hasher.update(msg);
+            let r = Fs::to_uniform(hasher.finalize().as_ref());
+
+            let mut rbar = [0u8; 32];
+            params.generator(p_g).mul(r, params).write(&mut &mut rbar[..]).unwrap();
+            assert_eq!(rbar, sig.rbar);
+        }
+    }
 }
@@ -157,9 +157,7 @@ impl<E: JubjubEngine> ProofGenerationKey<E> {
     ) -> Self
     {
         ProofGenerationKey (
-            params
-                .generator(FixedGenerators::Diversifier)
-                .mul(spending_key.0.into_repr(), params)
+            params.mul_by_generator(FixedGenerators::Diversifier, spending_key.0)
         )
     }
 
@@ -170,7 +168,7 @@ impl<E: JubjubEngine> ProofGenerationKey<E> {
         params: &E::Params
     ) -> RandomizedSigVk<E> {
         let point = self.0.add(
-            &params.generator(FixedGenerators::Diversifier).mul(alpha, params),
+            &params.mul_by_generator(FixedGenerators::Diversifier, alpha),
             params
         );
 
@@ -204,9 +202,7 @@ impl<E: JubjubEngine> ProofGenerationKey<E> {
         params: &E::Params
     ) -> io::Result<EncryptionKey<E>>
     {
-        let pk_d = params
-            .generator(FixedGenerators::Diversifier)
-            .mul(self.into_decryption_key()?.0, params);
+        let pk_d = params.mul_by_generator(FixedGenerators::Diversifier, self.into_decryption_key()?.0);
 
         Ok(EncryptionKey(pk_d))
     }
@@ -266,9 +262,7 @@ impl<E: JubjubEngine> EncryptionKey<E> {
         params: &E::Params,
     ) -> Self
     {
-        let pk_d = params
-            .generator(FixedGenerators::Diversifier)
-            .mul(decryption_key.0, params);
+        let pk_d = params.mul_by_generator(FixedGenerators::Diversifier, decryption_key.0);
 
         EncryptionKey(pk_d)
     }
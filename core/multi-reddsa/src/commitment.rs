@@ -4,6 +4,7 @@ use jubjub::redjubjub::h_star;
 use pairing::io;
 use crate::mr_pubkey::MRPubkey;
 use crate::transcript::TranscriptProtocol;
+use crate::message::SessionId;
 
 const COMMITMENT_SIZE: usize = 32;
 
@@ -12,15 +13,33 @@ const COMMITMENT_SIZE: usize = 32;
 pub struct Commitment([u8; COMMITMENT_SIZE]);
 
 impl Commitment {
+    /// Commit to `R` bound to the session and the cosigner position,
+    /// so that a commitment cannot be replayed in another session or by another cosigner.
     #[allow(non_snake_case)]
-    pub(super) fn from_R<E: JubjubEngine>(R: &Point<E, PrimeOrder>) -> io::Result<Self> {
+    pub(super) fn from_R<E: JubjubEngine>(
+        session_id: &SessionId,
+        pos: usize,
+        R: &Point<E, PrimeOrder>
+    ) -> io::Result<Self> {
         let mut t = Transcript::new(b"R-commitment");
+        t.append_message(b"session-id", session_id.as_bytes());
+        t.append_message(b"pos", &(pos as u32).to_le_bytes());
         t.commit_point(b"", R)?;
         let mut commitment = [0u8; COMMITMENT_SIZE];
         t.challenge_bytes(b"commitment", &mut commitment[..]);
         Ok(Commitment(commitment))
     }
 
+    pub fn read<R: io::Read>(reader: &mut R) -> io::Result<Self> {
+        let mut commitment = [0u8; COMMITMENT_SIZE];
+        reader.read(&mut commitment)?;
+        Ok(Commitment(commitment))
+    }
+
+    pub fn write<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write(&self.0)
+    }
+
     pub fn ct_eq(&self, other: &Commitment) -> bool {
         use subtle::ConstantTimeEq;
 
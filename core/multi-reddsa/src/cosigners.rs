@@ -2,6 +2,7 @@ use jubjub::curve::{JubjubEngine, edwards::Point, PrimeOrder, FixedGenerators, J
 use jubjub::redjubjub::h_star;
 use pairing::{io, Field};
 use crate::commitment::{Commitment, SignerKeys};
+use crate::message::SessionId;
 
 pub struct Cosigners<E: JubjubEngine> {
     pos: usize,
@@ -32,8 +33,16 @@ pub struct CosignersCommited<E: JubjubEngine> {
 }
 
 impl<E: JubjubEngine> CosignersCommited<E> {
-    pub fn verify_witness(self, R: &Point<E, PrimeOrder>) -> io::Result<CosignersRevealed<E>> {
-        let received_comm = Commitment::from_R(R)?;
+    pub fn commitment(&self) -> &Commitment {
+        &self.commitment
+    }
+
+    pub fn verify_witness(
+        self,
+        session_id: &SessionId,
+        R: &Point<E, PrimeOrder>
+    ) -> io::Result<CosignersRevealed<E>> {
+        let received_comm = Commitment::from_R(session_id, self.pos, R)?;
         let eq = self.commitment.ct_eq(&received_comm);
 
         if !eq {
@@ -56,6 +65,18 @@ pub struct CosignersRevealed<E: JubjubEngine> {
 }
 
 impl<E: JubjubEngine> CosignersRevealed<E> {
+    pub(crate) fn new(pos: usize, pub_key: Point<E, PrimeOrder>, reveal: Point<E, PrimeOrder>) -> Self {
+        CosignersRevealed {
+            pos,
+            pub_key,
+            reveal,
+        }
+    }
+
+    pub fn reveal(&self) -> &Point<E, PrimeOrder> {
+        &self.reveal
+    }
+
     pub fn verify_share(
         self,
         msg: &[u8],
@@ -65,7 +86,7 @@ impl<E: JubjubEngine> CosignersRevealed<E> {
         p_g: FixedGenerators,
         params: &E::Params
     ) -> io::Result<E::Fs> {
-        let S_i = params.generator(p_g).mul(share, params);
+        let S_i = params.mul_by_generator(p_g, share);
         let mut c_i = h_star::<E>(&X_bar_R_buf[..], msg);
         c_i.mul_assign(&signer_keys.get_a(&signer_keys.get_pub_key(self.pos))?);
         let X_i = self.pub_key;
@@ -5,6 +5,7 @@ use jubjub::curve::{JubjubEngine, edwards::Point, PrimeOrder, FixedGenerators, J
 use jubjub::redjubjub::{Signature, h_star};
 use commitment::*;
 use cosigners::*;
+use message::*;
 use core::convert::TryFrom;
 use rand::Rng;
 
@@ -12,10 +13,18 @@ mod transcript;
 mod commitment;
 mod cosigners;
 mod mr_pubkey;
+mod message;
 
+pub use commitment::{Commitment, SignerKeys};
+pub use message::{SessionId, CommitmentMessage, RevealMessage, ShareMessage, WIRE_VERSION, MAX_MESSAGE_SIZE, MAX_SIGNERS};
+
+/// The first stage of a signing session.
+/// It is deliberately not serializable: if the nonce `r_i` were restored
+/// and committed again with different cosigner commitments, the private key would leak.
 #[allow(non_snake_case)]
-pub struct CommitmentStage<'m, E: JubjubEngine>{
-    msg: &'m [u8],
+pub struct CommitmentStage<E: JubjubEngine>{
+    session_id: SessionId,
+    msg: Vec<u8>,
     x_i: E::Fs,
     r_i: E::Fs,
     R_i: Point<E, PrimeOrder>,
@@ -24,64 +33,95 @@ pub struct CommitmentStage<'m, E: JubjubEngine>{
     pos: usize,
 }
 
-impl<'m, E: JubjubEngine> CommitmentStage<'m, E> {
+impl<E: JubjubEngine> CommitmentStage<E> {
     #[allow(non_snake_case)]
     pub fn new<R: Rng>(
-        msg: &'m [u8],
+        session_id: SessionId,
+        msg: &[u8],
         x_i: E::Fs,
         signer_keys: SignerKeys<E>,
         pos: usize,
         p_g: FixedGenerators,
         params: &E::Params,
         rng: &mut R,
-    ) -> io::Result<(CommitmentStage<'m, E>, Commitment)>
+    ) -> io::Result<(CommitmentStage<E>, CommitmentMessage)>
     {
-        // T = (l_H + 128) bits of randomness
+        if pos >= signer_keys.len() || msg.len() > MAX_MESSAGE_SIZE {
+            return Err(io::Error::InvalidData)
+        }
+
+        // T = (l_H + 128) bits of randomness || session id
         // For H*, l_H = 512 bits
-        let mut t = [0u8; 80];
-        rng.fill_bytes(&mut t[..]);
+        let mut t = [0u8; 80 + SESSION_ID_SIZE];
+        rng.fill_bytes(&mut t[..80]);
+        t[80..].copy_from_slice(session_id.as_bytes());
         let r_i = h_star::<E>(&t[..], msg);
 
-        let R_i = params.generator(p_g).mul(r_i, params);
-        let commitment = Commitment::from_R(&R_i)?;
+        let R_i = params.mul_by_generator(p_g, r_i);
+        let commitment = Commitment::from_R(&session_id, pos, &R_i)?;
 
         let cosigners = (0..signer_keys.len())
             .map(|i| Cosigners::new(i, signer_keys.get_pub_key(i)))
             .collect();
 
         Ok((CommitmentStage {
-            msg,
+            session_id,
+            msg: msg.to_vec(),
             x_i,
             r_i,
             R_i,
             cosigners,
             signer_keys,
             pos,
-        }, commitment))
+        }, CommitmentMessage {
+            session_id,
+            pos,
+            commitment,
+        }))
     }
 
-    #[allow(non_snake_case)]
     pub fn commit(
         self,
-        commitment: Vec<Commitment>,
-    ) -> io::Result<(RevealStage<'m, E>, Point<E, PrimeOrder>)>
+        commitments: Vec<CommitmentMessage>,
+    ) -> io::Result<(RevealStage<E>, RevealMessage<E>)>
     {
-        let cosigners = self.cosigners.into_iter().zip(commitment)
-            .map(|(signer, comm)| signer.commit(comm)).collect();
+        check_messages(
+            &self.session_id,
+            self.signer_keys.len(),
+            commitments.iter().map(|c| (c.session_id, c.pos))
+        )?;
+
+        // Our own commitment must not have been replaced.
+        let own = Commitment::from_R(&self.session_id, self.pos, &self.R_i)?;
+        if !own.ct_eq(&commitments[self.pos].commitment) {
+            return Err(io::Error::InvalidData)
+        }
+
+        let cosigners = self.cosigners.into_iter().zip(commitments)
+            .map(|(signer, comm)| signer.commit(comm.commitment)).collect();
 
         Ok((RevealStage {
+            session_id: self.session_id,
             msg: self.msg,
             x_i: self.x_i,
             r_i: self.r_i,
             cosigners,
             signer_keys: self.signer_keys,
             pos: self.pos,
-        }, self.R_i))
+        }, RevealMessage {
+            session_id: self.session_id,
+            pos: self.pos,
+            R: self.R_i,
+        }))
     }
 }
 
-pub struct RevealStage<'m, E: JubjubEngine>{
-    msg: &'m [u8],
+/// The second stage of a signing session.
+/// All commitments are fixed at this point, so the stage can be persisted
+/// and restored without the risk of signing twice with the same nonce.
+pub struct RevealStage<E: JubjubEngine>{
+    session_id: SessionId,
+    msg: Vec<u8>,
     x_i: E::Fs,
     r_i: E::Fs,
     cosigners: Vec<CosignersCommited<E>>,
@@ -89,18 +129,26 @@ pub struct RevealStage<'m, E: JubjubEngine>{
     pos: usize,
 }
 
-impl<'m, E: JubjubEngine> RevealStage<'m, E> {
+impl<E: JubjubEngine> RevealStage<E> {
     #[allow(non_snake_case)]
     pub fn reveal(
         self,
-        reveals: Vec<Point<E, PrimeOrder>>,
+        reveals: Vec<RevealMessage<E>>,
         params: &E::Params
-    ) -> io::Result<(ShareStage<'m, E>, E::Fs)> {
+    ) -> io::Result<(ShareStage<E>, ShareMessage<E>)> {
+        check_messages(
+            &self.session_id,
+            self.signer_keys.len(),
+            reveals.iter().map(|r| (r.session_id, r.pos))
+        )?;
+
+        let reveals: Vec<_> = reveals.into_iter().map(|r| r.R).collect();
         let sum_R = sum_commitment(&reveals[..], params);
 
         // Verify nonce
+        let session_id = self.session_id;
         let cosigners = self.cosigners.into_iter().zip(reveals)
-            .map(|(signer, reveal)| signer.verify_witness(&reveal))
+            .map(|(signer, reveal)| signer.verify_witness(&session_id, &reveal))
             .collect::<Result<_, _>>()?;
 
         let mut X_bar_R_buf = [0u8; 64];
@@ -108,7 +156,7 @@ impl<'m, E: JubjubEngine> RevealStage<'m, E> {
         sum_R.write(&mut &mut X_bar_R_buf[32..])?;
 
         // c = H*(X_bar, R, m)
-        let mut s_i = h_star::<E>(&X_bar_R_buf[..], self.msg);
+        let mut s_i = h_star::<E>(&X_bar_R_buf[..], &self.msg[..]);
         // c * a
         s_i.mul_assign(&self.signer_keys.get_a(&self.signer_keys.get_pub_key(self.pos))?);
         // c * a * x
@@ -117,19 +165,63 @@ impl<'m, E: JubjubEngine> RevealStage<'m, E> {
         s_i.add_assign(&self.r_i);
 
         Ok((ShareStage {
+            session_id: self.session_id,
             msg: self.msg,
             X_bar_R_buf,
             sum_R,
             cosigners,
             signer_keys: self.signer_keys,
             pos: self.pos,
-        }, s_i))
+        }, ShareMessage {
+            session_id: self.session_id,
+            pos: self.pos,
+            share: s_i,
+        }))
+    }
+
+    pub fn read<R: io::Read>(reader: &mut R, params: &E::Params) -> io::Result<Self> {
+        Tag::RevealStage.read_expected(reader)?;
+        let (session_id, pos, msg, signer_keys) = read_session(reader, params)?;
+        let x_i = read_fs::<E, _>(reader)?;
+        let r_i = read_fs::<E, _>(reader)?;
+
+        let mut cosigners = Vec::with_capacity(signer_keys.len());
+        for i in 0..signer_keys.len() {
+            let commitment = Commitment::read(reader)?;
+            cosigners.push(Cosigners::new(i, signer_keys.get_pub_key(i)).commit(commitment));
+        }
+
+        Ok(RevealStage {
+            session_id,
+            msg,
+            x_i,
+            r_i,
+            cosigners,
+            signer_keys,
+            pos,
+        })
+    }
+
+    pub fn write<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        Tag::RevealStage.write(writer)?;
+        write_session(&self.session_id, self.pos, &self.msg[..], &self.signer_keys, writer)?;
+        self.x_i.into_repr().write_le(writer)?;
+        self.r_i.into_repr().write_le(writer)?;
+
+        for c in &self.cosigners {
+            c.commitment().write(writer)?;
+        }
+        Ok(())
     }
 }
 
+/// The last stage of a signing session.
+/// It holds no secrets and can be persisted freely.
+#[allow(non_snake_case)]
 #[derive(Clone)]
-pub struct ShareStage<'m, E: JubjubEngine> {
-    msg: &'m [u8],
+pub struct ShareStage<E: JubjubEngine> {
+    session_id: SessionId,
+    msg: Vec<u8>,
     X_bar_R_buf: [u8; 64],
     sum_R: Point<E, PrimeOrder>,
     cosigners: Vec<CosignersRevealed<E>>,
@@ -137,29 +229,118 @@ pub struct ShareStage<'m, E: JubjubEngine> {
     pos: usize,
 }
 
-impl<'m, E: JubjubEngine> ShareStage<'m, E> {
+impl<E: JubjubEngine> ShareStage<E> {
     pub fn share(
         self,
-        shares: Vec<E::Fs>,
+        shares: Vec<ShareMessage<E>>,
         p_g: FixedGenerators,
         params: &E::Params,
-    ) -> AggSignature<E> {
-        let s = self.clone().cosigners.into_iter().zip(shares)
-            .map(|(signer, share)| signer.verify_share(
-                self.msg,
-                share,
+    ) -> io::Result<AggSignature<E>> {
+        check_messages(
+            &self.session_id,
+            self.signer_keys.len(),
+            shares.iter().map(|s| (s.session_id, s.pos))
+        )?;
+
+        let mut s = E::Fs::zero();
+        for (signer, share) in self.cosigners.into_iter().zip(shares) {
+            let share = signer.verify_share(
+                &self.msg[..],
+                share.share,
                 &self.X_bar_R_buf[..],
                 &self.signer_keys,
                 p_g,
                 params
-            ).unwrap()) // TODO
-            .fold(E::Fs::zero(), |mut sum, s| { sum.add_assign(&s); sum });
+            )?;
+            s.add_assign(&share);
+        }
 
-        AggSignature {
+        Ok(AggSignature {
             s,
             R: self.sum_R,
+        })
+    }
+
+    #[allow(non_snake_case)]
+    pub fn read<R: io::Read>(reader: &mut R, params: &E::Params) -> io::Result<Self> {
+        Tag::ShareStage.read_expected(reader)?;
+        let (session_id, pos, msg, signer_keys) = read_session(reader, params)?;
+
+        let mut reveals = Vec::with_capacity(signer_keys.len());
+        for _ in 0..signer_keys.len() {
+            reveals.push(read_point::<E, _>(reader, params)?);
+        }
+        let sum_R = sum_commitment(&reveals[..], params);
+
+        let mut X_bar_R_buf = [0u8; 64];
+        signer_keys.get_agg_pub_key().write(&mut &mut X_bar_R_buf[..32])?;
+        sum_R.write(&mut &mut X_bar_R_buf[32..])?;
+
+        let cosigners = reveals.into_iter().enumerate()
+            .map(|(i, reveal)| CosignersRevealed::new(i, signer_keys.get_pub_key(i), reveal))
+            .collect();
+
+        Ok(ShareStage {
+            session_id,
+            msg,
+            X_bar_R_buf,
+            sum_R,
+            cosigners,
+            signer_keys,
+            pos,
+        })
+    }
+
+    pub fn write<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        Tag::ShareStage.write(writer)?;
+        write_session(&self.session_id, self.pos, &self.msg[..], &self.signer_keys, writer)?;
+
+        for c in &self.cosigners {
+            c.reveal().write(writer)?;
         }
+        Ok(())
+    }
+}
+
+/// Read the parameters shared by every persisted stage.
+fn read_session<E: JubjubEngine, R: io::Read>(
+    reader: &mut R,
+    params: &E::Params,
+) -> io::Result<(SessionId, usize, Vec<u8>, SignerKeys<E>)> {
+    let session_id = SessionId::read(reader)?;
+    let pos = read_pos(reader)?;
+    let msg = read_bytes(reader)?;
+
+    let n = read_pos(reader)?;
+    if n < 2 || n > MAX_SIGNERS || pos >= n {
+        return Err(io::Error::InvalidData)
+    }
+
+    let mut pub_keys = Vec::with_capacity(n);
+    for _ in 0..n {
+        pub_keys.push(read_point::<E, _>(reader, params)?);
+    }
+    let signer_keys = SignerKeys::new(pub_keys, params)?;
+
+    Ok((session_id, pos, msg, signer_keys))
+}
+
+fn write_session<E: JubjubEngine, W: io::Write>(
+    session_id: &SessionId,
+    pos: usize,
+    msg: &[u8],
+    signer_keys: &SignerKeys<E>,
+    writer: &mut W,
+) -> io::Result<()> {
+    session_id.write(writer)?;
+    write_pos(pos, writer)?;
+    write_bytes(msg, writer)?;
+
+    write_pos(signer_keys.len(), writer)?;
+    for i in 0..signer_keys.len() {
+        signer_keys.get_pub_key(i).write(writer)?;
     }
+    Ok(())
 }
 
 #[derive(Clone)]
@@ -197,14 +378,15 @@ mod tests {
         let rng = &mut rand::thread_rng();
         let params = &JubjubBls12::new();
         let p_g = FixedGenerators::Diversifier;
+        let session_id = SessionId::random(rng);
 
         let (cosigners, comms): (Vec<_>, Vec<_>) = secrets.clone().into_iter().enumerate()
-            .map(|(i, x_i)| CommitmentStage::new(msg, *x_i, signer_keys.clone(), i, p_g, params, rng).unwrap())
+            .map(|(i, x_i)| CommitmentStage::new(session_id, msg, *x_i, signer_keys.clone(), i, p_g, params, rng).unwrap())
             .unzip();
 
         let (cosigners, reveals): (Vec<_>, Vec<_>) = cosigners.into_iter().map(|c| c.commit(comms.clone()).unwrap()).unzip();
         let (cosigners, shares): (Vec<_>, Vec<_>) = cosigners.into_iter().map(|c| c.reveal(reveals.clone(), params).unwrap()).unzip();
-        let sigs: Vec<Signature> = cosigners.into_iter().map(|c| c.share(shares.clone(), p_g, params).try_into().unwrap()).collect();
+        let sigs: Vec<Signature> = cosigners.into_iter().map(|c| c.share(shares.clone(), p_g, params).unwrap().try_into().unwrap()).collect();
 
         let cmp = &sigs[0];
         for s in &sigs {
@@ -221,19 +403,160 @@ mod tests {
         SignerKeys::new(pub_keys, params).unwrap()
     }
 
+    fn secrets_helper() -> Vec<Fs> {
+        vec![
+            Fs::from_str("1").unwrap(),
+            Fs::from_str("2").unwrap(),
+            Fs::from_str("3").unwrap(),
+        ]
+    }
+
     #[test]
     fn test_multi_verify() {
         let params = &JubjubBls12::new();
         let p_g = FixedGenerators::Diversifier;
-        let secrets = vec![
-            Fs::from_str("1").unwrap(),
-            Fs::from_str("2").unwrap(),
-            Fs::from_str("3").unwrap(),
-        ];
+        let secrets = secrets_helper();
 
         let signer_keys = signer_keys_helper(&secrets[..]);
         let sig = sign_helper(b"test-sign", &secrets[..], &signer_keys);
 
         assert!(signer_keys.get_mr_pub_key().verify(b"test-sign", &sig, p_g, params));
     }
+
+    #[test]
+    fn test_persisted_session() {
+        let rng = &mut rand::thread_rng();
+        let params = &JubjubBls12::new();
+        let p_g = FixedGenerators::Diversifier;
+        let msg = b"test-sign";
+        let secrets = secrets_helper();
+        let signer_keys = signer_keys_helper(&secrets[..]);
+        let session_id = SessionId::random(rng);
+
+        let (cosigners, comms): (Vec<_>, Vec<_>) = secrets.iter().enumerate()
+            .map(|(i, x_i)| CommitmentStage::new(session_id, msg, *x_i, signer_keys.clone(), i, p_g, params, rng).unwrap())
+            .unzip();
+
+        // Every message goes through the wire format.
+        let comms: Vec<_> = comms.iter().map(|c| {
+            let mut buf = vec![];
+            c.write(&mut buf).unwrap();
+            CommitmentMessage::read(&mut &buf[..]).unwrap()
+        }).collect();
+
+        let (cosigners, reveals): (Vec<_>, Vec<_>) = cosigners.into_iter().map(|c| c.commit(comms.clone()).unwrap()).unzip();
+        let reveals: Vec<_> = reveals.iter().map(|r| {
+            let mut buf = vec![];
+            r.write(&mut buf).unwrap();
+            RevealMessage::<Bls12>::read(&mut &buf[..], params).unwrap()
+        }).collect();
+
+        // Restart every cosigner from the persisted state.
+        let cosigners: Vec<_> = cosigners.iter().map(|c| {
+            let mut buf = vec![];
+            c.write(&mut buf).unwrap();
+            RevealStage::<Bls12>::read(&mut &buf[..], params).unwrap()
+        }).collect();
+
+        let (cosigners, shares): (Vec<_>, Vec<_>) = cosigners.into_iter().map(|c| c.reveal(reveals.clone(), params).unwrap()).unzip();
+        let shares: Vec<_> = shares.iter().map(|s| {
+            let mut buf = vec![];
+            s.write(&mut buf).unwrap();
+            ShareMessage::<Bls12>::read(&mut &buf[..]).unwrap()
+        }).collect();
+
+        let cosigners: Vec<_> = cosigners.iter().map(|c| {
+            let mut buf = vec![];
+            c.write(&mut buf).unwrap();
+            ShareStage::<Bls12>::read(&mut &buf[..], params).unwrap()
+        }).collect();
+
+        for c in cosigners {
+            let sig: Signature = c.share(shares.clone(), p_g, params).unwrap().try_into().unwrap();
+            assert!(signer_keys.clone().get_mr_pub_key().verify(msg, &sig, p_g, params));
+        }
+    }
+
+    #[test]
+    fn test_reject_other_session() {
+        let rng = &mut rand::thread_rng();
+        let params = &JubjubBls12::new();
+        let p_g = FixedGenerators::Diversifier;
+        let msg = b"test-sign";
+        let secrets = secrets_helper();
+        let signer_keys = signer_keys_helper(&secrets[..]);
+        let session_id = SessionId::random(rng);
+        let other_session_id = SessionId::random(rng);
+
+        let (mut cosigners, comms): (Vec<_>, Vec<_>) = secrets.iter().enumerate()
+            .map(|(i, x_i)| CommitmentStage::new(session_id, msg, *x_i, signer_keys.clone(), i, p_g, params, rng).unwrap())
+            .unzip();
+        let (_, other_comm) = CommitmentStage::new(other_session_id, msg, secrets[1], signer_keys.clone(), 1, p_g, params, rng).unwrap();
+
+        // A commitment from another session is rejected.
+        let mut replayed = comms.clone();
+        replayed[1] = other_comm;
+        let c = cosigners.remove(0);
+        assert!(c.commit(replayed).is_err());
+
+        // Reveals carrying another session id are rejected.
+        let (cosigners, mut reveals): (Vec<_>, Vec<_>) = cosigners.into_iter().map(|c| c.commit(comms.clone()).unwrap()).unzip();
+        reveals[0].session_id = other_session_id;
+        for c in cosigners {
+            assert!(c.reveal(reveals.clone(), params).is_err());
+        }
+    }
+
+    #[test]
+    fn test_reject_wrong_tag() {
+        let rng = &mut rand::thread_rng();
+        let params = &JubjubBls12::new();
+        let p_g = FixedGenerators::Diversifier;
+        let secrets = secrets_helper();
+        let signer_keys = signer_keys_helper(&secrets[..]);
+        let session_id = SessionId::random(rng);
+
+        let (_, comm) = CommitmentStage::new(session_id, b"test-sign", secrets[0], signer_keys, 0, p_g, params, rng).unwrap();
+        let mut buf = vec![];
+        comm.write(&mut buf).unwrap();
+
+        assert!(ShareMessage::<Bls12>::read(&mut &buf[..]).is_err());
+        assert!(RevealStage::<Bls12>::read(&mut &buf[..], params).is_err());
+
+        buf[0] = WIRE_VERSION + 1;
+        assert!(CommitmentMessage::read(&mut &buf[..]).is_err());
+    }
+
+    #[test]
+    fn test_reject_oversized_lengths() {
+        let rng = &mut rand::thread_rng();
+        let params = &JubjubBls12::new();
+        let p_g = FixedGenerators::Diversifier;
+        let msg = b"test-sign";
+        let secrets = secrets_helper();
+        let signer_keys = signer_keys_helper(&secrets[..]);
+        let session_id = SessionId::random(rng);
+
+        let long_msg = vec![0u8; MAX_MESSAGE_SIZE + 1];
+        assert!(CommitmentStage::new(session_id, &long_msg[..], secrets[0], signer_keys.clone(), 0, p_g, params, rng).is_err());
+
+        let (cosigners, comms): (Vec<_>, Vec<_>) = secrets.iter().enumerate()
+            .map(|(i, x_i)| CommitmentStage::new(session_id, msg, *x_i, signer_keys.clone(), i, p_g, params, rng).unwrap())
+            .unzip();
+        let (stage, _) = cosigners.into_iter().next().unwrap().commit(comms).unwrap();
+        let mut buf = vec![];
+        stage.write(&mut buf).unwrap();
+        assert!(RevealStage::<Bls12>::read(&mut &buf[..], params).is_ok());
+
+        // header, session id and position come before the message length
+        let msg_len_at = 2 + SESSION_ID_SIZE + 4;
+        let mut oversized = buf.clone();
+        oversized[msg_len_at..msg_len_at + 4].copy_from_slice(&u32::max_value().to_le_bytes());
+        assert!(RevealStage::<Bls12>::read(&mut &oversized[..], params).is_err());
+
+        let signers_at = msg_len_at + 4 + msg.len();
+        let mut oversized = buf.clone();
+        oversized[signers_at..signers_at + 4].copy_from_slice(&((MAX_SIGNERS + 1) as u32).to_le_bytes());
+        assert!(RevealStage::<Bls12>::read(&mut &oversized[..], params).is_err());
+    }
 }
@@ -0,0 +1,226 @@
+//! Wire format of the messages exchanged between cosigners.
+//!
+//! Every message starts with a version byte and a stage tag, followed by the
+//! session id and the position of the sender in the signer set.
+
+use jubjub::curve::{JubjubEngine, edwards::Point, PrimeOrder, Unknown};
+use pairing::{io, PrimeField, PrimeFieldRepr};
+use rand::Rng;
+use crate::commitment::Commitment;
+
+/// Version of the wire format.
+pub const WIRE_VERSION: u8 = 1;
+
+pub const SESSION_ID_SIZE: usize = 32;
+
+/// Maximum length of a message to be signed in a session.
+/// Lengths read from the wire are checked against it before allocating.
+pub const MAX_MESSAGE_SIZE: usize = 1 << 16;
+
+/// Maximum number of signers in a session.
+pub const MAX_SIGNERS: usize = 1 << 10;
+
+/// Tags which prefix every message and persisted stage.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub(crate) enum Tag {
+    Commitment = 0x00,
+    Reveal = 0x01,
+    Share = 0x02,
+    RevealStage = 0x81,
+    ShareStage = 0x82,
+}
+
+impl Tag {
+    pub(crate) fn write<W: io::Write>(self, writer: &mut W) -> io::Result<()> {
+        writer.write(&[WIRE_VERSION, self as u8])
+    }
+
+    /// Read the header and check it is the expected version and tag.
+    pub(crate) fn read_expected<R: io::Read>(self, reader: &mut R) -> io::Result<()> {
+        let mut header = [0u8; 2];
+        reader.read(&mut header)?;
+
+        if header[0] != WIRE_VERSION || header[1] != self as u8 {
+            return Err(io::Error::InvalidData)
+        }
+        Ok(())
+    }
+}
+
+/// An identifier of a signing session.
+/// It is bound into the commitments, so that messages from one session
+/// cannot be replayed into another one.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct SessionId(pub [u8; SESSION_ID_SIZE]);
+
+impl SessionId {
+    pub fn random<R: Rng>(rng: &mut R) -> Self {
+        let mut id = [0u8; SESSION_ID_SIZE];
+        rng.fill_bytes(&mut id[..]);
+        SessionId(id)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0[..]
+    }
+
+    pub fn read<R: io::Read>(reader: &mut R) -> io::Result<Self> {
+        let mut id = [0u8; SESSION_ID_SIZE];
+        reader.read(&mut id)?;
+        Ok(SessionId(id))
+    }
+
+    pub fn write<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write(&self.0)
+    }
+}
+
+pub(crate) fn read_pos<R: io::Read>(reader: &mut R) -> io::Result<usize> {
+    let mut buf = [0u8; 4];
+    reader.read(&mut buf)?;
+    Ok(u32::from_le_bytes(buf) as usize)
+}
+
+pub(crate) fn write_pos<W: io::Write>(pos: usize, writer: &mut W) -> io::Result<()> {
+    writer.write(&(pos as u32).to_le_bytes())
+}
+
+pub(crate) fn read_bytes<R: io::Read>(reader: &mut R) -> io::Result<Vec<u8>> {
+    let len = read_pos(reader)?;
+    if len > MAX_MESSAGE_SIZE {
+        return Err(io::Error::InvalidData)
+    }
+    let mut buf = vec![0u8; len];
+    reader.read(&mut buf[..])?;
+    Ok(buf)
+}
+
+pub(crate) fn write_bytes<W: io::Write>(buf: &[u8], writer: &mut W) -> io::Result<()> {
+    write_pos(buf.len(), writer)?;
+    writer.write(buf)
+}
+
+pub(crate) fn read_point<E: JubjubEngine, R: io::Read>(
+    reader: &mut R,
+    params: &E::Params
+) -> io::Result<Point<E, PrimeOrder>> {
+    Point::<E, Unknown>::read(reader, params)?
+        .as_prime_order(params)
+        .ok_or(io::Error::InvalidData)
+}
+
+pub(crate) fn read_fs<E: JubjubEngine, R: io::Read>(reader: &mut R) -> io::Result<E::Fs> {
+    let mut repr = <E::Fs as PrimeField>::Repr::default();
+    repr.read_le(reader)?;
+    E::Fs::from_repr(repr).map_err(|_| io::Error::NotInField)
+}
+
+/// Message broadcasted in the first round: a commitment to `R_i`.
+#[derive(Copy, Clone)]
+pub struct CommitmentMessage {
+    pub session_id: SessionId,
+    pub pos: usize,
+    pub commitment: Commitment,
+}
+
+impl CommitmentMessage {
+    pub fn read<R: io::Read>(reader: &mut R) -> io::Result<Self> {
+        Tag::Commitment.read_expected(reader)?;
+        let session_id = SessionId::read(reader)?;
+        let pos = read_pos(reader)?;
+        let commitment = Commitment::read(reader)?;
+
+        Ok(CommitmentMessage {
+            session_id,
+            pos,
+            commitment,
+        })
+    }
+
+    pub fn write<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        Tag::Commitment.write(writer)?;
+        self.session_id.write(writer)?;
+        write_pos(self.pos, writer)?;
+        self.commitment.write(writer)
+    }
+}
+
+/// Message broadcasted in the second round: the revealed `R_i`.
+#[allow(non_snake_case)]
+#[derive(Clone)]
+pub struct RevealMessage<E: JubjubEngine> {
+    pub session_id: SessionId,
+    pub pos: usize,
+    pub R: Point<E, PrimeOrder>,
+}
+
+impl<E: JubjubEngine> RevealMessage<E> {
+    #[allow(non_snake_case)]
+    pub fn read<R: io::Read>(reader: &mut R, params: &E::Params) -> io::Result<Self> {
+        Tag::Reveal.read_expected(reader)?;
+        let session_id = SessionId::read(reader)?;
+        let pos = read_pos(reader)?;
+        let R = read_point::<E, _>(reader, params)?;
+
+        Ok(RevealMessage {
+            session_id,
+            pos,
+            R,
+        })
+    }
+
+    pub fn write<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        Tag::Reveal.write(writer)?;
+        self.session_id.write(writer)?;
+        write_pos(self.pos, writer)?;
+        self.R.write(writer)
+    }
+}
+
+/// Message broadcasted in the last round: the signature share `s_i`.
+#[derive(Clone)]
+pub struct ShareMessage<E: JubjubEngine> {
+    pub session_id: SessionId,
+    pub pos: usize,
+    pub share: E::Fs,
+}
+
+impl<E: JubjubEngine> ShareMessage<E> {
+    pub fn read<R: io::Read>(reader: &mut R) -> io::Result<Self> {
+        Tag::Share.read_expected(reader)?;
+        let session_id = SessionId::read(reader)?;
+        let pos = read_pos(reader)?;
+        let share = read_fs::<E, _>(reader)?;
+
+        Ok(ShareMessage {
+            session_id,
+            pos,
+            share,
+        })
+    }
+
+    pub fn write<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        Tag::Share.write(writer)?;
+        self.session_id.write(writer)?;
+        write_pos(self.pos, writer)?;
+        self.share.into_repr().write_le(writer)
+    }
+}
+
+/// Check that the received messages belong to the session and
+/// come from every cosigner exactly once, ordered by position.
+pub(crate) fn check_messages<I>(session_id: &SessionId, n: usize, headers: I) -> io::Result<()>
+where
+    I: ExactSizeIterator<Item = (SessionId, usize)>,
+{
+    if headers.len() != n {
+        return Err(io::Error::InvalidData)
+    }
+
+    for (i, (id, pos)) in headers.enumerate() {
+        if id != *session_id || pos != i {
+            return Err(io::Error::InvalidData)
+        }
+    }
+    Ok(())
+}
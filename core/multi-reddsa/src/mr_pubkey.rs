@@ -39,7 +39,7 @@ impl<E: JubjubEngine> MRPubkey<E> {
 
         // 0 = h_G(-S . P_G + R + c . vk)
         self.0.mul(c, params).add(&r, params).add(
-            &params.generator(p_g).mul(s, params).negate().into(),
+            &params.mul_by_generator(p_g, s).negate().into(),
             params
         ).mul_by_cofactor(params).eq(&Point::zero())
     }
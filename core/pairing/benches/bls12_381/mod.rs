@@ -3,6 +3,7 @@ mod fq;
 mod fq12;
 mod fq2;
 mod fr;
+mod multiexp;
 
 use rand::{Rand, SeedableRng, XorShiftRng};
 
@@ -0,0 +1,49 @@
+use rand::{Rand, SeedableRng, XorShiftRng};
+
+use pairing::bls12_381::*;
+use pairing::{multiexp, CurveAffine, CurveProjective, PrimeField};
+
+// The numbers of public inputs of the confidential and the anonymous circuits.
+const CONFIDENTIAL_INPUTS: usize = 22;
+const ANONYMOUS_INPUTS: usize = 104;
+
+fn terms(n: usize) -> (Vec<G1Affine>, Vec<FrRepr>) {
+    let mut rng = XorShiftRng::from_seed([0x5dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+    let bases = (0..n).map(|_| G1::rand(&mut rng).into_affine()).collect();
+    let scalars = (0..n).map(|_| Fr::rand(&mut rng).into_repr()).collect();
+
+    (bases, scalars)
+}
+
+fn naive(bases: &[G1Affine], scalars: &[FrRepr]) -> G1 {
+    let mut acc = G1::zero();
+    for (base, scalar) in bases.iter().zip(scalars.iter()) {
+        acc.add_assign(&base.mul(*scalar));
+    }
+    acc
+}
+
+#[bench]
+fn bench_g1_naive_multiexp_22(b: &mut ::test::Bencher) {
+    let (bases, scalars) = terms(CONFIDENTIAL_INPUTS);
+    b.iter(|| naive(&bases, &scalars));
+}
+
+#[bench]
+fn bench_g1_multiexp_22(b: &mut ::test::Bencher) {
+    let (bases, scalars) = terms(CONFIDENTIAL_INPUTS);
+    b.iter(|| multiexp(&bases, &scalars));
+}
+
+#[bench]
+fn bench_g1_naive_multiexp_104(b: &mut ::test::Bencher) {
+    let (bases, scalars) = terms(ANONYMOUS_INPUTS);
+    b.iter(|| naive(&bases, &scalars));
+}
+
+#[bench]
+fn bench_g1_multiexp_104(b: &mut ::test::Bencher) {
+    let (bases, scalars) = terms(ANONYMOUS_INPUTS);
+    b.iter(|| multiexp(&bases, &scalars));
+}
@@ -0,0 +1,168 @@
+#[macro_use]
+extern crate criterion;
+extern crate rand;
+extern crate zerochain_pairing as pairing;
+
+use criterion::Criterion;
+use rand::{Rand, SeedableRng, XorShiftRng};
+
+use pairing::bls12_381::*;
+use pairing::{CurveAffine, Engine, Field};
+
+/// The absolute value of the curve parameter.
+const BLS_X: u64 = 0xd201000000010000;
+
+const SAMPLES: usize = 100;
+
+/// Miller loop outputs after the easy part of the final exponentiation,
+/// which are in the cyclotomic subgroup.
+fn cyclotomic_samples() -> Vec<Fq12> {
+    let mut rng = XorShiftRng::from_seed([0x5dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+    (0..SAMPLES)
+        .map(|_| {
+            let f = Fq12::rand(&mut rng);
+            let mut r = f;
+            r.conjugate();
+            r.mul_assign(&f.inverse().unwrap());
+            let mut tmp = r;
+            tmp.frobenius_map(2);
+            r.mul_assign(&tmp);
+            r
+        })
+        .collect()
+}
+
+fn bench_squaring(c: &mut Criterion) {
+    let v = cyclotomic_samples();
+    let mut count = 0;
+    c.bench_function("fq12_square", move |b| {
+        b.iter(|| {
+            let mut tmp = v[count];
+            tmp.square();
+            count = (count + 1) % SAMPLES;
+            tmp
+        })
+    });
+
+    let v = cyclotomic_samples();
+    let mut count = 0;
+    c.bench_function("fq12_cyclotomic_square", move |b| {
+        b.iter(|| {
+            let mut tmp = v[count];
+            tmp.cyclotomic_square();
+            count = (count + 1) % SAMPLES;
+            tmp
+        })
+    });
+}
+
+fn bench_exp_by_x(c: &mut Criterion) {
+    let v = cyclotomic_samples();
+    let mut count = 0;
+    c.bench_function("fq12_pow_by_x", move |b| {
+        b.iter(|| {
+            let tmp = v[count].pow(&[BLS_X]);
+            count = (count + 1) % SAMPLES;
+            tmp
+        })
+    });
+
+    let v = cyclotomic_samples();
+    let mut count = 0;
+    c.bench_function("fq12_cyclotomic_exp_by_x", move |b| {
+        b.iter(|| {
+            let tmp = v[count].cyclotomic_exp(&[BLS_X]);
+            count = (count + 1) % SAMPLES;
+            tmp
+        })
+    });
+}
+
+fn bench_final_exponentiation(c: &mut Criterion) {
+    let mut rng = XorShiftRng::from_seed([0x5dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+    let v: Vec<Fq12> = (0..SAMPLES)
+        .map(|_| {
+            (
+                G1Affine::from(G1::rand(&mut rng)).prepare(),
+                G2Affine::from(G2::rand(&mut rng)).prepare(),
+            )
+        })
+        .map(|(ref p, ref q)| Bls12::miller_loop(&[(p, q)]))
+        .collect();
+
+    let mut count = 0;
+    c.bench_function("bls12_final_exponentiation", move |b| {
+        b.iter(|| {
+            let tmp = Bls12::final_exponentiation(&v[count]);
+            count = (count + 1) % SAMPLES;
+            tmp
+        })
+    });
+}
+
+fn bench_pairing(c: &mut Criterion) {
+    let mut rng = XorShiftRng::from_seed([0x5dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+    let v: Vec<(G1, G2)> = (0..SAMPLES)
+        .map(|_| (G1::rand(&mut rng), G2::rand(&mut rng)))
+        .collect();
+
+    let mut count = 0;
+    c.bench_function("bls12_pairing", move |b| {
+        b.iter(|| {
+            let tmp = Bls12::pairing(v[count].0, v[count].1);
+            count = (count + 1) % SAMPLES;
+            tmp
+        })
+    });
+}
+
+fn bench_bn256(c: &mut Criterion) {
+    use pairing::bn256::{Bn256, Fq12, G1Affine, G2Affine, G1, G2};
+
+    let mut rng = XorShiftRng::from_seed([0x5dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+    let v: Vec<Fq12> = (0..SAMPLES)
+        .map(|_| {
+            (
+                G1Affine::from(G1::rand(&mut rng)).prepare(),
+                G2Affine::from(G2::rand(&mut rng)).prepare(),
+            )
+        })
+        .map(|(ref p, ref q)| Bn256::miller_loop(&[(p, q)]))
+        .collect();
+
+    let mut count = 0;
+    c.bench_function("bn256_final_exponentiation", move |b| {
+        b.iter(|| {
+            let tmp = Bn256::final_exponentiation(&v[count]);
+            count = (count + 1) % SAMPLES;
+            tmp
+        })
+    });
+
+    let v: Vec<(G1, G2)> = (0..SAMPLES)
+        .map(|_| (G1::rand(&mut rng), G2::rand(&mut rng)))
+        .collect();
+
+    let mut count = 0;
+    c.bench_function("bn256_pairing", move |b| {
+        b.iter(|| {
+            let tmp = Bn256::pairing(v[count].0, v[count].1);
+            count = (count + 1) % SAMPLES;
+            tmp
+        })
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_squaring,
+    bench_exp_by_x,
+    bench_final_exponentiation,
+    bench_pairing,
+    bench_bn256
+);
+criterion_main!(benches);
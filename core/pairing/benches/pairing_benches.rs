@@ -1,7 +1,8 @@
 #![feature(test)]
 
-extern crate pairing;
+extern crate zerochain_pairing as pairing;
 extern crate rand;
 extern crate test;
 
 mod bls12_381;
+mod bn256;
@@ -2,6 +2,7 @@ use super::fq::FROBENIUS_COEFF_FQ12_C1;
 use super::fq2::Fq2;
 use super::fq6::Fq6;
 use rand::{Rand, Rng};
+use std::vec::Vec;
 use {Field, RW};
 
 /// An element of Fq12, represented by c0 + c1 * w.
@@ -64,6 +65,266 @@ impl Fq12 {
         self.c0.mul_by_nonresidue();
         self.c0.add_assign(&aa);
     }
+
+    /// Squares an element of the cyclotomic subgroup, i.e. an element whose
+    /// `q^6 + 1`-th power is one, which holds after the easy part of the final
+    /// exponentiation. This is the squaring of Granger and Scott, built on
+    /// three squarings in Fq4, and gives a wrong result for other elements.
+    pub fn cyclotomic_square(&mut self) {
+        fn fq4_square(a: &Fq2, b: &Fq2) -> (Fq2, Fq2) {
+            let mut t0 = *a;
+            t0.square();
+            let mut t1 = *b;
+            t1.square();
+
+            let mut c0 = t1;
+            c0.mul_by_nonresidue();
+            c0.add_assign(&t0);
+
+            let mut c1 = *a;
+            c1.add_assign(b);
+            c1.square();
+            c1.sub_assign(&t0);
+            c1.sub_assign(&t1);
+
+            (c0, c1)
+        }
+
+        // 3 * t - 2 * z
+        fn sub_triple(t: &Fq2, z: &Fq2) -> Fq2 {
+            let mut res = *t;
+            res.sub_assign(z);
+            res.double();
+            res.add_assign(t);
+            res
+        }
+
+        // 3 * t + 2 * z
+        fn add_triple(t: &Fq2, z: &Fq2) -> Fq2 {
+            let mut res = *t;
+            res.add_assign(z);
+            res.double();
+            res.add_assign(t);
+            res
+        }
+
+        let (t0, t1) = fq4_square(&self.c0.c0, &self.c1.c1);
+        let (t2, t3) = fq4_square(&self.c1.c0, &self.c0.c2);
+        let (t4, t5) = fq4_square(&self.c0.c1, &self.c1.c2);
+        let mut t5_nr = t5;
+        t5_nr.mul_by_nonresidue();
+
+        self.c0.c0 = sub_triple(&t0, &self.c0.c0);
+        self.c1.c1 = add_triple(&t1, &self.c1.c1);
+        self.c0.c1 = sub_triple(&t2, &self.c0.c1);
+        self.c1.c2 = add_triple(&t3, &self.c1.c2);
+        self.c1.c0 = add_triple(&t5_nr, &self.c1.c0);
+        self.c0.c2 = sub_triple(&t4, &self.c0.c2);
+    }
+
+    /// Exponentiates an element of the cyclotomic subgroup.
+    ///
+    /// The squarings are done in the compressed form of Karabina and only
+    /// the powers for the set bits of the exponent get decompressed, sharing
+    /// one inversion, so this is fast for sparse exponents like the curve parameter.
+    pub fn cyclotomic_exp<S: AsRef<[u64]>>(&self, exp: S) -> Self {
+        let exp = exp.as_ref();
+        let num_bits = exp
+            .iter()
+            .rposition(|limb| *limb != 0)
+            .map(|i| i * 64 + 64 - exp[i].leading_zeros() as usize)
+            .unwrap_or(0);
+
+        let mut powers = vec![];
+        let mut acc = CompressedFq12::compress(self);
+
+        for i in 0..num_bits {
+            if i != 0 {
+                acc.square();
+            }
+            if (exp[i / 64] >> (i % 64)) & 1 == 1 {
+                powers.push(acc);
+            }
+        }
+
+        let mut res = Fq12::one();
+        for power in CompressedFq12::decompress_batch(&powers) {
+            res.mul_assign(&power);
+        }
+
+        res
+    }
+}
+
+/// An element of the cyclotomic subgroup without `c0.c0` and `c1.c1`,
+/// which can be recovered from the other coefficients.
+/// The field names follow the numbering `g0 + g1 * v + g2 * v^2 + (g3 + g4 * v + g5 * v^2) * w`.
+#[derive(Copy, Clone, Debug)]
+struct CompressedFq12 {
+    g1: Fq2,
+    g2: Fq2,
+    g3: Fq2,
+    g5: Fq2,
+}
+
+impl CompressedFq12 {
+    fn compress(f: &Fq12) -> Self {
+        CompressedFq12 {
+            g1: f.c0.c1,
+            g2: f.c0.c2,
+            g3: f.c1.c0,
+            g5: f.c1.c2,
+        }
+    }
+
+    /// Squares in the compressed form, which takes 6 squarings in Fq2.
+    fn square(&mut self) {
+        let mut g1g1 = self.g1;
+        g1g1.square();
+        let mut g2g2 = self.g2;
+        g2g2.square();
+        let mut g3g3 = self.g3;
+        g3g3.square();
+        let mut g5g5 = self.g5;
+        g5g5.square();
+
+        // 2 * g1 * g5
+        let mut g1g5 = self.g1;
+        g1g5.add_assign(&self.g5);
+        g1g5.square();
+        g1g5.sub_assign(&g1g1);
+        g1g5.sub_assign(&g5g5);
+
+        // 2 * g2 * g3
+        let mut g2g3 = self.g2;
+        g2g3.add_assign(&self.g3);
+        g2g3.square();
+        g2g3.sub_assign(&g2g2);
+        g2g3.sub_assign(&g3g3);
+
+        // g3' = 6 * nr * g1 * g5 + 2 * g3
+        let mut t = g1g5;
+        t.mul_by_nonresidue();
+        let mut g3 = t;
+        g3.add_assign(&self.g3);
+        g3.double();
+        g3.add_assign(&t);
+
+        // g2' = 3 * (g1^2 + nr * g5^2) - 2 * g2
+        let mut t = g5g5;
+        t.mul_by_nonresidue();
+        t.add_assign(&g1g1);
+        let mut g2 = t;
+        g2.sub_assign(&self.g2);
+        g2.double();
+        g2.add_assign(&t);
+
+        // g1' = 3 * (g3^2 + nr * g2^2) - 2 * g1
+        let mut t = g2g2;
+        t.mul_by_nonresidue();
+        t.add_assign(&g3g3);
+        let mut g1 = t;
+        g1.sub_assign(&self.g1);
+        g1.double();
+        g1.add_assign(&t);
+
+        // g5' = 6 * g2 * g3 + 2 * g5
+        let mut g5 = g2g3;
+        g5.add_assign(&self.g5);
+        g5.double();
+        g5.add_assign(&g2g3);
+
+        self.g1 = g1;
+        self.g2 = g2;
+        self.g3 = g3;
+        self.g5 = g5;
+    }
+
+    /// Recovers the full elements, batching the inversions needed for `g4`.
+    fn decompress_batch(v: &[Self]) -> Vec<Fq12> {
+        let mut nums = Vec::with_capacity(v.len());
+        let mut dens = Vec::with_capacity(v.len());
+
+        for c in v {
+            if c.g3.is_zero() {
+                // g4 = 2 * g1 * g5 / g2
+                let mut num = c.g1;
+                num.mul_assign(&c.g5);
+                num.double();
+                nums.push(num);
+
+                // If g2 is zero as well, then g1 = g5 = 0 and the element is one.
+                dens.push(if c.g2.is_zero() { Fq2::one() } else { c.g2 });
+            } else {
+                // g4 = (nr * g5^2 + 3 * g1^2 - 2 * g2) / (4 * g3)
+                let mut g1g1 = c.g1;
+                g1g1.square();
+                let mut num = g1g1;
+                num.sub_assign(&c.g2);
+                num.double();
+                num.add_assign(&g1g1);
+                let mut g5g5 = c.g5;
+                g5g5.square();
+                g5g5.mul_by_nonresidue();
+                num.add_assign(&g5g5);
+                nums.push(num);
+
+                let mut den = c.g3;
+                den.double();
+                den.double();
+                dens.push(den);
+            }
+        }
+
+        // Montgomery's trick: invert all the denominators with one inversion.
+        let mut prods = Vec::with_capacity(dens.len());
+        let mut acc = Fq2::one();
+        for den in dens.iter() {
+            prods.push(acc);
+            acc.mul_assign(den);
+        }
+        let mut inv = acc.inverse().expect("denominators are nonzero");
+        for (den, prod) in dens.iter_mut().zip(prods.into_iter()).rev() {
+            let mut den_inv = prod;
+            den_inv.mul_assign(&inv);
+            inv.mul_assign(den);
+            *den = den_inv;
+        }
+
+        v.iter()
+            .zip(nums.into_iter().zip(dens.into_iter()))
+            .map(|(c, (mut g4, den_inv))| {
+                g4.mul_assign(&den_inv);
+
+                // g0 = nr * (2 * g4^2 + g3 * g5 - 3 * g1 * g2) + 1
+                let mut g1g2 = c.g1;
+                g1g2.mul_assign(&c.g2);
+                let mut g0 = g4;
+                g0.square();
+                g0.sub_assign(&g1g2);
+                g0.double();
+                g0.sub_assign(&g1g2);
+                let mut g3g5 = c.g3;
+                g3g5.mul_assign(&c.g5);
+                g0.add_assign(&g3g5);
+                g0.mul_by_nonresidue();
+                g0.add_assign(&Fq2::one());
+
+                Fq12 {
+                    c0: Fq6 {
+                        c0: g0,
+                        c1: c.g1,
+                        c2: c.g2,
+                    },
+                    c1: Fq6 {
+                        c0: c.g3,
+                        c1: g4,
+                        c2: c.g5,
+                    },
+                }
+            })
+            .collect()
+    }
 }
 
 impl Field for Fq12 {
@@ -199,6 +460,57 @@ fn test_fq12_mul_by_014() {
     }
 }
 
+#[cfg(test)]
+fn random_cyclotomic<R: Rng>(rng: &mut R) -> Fq12 {
+    // f^((q^6 - 1) * (q^2 + 1)) is in the cyclotomic subgroup.
+    let f = Fq12::rand(rng);
+    let mut res = f;
+    res.conjugate();
+    res.mul_assign(&f.inverse().unwrap());
+    let mut tmp = res;
+    tmp.frobenius_map(2);
+    res.mul_assign(&tmp);
+    res
+}
+
+#[test]
+fn test_fq12_cyclotomic_square() {
+    let mut rng = XorShiftRng::from_seed([0x5dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+    let mut one = Fq12::one();
+    one.cyclotomic_square();
+    assert_eq!(one, Fq12::one());
+
+    for _ in 0..1000 {
+        let mut a = random_cyclotomic(&mut rng);
+        let mut b = a;
+
+        a.cyclotomic_square();
+        b.square();
+
+        assert_eq!(a, b);
+    }
+}
+
+#[test]
+fn test_fq12_cyclotomic_exp() {
+    let mut rng = XorShiftRng::from_seed([0x5dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+    let a = random_cyclotomic(&mut rng);
+    assert_eq!(a.cyclotomic_exp(&[0]), Fq12::one());
+    assert_eq!(a.cyclotomic_exp(&[1]), a);
+    assert_eq!(a.cyclotomic_exp(&[0, 1]), a.pow(&[0, 1]));
+    assert_eq!(Fq12::one().cyclotomic_exp(&[super::BLS_X]), Fq12::one());
+
+    for _ in 0..100 {
+        let a = random_cyclotomic(&mut rng);
+
+        for exp in [super::BLS_X, super::BLS_X >> 1, rng.gen()].iter() {
+            assert_eq!(a.cyclotomic_exp(&[*exp]), a.pow(&[*exp]));
+        }
+    }
+}
+
 #[test]
 fn fq12_field_tests() {
     use PrimeField;
@@ -0,0 +1,634 @@
+//! Hashing to G1 via the simplified SWU map onto an 11-isogenous curve.
+
+use byteorder::{BigEndian, ByteOrder};
+
+use super::{encode_to_curve, hash_to_curve, iso_map, map_to_curve_simple_swu, HashToCurve,
+            HashToField, MapToCurve};
+use bls12_381::{Fq, FqRepr, FrRepr, G1};
+use {CurveProjective, Field, PrimeField, PrimeFieldRepr};
+
+/// The effective cofactor of G1, `1 - x`.
+const H_EFF: u64 = 0xd201000000010001;
+
+impl HashToField for Fq {
+    const OKM_SIZE: usize = 64;
+
+    fn from_okm(okm: &[u8]) -> Self {
+        // Reduces a 512 bits integer as `hi * 2^256 + lo`.
+        fn read_256(bytes: &[u8]) -> Fq {
+            let mut repr = FqRepr::default();
+            for (limb, chunk) in repr.as_mut().iter_mut().zip(bytes.chunks(8).rev()) {
+                *limb = BigEndian::read_u64(chunk);
+            }
+            Fq::from_repr(repr).expect("less than the modulus")
+        }
+
+        let two_256 = Fq::from_repr(FqRepr([0, 0, 0, 0, 1, 0])).expect("less than the modulus");
+
+        let mut res = read_256(&okm[..32]);
+        res.mul_assign(&two_256);
+        res.add_assign(&read_256(&okm[32..]));
+        res
+    }
+
+    fn sgn0(&self) -> bool {
+        self.into_repr().is_odd()
+    }
+}
+
+impl MapToCurve for G1 {
+    type Field = Fq;
+
+    fn map_to_curve(u: &Fq) -> Self {
+        let (x, y) = map_to_curve_simple_swu(u, &SSWU_A, &SSWU_B, &SSWU_Z);
+
+        match iso_map(&x, &y, [&ISO11_XNUM, &ISO11_XDEN, &ISO11_YNUM, &ISO11_YDEN]) {
+            Some((x, y)) => G1 { x, y, z: Fq::one() },
+            None => G1::zero(),
+        }
+    }
+
+    fn clear_cofactor(&self) -> Self {
+        let mut res = *self;
+        res.mul_assign(FrRepr::from(H_EFF));
+        res
+    }
+}
+
+impl HashToCurve for G1 {
+    fn hash_to_curve(msg: &[u8], dst: &[u8]) -> Self {
+        hash_to_curve(msg, dst)
+    }
+
+    fn encode_to_curve(msg: &[u8], dst: &[u8]) -> Self {
+        encode_to_curve(msg, dst)
+    }
+}
+
+/// Coefficients of the 11-isogeny x map's numerator
+const ISO11_XNUM: [Fq; 12] = [
+    Fq(FqRepr([
+        0x4d18b6f3af00131c,
+        0x19fa219793fee28c,
+        0x3f2885f1467f19ae,
+        0x23dcea34f2ffb304,
+        0xd15b58d2ffc00054,
+        0x913be200a20bef4,
+    ])),
+    Fq(FqRepr([
+        0x898985385cdbbd8b,
+        0x3c79e43cc7d966aa,
+        0x1597e193f4cd233a,
+        0x8637ef1e4d6623ad,
+        0x11b22deed20d827b,
+        0x7097bc5998784ad,
+    ])),
+    Fq(FqRepr([
+        0xa542583a480b664b,
+        0xfc7169c026e568c6,
+        0x5ba2ef314ed8b5a6,
+        0x5b5491c05102f0e7,
+        0xdf6e99707d2a0079,
+        0x784151ed7605524,
+    ])),
+    Fq(FqRepr([
+        0x494e212870f72741,
+        0xab9be52fbda43021,
+        0x26f5577994e34c3d,
+        0x49dfee82aefbd60,
+        0x65dadd7828505289,
+        0xe93d431ea011aeb,
+    ])),
+    Fq(FqRepr([
+        0x90ee774bd6a74d45,
+        0x7ada1c8a41bfb185,
+        0xf1a8953b325f464,
+        0x104c24211be4805c,
+        0x169139d319ea7a8f,
+        0x9f20ead8e532bf6,
+    ])),
+    Fq(FqRepr([
+        0x6ddd93e2f43626b7,
+        0xa5482c9aa1ccd7bd,
+        0x143245631883f4bd,
+        0x2e0a94ccf77ec0db,
+        0xb0282d480e56489f,
+        0x18f4bfcbb4368929,
+    ])),
+    Fq(FqRepr([
+        0x23c5f0c953402dfd,
+        0x7a43ff6958ce4fe9,
+        0x2c390d3d2da5df63,
+        0xd0df5c98e1f9d70f,
+        0xffd89869a572b297,
+        0x1277ffc72f25e8fe,
+    ])),
+    Fq(FqRepr([
+        0x79f4f0490f06a8a6,
+        0x85f894a88030fd81,
+        0x12da3054b18b6410,
+        0xe2a57f6505880d65,
+        0xbba074f260e400f1,
+        0x8b76279f621d028,
+    ])),
+    Fq(FqRepr([
+        0xe67245ba78d5b00b,
+        0x8456ba9a1f186475,
+        0x7888bff6e6b33bb4,
+        0xe21585b9a30f86cb,
+        0x5a69cdcef55feee,
+        0x9e699dd9adfa5ac,
+    ])),
+    Fq(FqRepr([
+        0xde5c357bff57107,
+        0xa0db4ae6b1a10b2,
+        0xe256bb67b3b3cd8d,
+        0x8ad456574e9db24f,
+        0x443915f50fd4179,
+        0x98c4bf7de8b6375,
+    ])),
+    Fq(FqRepr([
+        0xe6b0617e7dd929c7,
+        0xfe6e37d442537375,
+        0x1dafdeda137a489e,
+        0xe4efd1ad3f767ceb,
+        0x4a51d8667f0fe1cf,
+        0x54fdf4bbf1d821c,
+    ])),
+    Fq(FqRepr([
+        0x72db2a50658d767b,
+        0x8abf91faa257b3d5,
+        0xe969d6833764ab47,
+        0x464170142a1009eb,
+        0xb14f01aadb30be2f,
+        0x18ae6a856f40715d,
+    ])),
+];
+
+/// Coefficients of the 11-isogeny x map's denominator
+const ISO11_XDEN: [Fq; 11] = [
+    Fq(FqRepr([
+        0xb962a077fdb0f945,
+        0xa6a9740fefda13a0,
+        0xc14d568c3ed6c544,
+        0xb43fc37b908b133e,
+        0x9c0b3ac929599016,
+        0x165aa6c93ad115f,
+    ])),
+    Fq(FqRepr([
+        0x23279a3ba506c1d9,
+        0x92cfca0a9465176a,
+        0x3b294ab13755f0ff,
+        0x116dda1c5070ae93,
+        0xed4530924cec2045,
+        0x83383d6ed81f1ce,
+    ])),
+    Fq(FqRepr([
+        0x9885c2a6449fecfc,
+        0x4a2b54ccd37733f0,
+        0x17da9ffd8738c142,
+        0xa0fba72732b3fafd,
+        0xff364f36e54b6812,
+        0xf29c13c660523e2,
+    ])),
+    Fq(FqRepr([
+        0xe349cc118278f041,
+        0xd487228f2f3204fb,
+        0xc9d325849ade5150,
+        0x43a92bd69c15c2df,
+        0x1c2c7844bc417be4,
+        0x12025184f407440c,
+    ])),
+    Fq(FqRepr([
+        0x587f65ae6acb057b,
+        0x1444ef325140201f,
+        0xfbf995e71270da49,
+        0xccda066072436a42,
+        0x7408904f0f186bb2,
+        0x13b93c63edf6c015,
+    ])),
+    Fq(FqRepr([
+        0xfb918622cd141920,
+        0x4a4c64423ecaddb4,
+        0xbeb232927f7fb26,
+        0x30f94df6f83a3dc2,
+        0xaeedd424d780f388,
+        0x6cc402dd594bbeb,
+    ])),
+    Fq(FqRepr([
+        0xd41f761151b23f8f,
+        0x32a92465435719b3,
+        0x64f436e888c62cb9,
+        0xdf70a9a1f757c6e4,
+        0x6933a38d5b594c81,
+        0xc6f7f7237b46606,
+    ])),
+    Fq(FqRepr([
+        0x693c08747876c8f7,
+        0x22c9850bf9cf80f0,
+        0x8e9071dab950c124,
+        0x89bc62d61c7baf23,
+        0xbc6be2d8dad57c23,
+        0x17916987aa14a122,
+    ])),
+    Fq(FqRepr([
+        0x1be3ff439c1316fd,
+        0x9965243a7571dfa7,
+        0xc7f7f62962f5cd81,
+        0x32c6aa9af394361c,
+        0xbbc2ee18e1c227f4,
+        0xc102cbac531bb34,
+    ])),
+    Fq(FqRepr([
+        0x997614c97bacbf07,
+        0x61f86372b99192c0,
+        0x5b8c95fc14353fc3,
+        0xca2b066c2a87492f,
+        0x16178f5bbf698711,
+        0x12a6dcd7f0f4e0e8,
+    ])),
+    Fq(FqRepr([
+        0x760900000002fffd,
+        0xebf4000bc40c0002,
+        0x5f48985753c758ba,
+        0x77ce585370525745,
+        0x5c071a97a256ec6d,
+        0x15f65ec3fa80e493,
+    ])),
+];
+
+/// Coefficients of the 11-isogeny y map's numerator
+const ISO11_YNUM: [Fq; 16] = [
+    Fq(FqRepr([
+        0x2b567ff3e2837267,
+        0x1d4d9e57b958a767,
+        0xce028fea04bd7373,
+        0xcc31a30a0b6cd3df,
+        0x7d7b18a682692693,
+        0xd300744d42a0310,
+    ])),
+    Fq(FqRepr([
+        0x99c2555fa542493f,
+        0xfe7f53cc4874f878,
+        0x5df0608b8f97608a,
+        0x14e03832052b49c8,
+        0x706326a6957dd5a4,
+        0xa8dadd9c2414555,
+    ])),
+    Fq(FqRepr([
+        0x13d942922a5cf63a,
+        0x357e33e36e261e7d,
+        0xcf05a27c8456088d,
+        0xbd1de7ba50f0,
+        0x83d0c7532f8c1fde,
+        0x13f70bf38bbf2905,
+    ])),
+    Fq(FqRepr([
+        0x5c57fd95bfafbdbb,
+        0x28a359a65e541707,
+        0x3983ceb4f6360b6d,
+        0xafe19ff6f97e6d53,
+        0xb3468f4550192bf7,
+        0xbb6cde49d8ba257,
+    ])),
+    Fq(FqRepr([
+        0x590b62c7ff8a513f,
+        0x314b4ce372cacefd,
+        0x6bef32ce94b8a800,
+        0x6ddf84a095713d5f,
+        0x64eace4cb0982191,
+        0x386213c651b888d,
+    ])),
+    Fq(FqRepr([
+        0xa5310a31111bbcdd,
+        0xa14ac0f5da148982,
+        0xf9ad9cc95423d2e9,
+        0xaa6ec095283ee4a7,
+        0xcf5b1f022e1c9107,
+        0x1fddf5aed881793,
+    ])),
+    Fq(FqRepr([
+        0x65a572b0d7a7d950,
+        0xe25c2d8183473a19,
+        0xc2fcebe7cb877dbd,
+        0x5b2d36c769a89b0,
+        0xba12961be86e9efb,
+        0x7eb1b29c1dfde1f,
+    ])),
+    Fq(FqRepr([
+        0x93e09572f7c4cd24,
+        0x364e929076795091,
+        0x8569467e68af51b5,
+        0xa47da89439f5340f,
+        0xf4fa918082e44d64,
+        0xad52ba3e6695a79,
+    ])),
+    Fq(FqRepr([
+        0x911429844e0d5f54,
+        0xd03f51a3516bb233,
+        0x3d587e5640536e66,
+        0xfa86d2a3a9a73482,
+        0xa90ed5adf1ed5537,
+        0x149c9c326a5e7393,
+    ])),
+    Fq(FqRepr([
+        0x462bbeb03c12921a,
+        0xdc9af5fa0a274a17,
+        0x9a558ebde836ebed,
+        0x649ef8f11a4fae46,
+        0x8100e1652b3cdc62,
+        0x1862bd62c291dacb,
+    ])),
+    Fq(FqRepr([
+        0x5c9b8ca89f12c26,
+        0x194160fa9b9ac4f,
+        0x6a643d5a6879fa2c,
+        0x14665bdd8846e19d,
+        0xbb1d0d53af3ff6bf,
+        0x12c7e1c3b28962e5,
+    ])),
+    Fq(FqRepr([
+        0xb55ebf900b8a3e17,
+        0xfedc77ec1a9201c4,
+        0x1f07db10ea1a4df4,
+        0xdfbd15dc41a594d,
+        0x389547f2334a5391,
+        0x2419f98165871a4,
+    ])),
+    Fq(FqRepr([
+        0xb416af000745fc20,
+        0x8e563e9d1ea6d0f5,
+        0x7c763e17763a0652,
+        0x1458ef0159ebbef,
+        0x8346fe421f96bb13,
+        0xd2d7b829ce324d2,
+    ])),
+    Fq(FqRepr([
+        0x93096bb538d64615,
+        0x6f2a2619951d823a,
+        0x8f66b3ea59514fa4,
+        0xf563e63704f7092f,
+        0x724b136c4cf2d9fa,
+        0x46959cfcfd0bf49,
+    ])),
+    Fq(FqRepr([
+        0xea748d4b6e405346,
+        0x91e9079c2c02d58f,
+        0x41064965946d9b59,
+        0xa06731f1d2bbe1ee,
+        0x7f897e267a33f1b,
+        0x1017290919210e5f,
+    ])),
+    Fq(FqRepr([
+        0x872aa6c17d985097,
+        0xeecc53161264562a,
+        0x7afe37afff55002,
+        0x54759078e5be6838,
+        0xc4b92d15db8acca8,
+        0x106d87d1b51d13b9,
+    ])),
+];
+
+/// Coefficients of the 11-isogeny y map's denominator
+const ISO11_YDEN: [Fq; 16] = [
+    Fq(FqRepr([
+        0xeb6c359d47e52b1c,
+        0x18ef5f8a10634d60,
+        0xddfa71a0889d5b7e,
+        0x723e71dcc5fc1323,
+        0x52f45700b70d5c69,
+        0xa8b981ee47691f1,
+    ])),
+    Fq(FqRepr([
+        0x616a3c4f5535b9fb,
+        0x6f5f037395dbd911,
+        0xf25f4cc5e35c65da,
+        0x3e50dffea3c62658,
+        0x6a33dca523560776,
+        0xfadeff77b6bfe3e,
+    ])),
+    Fq(FqRepr([
+        0x2be9b66df470059c,
+        0x24a2c159a3d36742,
+        0x115dbe7ad10c2a37,
+        0xb6634a652ee5884d,
+        0x4fe8bb2b8d81af4,
+        0x1c2a7a256fe9c41,
+    ])),
+    Fq(FqRepr([
+        0xf27bf8ef3b75a386,
+        0x898b367476c9073f,
+        0x24482e6b8c2f4e5f,
+        0xc8e0bbd6fe110806,
+        0x59b0c17f7631448a,
+        0x11037cd58b3dbfbd,
+    ])),
+    Fq(FqRepr([
+        0x31c7912ea267eec6,
+        0x1dbf6f1c5fcdb700,
+        0xd30d4fe3ba86fdb1,
+        0x3cae528fbee9a2a4,
+        0xb1cce69b6aa9ad9a,
+        0x44393bb632d94fb,
+    ])),
+    Fq(FqRepr([
+        0xc66ef6efeeb5c7e8,
+        0x9824c289dd72bb55,
+        0x71b1a4d2f119981d,
+        0x104fc1aafb0919cc,
+        0xe49df01d942a628,
+        0x96c3a09773272d4,
+    ])),
+    Fq(FqRepr([
+        0x9abc11eb5fadeff4,
+        0x32dca50a885728f0,
+        0xfb1fa3721569734c,
+        0xc4b76271ea6506b3,
+        0xd466a75599ce728e,
+        0xc81d4645f4cb6ed,
+    ])),
+    Fq(FqRepr([
+        0x4199f10e5b8be45b,
+        0xda64e495b1e87930,
+        0xcb353efe9b33e4ff,
+        0x9e9efb24aa6424c6,
+        0xf08d33680a237465,
+        0xd3378023e4c7406,
+    ])),
+    Fq(FqRepr([
+        0x7eb4ae92ec74d3a5,
+        0xc341b4aa9fac3497,
+        0x5be603899e907687,
+        0x3bfd9cca75cbdeb,
+        0x564c2935a96bfa93,
+        0xef3c33371e2fdb5,
+    ])),
+    Fq(FqRepr([
+        0x7ee91fd449f6ac2e,
+        0xe5d5bd5cb9357a30,
+        0x773a8ca5196b1380,
+        0xd0fda172174ed023,
+        0x6cb95e0fa776aead,
+        0xd22d5a40cec7cff,
+    ])),
+    Fq(FqRepr([
+        0xf727e09285fd8519,
+        0xdc9d55a83017897b,
+        0x7549d8bd057894ae,
+        0x178419613d90d8f8,
+        0xfce95ebdeb5b490a,
+        0x467ffaef23fc49e,
+    ])),
+    Fq(FqRepr([
+        0xc1769e6a7c385f1b,
+        0x79bc930deac01c03,
+        0x5461c75a23ede3b5,
+        0x6e20829e5c230c45,
+        0x828e0f1e772a53cd,
+        0x116aefa749127bff,
+    ])),
+    Fq(FqRepr([
+        0x101c10bf2744c10a,
+        0xbbf18d053a6a3154,
+        0xa0ecf39ef026f602,
+        0xfc009d4996dc5153,
+        0xb9000209d5bd08d3,
+        0x189e5fe4470cd73c,
+    ])),
+    Fq(FqRepr([
+        0x7ebd546ca1575ed2,
+        0xe47d5a981d081b55,
+        0x57b2b625b6d4ca21,
+        0xb0a1ba04228520cc,
+        0x98738983c2107ff3,
+        0x13dddbc4799d81d6,
+    ])),
+    Fq(FqRepr([
+        0x9319f2e39834935,
+        0x39e952cbdb05c21,
+        0x55ba77a9a2f76493,
+        0xfd04e3dfc6086467,
+        0xfb95832e7d78742e,
+        0xef9c24eccaf5e0e,
+    ])),
+    Fq(FqRepr([
+        0x760900000002fffd,
+        0xebf4000bc40c0002,
+        0x5f48985753c758ba,
+        0x77ce585370525745,
+        0x5c071a97a256ec6d,
+        0x15f65ec3fa80e493,
+    ])),
+];
+
+/// `A'` of the curve `E'` isogenous to G1
+const SSWU_A: Fq = Fq(FqRepr([
+    0x2f65aa0e9af5aa51,
+    0x86464c2d1e8416c3,
+    0xb85ce591b7bd31e2,
+    0x27e11c91b5f24e7c,
+    0x28376eda6bfc1835,
+    0x155455c3e5071d85,
+]));
+
+/// `B'` of the curve `E'` isogenous to G1
+const SSWU_B: Fq = Fq(FqRepr([
+    0xfb996971fe22a1e0,
+    0x9aa93eb35b742d6f,
+    0x8c476013de99c5c4,
+    0x873e27c3a221e571,
+    0xca72b5e45a52d888,
+    0x6824061418a386b,
+]));
+
+/// `Z = 11`, the non-square of the simplified SWU map
+const SSWU_Z: Fq = Fq(FqRepr([
+    0x886c00000023ffdc,
+    0xf70008d3090001d,
+    0x77672417ed5828c3,
+    0x9dac23e943dc1740,
+    0x50553f1b9c131521,
+    0x78c712fbe0ab6e8,
+]));
+
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::messages;
+    use super::*;
+    use hex;
+    use CurveAffine;
+
+    fn check_vectors(hash: fn(&[u8], &[u8]) -> G1, dst: &[u8], expected: &[[&str; 2]]) {
+        for (msg, expected) in messages().iter().zip(expected.iter()) {
+            let p = hash(msg, dst).into_affine();
+
+            assert_eq!(
+                hex::encode(p.into_uncompressed().as_ref()),
+                expected.concat()
+            );
+        }
+    }
+
+    #[test]
+    fn test_hash_to_curve_g1() {
+        // RFC 9380, J.9.1
+        check_vectors(
+            G1::hash_to_curve,
+            b"QUUX-V01-CS02-with-BLS12381G1_XMD:SHA-256_SSWU_RO_",
+            &[
+                [
+                    "052926add2207b76ca4fa57a8734416c8dc95e24501772c814278700eed6d1e4e8cf62d9c09db0fac349612b759e79a1",
+                    "08ba738453bfed09cb546dbb0783dbb3a5f1f566ed67bb6be0e8c67e2e81a4cc68ee29813bb7994998f3eae0c9c6a265",
+                ],
+                [
+                    "03567bc5ef9c690c2ab2ecdf6a96ef1c139cc0b2f284dca0a9a7943388a49a3aee664ba5379a7655d3c68900be2f6903",
+                    "0b9c15f3fe6e5cf4211f346271d7b01c8f3b28be689c8429c85b67af215533311f0b8dfaaa154fa6b88176c229f2885d",
+                ],
+                [
+                    "11e0b079dea29a68f0383ee94fed1b940995272407e3bb916bbf268c263ddd57a6a27200a784cbc248e84f357ce82d98",
+                    "03a87ae2caf14e8ee52e51fa2ed8eefe80f02457004ba4d486d6aa1f517c0889501dc7413753f9599b099ebcbbd2d709",
+                ],
+                [
+                    "15f68eaa693b95ccb85215dc65fa81038d69629f70aeee0d0f677cf22285e7bf58d7cb86eefe8f2e9bc3f8cb84fac488",
+                    "1807a1d50c29f430b8cafc4f8638dfeeadf51211e1602a5f184443076715f91bb90a48ba1e370edce6ae1062f5e6dd38",
+                ],
+                [
+                    "082aabae8b7dedb0e78aeb619ad3bfd9277a2f77ba7fad20ef6aabdc6c31d19ba5a6d12283553294c1825c4b3ca2dcfe",
+                    "05b84ae5a942248eea39e1d91030458c40153f3b654ab7872d779ad1e942856a20c438e8d99bc8abfbf74729ce1f7ac8",
+                ],
+            ],
+        );
+    }
+
+    #[test]
+    fn test_encode_to_curve_g1() {
+        // RFC 9380, J.9.2
+        check_vectors(
+            G1::encode_to_curve,
+            b"QUUX-V01-CS02-with-BLS12381G1_XMD:SHA-256_SSWU_NU_",
+            &[
+                [
+                    "184bb665c37ff561a89ec2122dd343f20e0f4cbcaec84e3c3052ea81d1834e192c426074b02ed3dca4e7676ce4ce48ba",
+                    "04407b8d35af4dacc809927071fc0405218f1401a6d15af775810e4e460064bcc9468beeba82fdc751be70476c888bf3",
+                ],
+                [
+                    "009769f3ab59bfd551d53a5f846b9984c59b97d6842b20a2c565baa167945e3d026a3755b6345df8ec7e6acb6868ae6d",
+                    "1532c00cf61aa3d0ce3e5aa20c3b531a2abd2c770a790a2613818303c6b830ffc0ecf6c357af3317b9575c567f11cd2c",
+                ],
+                [
+                    "1974dbb8e6b5d20b84df7e625e2fbfecb2cdb5f77d5eae5fb2955e5ce7313cae8364bc2fff520a6c25619739c6bdcb6a",
+                    "15f9897e11c6441eaa676de141c8d83c37aab8667173cbe1dfd6de74d11861b961dccebcd9d289ac633455dfcc7013a3",
+                ],
+                [
+                    "0a7a047c4a8397b3446450642c2ac64d7239b61872c9ae7a59707a8f4f950f101e766afe58223b3bff3a19a7f754027c",
+                    "1383aebba1e4327ccff7cf9912bda0dbc77de048b71ef8c8a81111d71dc33c5e3aa6edee9cf6f5fe525d50cc50b77cc9",
+                ],
+                [
+                    "0e7a16a975904f131682edbb03d9560d3e48214c9986bd50417a77108d13dc957500edf96462a3d01e62dc6cd468ef11",
+                    "0ae89e677711d05c30a48d6d75e76ca9fb70fe06c6dd6ff988683d89ccde29ac7d46c53bb97a59b1901abf1db66052db",
+                ],
+            ],
+        );
+    }
+}
@@ -0,0 +1,531 @@
+//! Hashing to G2 via the simplified SWU map onto a 3-isogenous curve.
+
+use super::{encode_to_curve, hash_to_curve, iso_map, map_to_curve_simple_swu, HashToCurve,
+            HashToField, MapToCurve};
+use bls12_381::{Fq, Fq2, FqRepr, FrRepr, G2, BLS_X, BLS_X_IS_NEGATIVE};
+use {CurveProjective, Field};
+
+/// `1 / (u + 1)^((p - 1) / 3)`
+const PSI_COEFF_X: Fq2 = Fq2 {
+    c0: Fq(FqRepr([0x0, 0x0, 0x0, 0x0, 0x0, 0x0])),
+    c1: Fq(FqRepr([
+        0x890dc9e4867545c3,
+        0x2af322533285a5d5,
+        0x50880866309b7e2c,
+        0xa20d1b8c7e881024,
+        0x14e4f04fe2db9068,
+        0x14e56d3f1564853a,
+    ])),
+};
+
+/// `1 / (u + 1)^((p - 1) / 2)`
+const PSI_COEFF_Y: Fq2 = Fq2 {
+    c0: Fq(FqRepr([
+        0x3e2f585da55c9ad1,
+        0x4294213d86c18183,
+        0x382844c88b623732,
+        0x92ad2afd19103e18,
+        0x1d794e4fac7cf0b9,
+        0xbd592fc7d825ec8,
+    ])),
+    c1: Fq(FqRepr([
+        0x7bcfa7a25aa30fda,
+        0xdc17dec12a927e7c,
+        0x2f088dd86b4ebef1,
+        0xd1ca2087da74d4a7,
+        0x2da2596696cebc1d,
+        0xe2b7eedbbfd87d2,
+    ])),
+};
+
+/// `1 / 2^((p - 1) / 3)`
+const PSI2_COEFF_X: Fq2 = Fq2 {
+    c0: Fq(FqRepr([
+        0xcd03c9e48671f071,
+        0x5dab22461fcda5d2,
+        0x587042afd3851b95,
+        0x8eb60ebe01bacb9e,
+        0x3f97d6e83d050d2,
+        0x18f0206554638741,
+    ])),
+    c1: Fq(FqRepr([0x0, 0x0, 0x0, 0x0, 0x0, 0x0])),
+};
+
+impl HashToField for Fq2 {
+    const OKM_SIZE: usize = 128;
+
+    fn from_okm(okm: &[u8]) -> Self {
+        Fq2 {
+            c0: Fq::from_okm(&okm[..64]),
+            c1: Fq::from_okm(&okm[64..]),
+        }
+    }
+
+    fn sgn0(&self) -> bool {
+        self.c0.sgn0() || (self.c0.is_zero() && self.c1.sgn0())
+    }
+}
+
+/// The untwist-Frobenius-twist endomorphism.
+fn psi(p: &G2) -> G2 {
+    let mut x = p.x;
+    x.frobenius_map(1);
+    x.mul_assign(&PSI_COEFF_X);
+
+    let mut y = p.y;
+    y.frobenius_map(1);
+    y.mul_assign(&PSI_COEFF_Y);
+
+    let mut z = p.z;
+    z.frobenius_map(1);
+
+    G2 { x, y, z }
+}
+
+/// `psi` applied twice.
+fn psi2(p: &G2) -> G2 {
+    let mut x = p.x;
+    x.mul_assign(&PSI2_COEFF_X);
+
+    let mut y = p.y;
+    y.negate();
+
+    G2 { x, y, z: p.z }
+}
+
+/// Multiplies the point by the BLS parameter `x`.
+fn mul_by_x(p: &G2) -> G2 {
+    let mut res = *p;
+    res.mul_assign(FrRepr::from(BLS_X));
+    if BLS_X_IS_NEGATIVE {
+        res.negate();
+    }
+    res
+}
+
+impl MapToCurve for G2 {
+    type Field = Fq2;
+
+    fn map_to_curve(u: &Fq2) -> Self {
+        let (x, y) = map_to_curve_simple_swu(u, &SSWU_A, &SSWU_B, &SSWU_Z);
+
+        match iso_map(&x, &y, [&ISO3_XNUM, &ISO3_XDEN, &ISO3_YNUM, &ISO3_YDEN]) {
+            Some((x, y)) => G2 { x, y, z: Fq2::one() },
+            None => G2::zero(),
+        }
+    }
+
+    /// Clears the cofactor by Budroni-Pintore (https://ia.cr/2017/419),
+    /// `psi^2(2P) + [x^2 - x - 1]P + [x - 1]psi(P)`.
+    fn clear_cofactor(&self) -> Self {
+        let t1 = mul_by_x(self);
+        let t2 = psi(self);
+
+        let mut t3 = *self;
+        t3.double();
+        let mut res = psi2(&t3);
+
+        let mut t1_t2 = t1;
+        t1_t2.add_assign(&t2);
+        res.add_assign(&mul_by_x(&t1_t2));
+        res.sub_assign(&t1);
+        res.sub_assign(&t2);
+        res.sub_assign(self);
+        res
+    }
+}
+
+impl HashToCurve for G2 {
+    fn hash_to_curve(msg: &[u8], dst: &[u8]) -> Self {
+        hash_to_curve(msg, dst)
+    }
+
+    fn encode_to_curve(msg: &[u8], dst: &[u8]) -> Self {
+        encode_to_curve(msg, dst)
+    }
+}
+
+/// Coefficients of the 3-isogeny x map's numerator
+const ISO3_XNUM: [Fq2; 4] = [
+    Fq2 {
+        c0: Fq(FqRepr([
+            0x47f671c71ce05e62,
+            0x6dd57071206393e,
+            0x7c80cd2af3fd71a2,
+            0x48103ea9e6cd062,
+            0xc54516acc8d037f6,
+            0x13808f550920ea41,
+        ])),
+        c1: Fq(FqRepr([
+            0x47f671c71ce05e62,
+            0x6dd57071206393e,
+            0x7c80cd2af3fd71a2,
+            0x48103ea9e6cd062,
+            0xc54516acc8d037f6,
+            0x13808f550920ea41,
+        ])),
+    },
+    Fq2 {
+        c0: Fq(FqRepr([0x0, 0x0, 0x0, 0x0, 0x0, 0x0])),
+        c1: Fq(FqRepr([
+            0x5fe55555554c71d0,
+            0x873fffdd236aaaa3,
+            0x6a6b4619b26ef918,
+            0x21c2888408874945,
+            0x2836cda7028cabc5,
+            0xac73310a7fd5abd,
+        ])),
+    },
+    Fq2 {
+        c0: Fq(FqRepr([
+            0xa0c5555555971c3,
+            0xdb0c00101f9eaaae,
+            0xb1fb2f941d797997,
+            0xd3960742ef416e1c,
+            0xb70040e2c20556f4,
+            0x149d7861e581393b,
+        ])),
+        c1: Fq(FqRepr([
+            0xaff2aaaaaaa638e8,
+            0x439fffee91b55551,
+            0xb535a30cd9377c8c,
+            0x90e144420443a4a2,
+            0x941b66d3814655e2,
+            0x563998853fead5e,
+        ])),
+    },
+    Fq2 {
+        c0: Fq(FqRepr([
+            0x40aac71c71c725ed,
+            0x190955557a84e38e,
+            0xd817050a8f41abc3,
+            0xd86485d4c87f6fb1,
+            0x696eb479f885d059,
+            0x198e1a74328002d2,
+        ])),
+        c1: Fq(FqRepr([0x0, 0x0, 0x0, 0x0, 0x0, 0x0])),
+    },
+];
+
+/// Coefficients of the 3-isogeny x map's denominator
+const ISO3_XDEN: [Fq2; 3] = [
+    Fq2 {
+        c0: Fq(FqRepr([0x0, 0x0, 0x0, 0x0, 0x0, 0x0])),
+        c1: Fq(FqRepr([
+            0x1f3affffff13ab97,
+            0xf25bfc611da3ff3e,
+            0xca3757cb3819b208,
+            0x3e6427366f8cec18,
+            0x3977bc86095b089,
+            0x4f69db13f39a952,
+        ])),
+    },
+    Fq2 {
+        c0: Fq(FqRepr([
+            0x447600000027552e,
+            0xdcb8009a43480020,
+            0x6f7ee9ce4a6e8b59,
+            0xb10330b7c0a95bc6,
+            0x6140b1fcfb1e54b7,
+            0x381be097f0bb4e1,
+        ])),
+        c1: Fq(FqRepr([
+            0x7588ffffffd8557d,
+            0x41f3ff646e0bffdf,
+            0xf7b1e8d2ac426aca,
+            0xb3741acd32dbb6f8,
+            0xe9daf5b9482d581f,
+            0x167f53e0ba7431b8,
+        ])),
+    },
+    Fq2 {
+        c0: Fq(FqRepr([
+            0x760900000002fffd,
+            0xebf4000bc40c0002,
+            0x5f48985753c758ba,
+            0x77ce585370525745,
+            0x5c071a97a256ec6d,
+            0x15f65ec3fa80e493,
+        ])),
+        c1: Fq(FqRepr([0x0, 0x0, 0x0, 0x0, 0x0, 0x0])),
+    },
+];
+
+/// Coefficients of the 3-isogeny y map's numerator
+const ISO3_YNUM: [Fq2; 4] = [
+    Fq2 {
+        c0: Fq(FqRepr([
+            0x96d8f684bdfc77be,
+            0xb530e4f43b66d0e2,
+            0x184a88ff379652fd,
+            0x57cb23ecfae804e1,
+            0xfd2e39eada3eba9,
+            0x8c8055e31c5d5c3,
+        ])),
+        c1: Fq(FqRepr([
+            0x96d8f684bdfc77be,
+            0xb530e4f43b66d0e2,
+            0x184a88ff379652fd,
+            0x57cb23ecfae804e1,
+            0xfd2e39eada3eba9,
+            0x8c8055e31c5d5c3,
+        ])),
+    },
+    Fq2 {
+        c0: Fq(FqRepr([0x0, 0x0, 0x0, 0x0, 0x0, 0x0])),
+        c1: Fq(FqRepr([
+            0xbf0a71c71c91b406,
+            0x4d6d55d28b7638fd,
+            0x9d82f98e5f205aee,
+            0xa27aa27b1d1a18d5,
+            0x2c3b2b2d2938e86,
+            0xc7d13420b09807f,
+        ])),
+    },
+    Fq2 {
+        c0: Fq(FqRepr([
+            0xd7f9555555531c74,
+            0x21cffff748daaaa8,
+            0x5a9ad1866c9bbe46,
+            0x4870a2210221d251,
+            0x4a0db369c0a32af1,
+            0x2b1ccc429ff56af,
+        ])),
+        c1: Fq(FqRepr([
+            0xe205aaaaaaac8e37,
+            0xfcdc000768795556,
+            0xc96011a8a1537dd,
+            0x1c06a963f163406e,
+            0x10df44c82a881e6,
+            0x174f45260f808feb,
+        ])),
+    },
+    Fq2 {
+        c0: Fq(FqRepr([
+            0xa470bda12f67f35c,
+            0xc0fe38e23327b425,
+            0xc9d3d0f2c6f0678d,
+            0x1c55c9935b5a982e,
+            0x27f6c0e2f0746764,
+            0x117c5e6e28aa9054,
+        ])),
+        c1: Fq(FqRepr([0x0, 0x0, 0x0, 0x0, 0x0, 0x0])),
+    },
+];
+
+/// Coefficients of the 3-isogeny y map's denominator
+const ISO3_YDEN: [Fq2; 4] = [
+    Fq2 {
+        c0: Fq(FqRepr([
+            0x162fffffa765adf,
+            0x8f7bea480083fb75,
+            0x561b3c2259e93611,
+            0x11e19fc1a9c875d5,
+            0xca713efc00367660,
+            0x3c6a03d41da1151,
+        ])),
+        c1: Fq(FqRepr([
+            0x162fffffa765adf,
+            0x8f7bea480083fb75,
+            0x561b3c2259e93611,
+            0x11e19fc1a9c875d5,
+            0xca713efc00367660,
+            0x3c6a03d41da1151,
+        ])),
+    },
+    Fq2 {
+        c0: Fq(FqRepr([0x0, 0x0, 0x0, 0x0, 0x0, 0x0])),
+        c1: Fq(FqRepr([
+            0x5db0fffffd3b02c5,
+            0xd713f52358ebfdba,
+            0x5ea60761a84d161a,
+            0xbb2c75a34ea6c44a,
+            0xac6735921c1119b,
+            0xee3d913bdacfbf6,
+        ])),
+    },
+    Fq2 {
+        c0: Fq(FqRepr([
+            0x66b10000003affc5,
+            0xcb1400e764ec0030,
+            0xa73e5eb56fa5d106,
+            0x8984c913a0fe09a9,
+            0x11e10afb78ad7f13,
+            0x5429d0e3e918f52,
+        ])),
+        c1: Fq(FqRepr([
+            0x534dffffffc4aae6,
+            0x5397ff174c67ffcf,
+            0xbff273eb870b251d,
+            0xdaf2827152870915,
+            0x393a9cbaca9e2dc3,
+            0x14be74dbfaee5748,
+        ])),
+    },
+    Fq2 {
+        c0: Fq(FqRepr([
+            0x760900000002fffd,
+            0xebf4000bc40c0002,
+            0x5f48985753c758ba,
+            0x77ce585370525745,
+            0x5c071a97a256ec6d,
+            0x15f65ec3fa80e493,
+        ])),
+        c1: Fq(FqRepr([0x0, 0x0, 0x0, 0x0, 0x0, 0x0])),
+    },
+];
+
+/// `A'` of the curve `E'` isogenous to G2
+const SSWU_A: Fq2 = Fq2 {
+    c0: Fq(FqRepr([0x0, 0x0, 0x0, 0x0, 0x0, 0x0])),
+    c1: Fq(FqRepr([
+        0xe53a000003135242,
+        0x1080c0fdef80285,
+        0xe7889edbe340f6bd,
+        0xb51375126310601,
+        0x2d6985717c744ab,
+        0x1220b4e979ea5467,
+    ])),
+};
+
+/// `B'` of the curve `E'` isogenous to G2
+const SSWU_B: Fq2 = Fq2 {
+    c0: Fq(FqRepr([
+        0x22ea00000cf89db2,
+        0x6ec832df71380aa4,
+        0x6e1b94403db5a66e,
+        0x75bf3c53a79473ba,
+        0x3dd3a569412c0a34,
+        0x125cdb5e74dc4fd1,
+    ])),
+    c1: Fq(FqRepr([
+        0x22ea00000cf89db2,
+        0x6ec832df71380aa4,
+        0x6e1b94403db5a66e,
+        0x75bf3c53a79473ba,
+        0x3dd3a569412c0a34,
+        0x125cdb5e74dc4fd1,
+    ])),
+};
+
+/// `Z = -(2 + u)`, the non-square of the simplified SWU map
+const SSWU_Z: Fq2 = Fq2 {
+    c0: Fq(FqRepr([
+        0x87ebfffffff9555c,
+        0x656fffe5da8ffffa,
+        0xfd0749345d33ad2,
+        0xd951e663066576f4,
+        0xde291a3d41e980d3,
+        0x815664c7dfe040d,
+    ])),
+    c1: Fq(FqRepr([
+        0x43f5fffffffcaaae,
+        0x32b7fff2ed47fffd,
+        0x7e83a49a2e99d69,
+        0xeca8f3318332bb7a,
+        0xef148d1ea0f4c069,
+        0x40ab3263eff0206,
+    ])),
+};
+
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::messages;
+    use super::*;
+    use hex;
+    use CurveAffine;
+
+    fn check_vectors(hash: fn(&[u8], &[u8]) -> G2, dst: &[u8], expected: &[[&str; 4]]) {
+        for (msg, expected) in messages().iter().zip(expected.iter()) {
+            let p = hash(msg, dst).into_affine();
+
+            assert_eq!(
+                hex::encode(p.into_uncompressed().as_ref()),
+                expected.concat()
+            );
+        }
+    }
+
+    #[test]
+    fn test_hash_to_curve_g2() {
+        // RFC 9380, J.10.1
+        check_vectors(
+            G2::hash_to_curve,
+            b"QUUX-V01-CS02-with-BLS12381G2_XMD:SHA-256_SSWU_RO_",
+            &[
+                [
+                    "05cb8437535e20ecffaef7752baddf98034139c38452458baeefab379ba13dff5bf5dd71b72418717047f5b0f37da03d",
+                    "0141ebfbdca40eb85b87142e130ab689c673cf60f1a3e98d69335266f30d9b8d4ac44c1038e9dcdd5393faf5c41fb78a",
+                    "12424ac32561493f3fe3c260708a12b7c620e7be00099a974e259ddc7d1f6395c3c811cdd19f1e8dbf3e9ecfdcbab8d6",
+                    "0503921d7f6a12805e72940b963c0cf3471c7b2a524950ca195d11062ee75ec076daf2d4bc358c4b190c0c98064fdd92",
+                ],
+                [
+                    "139cddbccdc5e91b9623efd38c49f81a6f83f175e80b06fc374de9eb4b41dfe4ca3a230ed250fbe3a2acf73a41177fd8",
+                    "02c2d18e033b960562aae3cab37a27ce00d80ccd5ba4b7fe0e7a210245129dbec7780ccc7954725f4168aff2787776e6",
+                    "00aa65dae3c8d732d10ecd2c50f8a1baf3001578f71c694e03866e9f3d49ac1e1ce70dd94a733534f106d4cec0eddd16",
+                    "1787327b68159716a37440985269cf584bcb1e621d3a7202be6ea05c4cfe244aeb197642555a0645fb87bf7466b2ba48",
+                ],
+                [
+                    "190d119345b94fbd15497bcba94ecf7db2cbfd1e1fe7da034d26cbba169fb3968288b3fafb265f9ebd380512a71c3f2c",
+                    "121982811d2491fde9ba7ed31ef9ca474f0e1501297f68c298e9f4c0028add35aea8bb83d53c08cfc007c1e005723cd0",
+                    "0bb5e7572275c567462d91807de765611490205a941a5a6af3b1691bfe596c31225d3aabdf15faff860cb4ef17c7c3be",
+                    "05571a0f8d3c08d094576981f4a3b8eda0a8e771fcdcc8ecceaf1356a6acf17574518acb506e435b639353c2e14827c8",
+                ],
+                [
+                    "0934aba516a52d8ae479939a91998299c76d39cc0c035cd18813bec433f587e2d7a4fef038260eef0cef4d02aae3eb91",
+                    "19a84dd7248a1066f737cc34502ee5555bd3c19f2ecdb3c7d9e24dc65d4e25e50d83f0f77105e955d78f4762d33c17da",
+                    "09bcccfa036b4847c9950780733633f13619994394c23ff0b32fa6b795844f4a0673e20282d07bc69641cee04f5e5662",
+                    "14f81cd421617428bc3b9fe25afbb751d934a00493524bc4e065635b0555084dd54679df1536101b2c979c0152d09192",
+                ],
+                [
+                    "11fca2ff525572795a801eed17eb12785887c7b63fb77a42be46ce4a34131d71f7a73e95fee3f812aea3de78b4d01569",
+                    "01a6ba2f9a11fa5598b2d8ace0fbe0a0eacb65deceb476fbbcb64fd24557c2f4b18ecfc5663e54ae16a84f5ab7f62534",
+                    "03a47f8e6d1763ba0cad63d6114c0accbef65707825a511b251a660a9b3994249ae4e63fac38b23da0c398689ee2ab52",
+                    "0b6798718c8aed24bc19cb27f866f1c9effcdbf92397ad6448b5c9db90d2b9da6cbabf48adc1adf59a1a28344e79d57e",
+                ],
+            ],
+        );
+    }
+
+    #[test]
+    fn test_encode_to_curve_g2() {
+        // RFC 9380, J.10.2
+        check_vectors(
+            G2::encode_to_curve,
+            b"QUUX-V01-CS02-with-BLS12381G2_XMD:SHA-256_SSWU_NU_",
+            &[
+                [
+                    "126b855e9e69b1f691f816e48ac6977664d24d99f8724868a184186469ddfd4617367e94527d4b74fc86413483afb35b",
+                    "00e7f4568a82b4b7dc1f14c6aaa055edf51502319c723c4dc2688c7fe5944c213f510328082396515734b6612c4e7bb7",
+                    "1498aadcf7ae2b345243e281ae076df6de84455d766ab6fcdaad71fab60abb2e8b980a440043cd305db09d283c895e3d",
+                    "0caead0fd7b6176c01436833c79d305c78be307da5f6af6c133c47311def6ff1e0babf57a0fb5539fce7ee12407b0a42",
+                ],
+                [
+                    "0296238ea82c6d4adb3c838ee3cb2346049c90b96d602d7bb1b469b905c9228be25c627bffee872def773d5b2a2eb57d",
+                    "108ed59fd9fae381abfd1d6bce2fd2fa220990f0f837fa30e0f27914ed6e1454db0d1ee957b219f61da6ff8be0d6441f",
+                    "153606c417e59fb331b7ae6bce4fbf7c5190c33ce9402b5ebe2b70e44fca614f3f1382a3625ed5493843d0b0a652fc3f",
+                    "033f90f6057aadacae7963b0a0b379dd46750c1c94a6357c99b65f63b79e321ff50fe3053330911c56b6ceea08fee656",
+                ],
+                [
+                    "0da75be60fb6aa0e9e3143e40c42796edf15685cafe0279afd2a67c3dff1c82341f17effd402e4f1af240ea90f4b659b",
+                    "038af300ef34c7759a6caaa4e69363cafeed218a1f207e93b2c70d91a1263d375d6730bd6b6509dcac3ba5b567e85bf3",
+                    "0492f4fed741b073e5a82580f7c663f9b79e036b70ab3e51162359cec4e77c78086fe879b65ca7a47d34374c8315ac5e",
+                    "19b148cbdf163cf0894f29660d2e7bfb2b68e37d54cc83fd4e6e62c020eaa48709302ef8e746736c0e19342cc1ce3df4",
+                ],
+                [
+                    "12c8c05c1d5fc7bfa847f4d7d81e294e66b9a78bc9953990c358945e1f042eedafce608b67fdd3ab0cb2e6e263b9b1ad",
+                    "0c5ae723be00e6c3f0efe184fdc0702b64588fe77dda152ab13099a3bacd3876767fa7bbad6d6fd90b3642e902b208f9",
+                    "11c624c56dbe154d759d021eec60fab3d8b852395a89de497e48504366feedd4662d023af447d66926a28076813dd646",
+                    "04e77ddb3ede41b5ec4396b7421dd916efc68a358a0d7425bddd253547f2fb4830522358491827265dfc5bcc1928a569",
+                ],
+                [
+                    "1565c2f625032d232f13121d3cfb476f45275c303a037faa255f9da62000c2c864ea881e2bcddd111edc4a3c0da3e88d",
+                    "0ea4e7c33d43e17cc516a72f76437c4bf81d8f4eac69ac355d3bf9b71b8138d55dc10fd458be115afa798b55dac34be1",
+                    "0f8991d2a1ad662e7b6f58ab787947f1fa607fce12dde171bc17903b012091b657e15333e11701edcf5b63ba2a561247",
+                    "043b6f5fe4e52c839148dc66f2b3751e69a0f6ebb3d056d6465d50d4108543ecd956e10fa1640dfd9bc0030cc2558d28",
+                ],
+            ],
+        );
+    }
+}
@@ -0,0 +1,308 @@
+//! Hashing to G1 and G2 following the IETF hash-to-curve specification (RFC 9380),
+//! with the `BLS12381G1_XMD:SHA-256_SSWU_RO_` / `_NU_` and
+//! `BLS12381G2_XMD:SHA-256_SSWU_RO_` / `_NU_` suites.
+//!
+//! A message is expanded with `expand_message_xmd` into field elements,
+//! which are mapped by the simplified SWU map onto a curve isogenous to
+//! G1 / G2, sent back through the isogeny and multiplied by the effective cofactor.
+
+mod g1;
+mod g2;
+
+use sha2::{Digest, Sha256};
+use std::vec::Vec;
+use {CurveProjective, Field, SqrtField};
+
+/// Hashing of byte strings to the points of the prime order subgroup.
+pub trait HashToCurve: CurveProjective {
+    /// Hashes `msg` under the domain separation tag `dst`.
+    /// The output is indistinguishable from a random oracle (the `_RO_` suites).
+    fn hash_to_curve(msg: &[u8], dst: &[u8]) -> Self;
+
+    /// Encodes `msg` under the domain separation tag `dst` with a single
+    /// map to the curve (the `_NU_` suites). It's cheaper than `hash_to_curve`,
+    /// but the output is not uniformly distributed.
+    fn encode_to_curve(msg: &[u8], dst: &[u8]) -> Self;
+}
+
+/// Output size of SHA-256 in bytes.
+const HASH_SIZE: usize = 32;
+
+/// Input block size of SHA-256 in bytes.
+const HASH_BLOCK_SIZE: usize = 64;
+
+/// Prefix to hash domain separation tags longer than 255 bytes with.
+const OVERSIZE_DST_PREFIX: &[u8] = b"H2C-OVERSIZE-DST-";
+
+/// Expands `msg` into `len_in_bytes` uniformly random bytes with SHA-256
+/// under the domain separation tag `dst`.
+///
+/// Panics if `len_in_bytes` is greater than `255 * 32`.
+pub fn expand_message_xmd(msg: &[u8], dst: &[u8], len_in_bytes: usize) -> Vec<u8> {
+    let ell = (len_in_bytes + HASH_SIZE - 1) / HASH_SIZE;
+    assert!(ell <= 255, "expand_message_xmd: requested too many bytes");
+
+    let oversize_dst;
+    let dst = if dst.len() > 255 {
+        oversize_dst = Sha256::new()
+            .chain(OVERSIZE_DST_PREFIX)
+            .chain(dst)
+            .result();
+        &oversize_dst[..]
+    } else {
+        dst
+    };
+    let dst_len = [dst.len() as u8];
+
+    let b_0 = Sha256::new()
+        .chain(&[0u8; HASH_BLOCK_SIZE][..])
+        .chain(msg)
+        .chain(&[(len_in_bytes >> 8) as u8, len_in_bytes as u8, 0u8][..])
+        .chain(dst)
+        .chain(&dst_len[..])
+        .result();
+
+    let mut b_i = Sha256::new()
+        .chain(&b_0[..])
+        .chain(&[1u8][..])
+        .chain(dst)
+        .chain(&dst_len[..])
+        .result();
+
+    let mut uniform_bytes = Vec::with_capacity(ell * HASH_SIZE);
+    uniform_bytes.extend_from_slice(&b_i[..]);
+
+    for i in 2..(ell + 1) {
+        let mut xored = [0u8; HASH_SIZE];
+        for (x, (b0, bi)) in xored.iter_mut().zip(b_0.iter().zip(b_i.iter())) {
+            *x = b0 ^ bi;
+        }
+
+        b_i = Sha256::new()
+            .chain(&xored[..])
+            .chain(&[i as u8][..])
+            .chain(dst)
+            .chain(&dst_len[..])
+            .result();
+        uniform_bytes.extend_from_slice(&b_i[..]);
+    }
+
+    uniform_bytes.truncate(len_in_bytes);
+    uniform_bytes
+}
+
+/// A field which the field elements are hashed into.
+trait HashToField: SqrtField {
+    /// Bytes of the expanded message per field element.
+    const OKM_SIZE: usize;
+
+    /// Reduces the big endian output keying material into a field element.
+    fn from_okm(okm: &[u8]) -> Self;
+
+    /// The "sign" of the field element; zero is positive.
+    fn sgn0(&self) -> bool;
+}
+
+/// Hashes `msg` into `count` field elements.
+fn hash_to_field<F: HashToField>(msg: &[u8], dst: &[u8], count: usize) -> Vec<F> {
+    expand_message_xmd(msg, dst, count * F::OKM_SIZE)
+        .chunks(F::OKM_SIZE)
+        .map(F::from_okm)
+        .collect()
+}
+
+/// The simplified SWU map onto the curve `y^2 = x^3 + a * x + b`,
+/// which needs `a * b != 0`.
+/// `z` is a non-square such that `x^3 + a * x + b - z` is irreducible.
+fn map_to_curve_simple_swu<F: HashToField>(u: &F, a: &F, b: &F, z: &F) -> (F, F) {
+    fn curve_rhs<F: Field>(x: &F, a: &F, b: &F) -> F {
+        let mut ax = *a;
+        ax.mul_assign(x);
+
+        let mut res = *x;
+        res.square();
+        res.mul_assign(x);
+        res.add_assign(&ax);
+        res.add_assign(b);
+        res
+    }
+
+    // z * u^2
+    let mut z_u2 = *u;
+    z_u2.square();
+    z_u2.mul_assign(z);
+
+    // z^2 * u^4 + z * u^2
+    let mut tv = z_u2;
+    tv.square();
+    tv.add_assign(&z_u2);
+
+    let x1 = match tv.inverse() {
+        // x1 = -b / a * (1 + 1 / tv)
+        Some(mut x1) => {
+            x1.add_assign(&F::one());
+            x1.mul_assign(b);
+            x1.mul_assign(&a.inverse().expect("a is nonzero"));
+            x1.negate();
+            x1
+        }
+        // x1 = b / (z * a)
+        None => {
+            let mut za = *z;
+            za.mul_assign(a);
+            let mut x1 = za.inverse().expect("a and z are nonzero");
+            x1.mul_assign(b);
+            x1
+        }
+    };
+
+    let (x, mut y) = match curve_rhs(&x1, a, b).sqrt() {
+        Some(y1) => (x1, y1),
+        None => {
+            // If g(x1) is not square, then g(x2) is square for x2 = z * u^2 * x1.
+            let mut x2 = x1;
+            x2.mul_assign(&z_u2);
+            let y2 = curve_rhs(&x2, a, b)
+                .sqrt()
+                .expect("either g(x1) or g(x2) is square");
+            (x2, y2)
+        }
+    };
+
+    if u.sgn0() != y.sgn0() {
+        y.negate();
+    }
+
+    (x, y)
+}
+
+/// Evaluates the isogeny, given by the coefficients of `x_num / x_den` and
+/// `y * y_num / y_den` from the constant terms, at an affine point.
+/// Returns `None` for the points in the kernel, which are mapped to zero.
+fn iso_map<F: Field>(x: &F, y: &F, coeffs: [&[F]; 4]) -> Option<(F, F)> {
+    let mut values = [F::zero(); 4];
+
+    for (value, coeffs) in values.iter_mut().zip(coeffs.iter()) {
+        // Horner's rule
+        let mut acc = F::zero();
+        for c in coeffs.iter().rev() {
+            acc.mul_assign(x);
+            acc.add_assign(c);
+        }
+        *value = acc;
+    }
+
+    let [x_num, x_den, y_num, y_den] = values;
+
+    let mut den = x_den;
+    den.mul_assign(&y_den);
+    let den_inv = den.inverse()?;
+
+    let mut x = x_num;
+    x.mul_assign(&y_den);
+    x.mul_assign(&den_inv);
+
+    let mut y_mapped = *y;
+    y_mapped.mul_assign(&y_num);
+    y_mapped.mul_assign(&x_den);
+    y_mapped.mul_assign(&den_inv);
+
+    Some((x, y_mapped))
+}
+
+/// Maps field elements to the points of the curve and clears the cofactor.
+trait MapToCurve: CurveProjective {
+    type Field: HashToField;
+
+    /// Maps a field element to a point on the curve, which is not necessarily
+    /// in the prime order subgroup.
+    fn map_to_curve(u: &Self::Field) -> Self;
+
+    /// Multiplies the point by the effective cofactor `h_eff`.
+    fn clear_cofactor(&self) -> Self;
+}
+
+fn hash_to_curve<G: MapToCurve>(msg: &[u8], dst: &[u8]) -> G {
+    let u = hash_to_field::<G::Field>(msg, dst, 2);
+
+    let mut p = G::map_to_curve(&u[0]);
+    p.add_assign(&G::map_to_curve(&u[1]));
+    p.clear_cofactor()
+}
+
+fn encode_to_curve<G: MapToCurve>(msg: &[u8], dst: &[u8]) -> G {
+    let u = hash_to_field::<G::Field>(msg, dst, 1);
+
+    G::map_to_curve(&u[0]).clear_cofactor()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use hex;
+    use std::vec::Vec;
+
+    // The messages of the test vectors in RFC 9380.
+    pub(crate) fn messages() -> Vec<Vec<u8>> {
+        vec![
+            b"".to_vec(),
+            b"abc".to_vec(),
+            b"abcdef0123456789".to_vec(),
+            [&b"q128_"[..], &[b'q'; 128][..]].concat(),
+            [&b"a512_"[..], &[b'a'; 512][..]].concat(),
+        ]
+    }
+
+    fn check_expand_message_xmd(dst: &[u8], len_in_bytes: usize, expected: &[&str]) {
+        for (msg, expected) in messages().iter().zip(expected.iter()) {
+            assert_eq!(
+                hex::encode(expand_message_xmd(msg, dst, len_in_bytes)),
+                *expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_expand_message_xmd() {
+        // RFC 9380, K.1
+        let dst = b"QUUX-V01-CS02-with-expander-SHA256-128";
+
+        check_expand_message_xmd(dst, 0x20, &[
+            "68a985b87eb6b46952128911f2a4412bbc302a9d759667f87f7a21d803f07235",
+            "d8ccab23b5985ccea865c6c97b6e5b8350e794e603b4b97902f53a8a0d605615",
+            "eff31487c770a893cfb36f912fbfcbff40d5661771ca4b2cb4eafe524333f5c1",
+            "b23a1d2b4d97b2ef7785562a7e8bac7eed54ed6e97e29aa51bfe3f12ddad1ff9",
+            "4623227bcc01293b8c130bf771da8c298dede7383243dc0993d2d94823958c4c",
+        ]);
+
+        check_expand_message_xmd(dst, 0x80, &[
+            "af84c27ccfd45d41914fdff5df25293e221afc53d8ad2ac06d5e3e29485dadbee0d121587713a3e0dd4d5e69e93eb7cd4f5df4cd103e188cf60cb02edc3edf18eda8576c412b18ffb658e3dd6ec849469b979d444cf7b26911a08e63cf31f9dcc541708d3491184472c2c29bb749d4286b004ceb5ee6b9a7fa5b646c993f0ced",
+            "abba86a6129e366fc877aab32fc4ffc70120d8996c88aee2fe4b32d6c7b6437a647e6c3163d40b76a73cf6a5674ef1d890f95b664ee0afa5359a5c4e07985635bbecbac65d747d3d2da7ec2b8221b17b0ca9dc8a1ac1c07ea6a1e60583e2cb00058e77b7b72a298425cd1b941ad4ec65e8afc50303a22c0f99b0509b4c895f40",
+            "ef904a29bffc4cf9ee82832451c946ac3c8f8058ae97d8d629831a74c6572bd9ebd0df635cd1f208e2038e760c4994984ce73f0d55ea9f22af83ba4734569d4bc95e18350f740c07eef653cbb9f87910d833751825f0ebefa1abe5420bb52be14cf489b37fe1a72f7de2d10be453b2c9d9eb20c7e3f6edc5a60629178d9478df",
+            "80be107d0884f0d881bb460322f0443d38bd222db8bd0b0a5312a6fedb49c1bbd88fd75d8b9a09486c60123dfa1d73c1cc3169761b17476d3c6b7cbbd727acd0e2c942f4dd96ae3da5de368d26b32286e32de7e5a8cb2949f866a0b80c58116b29fa7fabb3ea7d520ee603e0c25bcaf0b9a5e92ec6a1fe4e0391d1cdbce8c68a",
+            "546aff5444b5b79aa6148bd81728704c32decb73a3ba76e9e75885cad9def1d06d6792f8a7d12794e90efed817d96920d728896a4510864370c207f99bd4a608ea121700ef01ed879745ee3e4ceef777eda6d9e5e38b90c86ea6fb0b36504ba4a45d22e86f6db5dd43d98a294bebb9125d5b794e9d2a81181066eb954966a487",
+        ]);
+    }
+
+    #[test]
+    fn test_expand_message_xmd_long_dst() {
+        // RFC 9380, K.2
+        let dst = [&b"QUUX-V01-CS02-with-expander-SHA256-128-long-DST-"[..], &[b'1'; 208][..]].concat();
+
+        check_expand_message_xmd(&dst, 0x20, &[
+            "e8dc0c8b686b7ef2074086fbdd2f30e3f8bfbd3bdf177f73f04b97ce618a3ed3",
+            "52dbf4f36cf560fca57dedec2ad924ee9c266341d8f3d6afe5171733b16bbb12",
+            "35387dcf22618f3728e6c686490f8b431f76550b0b2c61cbc1ce7001536f4521",
+            "01b637612bb18e840028be900a833a74414140dde0c4754c198532c3a0ba42bc",
+            "20cce7033cabc5460743180be6fa8aac5a103f56d481cf369a8accc0c374431b",
+        ]);
+
+        check_expand_message_xmd(&dst, 0x80, &[
+            "14604d85432c68b757e485c8894db3117992fc57e0e136f71ad987f789a0abc287c47876978e2388a02af86b1e8d1342e5ce4f7aaa07a87321e691f6fba7e0072eecc1218aebb89fb14a0662322d5edbd873f0eb35260145cd4e64f748c5dfe60567e126604bcab1a3ee2dc0778102ae8a5cfd1429ebc0fa6bf1a53c36f55dfc",
+            "1a30a5e36fbdb87077552b9d18b9f0aee16e80181d5b951d0471d55b66684914aef87dbb3626eaabf5ded8cd0686567e503853e5c84c259ba0efc37f71c839da2129fe81afdaec7fbdc0ccd4c794727a17c0d20ff0ea55e1389d6982d1241cb8d165762dbc39fb0cee4474d2cbbd468a835ae5b2f20e4f959f56ab24cd6fe267",
+            "d2ecef3635d2397f34a9f86438d772db19ffe9924e28a1caf6f1c8f15603d4028f40891044e5c7e39ebb9b31339979ff33a4249206f67d4a1e7c765410bcd249ad78d407e303675918f20f26ce6d7027ed3774512ef5b00d816e51bfcc96c3539601fa48ef1c07e494bdc37054ba96ecb9dbd666417e3de289d4f424f502a982",
+            "ed6e8c036df90111410431431a232d41a32c86e296c05d426e5f44e75b9a50d335b2412bc6c91e0a6dc131de09c43110d9180d0a70f0d6289cb4e43b05f7ee5e9b3f42a1fad0f31bac6a625b3b5c50e3a83316783b649e5ecc9d3b1d9471cb5024b7ccf40d41d1751a04ca0356548bc6e703fca02ab521b505e8e45600508d32",
+            "78b53f2413f3c688f07732c10e5ced29a17c6a16f717179ffbe38d92d6c9ec296502eb9889af83a1928cd162e845b0d3c5424e83280fed3d10cffb2f8431f14e7a23f4c68819d40617589e4c41169d0b56e0e3535be1fd71fbb08bb70c5b5ffed953d6c14bf7618b35fc1f4c4b30538236b4b08c9fbf90462447a8ada60be495",
+        ]);
+    }
+}
@@ -4,6 +4,7 @@ mod fq12;
 mod fq2;
 mod fq6;
 mod fr;
+mod hash_to_curve;
 
 #[cfg(test)]
 mod tests;
@@ -17,6 +18,7 @@ pub use self::fq12::Fq12;
 pub use self::fq2::Fq2;
 pub use self::fq6::Fq6;
 pub use self::fr::{Fr, FrRepr};
+pub use self::hash_to_curve::{expand_message_xmd, HashToCurve};
 
 use super::{BitIterator, CurveAffine, Engine, Field};
 
@@ -114,7 +116,7 @@ impl Engine for Bls12 {
                 r.mul_assign(&f2);
 
                 fn exp_by_x(f: &mut Fq12, x: u64) {
-                    *f = f.pow(&[x]);
+                    *f = f.cyclotomic_exp(&[x]);
                     if BLS_X_IS_NEGATIVE {
                         f.conjugate();
                     }
@@ -122,7 +124,7 @@ impl Engine for Bls12 {
 
                 let mut x = BLS_X;
                 let mut y0 = r;
-                y0.square();
+                y0.cyclotomic_square();
                 let mut y1 = y0;
                 exp_by_x(&mut y1, x);
                 x >>= 1;
@@ -0,0 +1,1206 @@
+use super::fq2::Fq2;
+use std::cmp::Ordering;
+use LegendreSymbol::*;
+use {Field, PrimeField, PrimeFieldDecodingError, PrimeFieldRepr, SqrtField};
+
+// q = 21888242871839275222246405745257275088696311157297823662689037894645226208583
+const MODULUS: FqRepr = FqRepr([
+    0x3c208c16d87cfd47,
+    0x97816a916871ca8d,
+    0xb85045b68181585d,
+    0x30644e72e131a029,
+]);
+
+// The number of bits needed to represent the modulus.
+const MODULUS_BITS: u32 = 254;
+
+// The number of bits that must be shaved from the beginning of
+// the representation when randomly sampling.
+const REPR_SHAVE_BITS: u32 = 2;
+
+// R = 2**256 % q
+const R: FqRepr = FqRepr([
+    0xd35d438dc58f0d9d,
+    0xa78eb28f5c70b3d,
+    0x666ea36f7879462c,
+    0xe0a77c19a07df2f,
+]);
+
+// R2 = R^2 % q
+const R2: FqRepr = FqRepr([
+    0xf32cfc5b538afa89,
+    0xb5e71911d44501fb,
+    0x47ab1eff0a417ff6,
+    0x6d89f71cab8351f,
+]);
+
+// INV = -(q^{-1} mod 2^64) mod 2^64
+const INV: u64 = 0x87d20782e4866389;
+
+// GENERATOR = 3 (multiplicative generator of q-1 order, that is also quadratic nonresidue)
+const GENERATOR: FqRepr = FqRepr([
+    0x7a17caa950ad28d7,
+    0x1f6ac17ae15521b9,
+    0x334bea4e696bd284,
+    0x2a1f6744ce179d8e,
+]);
+
+// 2^s * t = MODULUS - 1 with t odd
+const S: u32 = 1;
+
+// 2^s root of unity computed by GENERATOR^t
+const ROOT_OF_UNITY: FqRepr = FqRepr([
+    0x68c3488912edefaa,
+    0x8d087f6872aabf4f,
+    0x51e1a24709081231,
+    0x2259d6b14729c0fa,
+]);
+
+
+// B coefficient of BN256 curve, B = 3
+// In Montgommery form with R = 2^256
+pub const B_COEFF: Fq = Fq(FqRepr([
+    0x7a17caa950ad28d7,
+    0x1f6ac17ae15521b9,
+    0x334bea4e696bd284,
+    0x2a1f6744ce179d8e,
+]));
+
+pub const B_COEFF_FQ2: Fq2 = Fq2 {
+    c0: Fq(FqRepr([
+        0x3bf938e377b802a8,
+        0x020b1b273633535d,
+        0x26b7edf049755260,
+        0x2514c6324384a86d,
+    ])),
+    c1: Fq(FqRepr([
+        0x38e7ecccd1dcff67,
+        0x65f0b37d93ce0d3e,
+        0xd749d0dd22ac00aa,
+        0x0141b9ce4a688d4d,
+    ])),
+};
+
+
+// The generators of G1/G2
+
+// Generator of G1
+// x = 1
+// y = 2
+pub const G1_GENERATOR_X: Fq = Fq(FqRepr([
+    0xd35d438dc58f0d9d,
+    0x0a78eb28f5c70b3d,
+    0x666ea36f7879462c,
+    0x0e0a77c19a07df2f,
+]));
+pub const G1_GENERATOR_Y: Fq = Fq(FqRepr([
+    0xa6ba871b8b1e1b3a,
+    0x14f1d651eb8e167b,
+    0xccdd46def0f28c58,
+    0x1c14ef83340fbe5e,
+]));
+
+// Generator of G2
+// 
+// x = 11559732032986387107991004021392285783925812861821192530917403151452391805634*u 
+//     + 10857046999023057135944570762232829481370756359578518086990519993285655852781
+//
+// y = 4082367875863433681332203403145435568316851327593401208105741076214120093531*u 
+//     + 8495653923123431417604973247489272438418190587263600148770280649306958101930
+
+pub const G2_GENERATOR_X_C0: Fq = Fq(FqRepr([
+    0x8e83b5d102bc2026,
+    0xdceb1935497b0172,
+    0xfbb8264797811adf,
+    0x19573841af96503b,
+]));
+pub const G2_GENERATOR_X_C1: Fq = Fq(FqRepr([
+    0xafb4737da84c6140,
+    0x6043dd5a5802d8c4,
+    0x09e950fc52a02f86,
+    0x14fef0833aea7b6b,
+]));
+pub const G2_GENERATOR_Y_C0: Fq = Fq(FqRepr([
+    0x619dfa9d886be9f6,
+    0xfe7fd297f59e9b78,
+    0xff9e1a62231b7dfe,
+    0x28fd7eebae9e4206,
+]));
+pub const G2_GENERATOR_Y_C1: Fq = Fq(FqRepr([
+    0x64095b56c71856ee,
+    0xdc57f922327d3cbb,
+    0x55f935be33351076,
+    0x0da4a0e693fd6482,
+]));
+
+
+// Coefficients for the Frobenius automorphism.
+pub const FROBENIUS_COEFF_FQ2_C1: [Fq; 2] = [
+    // Fq(-1)**(((q^0) - 1) / 2)
+    // it's 1 in Montgommery form
+    Fq(FqRepr([
+        0xd35d438dc58f0d9d,
+        0x0a78eb28f5c70b3d,
+        0x666ea36f7879462c,
+        0x0e0a77c19a07df2f,
+    ])),
+    // Fq(-1)**(((q^1) - 1) / 2)
+    Fq(FqRepr([
+        0x68c3488912edefaa,
+        0x8d087f6872aabf4f,
+        0x51e1a24709081231,
+        0x2259d6b14729c0fa,
+    ])),
+];
+
+ // Fq2(u + 9)**(((q^1) - 1) / 2)
+pub const XI_TO_Q_MINUS_1_OVER_2: Fq2 = Fq2 {
+    c0: Fq(FqRepr([
+        0xe4bbdd0c2936b629, 
+        0xbb30f162e133bacb, 
+        0x31a9d1b6f9645366, 
+        0x253570bea500f8dd,
+    ])),
+    c1: Fq(FqRepr([
+        0xa1d77ce45ffe77c7, 
+        0x07affd117826d1db, 
+        0x6d16bd27bb7edc6b, 
+        0x2c87200285defecc,
+    ])),
+};
+
+pub const FROBENIUS_COEFF_FQ6_C1: [Fq2; 6] = [
+    // Fq2(u + 9)**(((q^0) - 1) / 3)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0xd35d438dc58f0d9d,
+            0x0a78eb28f5c70b3d,
+            0x666ea36f7879462c,
+            0x0e0a77c19a07df2f,
+        ])),
+        c1: Fq(FqRepr([0x0, 0x0, 0x0, 0x0])),
+    },
+    // Fq2(u + 9)**(((q^1) - 1) / 3)
+    // taken from go-ethereum and also re-calculated manually
+    Fq2 {
+        c0: Fq(FqRepr([
+            0xb5773b104563ab30,
+            0x347f91c8a9aa6454,
+            0x7a007127242e0991,
+            0x1956bcd8118214ec,
+            ])),
+        c1: Fq(FqRepr([
+            0x6e849f1ea0aa4757, 
+            0xaa1c7b6d89f89141, 
+            0xb6e713cdfae0ca3a, 
+            0x26694fbb4e82ebc3,
+        ])),
+    },
+    // Fq2(u + 9)**(((q^2) - 1) / 3)
+    // this one and other below are recalculated manually
+    Fq2 {
+        c0: Fq(FqRepr([
+            0x3350c88e13e80b9c,
+            0x7dce557cdb5e56b9,
+            0x6001b4b8b615564a,
+            0x2682e617020217e0,
+        ])),
+        c1: Fq(FqRepr([0x0, 0x0, 0x0, 0x0])),
+    },
+    // Fq2(u + 9)**(((q^3) - 1) / 3)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0xc9af22f716ad6bad,
+            0xb311782a4aa662b2,
+            0x19eeaf64e248c7f4,
+            0x20273e77e3439f82,
+        ])),
+        c1: Fq(FqRepr([
+            0xacc02860f7ce93ac,
+            0x3933d5817ba76b4c,
+            0x69e6188b446c8467,
+            0x0a46036d4417cc55,
+        ])),
+    },
+    // Fq2(u + 9)**(((q^4) - 1) / 3)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0x71930c11d782e155,
+            0xa6bb947cffbe3323,
+            0xaa303344d4741444,
+            0x2c3b3f0d26594943,
+        ])),
+        c1: Fq(FqRepr([0x0, 0x0, 0x0, 0x0,])),
+    },
+    // Fq2(u + 9)**(((q^5) - 1) / 3)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0xf91aba2654e8e3b1,
+            0x4771cb2fdc92ce12,
+            0xdcb16ae0fc8bdf35,
+            0x274aa195cd9d8be4,
+        ])),
+        c1: Fq(FqRepr([
+            0x5cfc50ae18811f8b,
+            0x4bb28433cb43988c,
+            0x4fd35f13c3b56219,
+            0x301949bd2fc8883a,
+        ])),
+    },
+];
+
+pub const FROBENIUS_COEFF_FQ6_C2: [Fq2; 6] = [
+    // Fq2(u + 1)**(((2q^0) - 2) / 3)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0xd35d438dc58f0d9d,
+            0x0a78eb28f5c70b3d,
+            0x666ea36f7879462c,
+            0x0e0a77c19a07df2f,
+        ])),
+        c1: Fq(FqRepr([0x0, 0x0, 0x0, 0x0])),
+    },
+    // Fq2(u + 1)**(((2q^1) - 2) / 3)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0x7361d77f843abe92,
+            0xa5bb2bd3273411fb,
+            0x9c941f314b3e2399,
+            0x15df9cddbb9fd3ec,
+        ])),
+        c1: Fq(FqRepr([
+            0x5dddfd154bd8c949,
+            0x62cb29a5a4445b60,
+            0x37bc870a0c7dd2b9,
+            0x24830a9d3171f0fd,
+        ])),
+    },
+    // Fq2(u + 1)**(((2q^2) - 2) / 3)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0x71930c11d782e155,
+            0xa6bb947cffbe3323,
+            0xaa303344d4741444,
+            0x2c3b3f0d26594943,
+        ])),
+        c1: Fq(FqRepr([0x0, 0x0, 0x0, 0x0,])),
+    },
+    // Fq2(u + 1)**(((2q^3) - 2) / 3)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0x448a93a57b6762df,
+            0xbfd62df528fdeadf,
+            0xd858f5d00e9bd47a,
+            0x06b03d4d3476ec58,
+        ])),
+        c1: Fq(FqRepr([
+            0x2b19daf4bcc936d1,
+            0xa1a54e7a56f4299f,
+            0xb533eee05adeaef1,
+            0x170c812b84dda0b2,
+        ])),
+    },
+    // Fq2(u + 1)**(((2q^4) - 2) / 3)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0x3350c88e13e80b9c,
+            0x7dce557cdb5e56b9,
+            0x6001b4b8b615564a,
+            0x2682e617020217e0,
+        ])),
+        c1: Fq(FqRepr([0x0, 0x0, 0x0, 0x0,])),
+    },
+    // Fq2(u + 1)**(((2q^5) - 2) / 3)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0x843420f1d8dadbd6,
+            0x31f010c9183fcdb2,
+            0x436330b527a76049,
+            0x13d47447f11adfe4,
+        ])),
+        c1: Fq(FqRepr([
+            0xef494023a857fa74,
+            0x2a925d02d5ab101a,
+            0x83b015829ba62f10,
+            0x2539111d0c13aea3,
+        ])),
+    },
+];
+
+// non_residue^((modulus^i-1)/6) for i=0,...,11
+pub const FROBENIUS_COEFF_FQ12_C1: [Fq2; 12] = [
+    // Fq2(u + 1)**(((q^0) - 1) / 6)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0xd35d438dc58f0d9d,
+            0x0a78eb28f5c70b3d,
+            0x666ea36f7879462c,
+            0x0e0a77c19a07df2f,
+        ])),
+        c1: Fq(FqRepr([0x0, 0x0, 0x0, 0x0])),
+    },
+    // Fq2(u + 1)**(((q^1) - 1) / 6)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0xaf9ba69633144907,
+            0xca6b1d7387afb78a,
+            0x11bded5ef08a2087,
+            0x02f34d751a1f3a7c,
+        ])),
+        c1: Fq(FqRepr([
+            0xa222ae234c492d72,
+            0xd00f02a4565de15b,
+            0xdc2ff3a253dfc926,
+            0x10a75716b3899551,
+        ])),
+    },
+    // Fq2(u + 1)**(((q^2) - 1) / 6)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0xca8d800500fa1bf2,
+            0xf0c5d61468b39769,
+            0x0e201271ad0d4418,
+            0x04290f65bad856e6,
+        ])),
+        c1: Fq(FqRepr([0x0, 0x0, 0x0, 0x0])),
+    },
+    // Fq2(u + 1)**(((q^3) - 1) / 6)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0x365316184e46d97d,
+            0x0af7129ed4c96d9f,
+            0x659da72fca1009b5,
+            0x08116d8983a20d23,
+        ])),
+        c1: Fq(FqRepr([
+            0xb1df4af7c39c1939,
+            0x3d9f02878a73bf7f,
+            0x9b2220928caf0ae0,
+            0x26684515eff054a6,
+        ])),
+    },
+    // Fq2(u + 1)**(((q^4) - 1) / 6)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0x3350c88e13e80b9c,
+            0x7dce557cdb5e56b9,
+            0x6001b4b8b615564a,
+            0x2682e617020217e0,
+        ])),
+        c1: Fq(FqRepr([0x0, 0x0, 0x0, 0x0,])),
+    },
+    // Fq2(u + 1)**(((q^5) - 1) / 6)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0x86b76f821b329076,
+            0x408bf52b4d19b614,
+            0x53dfb9d0d985e92d,
+            0x051e20146982d2a7,
+        ])),
+        c1: Fq(FqRepr([
+            0x0fbc9cd47752ebc7,
+            0x6d8fffe33415de24,
+            0xbef22cf038cf41b9,
+            0x15c0edff3c66bf54,
+        ])),
+    },
+    // Fq2(u + 1)**(((q^6) - 1) / 6)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0x68c3488912edefaa,
+            0x8d087f6872aabf4f,
+            0x51e1a24709081231,
+            0x2259d6b14729c0fa,
+        ])),
+        c1: Fq(FqRepr([0x0, 0x0, 0x0, 0x0,])),
+    },
+    // Fq2(u + 1)**(((q^7) - 1) / 6)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0x8c84e580a568b440,
+            0xcd164d1de0c21302,
+            0xa692585790f737d5,
+            0x2d7100fdc71265ad,
+        ])),
+        c1: Fq(FqRepr([
+            0x99fdddf38c33cfd5,
+            0xc77267ed1213e931,
+            0xdc2052142da18f36,
+            0x1fbcf75c2da80ad7,
+        ])),
+    },
+    // Fq2(u + 1)**(((q^8) - 1) / 6)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0x71930c11d782e155,
+            0xa6bb947cffbe3323,
+            0xaa303344d4741444,
+            0x2c3b3f0d26594943,
+        ])),
+        c1: Fq(FqRepr([0x0, 0x0, 0x0, 0x0,])),
+    },
+    // Fq2(u + 1)**(((q^9) - 1) / 6)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0x05cd75fe8a3623ca,
+            0x8c8a57f293a85cee,
+            0x52b29e86b7714ea8,
+            0x2852e0e95d8f9306,
+        ])),
+        c1: Fq(FqRepr([
+            0x8a41411f14e0e40e,
+            0x59e26809ddfe0b0d,
+            0x1d2e2523f4d24d7d,
+            0x09fc095cf1414b83,
+        ])),
+    },
+    // Fq2(u + 1)**(((q^10) - 1) / 6)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0x08cfc388c494f1ab,
+            0x19b315148d1373d4,
+            0x584e90fdcb6c0213,
+            0x09e1685bdf2f8849,
+        ])),
+        c1: Fq(FqRepr([0x0, 0x0, 0x0, 0x0,])),
+    },
+    // Fq2(u + 1)**(((q^11) - 1) / 6)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0xb5691c94bd4a6cd1,
+            0x56f575661b581478,
+            0x64708be5a7fb6f30,
+            0x2b462e5e77aecd82,
+        ])),
+        c1: Fq(FqRepr([
+            0x2c63ef42612a1180,
+            0x29f16aae345bec69,
+            0xf95e18c648b216a4,
+            0x1aa36073a4cae0d4,
+        ])),
+    },
+];
+
+// -((2**256) mod q) mod q
+pub const NEGATIVE_ONE: Fq = Fq(FqRepr([
+    0x68c3488912edefaa,
+    0x8d087f6872aabf4f,
+    0x51e1a24709081231,
+    0x2259d6b14729c0fa,
+]));
+
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct FqRepr(pub [u64; 4]);
+
+impl ::rand::Rand for FqRepr {
+    #[inline(always)]
+    fn rand<R: ::rand::Rng>(rng: &mut R) -> Self {
+        FqRepr(rng.gen())
+    }
+}
+
+impl ::std::fmt::Display for FqRepr {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        (write!(f, "0x"))?;
+        for i in self.0.iter().rev() {
+            (write!(f, "{:016x}", *i))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl AsRef<[u64]> for FqRepr {
+    #[inline(always)]
+    fn as_ref(&self) -> &[u64] {
+        &self.0
+    }
+}
+
+impl AsMut<[u64]> for FqRepr {
+    #[inline(always)]
+    fn as_mut(&mut self) -> &mut [u64] {
+        &mut self.0
+    }
+}
+
+impl From<u64> for FqRepr {
+    #[inline(always)]
+    fn from(val: u64) -> FqRepr {
+        let mut repr = Self::default();
+        repr.0[0] = val;
+        repr
+    }
+}
+
+impl Ord for FqRepr {
+    #[inline(always)]
+    fn cmp(&self, other: &FqRepr) -> ::std::cmp::Ordering {
+        for (a, b) in self.0.iter().rev().zip(other.0.iter().rev()) {
+            if a < b {
+                return ::std::cmp::Ordering::Less;
+            } else if a > b {
+                return ::std::cmp::Ordering::Greater;
+            }
+        }
+
+        ::std::cmp::Ordering::Equal
+    }
+}
+
+impl PartialOrd for FqRepr {
+    #[inline(always)]
+    fn partial_cmp(&self, other: &FqRepr) -> Option<::std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PrimeFieldRepr for FqRepr {
+    #[inline(always)]
+    fn is_odd(&self) -> bool {
+        self.0[0] & 1 == 1
+    }
+
+    #[inline(always)]
+    fn is_even(&self) -> bool {
+        !self.is_odd()
+    }
+
+    #[inline(always)]
+    fn is_zero(&self) -> bool {
+        self.0.iter().all(|&e| e == 0)
+    }
+
+    #[inline(always)]
+    fn shr(&mut self, mut n: u32) {
+        if n >= 64 * 4 {
+            *self = Self::from(0);
+            return;
+        }
+
+        while n >= 64 {
+            let mut t = 0;
+            for i in self.0.iter_mut().rev() {
+                ::std::mem::swap(&mut t, i);
+            }
+            n -= 64;
+        }
+
+        if n > 0 {
+            let mut t = 0;
+            for i in self.0.iter_mut().rev() {
+                let t2 = *i << (64 - n);
+                *i >>= n;
+                *i |= t;
+                t = t2;
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn div2(&mut self) {
+        let mut t = 0;
+        for i in self.0.iter_mut().rev() {
+            let t2 = *i << 63;
+            *i >>= 1;
+            *i |= t;
+            t = t2;
+        }
+    }
+
+    #[inline(always)]
+    fn mul2(&mut self) {
+        let mut last = 0;
+        for i in &mut self.0 {
+            let tmp = *i >> 63;
+            *i <<= 1;
+            *i |= last;
+            last = tmp;
+        }
+    }
+
+    #[inline(always)]
+    fn shl(&mut self, mut n: u32) {
+        if n >= 64 * 4 {
+            *self = Self::from(0);
+            return;
+        }
+
+        while n >= 64 {
+            let mut t = 0;
+            for i in &mut self.0 {
+                ::std::mem::swap(&mut t, i);
+            }
+            n -= 64;
+        }
+
+        if n > 0 {
+            let mut t = 0;
+            for i in &mut self.0 {
+                let t2 = *i >> (64 - n);
+                *i <<= n;
+                *i |= t;
+                t = t2;
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn num_bits(&self) -> u32 {
+        let mut ret = (4 as u32) * 64;
+        for i in self.0.iter().rev() {
+            let leading = i.leading_zeros();
+            ret -= leading;
+            if leading != 64 {
+                break;
+            }
+        }
+
+        ret
+    }
+
+    #[inline(always)]
+    fn add_nocarry(&mut self, other: &FqRepr) {
+        let mut carry = 0;
+
+        for (a, b) in self.0.iter_mut().zip(other.0.iter()) {
+            *a = ::adc(*a, *b, &mut carry);
+        }
+    }
+
+    #[inline(always)]
+    fn sub_noborrow(&mut self, other: &FqRepr) {
+        let mut borrow = 0;
+
+        for (a, b) in self.0.iter_mut().zip(other.0.iter()) {
+            *a = ::sbb(*a, *b, &mut borrow);
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Fq(pub FqRepr);
+
+/// `Fq` elements are ordered lexicographically.
+impl Ord for Fq {
+    #[inline(always)]
+    fn cmp(&self, other: &Fq) -> Ordering {
+        self.into_repr().cmp(&other.into_repr())
+    }
+}
+
+impl PartialOrd for Fq {
+    #[inline(always)]
+    fn partial_cmp(&self, other: &Fq) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl ::std::fmt::Display for Fq {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        write!(f, "Fq({})", self.into_repr())
+    }
+}
+
+impl ::rand::Rand for Fq {
+    fn rand<R: ::rand::Rng>(rng: &mut R) -> Self {
+        loop {
+            let mut tmp = Fq(FqRepr::rand(rng));
+
+            // Mask away the unused bits at the beginning.
+            tmp.0.as_mut()[3] &= 0xffffffffffffffff >> REPR_SHAVE_BITS;
+
+            if tmp.is_valid() {
+                return tmp;
+            }
+        }
+    }
+}
+
+impl From<Fq> for FqRepr {
+    fn from(e: Fq) -> FqRepr {
+        e.into_repr()
+    }
+}
+
+impl PrimeField for Fq {
+    type Repr = FqRepr;
+
+    fn from_repr(r: FqRepr) -> Result<Fq, PrimeFieldDecodingError> {
+        let mut r = Fq(r);
+        if r.is_valid() {
+            r.mul_assign(&Fq(R2));
+
+            Ok(r)
+        } else {
+            Err(PrimeFieldDecodingError::NotInField(format!("{}", r.0)))
+        }
+    }
+
+    fn into_repr(&self) -> FqRepr {
+        let mut r = *self;
+        r.mont_reduce(
+            (self.0).0[0],
+            (self.0).0[1],
+            (self.0).0[2],
+            (self.0).0[3],
+            0,
+            0,
+            0,
+            0,
+        );
+        r.0
+    }
+
+    fn char() -> FqRepr {
+        MODULUS
+    }
+
+    const NUM_BITS: u32 = MODULUS_BITS;
+
+    const CAPACITY: u32 = Self::NUM_BITS - 1;
+
+    fn multiplicative_generator() -> Self {
+        Fq(GENERATOR)
+    }
+
+    const S: u32 = S;
+
+    fn root_of_unity() -> Self {
+        Fq(ROOT_OF_UNITY)
+    }
+}
+
+impl Field for Fq {
+    #[inline]
+    fn zero() -> Self {
+        Fq(FqRepr::from(0))
+    }
+
+    #[inline]
+    fn one() -> Self {
+        Fq(R)
+    }
+
+    #[inline]
+    fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+
+    #[inline]
+    fn add_assign(&mut self, other: &Fq) {
+        // This cannot exceed the backing capacity.
+        self.0.add_nocarry(&other.0);
+
+        // However, it may need to be reduced.
+        self.reduce();
+    }
+
+    #[inline]
+    fn double(&mut self) {
+        // This cannot exceed the backing capacity.
+        self.0.mul2();
+
+        // However, it may need to be reduced.
+        self.reduce();
+    }
+
+    #[inline]
+    fn sub_assign(&mut self, other: &Fq) {
+        // If `other` is larger than `self`, we'll need to add the modulus to self first.
+        if other.0 > self.0 {
+            self.0.add_nocarry(&MODULUS);
+        }
+
+        self.0.sub_noborrow(&other.0);
+    }
+
+    #[inline]
+    fn negate(&mut self) {
+        if !self.is_zero() {
+            let mut tmp = MODULUS;
+            tmp.sub_noborrow(&self.0);
+            self.0 = tmp;
+        }
+    }
+
+    fn inverse(&self) -> Option<Self> {
+        if self.is_zero() {
+            None
+        } else {
+            // Guajardo Kumar Paar Pelzl
+            // Efficient Software-Implementation of Finite Fields with Applications to Cryptography
+            // Algorithm 16 (BEA for Inversion in Fp)
+
+            let one = FqRepr::from(1);
+
+            let mut u = self.0;
+            let mut v = MODULUS;
+            let mut b = Fq(R2); // Avoids unnecessary reduction step.
+            let mut c = Self::zero();
+
+            while u != one && v != one {
+                while u.is_even() {
+                    u.div2();
+
+                    if b.0.is_even() {
+                        b.0.div2();
+                    } else {
+                        b.0.add_nocarry(&MODULUS);
+                        b.0.div2();
+                    }
+                }
+
+                while v.is_even() {
+                    v.div2();
+
+                    if c.0.is_even() {
+                        c.0.div2();
+                    } else {
+                        c.0.add_nocarry(&MODULUS);
+                        c.0.div2();
+                    }
+                }
+
+                if v < u {
+                    u.sub_noborrow(&v);
+                    b.sub_assign(&c);
+                } else {
+                    v.sub_noborrow(&u);
+                    c.sub_assign(&b);
+                }
+            }
+
+            if u == one {
+                Some(b)
+            } else {
+                Some(c)
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn frobenius_map(&mut self, _: usize) {
+        // This has no effect in a prime field.
+    }
+
+    #[inline]
+    fn mul_assign(&mut self, other: &Fq) {
+        let mut carry = 0;
+        let r0 = ::mac_with_carry(0, (self.0).0[0], (other.0).0[0], &mut carry);
+        let r1 = ::mac_with_carry(0, (self.0).0[0], (other.0).0[1], &mut carry);
+        let r2 = ::mac_with_carry(0, (self.0).0[0], (other.0).0[2], &mut carry);
+        let r3 = ::mac_with_carry(0, (self.0).0[0], (other.0).0[3], &mut carry);
+        let r4 = carry;
+        let mut carry = 0;
+        let r1 = ::mac_with_carry(r1, (self.0).0[1], (other.0).0[0], &mut carry);
+        let r2 = ::mac_with_carry(r2, (self.0).0[1], (other.0).0[1], &mut carry);
+        let r3 = ::mac_with_carry(r3, (self.0).0[1], (other.0).0[2], &mut carry);
+        let r4 = ::mac_with_carry(r4, (self.0).0[1], (other.0).0[3], &mut carry);
+        let r5 = carry;
+        let mut carry = 0;
+        let r2 = ::mac_with_carry(r2, (self.0).0[2], (other.0).0[0], &mut carry);
+        let r3 = ::mac_with_carry(r3, (self.0).0[2], (other.0).0[1], &mut carry);
+        let r4 = ::mac_with_carry(r4, (self.0).0[2], (other.0).0[2], &mut carry);
+        let r5 = ::mac_with_carry(r5, (self.0).0[2], (other.0).0[3], &mut carry);
+        let r6 = carry;
+        let mut carry = 0;
+        let r3 = ::mac_with_carry(r3, (self.0).0[3], (other.0).0[0], &mut carry);
+        let r4 = ::mac_with_carry(r4, (self.0).0[3], (other.0).0[1], &mut carry);
+        let r5 = ::mac_with_carry(r5, (self.0).0[3], (other.0).0[2], &mut carry);
+        let r6 = ::mac_with_carry(r6, (self.0).0[3], (other.0).0[3], &mut carry);
+        let r7 = carry;
+        self.mont_reduce(r0, r1, r2, r3, r4, r5, r6, r7);
+    }
+
+    #[inline]
+    fn square(&mut self) {
+        let mut carry = 0;
+        let r1 = ::mac_with_carry(0, (self.0).0[0], (self.0).0[1], &mut carry);
+        let r2 = ::mac_with_carry(0, (self.0).0[0], (self.0).0[2], &mut carry);
+        let r3 = ::mac_with_carry(0, (self.0).0[0], (self.0).0[3], &mut carry);
+        let r4 = carry;
+        let mut carry = 0;
+        let r3 = ::mac_with_carry(r3, (self.0).0[1], (self.0).0[2], &mut carry);
+        let r4 = ::mac_with_carry(r4, (self.0).0[1], (self.0).0[3], &mut carry);
+        let r5 = carry;
+        let mut carry = 0;
+        let r5 = ::mac_with_carry(r5, (self.0).0[2], (self.0).0[3], &mut carry);
+        let r6 = carry;
+
+        let r7 = r6 >> 63;
+        let r6 = (r6 << 1) | (r5 >> 63);
+        let r5 = (r5 << 1) | (r4 >> 63);
+        let r4 = (r4 << 1) | (r3 >> 63);
+        let r3 = (r3 << 1) | (r2 >> 63);
+        let r2 = (r2 << 1) | (r1 >> 63);
+        let r1 = r1 << 1;
+
+        let mut carry = 0;
+        let r0 = ::mac_with_carry(0, (self.0).0[0], (self.0).0[0], &mut carry);
+        let r1 = ::adc(r1, 0, &mut carry);
+        let r2 = ::mac_with_carry(r2, (self.0).0[1], (self.0).0[1], &mut carry);
+        let r3 = ::adc(r3, 0, &mut carry);
+        let r4 = ::mac_with_carry(r4, (self.0).0[2], (self.0).0[2], &mut carry);
+        let r5 = ::adc(r5, 0, &mut carry);
+        let r6 = ::mac_with_carry(r6, (self.0).0[3], (self.0).0[3], &mut carry);
+        let r7 = ::adc(r7, 0, &mut carry);
+        self.mont_reduce(r0, r1, r2, r3, r4, r5, r6, r7);
+    }
+}
+
+impl Fq {
+    /// Determines if the element is really in the field. This is only used
+    /// internally.
+    #[inline(always)]
+    fn is_valid(&self) -> bool {
+        self.0 < MODULUS
+    }
+
+    /// Subtracts the modulus from this element if this element is not in the
+    /// field. Only used internally.
+    #[inline(always)]
+    fn reduce(&mut self) {
+        if !self.is_valid() {
+            self.0.sub_noborrow(&MODULUS);
+        }
+    }
+
+    #[inline(always)]
+    fn mont_reduce(
+        &mut self,
+        r0: u64,
+        mut r1: u64,
+        mut r2: u64,
+        mut r3: u64,
+        mut r4: u64,
+        mut r5: u64,
+        mut r6: u64,
+        mut r7: u64,
+    ) {
+        // The Montgomery reduction here is based on Algorithm 14.32 in
+        // Handbook of Applied Cryptography
+        // <http://cacr.uwaterloo.ca/hac/about/chap14.pdf>.
+
+        let k = r0.wrapping_mul(INV);
+        let mut carry = 0;
+        ::mac_with_carry(r0, k, MODULUS.0[0], &mut carry);
+        r1 = ::mac_with_carry(r1, k, MODULUS.0[1], &mut carry);
+        r2 = ::mac_with_carry(r2, k, MODULUS.0[2], &mut carry);
+        r3 = ::mac_with_carry(r3, k, MODULUS.0[3], &mut carry);
+        r4 = ::adc(r4, 0, &mut carry);
+        let carry2 = carry;
+        let k = r1.wrapping_mul(INV);
+        let mut carry = 0;
+        ::mac_with_carry(r1, k, MODULUS.0[0], &mut carry);
+        r2 = ::mac_with_carry(r2, k, MODULUS.0[1], &mut carry);
+        r3 = ::mac_with_carry(r3, k, MODULUS.0[2], &mut carry);
+        r4 = ::mac_with_carry(r4, k, MODULUS.0[3], &mut carry);
+        r5 = ::adc(r5, carry2, &mut carry);
+        let carry2 = carry;
+        let k = r2.wrapping_mul(INV);
+        let mut carry = 0;
+        ::mac_with_carry(r2, k, MODULUS.0[0], &mut carry);
+        r3 = ::mac_with_carry(r3, k, MODULUS.0[1], &mut carry);
+        r4 = ::mac_with_carry(r4, k, MODULUS.0[2], &mut carry);
+        r5 = ::mac_with_carry(r5, k, MODULUS.0[3], &mut carry);
+        r6 = ::adc(r6, carry2, &mut carry);
+        let carry2 = carry;
+        let k = r3.wrapping_mul(INV);
+        let mut carry = 0;
+        ::mac_with_carry(r3, k, MODULUS.0[0], &mut carry);
+        r4 = ::mac_with_carry(r4, k, MODULUS.0[1], &mut carry);
+        r5 = ::mac_with_carry(r5, k, MODULUS.0[2], &mut carry);
+        r6 = ::mac_with_carry(r6, k, MODULUS.0[3], &mut carry);
+        r7 = ::adc(r7, carry2, &mut carry);
+        (self.0).0[0] = r4;
+        (self.0).0[1] = r5;
+        (self.0).0[2] = r6;
+        (self.0).0[3] = r7;
+        self.reduce();
+    }
+}
+
+impl SqrtField for Fq {
+    fn legendre(&self) -> ::LegendreSymbol {
+        // s = self^((q - 1) // 2)
+        let s = self.pow([
+            0x9e10460b6c3e7ea3,
+            0xcbc0b548b438e546,
+            0xdc2822db40c0ac2e,
+            0x183227397098d014,
+        ]);
+        if s == Self::zero() {
+            Zero
+        } else if s == Self::one() {
+            QuadraticResidue
+        } else {
+            QuadraticNonResidue
+        }
+    }
+
+    fn sqrt(&self) -> Option<Self> {
+        // Shank's algorithm for q mod 4 = 3
+        // https://eprint.iacr.org/2012/685.pdf (page 9, algorithm 2)
+
+        // a1 = self^((q - 3) // 4)
+        let mut a1 = self.pow([
+            0x4f082305b61f3f51,
+            0x65e05aa45a1c72a3,
+            0x6e14116da0605617,
+            0xc19139cb84c680a,
+        ]);
+        let mut a0 = a1;
+        a0.square();
+        a0.mul_assign(self);
+
+        if a0 == NEGATIVE_ONE {
+            None
+        } else {
+            a1.mul_assign(self);
+            Some(a1)
+        }
+    }
+}
+
+#[cfg(test)]
+use rand::{Rand, SeedableRng, XorShiftRng};
+
+#[test]
+fn test_fq_repr_from() {
+    assert_eq!(FqRepr::from(100), FqRepr([100, 0, 0, 0]));
+    assert_eq!(FqRepr::from(3), FqRepr([3, 0, 0, 0]));
+}
+
+#[test]
+fn test_fq_is_valid() {
+    let mut a = Fq(MODULUS);
+    assert!(!a.is_valid());
+    a.0.sub_noborrow(&FqRepr::from(1));
+    assert!(a.is_valid());
+    assert!(Fq(FqRepr::from(0)).is_valid());
+    assert!(
+        Fq(FqRepr([
+            0xdf4671abd14dab3e,
+            0xe2dc0c9f534fbd33,
+            0x31ca6c880cc444a6,
+            0x257a67e70ef33359
+        ])).is_valid()
+    );
+    assert!(
+        !Fq(FqRepr([
+            0xffffffffffffffff,
+            0xffffffffffffffff,
+            0xffffffffffffffff,
+            0xffffffffffffffff
+        ])).is_valid()
+    );
+
+    let mut rng = XorShiftRng::from_seed([0x5dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+    for _ in 0..1000 {
+        let a = Fq::rand(&mut rng);
+        assert!(a.is_valid());
+    }
+}
+
+#[test]
+fn test_fq_repr_display() {
+    assert_eq!(
+        format!("{}", Fq::one().into_repr()),
+        "0x0000000000000000000000000000000000000000000000000000000000000001".to_string()
+    );
+    assert_eq!(
+        format!("{}", FqRepr([0, 0, 0, 0])),
+        "0x0000000000000000000000000000000000000000000000000000000000000000".to_string()
+    );
+}
+
+#[test]
+fn test_fq_num_bits() {
+    assert_eq!(Fq::NUM_BITS, 254);
+    assert_eq!(Fq::CAPACITY, 253);
+}
+
+#[test]
+fn test_fq_constants() {
+    assert_eq!(B_COEFF, Fq::from_repr(FqRepr::from(3)).unwrap());
+    assert_eq!(G1_GENERATOR_X, Fq::one());
+    assert_eq!(G1_GENERATOR_Y, Fq::from_repr(FqRepr::from(2)).unwrap());
+
+    let mut neg_one = Fq::one();
+    neg_one.negate();
+    assert_eq!(NEGATIVE_ONE, neg_one);
+
+    // B_COEFF_FQ2 = 3 / (u + 9)
+    let mut xi = Fq2::one();
+    xi.mul_by_nonresidue();
+    let mut b = B_COEFF_FQ2;
+    b.mul_assign(&xi);
+    assert_eq!(b, Fq2 { c0: B_COEFF, c1: Fq::zero() });
+}
+
+#[test]
+fn test_fq_sqrt() {
+    let mut rng = XorShiftRng::from_seed([0x5dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+    assert_eq!(Fq::zero().sqrt().unwrap(), Fq::zero());
+
+    for _ in 0..1000 {
+        // Ensure sqrt(a^2) = a or -a
+        let a = Fq::rand(&mut rng);
+        let mut nega = a;
+        nega.negate();
+        let mut b = a;
+        b.square();
+
+        let b = b.sqrt().unwrap();
+
+        assert!(a == b || nega == b);
+    }
+
+    for _ in 0..1000 {
+        // Ensure sqrt(a)^2 = a for random a
+        let a = Fq::rand(&mut rng);
+
+        if let Some(mut tmp) = a.sqrt() {
+            tmp.square();
+
+            assert_eq!(a, tmp);
+        }
+    }
+}
+
+#[test]
+fn test_fq_legendre() {
+    assert_eq!(QuadraticResidue, Fq::one().legendre());
+    assert_eq!(Zero, Fq::zero().legendre());
+    assert_eq!(
+        QuadraticNonResidue,
+        Fq::multiplicative_generator().legendre()
+    );
+}
+
+#[test]
+fn test_fq_ordering() {
+    // We don't compare in Montgomery form.
+    for i in 0..100 {
+        assert!(
+            Fq::from_repr(FqRepr::from(i + 1)).unwrap()
+                > Fq::from_repr(FqRepr::from(i)).unwrap()
+        );
+    }
+}
+
+#[test]
+fn fq_field_tests() {
+    ::tests::field::random_field_tests::<Fq>();
+    ::tests::field::random_sqrt_tests::<Fq>();
+    ::tests::field::random_frobenius_tests::<Fq, _>(Fq::char(), 13);
+    ::tests::field::from_str_tests::<Fq>();
+}
+
+#[test]
+fn fq_repr_tests() {
+    ::tests::repr::random_repr_tests::<FqRepr>();
+}
@@ -0,0 +1,721 @@
+use LegendreSymbol::*;
+use {Field, PrimeField, PrimeFieldDecodingError, PrimeFieldRepr, SqrtField};
+
+// r = 21888242871839275222246405745257275088548364400416034343698204186575808495617
+const MODULUS: FrRepr = FrRepr([
+    0x43e1f593f0000001,
+    0x2833e84879b97091,
+    0xb85045b68181585d,
+    0x30644e72e131a029,
+]);
+
+// The number of bits needed to represent the modulus.
+const MODULUS_BITS: u32 = 254;
+
+// The number of bits that must be shaved from the beginning of
+// the representation when randomly sampling.
+const REPR_SHAVE_BITS: u32 = 2;
+
+// R = 2**256 % r
+const R: FrRepr = FrRepr([
+    0xac96341c4ffffffb,
+    0x36fc76959f60cd29,
+    0x666ea36f7879462e,
+    0xe0a77c19a07df2f,
+]);
+
+// R2 = R^2 % r
+const R2: FrRepr = FrRepr([
+    0x1bb8e645ae216da7,
+    0x53fe3ab1e35c59e3,
+    0x8c49833d53bb8085,
+    0x216d0b17f4e44a5,
+]);
+
+// INV = -(r^{-1} mod 2^64) mod 2^64
+const INV: u64 = 0xc2e1f593efffffff;
+
+// GENERATOR = 7 (multiplicative generator of r-1 order, that is also quadratic nonresidue)
+const GENERATOR: FrRepr = FrRepr([
+    0x3057819e4fffffdb,
+    0x307f6d866832bb01,
+    0x5c65ec9f484e3a89,
+    0x180a96573d3d9f8,
+]);
+
+// 2^s * t = MODULUS - 1 with t odd
+const S: u32 = 28;
+
+// 2^s root of unity computed by GENERATOR^t
+const ROOT_OF_UNITY: FrRepr = FrRepr([
+    0x9632c7c5b639feb8,
+    0x985ce3400d0ff299,
+    0xb2dd880001b0ecd8,
+    0x1d69070d6d98ce29,
+]);
+
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct FrRepr(pub [u64; 4]);
+
+impl ::rand::Rand for FrRepr {
+    #[inline(always)]
+    fn rand<R: ::rand::Rng>(rng: &mut R) -> Self {
+        FrRepr(rng.gen())
+    }
+}
+
+impl ::std::fmt::Display for FrRepr {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        (write!(f, "0x"))?;
+        for i in self.0.iter().rev() {
+            (write!(f, "{:016x}", *i))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl AsRef<[u64]> for FrRepr {
+    #[inline(always)]
+    fn as_ref(&self) -> &[u64] {
+        &self.0
+    }
+}
+
+impl AsMut<[u64]> for FrRepr {
+    #[inline(always)]
+    fn as_mut(&mut self) -> &mut [u64] {
+        &mut self.0
+    }
+}
+
+impl From<u64> for FrRepr {
+    #[inline(always)]
+    fn from(val: u64) -> FrRepr {
+        let mut repr = Self::default();
+        repr.0[0] = val;
+        repr
+    }
+}
+
+impl Ord for FrRepr {
+    #[inline(always)]
+    fn cmp(&self, other: &FrRepr) -> ::std::cmp::Ordering {
+        for (a, b) in self.0.iter().rev().zip(other.0.iter().rev()) {
+            if a < b {
+                return ::std::cmp::Ordering::Less;
+            } else if a > b {
+                return ::std::cmp::Ordering::Greater;
+            }
+        }
+
+        ::std::cmp::Ordering::Equal
+    }
+}
+
+impl PartialOrd for FrRepr {
+    #[inline(always)]
+    fn partial_cmp(&self, other: &FrRepr) -> Option<::std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PrimeFieldRepr for FrRepr {
+    #[inline(always)]
+    fn is_odd(&self) -> bool {
+        self.0[0] & 1 == 1
+    }
+
+    #[inline(always)]
+    fn is_even(&self) -> bool {
+        !self.is_odd()
+    }
+
+    #[inline(always)]
+    fn is_zero(&self) -> bool {
+        self.0.iter().all(|&e| e == 0)
+    }
+
+    #[inline(always)]
+    fn shr(&mut self, mut n: u32) {
+        if n >= 64 * 4 {
+            *self = Self::from(0);
+            return;
+        }
+
+        while n >= 64 {
+            let mut t = 0;
+            for i in self.0.iter_mut().rev() {
+                ::std::mem::swap(&mut t, i);
+            }
+            n -= 64;
+        }
+
+        if n > 0 {
+            let mut t = 0;
+            for i in self.0.iter_mut().rev() {
+                let t2 = *i << (64 - n);
+                *i >>= n;
+                *i |= t;
+                t = t2;
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn div2(&mut self) {
+        let mut t = 0;
+        for i in self.0.iter_mut().rev() {
+            let t2 = *i << 63;
+            *i >>= 1;
+            *i |= t;
+            t = t2;
+        }
+    }
+
+    #[inline(always)]
+    fn mul2(&mut self) {
+        let mut last = 0;
+        for i in &mut self.0 {
+            let tmp = *i >> 63;
+            *i <<= 1;
+            *i |= last;
+            last = tmp;
+        }
+    }
+
+    #[inline(always)]
+    fn shl(&mut self, mut n: u32) {
+        if n >= 64 * 4 {
+            *self = Self::from(0);
+            return;
+        }
+
+        while n >= 64 {
+            let mut t = 0;
+            for i in &mut self.0 {
+                ::std::mem::swap(&mut t, i);
+            }
+            n -= 64;
+        }
+
+        if n > 0 {
+            let mut t = 0;
+            for i in &mut self.0 {
+                let t2 = *i >> (64 - n);
+                *i <<= n;
+                *i |= t;
+                t = t2;
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn num_bits(&self) -> u32 {
+        let mut ret = (4 as u32) * 64;
+        for i in self.0.iter().rev() {
+            let leading = i.leading_zeros();
+            ret -= leading;
+            if leading != 64 {
+                break;
+            }
+        }
+
+        ret
+    }
+
+    #[inline(always)]
+    fn add_nocarry(&mut self, other: &FrRepr) {
+        let mut carry = 0;
+
+        for (a, b) in self.0.iter_mut().zip(other.0.iter()) {
+            *a = ::adc(*a, *b, &mut carry);
+        }
+    }
+
+    #[inline(always)]
+    fn sub_noborrow(&mut self, other: &FrRepr) {
+        let mut borrow = 0;
+
+        for (a, b) in self.0.iter_mut().zip(other.0.iter()) {
+            *a = ::sbb(*a, *b, &mut borrow);
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Fr(FrRepr);
+
+impl ::std::fmt::Display for Fr {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        write!(f, "Fr({})", self.into_repr())
+    }
+}
+
+impl ::rand::Rand for Fr {
+    fn rand<R: ::rand::Rng>(rng: &mut R) -> Self {
+        loop {
+            let mut tmp = Fr(FrRepr::rand(rng));
+
+            // Mask away the unused bits at the beginning.
+            tmp.0.as_mut()[3] &= 0xffffffffffffffff >> REPR_SHAVE_BITS;
+
+            if tmp.is_valid() {
+                return tmp;
+            }
+        }
+    }
+}
+
+impl From<Fr> for FrRepr {
+    fn from(e: Fr) -> FrRepr {
+        e.into_repr()
+    }
+}
+
+impl PrimeField for Fr {
+    type Repr = FrRepr;
+
+    fn from_repr(r: FrRepr) -> Result<Fr, PrimeFieldDecodingError> {
+        let mut r = Fr(r);
+        if r.is_valid() {
+            r.mul_assign(&Fr(R2));
+
+            Ok(r)
+        } else {
+            Err(PrimeFieldDecodingError::NotInField(format!("{}", r.0)))
+        }
+    }
+
+    fn into_repr(&self) -> FrRepr {
+        let mut r = *self;
+        r.mont_reduce(
+            (self.0).0[0],
+            (self.0).0[1],
+            (self.0).0[2],
+            (self.0).0[3],
+            0,
+            0,
+            0,
+            0,
+        );
+        r.0
+    }
+
+    fn char() -> FrRepr {
+        MODULUS
+    }
+
+    const NUM_BITS: u32 = MODULUS_BITS;
+
+    const CAPACITY: u32 = Self::NUM_BITS - 1;
+
+    fn multiplicative_generator() -> Self {
+        Fr(GENERATOR)
+    }
+
+    const S: u32 = S;
+
+    fn root_of_unity() -> Self {
+        Fr(ROOT_OF_UNITY)
+    }
+}
+
+impl Field for Fr {
+    #[inline]
+    fn zero() -> Self {
+        Fr(FrRepr::from(0))
+    }
+
+    #[inline]
+    fn one() -> Self {
+        Fr(R)
+    }
+
+    #[inline]
+    fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+
+    #[inline]
+    fn add_assign(&mut self, other: &Fr) {
+        // This cannot exceed the backing capacity.
+        self.0.add_nocarry(&other.0);
+
+        // However, it may need to be reduced.
+        self.reduce();
+    }
+
+    #[inline]
+    fn double(&mut self) {
+        // This cannot exceed the backing capacity.
+        self.0.mul2();
+
+        // However, it may need to be reduced.
+        self.reduce();
+    }
+
+    #[inline]
+    fn sub_assign(&mut self, other: &Fr) {
+        // If `other` is larger than `self`, we'll need to add the modulus to self first.
+        if other.0 > self.0 {
+            self.0.add_nocarry(&MODULUS);
+        }
+
+        self.0.sub_noborrow(&other.0);
+    }
+
+    #[inline]
+    fn negate(&mut self) {
+        if !self.is_zero() {
+            let mut tmp = MODULUS;
+            tmp.sub_noborrow(&self.0);
+            self.0 = tmp;
+        }
+    }
+
+    fn inverse(&self) -> Option<Self> {
+        if self.is_zero() {
+            None
+        } else {
+            // Guajardo Kumar Paar Pelzl
+            // Efficient Software-Implementation of Finite Fields with Applications to Cryptography
+            // Algorithm 16 (BEA for Inversion in Fp)
+
+            let one = FrRepr::from(1);
+
+            let mut u = self.0;
+            let mut v = MODULUS;
+            let mut b = Fr(R2); // Avoids unnecessary reduction step.
+            let mut c = Self::zero();
+
+            while u != one && v != one {
+                while u.is_even() {
+                    u.div2();
+
+                    if b.0.is_even() {
+                        b.0.div2();
+                    } else {
+                        b.0.add_nocarry(&MODULUS);
+                        b.0.div2();
+                    }
+                }
+
+                while v.is_even() {
+                    v.div2();
+
+                    if c.0.is_even() {
+                        c.0.div2();
+                    } else {
+                        c.0.add_nocarry(&MODULUS);
+                        c.0.div2();
+                    }
+                }
+
+                if v < u {
+                    u.sub_noborrow(&v);
+                    b.sub_assign(&c);
+                } else {
+                    v.sub_noborrow(&u);
+                    c.sub_assign(&b);
+                }
+            }
+
+            if u == one {
+                Some(b)
+            } else {
+                Some(c)
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn frobenius_map(&mut self, _: usize) {
+        // This has no effect in a prime field.
+    }
+
+    #[inline]
+    fn mul_assign(&mut self, other: &Fr) {
+        let mut carry = 0;
+        let r0 = ::mac_with_carry(0, (self.0).0[0], (other.0).0[0], &mut carry);
+        let r1 = ::mac_with_carry(0, (self.0).0[0], (other.0).0[1], &mut carry);
+        let r2 = ::mac_with_carry(0, (self.0).0[0], (other.0).0[2], &mut carry);
+        let r3 = ::mac_with_carry(0, (self.0).0[0], (other.0).0[3], &mut carry);
+        let r4 = carry;
+        let mut carry = 0;
+        let r1 = ::mac_with_carry(r1, (self.0).0[1], (other.0).0[0], &mut carry);
+        let r2 = ::mac_with_carry(r2, (self.0).0[1], (other.0).0[1], &mut carry);
+        let r3 = ::mac_with_carry(r3, (self.0).0[1], (other.0).0[2], &mut carry);
+        let r4 = ::mac_with_carry(r4, (self.0).0[1], (other.0).0[3], &mut carry);
+        let r5 = carry;
+        let mut carry = 0;
+        let r2 = ::mac_with_carry(r2, (self.0).0[2], (other.0).0[0], &mut carry);
+        let r3 = ::mac_with_carry(r3, (self.0).0[2], (other.0).0[1], &mut carry);
+        let r4 = ::mac_with_carry(r4, (self.0).0[2], (other.0).0[2], &mut carry);
+        let r5 = ::mac_with_carry(r5, (self.0).0[2], (other.0).0[3], &mut carry);
+        let r6 = carry;
+        let mut carry = 0;
+        let r3 = ::mac_with_carry(r3, (self.0).0[3], (other.0).0[0], &mut carry);
+        let r4 = ::mac_with_carry(r4, (self.0).0[3], (other.0).0[1], &mut carry);
+        let r5 = ::mac_with_carry(r5, (self.0).0[3], (other.0).0[2], &mut carry);
+        let r6 = ::mac_with_carry(r6, (self.0).0[3], (other.0).0[3], &mut carry);
+        let r7 = carry;
+        self.mont_reduce(r0, r1, r2, r3, r4, r5, r6, r7);
+    }
+
+    #[inline]
+    fn square(&mut self) {
+        let mut carry = 0;
+        let r1 = ::mac_with_carry(0, (self.0).0[0], (self.0).0[1], &mut carry);
+        let r2 = ::mac_with_carry(0, (self.0).0[0], (self.0).0[2], &mut carry);
+        let r3 = ::mac_with_carry(0, (self.0).0[0], (self.0).0[3], &mut carry);
+        let r4 = carry;
+        let mut carry = 0;
+        let r3 = ::mac_with_carry(r3, (self.0).0[1], (self.0).0[2], &mut carry);
+        let r4 = ::mac_with_carry(r4, (self.0).0[1], (self.0).0[3], &mut carry);
+        let r5 = carry;
+        let mut carry = 0;
+        let r5 = ::mac_with_carry(r5, (self.0).0[2], (self.0).0[3], &mut carry);
+        let r6 = carry;
+
+        let r7 = r6 >> 63;
+        let r6 = (r6 << 1) | (r5 >> 63);
+        let r5 = (r5 << 1) | (r4 >> 63);
+        let r4 = (r4 << 1) | (r3 >> 63);
+        let r3 = (r3 << 1) | (r2 >> 63);
+        let r2 = (r2 << 1) | (r1 >> 63);
+        let r1 = r1 << 1;
+
+        let mut carry = 0;
+        let r0 = ::mac_with_carry(0, (self.0).0[0], (self.0).0[0], &mut carry);
+        let r1 = ::adc(r1, 0, &mut carry);
+        let r2 = ::mac_with_carry(r2, (self.0).0[1], (self.0).0[1], &mut carry);
+        let r3 = ::adc(r3, 0, &mut carry);
+        let r4 = ::mac_with_carry(r4, (self.0).0[2], (self.0).0[2], &mut carry);
+        let r5 = ::adc(r5, 0, &mut carry);
+        let r6 = ::mac_with_carry(r6, (self.0).0[3], (self.0).0[3], &mut carry);
+        let r7 = ::adc(r7, 0, &mut carry);
+        self.mont_reduce(r0, r1, r2, r3, r4, r5, r6, r7);
+    }
+}
+
+impl Fr {
+    /// Determines if the element is really in the field. This is only used
+    /// internally.
+    #[inline(always)]
+    fn is_valid(&self) -> bool {
+        self.0 < MODULUS
+    }
+
+    /// Subtracts the modulus from this element if this element is not in the
+    /// field. Only used internally.
+    #[inline(always)]
+    fn reduce(&mut self) {
+        if !self.is_valid() {
+            self.0.sub_noborrow(&MODULUS);
+        }
+    }
+
+    #[inline(always)]
+    fn mont_reduce(
+        &mut self,
+        r0: u64,
+        mut r1: u64,
+        mut r2: u64,
+        mut r3: u64,
+        mut r4: u64,
+        mut r5: u64,
+        mut r6: u64,
+        mut r7: u64,
+    ) {
+        // The Montgomery reduction here is based on Algorithm 14.32 in
+        // Handbook of Applied Cryptography
+        // <http://cacr.uwaterloo.ca/hac/about/chap14.pdf>.
+
+        let k = r0.wrapping_mul(INV);
+        let mut carry = 0;
+        ::mac_with_carry(r0, k, MODULUS.0[0], &mut carry);
+        r1 = ::mac_with_carry(r1, k, MODULUS.0[1], &mut carry);
+        r2 = ::mac_with_carry(r2, k, MODULUS.0[2], &mut carry);
+        r3 = ::mac_with_carry(r3, k, MODULUS.0[3], &mut carry);
+        r4 = ::adc(r4, 0, &mut carry);
+        let carry2 = carry;
+        let k = r1.wrapping_mul(INV);
+        let mut carry = 0;
+        ::mac_with_carry(r1, k, MODULUS.0[0], &mut carry);
+        r2 = ::mac_with_carry(r2, k, MODULUS.0[1], &mut carry);
+        r3 = ::mac_with_carry(r3, k, MODULUS.0[2], &mut carry);
+        r4 = ::mac_with_carry(r4, k, MODULUS.0[3], &mut carry);
+        r5 = ::adc(r5, carry2, &mut carry);
+        let carry2 = carry;
+        let k = r2.wrapping_mul(INV);
+        let mut carry = 0;
+        ::mac_with_carry(r2, k, MODULUS.0[0], &mut carry);
+        r3 = ::mac_with_carry(r3, k, MODULUS.0[1], &mut carry);
+        r4 = ::mac_with_carry(r4, k, MODULUS.0[2], &mut carry);
+        r5 = ::mac_with_carry(r5, k, MODULUS.0[3], &mut carry);
+        r6 = ::adc(r6, carry2, &mut carry);
+        let carry2 = carry;
+        let k = r3.wrapping_mul(INV);
+        let mut carry = 0;
+        ::mac_with_carry(r3, k, MODULUS.0[0], &mut carry);
+        r4 = ::mac_with_carry(r4, k, MODULUS.0[1], &mut carry);
+        r5 = ::mac_with_carry(r5, k, MODULUS.0[2], &mut carry);
+        r6 = ::mac_with_carry(r6, k, MODULUS.0[3], &mut carry);
+        r7 = ::adc(r7, carry2, &mut carry);
+        (self.0).0[0] = r4;
+        (self.0).0[1] = r5;
+        (self.0).0[2] = r6;
+        (self.0).0[3] = r7;
+        self.reduce();
+    }
+}
+
+impl SqrtField for Fr {
+    fn legendre(&self) -> ::LegendreSymbol {
+        // s = self^((r - 1) // 2)
+        let s = self.pow([
+            0xa1f0fac9f8000000,
+            0x9419f4243cdcb848,
+            0xdc2822db40c0ac2e,
+            0x183227397098d014,
+        ]);
+        if s == Self::zero() {
+            Zero
+        } else if s == Self::one() {
+            QuadraticResidue
+        } else {
+            QuadraticNonResidue
+        }
+    }
+
+    fn sqrt(&self) -> Option<Self> {
+        // Tonelli-Shank's algorithm for q mod 16 = 1
+        // https://eprint.iacr.org/2012/685.pdf (page 12, algorithm 5)
+        match self.legendre() {
+            Zero => Some(*self),
+            QuadraticNonResidue => None,
+            QuadraticResidue => {
+                let mut c = Fr(ROOT_OF_UNITY);
+                // r = self^((t + 1) // 2)
+                let mut r = self.pow([
+                    0xcdcb848a1f0faca0,
+                    0xc0ac2e9419f4243,
+                    0x98d014dc2822db4,
+                    0x183227397,
+                ]);
+                // t = self^t
+                let mut t = self.pow([
+                    0x9b9709143e1f593f,
+                    0x181585d2833e8487,
+                    0x131a029b85045b68,
+                    0x30644e72e,
+                ]);
+                let mut m = S;
+
+                while t != Self::one() {
+                    let mut i = 1;
+                    {
+                        let mut t2i = t;
+                        t2i.square();
+                        loop {
+                            if t2i == Self::one() {
+                                break;
+                            }
+                            t2i.square();
+                            i += 1;
+                        }
+                    }
+
+                    for _ in 0..(m - i - 1) {
+                        c.square();
+                    }
+                    r.mul_assign(&c);
+                    c.square();
+                    t.mul_assign(&c);
+                    m = i;
+                }
+
+                Some(r)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+use rand::{Rand, SeedableRng, XorShiftRng};
+
+#[test]
+fn test_fr_is_valid() {
+    let mut a = Fr(MODULUS);
+    assert!(!a.is_valid());
+    a.0.sub_noborrow(&FrRepr::from(1));
+    assert!(a.is_valid());
+    assert!(Fr(FrRepr::from(0)).is_valid());
+
+    let mut rng = XorShiftRng::from_seed([0x5dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+    for _ in 0..1000 {
+        let a = Fr::rand(&mut rng);
+        assert!(a.is_valid());
+    }
+}
+
+#[test]
+fn test_fr_sqrt() {
+    let mut rng = XorShiftRng::from_seed([0x5dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+    assert_eq!(Fr::zero().sqrt().unwrap(), Fr::zero());
+
+    for _ in 0..1000 {
+        // Ensure sqrt(a^2) = a or -a
+        let a = Fr::rand(&mut rng);
+        let mut nega = a;
+        nega.negate();
+        let mut b = a;
+        b.square();
+
+        let b = b.sqrt().unwrap();
+
+        assert!(a == b || nega == b);
+    }
+}
+
+#[test]
+fn test_fr_num_bits() {
+    assert_eq!(Fr::NUM_BITS, 254);
+    assert_eq!(Fr::CAPACITY, 253);
+}
+
+#[test]
+fn test_fr_root_of_unity() {
+    assert_eq!(Fr::S, 28);
+    assert_eq!(
+        Fr::multiplicative_generator(),
+        Fr::from_repr(FrRepr::from(7)).unwrap()
+    );
+    assert_eq!(
+        Fr::multiplicative_generator().pow([
+            0x9b9709143e1f593f,
+            0x181585d2833e8487,
+            0x131a029b85045b68,
+            0x30644e72e
+        ]),
+        Fr::root_of_unity()
+    );
+    assert_eq!(Fr::root_of_unity().pow([1 << Fr::S]), Fr::one());
+    assert!(Fr::multiplicative_generator().sqrt().is_none());
+}
+
+#[test]
+fn fr_field_tests() {
+    ::tests::field::random_field_tests::<Fr>();
+    ::tests::field::random_sqrt_tests::<Fr>();
+    ::tests::field::random_frobenius_tests::<Fr, _>(Fr::char(), 13);
+    ::tests::field::from_str_tests::<Fr>();
+}
+
+#[test]
+fn fr_repr_tests() {
+    ::tests::repr::random_repr_tests::<FrRepr>();
+}
@@ -17,6 +17,10 @@
 
 extern crate byteorder;
 extern crate rand;
+extern crate sha2;
+
+#[cfg(test)]
+extern crate hex;
 
 #[cfg(not(feature = "std"))]
 #[macro_use]
@@ -40,10 +44,14 @@ pub mod io;
 pub mod tests;
 
 pub mod bls12_381;
+pub mod bn256;
 
 mod wnaf;
 pub use self::wnaf::Wnaf;
 
+mod multiexp;
+pub use self::multiexp::multiexp;
+
 /// An "engine" is a collection of types (fields, elliptic curve groups, etc.)
 /// with well-defined relationships. In particular, the G1/G2 curve groups are
 /// of prime order `r`, and are equipped with a bilinear pairing function.
@@ -737,3 +745,43 @@ mod arith_impl {
         combine_u64(r1, r0)
     }
 }
+
+#[test]
+fn test_arith() {
+    use rand::{Rng, SeedableRng, XorShiftRng};
+
+    // Both backends have to agree with the plain 128-bit arithmetic.
+    fn check(a: u64, b: u64, c: u64, carry: u64) {
+        let mut tmp = carry;
+        let sum = u128::from(a) + u128::from(b) + u128::from(carry);
+        assert_eq!(adc(a, b, &mut tmp), sum as u64);
+        assert_eq!(tmp, (sum >> 64) as u64);
+
+        let mut tmp = carry;
+        let mac = u128::from(a) + u128::from(b) * u128::from(c) + u128::from(carry);
+        assert_eq!(mac_with_carry(a, b, c, &mut tmp), mac as u64);
+        assert_eq!(tmp, (mac >> 64) as u64);
+
+        let borrow = carry & 1;
+        let mut tmp = borrow;
+        let diff = u128::from(a).wrapping_sub(u128::from(b)).wrapping_sub(u128::from(borrow));
+        assert_eq!(sbb(a, b, &mut tmp), diff as u64);
+        assert_eq!(tmp, (u128::from(a) < u128::from(b) + u128::from(borrow)) as u64);
+    }
+
+    let edges = [0, 1, 0xffffffff, 0x100000000, u64::max_value() - 1, u64::max_value()];
+    for &a in edges.iter() {
+        for &b in edges.iter() {
+            for &c in edges.iter() {
+                for &carry in edges.iter() {
+                    check(a, b, c, carry);
+                }
+            }
+        }
+    }
+
+    let mut rng = XorShiftRng::from_seed([0x5dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+    for _ in 0..100000 {
+        check(rng.gen(), rng.gen(), rng.gen(), rng.gen());
+    }
+}
@@ -0,0 +1,85 @@
+use super::{CurveAffine, CurveProjective, PrimeField, PrimeFieldRepr};
+use std::vec::Vec;
+
+/// Returns the `c`-bit window of a scalar starting from the `skip`-th bit.
+fn scalar_window<S: PrimeFieldRepr>(scalar: &S, skip: usize, c: usize) -> usize {
+    let limbs = scalar.as_ref();
+    let limb = skip / 64;
+    let shift = skip % 64;
+
+    if limb >= limbs.len() {
+        return 0;
+    }
+
+    let mut window = limbs[limb] >> shift;
+    if shift + c > 64 && limb + 1 < limbs.len() {
+        window |= limbs[limb + 1] << (64 - shift);
+    }
+
+    (window & ((1 << c) - 1)) as usize
+}
+
+/// Recommends a bucket window size given the number of terms.
+/// It approximates `ln(n)`, which balances the bucket accumulation
+/// against the additions per window.
+fn recommended_window_size(num_terms: usize) -> usize {
+    if num_terms < 32 {
+        3
+    } else {
+        // floor(log2(n)) * ln(2), rounded up
+        let log2 = (0usize.leading_zeros() - num_terms.leading_zeros() - 1) as usize;
+        (log2 * 69 + 99) / 100
+    }
+}
+
+/// Computes `sum(bases[i] * scalars[i])` with Pippenger's bucket method.
+///
+/// Each `c`-bit window of every scalar puts the corresponding base into one of
+/// `2^c - 1` buckets with a single mixed addition, so the whole computation
+/// needs roughly `n * bits / c` additions instead of `n` full scalar multiplications.
+///
+/// Panics if `bases` and `scalars` don't have the same length.
+pub fn multiexp<G: CurveAffine>(
+    bases: &[G],
+    scalars: &[<G::Scalar as PrimeField>::Repr],
+) -> G::Projective {
+    assert_eq!(bases.len(), scalars.len());
+
+    if bases.is_empty() {
+        return G::Projective::zero();
+    }
+
+    let c = recommended_window_size(bases.len());
+    let num_bits = G::Scalar::NUM_BITS as usize;
+    let num_windows = (num_bits + c - 1) / c;
+
+    let mut buckets: Vec<G::Projective> = vec![G::Projective::zero(); (1 << c) - 1];
+    let mut acc = G::Projective::zero();
+
+    for w in (0..num_windows).rev() {
+        for _ in 0..c {
+            acc.double();
+        }
+
+        for bucket in buckets.iter_mut() {
+            *bucket = G::Projective::zero();
+        }
+
+        for (base, scalar) in bases.iter().zip(scalars.iter()) {
+            let index = scalar_window(scalar, w * c, c);
+            if index != 0 {
+                buckets[index - 1].add_assign_mixed(base);
+            }
+        }
+
+        // Sum up `j * buckets[j - 1]` with running sums:
+        // the bucket for `j` gets added exactly `j` times.
+        let mut running_sum = G::Projective::zero();
+        for bucket in buckets.iter().rev() {
+            running_sum.add_assign(bucket);
+            acc.add_assign(&running_sum);
+        }
+    }
+
+    acc
+}
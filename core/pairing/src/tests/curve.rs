@@ -64,9 +64,46 @@ pub fn curve_tests<G: CurveProjective>()
     random_negation_tests::<G>();
     random_transformation_tests::<G>();
     random_wnaf_tests::<G>();
+    random_multiexp_tests::<G>();
     random_encoding_tests::<G::Affine>();
 }
 
+fn random_multiexp_tests<G: CurveProjective>()
+    where G: ::std::fmt::Debug, G::Affine: ::std::fmt::Debug
+{
+    use multiexp;
+    use PrimeField;
+
+    let mut rng = XorShiftRng::from_seed([0x5dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+    assert!(multiexp::<G::Affine>(&[], &[]).is_zero());
+
+    // Cover both the small and the large window sizes.
+    for &n in [1, 2, 22, 33, 104].iter() {
+        let bases: Vec<G::Affine> = (0..n).map(|_| G::rand(&mut rng).into_affine()).collect();
+        let mut scalars: Vec<_> = (0..n).map(|_| G::Scalar::rand(&mut rng).into_repr()).collect();
+
+        // Edge cases with the zero scalar, the largest scalar and the point at infinity.
+        scalars[0] = G::Scalar::zero().into_repr();
+        if n > 1 {
+            let mut max = G::Scalar::zero();
+            max.sub_assign(&G::Scalar::one());
+            scalars[n - 1] = max.into_repr();
+        }
+        let mut bases_with_zero = bases.clone();
+        bases_with_zero[n / 2] = G::Affine::zero();
+
+        for bases in [bases, bases_with_zero].iter() {
+            let mut expected = G::zero();
+            for (base, scalar) in bases.iter().zip(scalars.iter()) {
+                expected.add_assign(&base.mul(*scalar));
+            }
+
+            assert_eq!(multiexp(&bases[..], &scalars[..]), expected);
+        }
+    }
+}
+
 fn random_wnaf_tests<G: CurveProjective>() 
     where G: ::std::fmt::Debug, G::Affine: ::std::fmt::Debug
 {
@@ -0,0 +1,73 @@
+#[cfg(feature = "std")]
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+#[cfg(feature = "std")]
+use substrate_primitives::bytes;
+#[cfg(feature = "std")]
+use substrate_primitives::hexdisplay::AsBytesRef;
+use fixed_hash::construct_fixed_hash;
+use parity_codec::{Encode, Decode, Input};
+use pairing::io;
+use core::convert::TryFrom;
+
+const SIZE: usize = bls::PUBLIC_KEY_SIZE;
+
+construct_fixed_hash! {
+    pub struct H384(SIZE);
+}
+
+/// A compressed BLS public key of an authority.
+pub type BlsPublicKey = H384;
+
+#[cfg(feature = "std")]
+impl Serialize for BlsPublicKey {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        bytes::serialize(&self.0, serializer)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'de> Deserialize<'de> for BlsPublicKey {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: Deserializer<'de>
+    {
+        bytes::deserialize_check_len(deserializer, bytes::ExpectedLen::Exact(SIZE))
+            .map(|x| BlsPublicKey::from_slice(&x))
+    }
+}
+
+impl Encode for BlsPublicKey {
+    fn using_encoded<R, F: FnOnce(&[u8]) -> R>(&self, f: F) -> R {
+        self.0.using_encoded(f)
+    }
+}
+
+impl Decode for BlsPublicKey {
+    fn decode<I: Input>(input: &mut I) -> Option<Self> {
+        <[u8; SIZE] as Decode>::decode(input).map(H384)
+    }
+}
+
+#[cfg(feature = "std")]
+impl AsBytesRef for BlsPublicKey {
+    fn as_bytes_ref(&self) -> &[u8] {
+        self.as_ref()
+    }
+}
+
+impl TryFrom<bls::PublicKey> for BlsPublicKey {
+    type Error = io::Error;
+
+    fn try_from(pk: bls::PublicKey) -> Result<Self, io::Error> {
+        Ok(H384(pk.to_bytes()))
+    }
+}
+
+impl TryFrom<&BlsPublicKey> for bls::PublicKey {
+    type Error = io::Error;
+
+    fn try_from(pk: &BlsPublicKey) -> Result<Self, io::Error> {
+        bls::PublicKey::read(&pk.0[..])
+    }
+}
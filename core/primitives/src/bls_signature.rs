@@ -0,0 +1,167 @@
+#[cfg(feature = "std")]
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+#[cfg(feature = "std")]
+use substrate_primitives::bytes;
+#[cfg(feature = "std")]
+use substrate_primitives::hexdisplay::AsBytesRef;
+use fixed_hash::construct_fixed_hash;
+use parity_codec::{Encode, Decode, Input};
+use runtime_primitives::traits::{Verify, Lazy};
+use pairing::io;
+use crate::BlsPublicKey;
+#[cfg(feature = "std")]
+use ::std::vec::Vec;
+#[cfg(not(feature = "std"))]
+use crate::std::vec::Vec;
+use core::convert::TryFrom;
+
+const SIZE: usize = bls::SIGNATURE_SIZE;
+
+construct_fixed_hash! {
+    pub struct H768(SIZE);
+}
+
+/// A compressed BLS signature, which is also the size of an aggregate signature
+/// of any number of authorities.
+pub type BlsSignature = H768;
+
+#[cfg(feature = "std")]
+impl Serialize for BlsSignature {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        bytes::serialize(&self.0, serializer)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'de> Deserialize<'de> for BlsSignature {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: Deserializer<'de>
+    {
+        bytes::deserialize_check_len(deserializer, bytes::ExpectedLen::Exact(SIZE))
+            .map(|x| BlsSignature::from_slice(&x))
+    }
+}
+
+impl Encode for BlsSignature {
+    fn using_encoded<R, F: FnOnce(&[u8]) -> R>(&self, f: F) -> R {
+        self.0.using_encoded(f)
+    }
+}
+
+impl Decode for BlsSignature {
+    fn decode<I: Input>(input: &mut I) -> Option<Self> {
+        <[u8; SIZE] as Decode>::decode(input).map(H768)
+    }
+}
+
+#[cfg(feature = "std")]
+impl AsBytesRef for BlsSignature {
+    fn as_bytes_ref(&self) -> &[u8] {
+        self.as_ref()
+    }
+}
+
+impl Verify for BlsSignature {
+    type Signer = BlsPublicKey;
+
+    fn verify<L: Lazy<[u8]>>(&self, mut msg: L, signer: &Self::Signer) -> bool {
+        let sig = match bls::Signature::try_from(self) {
+            Ok(s) => s,
+            Err(_) => return false
+        };
+
+        match bls::PublicKey::try_from(signer) {
+            Ok(pk) => pk.verify(msg.get(), &sig),
+            Err(_) => false
+        }
+    }
+}
+
+impl BlsSignature {
+    /// Verifies an aggregate signature of `msg` by all the `signers`,
+    /// whose proofs of possession must have been checked when they were registered.
+    pub fn fast_aggregate_verify(&self, msg: &[u8], signers: &[BlsPublicKey]) -> bool {
+        let sig = match bls::Signature::try_from(self) {
+            Ok(s) => s,
+            Err(_) => return false
+        };
+
+        let pks = match signers.iter().map(bls::PublicKey::try_from).collect::<Result<Vec<_>, _>>() {
+            Ok(pks) => pks,
+            Err(_) => return false
+        };
+
+        bls::fast_aggregate_verify(&pks, msg, &sig)
+    }
+
+    /// Aggregates the signatures of the same message into one.
+    pub fn aggregate(sigs: &[BlsSignature]) -> Result<Self, io::Error> {
+        let sigs = sigs.iter()
+            .map(bls::Signature::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        BlsSignature::try_from(bls::Signature::aggregate(&sigs)?)
+    }
+}
+
+impl TryFrom<bls::Signature> for BlsSignature {
+    type Error = io::Error;
+
+    fn try_from(sig: bls::Signature) -> Result<Self, io::Error> {
+        let mut writer = [0u8; SIZE];
+        sig.write(&mut writer[..])?;
+
+        Ok(H768(writer))
+    }
+}
+
+impl TryFrom<&BlsSignature> for bls::Signature {
+    type Error = io::Error;
+
+    fn try_from(sig: &BlsSignature) -> Result<Self, io::Error> {
+        bls::Signature::read(&sig.0[..])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{SeedableRng, XorShiftRng};
+    use bls::SecretKey;
+
+    #[test]
+    fn test_bls_sig_encode_decode() {
+        let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let sk = SecretKey::generate(rng);
+
+        let sig = BlsSignature::try_from(sk.sign(b"Foo bar")).unwrap();
+        let pk = BlsPublicKey::try_from(sk.public_key()).unwrap();
+        assert!(sig.verify(&b"Foo bar"[..], &pk));
+
+        let decoded_sig = BlsSignature::decode(&mut sig.encode().as_slice()).unwrap();
+        let decoded_pk = BlsPublicKey::decode(&mut pk.encode().as_slice()).unwrap();
+        assert_eq!(sig, decoded_sig);
+        assert_eq!(pk, decoded_pk);
+    }
+
+    #[test]
+    fn test_bls_fast_aggregate_verify() {
+        let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let msg = b"checkpoint";
+
+        let sks: Vec<_> = (0..4).map(|_| SecretKey::generate(rng)).collect();
+        let pks: Vec<_> = sks.iter()
+            .map(|sk| BlsPublicKey::try_from(sk.public_key()).unwrap())
+            .collect();
+        let sigs: Vec<_> = sks.iter()
+            .map(|sk| BlsSignature::try_from(sk.sign(msg)).unwrap())
+            .collect();
+
+        let agg = BlsSignature::aggregate(&sigs).unwrap();
+        assert!(agg.fast_aggregate_verify(msg, &pks));
+        assert!(!agg.fast_aggregate_verify(msg, &pks[..3]));
+        assert!(!BlsSignature::zero().fast_aggregate_verify(msg, &pks));
+    }
+}
@@ -1,28 +1,111 @@
 #[cfg(feature = "std")]
-use ::std::vec::Vec;
-#[cfg(not(feature = "std"))]
-use crate::std::vec::Vec;
-use crate::{PARAMS, LeftCiphertext, RightCiphertext};
+use ::std::fmt;
+#[cfg(feature = "std")]
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+#[cfg(feature = "std")]
+use substrate_primitives::bytes;
+use crate::{LeftCiphertext, RightCiphertext};
+use crate::point::{read_point, POINT_SIZE};
 use zcrypto::elgamal;
 use pairing::{
     bls12_381::Bls12,
     io
 };
-use parity_codec::{Encode, Decode};
+use parity_codec::{Encode, Decode, Input};
 use core::convert::{TryInto, TryFrom};
 
-#[derive(Eq, PartialEq, Clone, Default, Encode, Decode)]
-#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
-pub struct Ciphertext(Vec<u8>);
+const SIZE: usize = 64;
+
+/// An ElGamal ciphertext: the encoded left and right points.
+///
+/// Storage migration: it used to be a `Vec<u8>`, whose SCALE encoding starts with a compact
+/// length prefix (`0x0101` for 64 bytes). `EncryptedBalance` and `PendingTransfer` of the balance
+/// modules and `TotalSupply` of encrypted-assets stored in that layout do not decode any more.
+/// A chain started before must strip the prefix from every stored value, or restart from a new genesis.
+#[derive(Copy)]
+pub struct Ciphertext([u8; SIZE]);
+
+impl Clone for Ciphertext {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl PartialEq for Ciphertext {
+    fn eq(&self, other: &Self) -> bool {
+        self.0[..] == other.0[..]
+    }
+}
+
+impl Eq for Ciphertext { }
+
+/// The encryption of zero with no randomness, whose halves are both the identity.
+/// All zero bytes are not a valid encoding, and the default ends up in events.
+impl Default for Ciphertext {
+    fn default() -> Self {
+        Ciphertext::zero()
+    }
+}
+
+#[cfg(feature = "std")]
+impl fmt::Debug for Ciphertext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Ciphertext(0x")?;
+        for i in &self.0[..] {
+            write!(f, "{:02x}", i)?;
+        }
+        write!(f, ")")
+    }
+}
+
+#[cfg(feature = "std")]
+impl Serialize for Ciphertext {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        bytes::serialize(&self.0[..], serializer)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'de> Deserialize<'de> for Ciphertext {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: Deserializer<'de>
+    {
+        let x = bytes::deserialize_check_len(deserializer, bytes::ExpectedLen::Exact(SIZE))?;
+        let ct = Ciphertext::from_slice(&x);
+        elgamal::Ciphertext::<Bls12>::try_from(&ct).map_err(serde::de::Error::custom)?;
+
+        Ok(ct)
+    }
+}
+
+impl Encode for Ciphertext {
+    fn using_encoded<R, F: FnOnce(&[u8]) -> R>(&self, f: F) -> R {
+        f(&self.0[..])
+    }
+}
+
+impl Decode for Ciphertext {
+    fn decode<I: Input>(input: &mut I) -> Option<Self> {
+        let mut buf = [0u8; SIZE];
+        if input.read(&mut buf[..]) != SIZE {
+            return None;
+        }
+
+        let ct = Ciphertext(buf);
+        elgamal::Ciphertext::<Bls12>::try_from(&ct).ok().map(|_| ct)
+    }
+}
 
 impl TryFrom<elgamal::Ciphertext<Bls12>> for Ciphertext {
     type Error = io::Error;
 
     fn try_from(point: elgamal::Ciphertext<Bls12>) -> Result<Self, io::Error> {
-        let mut writer = [0u8; 64];
+        let mut writer = [0u8; SIZE];
         point.write(&mut writer[..])?;
 
-        Ok(Ciphertext(writer.to_vec()))
+        Ok(Ciphertext(writer))
     }
 }
 
@@ -30,10 +113,10 @@ impl TryFrom<&elgamal::Ciphertext<Bls12>> for Ciphertext {
     type Error = io::Error;
 
     fn try_from(point: &elgamal::Ciphertext<Bls12>) -> Result<Self, io::Error> {
-        let mut writer = [0u8; 64];
+        let mut writer = [0u8; SIZE];
         point.write(&mut writer[..])?;
 
-        Ok(Ciphertext(writer.to_vec()))
+        Ok(Ciphertext(writer))
     }
 }
 
@@ -41,7 +124,10 @@ impl TryFrom<Ciphertext> for elgamal::Ciphertext<Bls12> {
     type Error = io::Error;
 
     fn try_from(ct: Ciphertext) -> Result<Self, io::Error> {
-        elgamal::Ciphertext::read(&mut &ct.0[..], &*PARAMS)
+        Ok(elgamal::Ciphertext::new(
+            read_point(&ct.0[..POINT_SIZE])?,
+            read_point(&ct.0[POINT_SIZE..])?
+        ))
     }
 }
 
@@ -49,7 +135,10 @@ impl TryFrom<&Ciphertext> for elgamal::Ciphertext<Bls12> {
     type Error = io::Error;
 
     fn try_from(ct: &Ciphertext) -> Result<Self, io::Error> {
-        elgamal::Ciphertext::read(&mut &ct.0[..], &*PARAMS)
+        Ok(elgamal::Ciphertext::new(
+            read_point(&ct.0[..POINT_SIZE])?,
+            read_point(&ct.0[POINT_SIZE..])?
+        ))
     }
 }
 
@@ -74,8 +163,11 @@ impl TryFrom<Ciphertext> for RightCiphertext {
 }
 
 impl Ciphertext {
+    /// Panics if the slice is not 64 bytes long.
     pub fn from_slice(slice: &[u8]) -> Self {
-        Ciphertext(slice.to_vec())
+        let mut buf = [0u8; SIZE];
+        buf.copy_from_slice(slice);
+        Ciphertext(buf)
     }
 
     pub fn from_left_right(left: LeftCiphertext, right: RightCiphertext) -> Result<Self, io::Error> {
@@ -165,6 +257,15 @@ mod tests {
         assert_eq!(ciphertext_b, decoded_cipher);
     }
 
+    #[test]
+    fn test_default_round_trip() {
+        let default = Ciphertext::default();
+        assert_eq!(default, Ciphertext::zero());
+
+        let encoded = default.encode();
+        assert_eq!(Ciphertext::decode(&mut &encoded[..]), Some(default));
+    }
+
     #[test]
     fn test_ciphertext_rw() {
         let ciphertext = gen_ciphertext();
@@ -193,4 +294,32 @@ mod tests {
 
         assert!(ciphertext == ciphertext2);
     }
+
+    #[test]
+    fn test_ciphertext_decode_rejects_invalid() {
+        use crate::point::tests::{encode, random_point, small_order_point, non_canonical_identity};
+
+        let rng = &mut XorShiftRng::from_seed([0x5dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+        for _ in 0..20 {
+            let left = random_point(rng);
+            let right = random_point(rng);
+            let ct = Ciphertext::try_from(elgamal::Ciphertext::new(left.clone(), right.clone())).unwrap();
+
+            let encoded = ct.encode();
+            assert_eq!(encoded.len(), SIZE);
+            assert_eq!(Ciphertext::decode(&mut &encoded[..]), Some(ct));
+            assert!(Ciphertext::decode(&mut &encoded[..SIZE - 1]).is_none());
+
+            for bad in &[small_order_point(), non_canonical_identity()] {
+                let bad_left = [&bad[..], &encode(&right)[..]].concat();
+                let bad_right = [&encode(&left)[..], &bad[..]].concat();
+                assert!(Ciphertext::decode(&mut &bad_left[..]).is_none());
+                assert!(Ciphertext::decode(&mut &bad_right[..]).is_none());
+            }
+        }
+
+        let zero = Ciphertext::zero().encode();
+        assert_eq!(Ciphertext::decode(&mut &zero[..]), Some(Ciphertext::zero()));
+    }
 }
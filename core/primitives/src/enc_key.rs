@@ -9,7 +9,8 @@ use fixed_hash::construct_fixed_hash;
 use pairing::bls12_381::{Bls12, Fr};
 use pairing::io;
 use parity_codec::{Encode, Decode, Input};
-use crate::{PARAMS, IntoXY};
+use crate::IntoXY;
+use crate::point::read_non_identity_point;
 use core::convert::TryFrom;
 
 const SIZE: usize = 32;
@@ -34,8 +35,10 @@ impl<'de> Deserialize<'de> for EncKey {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
         where D: Deserializer<'de>
     {
-        bytes::deserialize_check_len(deserializer, bytes::ExpectedLen::Exact(SIZE))
-            .map(|x| EncKey::from_slice(&x))
+        let x = bytes::deserialize_check_len(deserializer, bytes::ExpectedLen::Exact(SIZE))?;
+        read_non_identity_point(&x[..]).map_err(serde::de::Error::custom)?;
+
+        Ok(EncKey::from_slice(&x))
     }
 }
 
@@ -47,7 +50,9 @@ impl Encode for EncKey {
 
 impl Decode for EncKey {
     fn decode<I: Input>(input: &mut I) -> Option<Self> {
-        <[u8; SIZE] as Decode>::decode(input).map(H256)
+        <[u8; SIZE] as Decode>::decode(input)
+            .map(H256)
+            .filter(|x| read_non_identity_point(x.as_bytes()).is_ok())
     }
 }
 
@@ -66,7 +71,7 @@ impl TryFrom<EncKey> for EncryptionKey<Bls12> {
     type Error = io::Error;
 
     fn try_from(enc_key: EncKey) -> Result<Self, io::Error> {
-        EncryptionKey::<Bls12>::read(&mut &enc_key.0[..], &PARAMS)
+        read_non_identity_point(enc_key.as_bytes()).map(EncryptionKey)
     }
 }
 
@@ -74,7 +79,7 @@ impl TryFrom<&EncKey> for EncryptionKey<Bls12> {
     type Error = io::Error;
 
     fn try_from(enc_key: &EncKey) -> Result<Self, io::Error> {
-        EncryptionKey::<Bls12>::read(&mut &enc_key.0[..], &PARAMS)
+        read_non_identity_point(enc_key.as_bytes()).map(EncryptionKey)
     }
 }
 
@@ -106,6 +111,7 @@ impl IntoXY<Bls12> for &EncKey {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::PARAMS;
     use rand::{Rng, SeedableRng, XorShiftRng};
     use core::convert::TryInto;
 
@@ -120,4 +126,11 @@ mod tests {
         let addr2 = account_id.try_into().unwrap();
         assert!(addr1 == addr2);
     }
+
+    #[test]
+    fn test_decode() {
+        use crate::point::tests::check_decode;
+
+        check_decode(|p| EncKey::try_from(EncryptionKey(p)).unwrap(), false);
+    }
 }
@@ -4,7 +4,7 @@ use serde::{Serialize, Serializer, Deserialize, Deserializer};
 use substrate_primitives::bytes;
 use crate::{PARAMS, IntoXY};
 use fixed_hash::construct_fixed_hash;
-use jubjub::curve::{JubjubBls12, edwards, PrimeOrder, Unknown};
+use jubjub::curve::{JubjubBls12, edwards, PrimeOrder};
 use jubjub::group_hash::group_hash;
 use pairing::{
     bls12_381::{Bls12, Fr},
@@ -12,6 +12,7 @@ use pairing::{
 };
 use parity_codec::{Encode, Decode, Input};
 use byteorder::{ByteOrder, LittleEndian};
+use crate::point::read_non_identity_point;
 use core::convert::TryFrom;
 
 const SIZE: usize = 32;
@@ -37,8 +38,10 @@ impl<'de> Deserialize<'de> for GEpoch {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
         where D: Deserializer<'de>
     {
-        bytes::deserialize_check_len(deserializer, bytes::ExpectedLen::Exact(SIZE))
-            .map(|x| GEpoch::from_slice(&x))
+        let x = bytes::deserialize_check_len(deserializer, bytes::ExpectedLen::Exact(SIZE))?;
+        read_non_identity_point(&x[..]).map_err(serde::de::Error::custom)?;
+
+        Ok(GEpoch::from_slice(&x))
     }
 }
 
@@ -50,7 +53,9 @@ impl Encode for GEpoch {
 
 impl Decode for GEpoch {
     fn decode<I: Input>(input: &mut I) -> Option<Self> {
-        <[u8; SIZE] as Decode>::decode(input).map(H256)
+        <[u8; SIZE] as Decode>::decode(input)
+            .map(H256)
+            .filter(|x| read_non_identity_point(x.as_bytes()).is_ok())
     }
 }
 
@@ -69,11 +74,7 @@ impl TryFrom<GEpoch> for edwards::Point<Bls12, PrimeOrder> {
     type Error = io::Error;
 
     fn try_from(g_epoch: GEpoch) -> Result<Self, io::Error> {
-        let mut bytes = g_epoch.as_bytes();
-
-        edwards::Point::<Bls12, Unknown>::read(&mut bytes, &PARAMS)?
-            .as_prime_order(&PARAMS)
-            .ok_or(io::Error::NotInField)
+        read_non_identity_point(g_epoch.as_bytes())
     }
 }
 
@@ -81,11 +82,7 @@ impl TryFrom<&GEpoch> for edwards::Point<Bls12, PrimeOrder> {
     type Error = io::Error;
 
     fn try_from(g_epoch: &GEpoch) -> Result<Self, io::Error> {
-        let mut bytes = g_epoch.as_bytes();
-
-        edwards::Point::<Bls12, Unknown>::read(&mut bytes, &PARAMS)?
-            .as_prime_order(&PARAMS)
-            .ok_or(io::Error::NotInField)
+        read_non_identity_point(g_epoch.as_bytes())
     }
 }
 
@@ -107,6 +104,23 @@ impl GEpoch {
         let g_epoch = find_group_hash(&epoch, GEPOCH_PERSONALIZATION, &PARAMS);
         GEpoch::try_from(g_epoch)
     }
+
+    /// The generator of the nonce of an account's `counter`-th transaction in the epoch.
+    /// The first one is the epoch generator itself, so a nonce `dec_key * g_epoch`
+    /// stays valid. Nonces of different counters can't be linked to each other.
+    pub fn group_hash_with_counter(curr_epoch: u32, counter: u32) -> Result<Self, io::Error> {
+        if counter == 0 {
+            return Self::group_hash(curr_epoch);
+        }
+
+        let mut epoch_counter = [0u8; 8];
+        LittleEndian::write_u32(&mut epoch_counter[..4], curr_epoch);
+        LittleEndian::write_u32(&mut epoch_counter[4..], counter);
+
+        // Hash_to_curve(GEPOCH_PERSONALIZATION || current_epoch || counter)
+        let g_epoch = find_group_hash(&epoch_counter, GEPOCH_PERSONALIZATION, &PARAMS);
+        GEpoch::try_from(g_epoch)
+    }
 }
 
 impl IntoXY<Bls12> for GEpoch {
@@ -148,6 +162,7 @@ fn find_group_hash(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use jubjub::curve::Unknown;
     use rand::{SeedableRng, XorShiftRng};
 
     #[test]
@@ -169,4 +184,23 @@ mod tests {
         println!("zero: {:?}", new_g_epoch);
         assert_eq!(new_g_epoch, zero_g_epch);
     }
+
+    #[test]
+    fn test_group_hash_with_counter() {
+        assert_eq!(GEpoch::group_hash_with_counter(3, 0).unwrap(), GEpoch::group_hash(3).unwrap());
+
+        let g1 = GEpoch::group_hash_with_counter(3, 1).unwrap();
+        let g2 = GEpoch::group_hash_with_counter(3, 2).unwrap();
+        assert!(g1 != g2);
+        assert!(g1 != GEpoch::group_hash(3).unwrap());
+        assert!(g1 != GEpoch::group_hash_with_counter(4, 1).unwrap());
+        assert!(edwards::Point::try_from(g1).is_ok());
+    }
+
+    #[test]
+    fn test_decode() {
+        use crate::point::tests::check_decode;
+
+        check_decode(|p| GEpoch::try_from(p).unwrap(), false);
+    }
 }
@@ -4,13 +4,14 @@ use serde::{Serialize, Serializer, Deserialize, Deserializer};
 use substrate_primitives::bytes;
 #[cfg(feature = "std")]
 use substrate_primitives::hexdisplay::AsBytesRef;
-use crate::{PARAMS, IntoXY};
+use crate::IntoXY;
 use fixed_hash::construct_fixed_hash;
 use pairing::bls12_381::{Bls12, Fr};
 use pairing::io;
-use jubjub::curve::{edwards, PrimeOrder, Unknown};
+use jubjub::curve::{edwards, PrimeOrder};
 use zcrypto::elgamal;
 use parity_codec::{Encode, Decode, Input};
+use crate::point::read_point;
 use core::convert::TryFrom;
 
 const SIZE: usize = 32;
@@ -35,8 +36,10 @@ impl<'de> Deserialize<'de> for LeftCiphertext {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
         where D: Deserializer<'de>
     {
-        bytes::deserialize_check_len(deserializer, bytes::ExpectedLen::Exact(SIZE))
-            .map(|x| LeftCiphertext::from_slice(&x))
+        let x = bytes::deserialize_check_len(deserializer, bytes::ExpectedLen::Exact(SIZE))?;
+        read_point(&x[..]).map_err(serde::de::Error::custom)?;
+
+        Ok(LeftCiphertext::from_slice(&x))
     }
 }
 
@@ -48,7 +51,9 @@ impl Encode for LeftCiphertext {
 
 impl Decode for LeftCiphertext {
     fn decode<I: Input>(input: &mut I) -> Option<Self> {
-        <[u8; SIZE] as Decode>::decode(input).map(H256)
+        <[u8; SIZE] as Decode>::decode(input)
+            .map(H256)
+            .filter(|x| read_point(x.as_bytes()).is_ok())
     }
 }
 
@@ -67,11 +72,7 @@ impl TryFrom<LeftCiphertext> for edwards::Point<Bls12, PrimeOrder> {
     type Error = io::Error;
 
     fn try_from(left_ciphertext: LeftCiphertext) -> Result<Self, io::Error> {
-        let mut bytes = left_ciphertext.as_bytes();
-
-        edwards::Point::<Bls12, Unknown>::read(&mut bytes, &PARAMS)?
-            .as_prime_order(&PARAMS)
-            .ok_or(io::Error::NotInField)
+        read_point(left_ciphertext.as_bytes())
     }
 }
 
@@ -79,11 +80,7 @@ impl TryFrom<&LeftCiphertext> for edwards::Point<Bls12, PrimeOrder> {
     type Error = io::Error;
 
     fn try_from(left_ciphertext: &LeftCiphertext) -> Result<Self, io::Error> {
-        let mut bytes = left_ciphertext.as_bytes();
-
-        edwards::Point::<Bls12, Unknown>::read(&mut bytes, &PARAMS)?
-            .as_prime_order(&PARAMS)
-            .ok_or(io::Error::NotInField)
+        read_point(left_ciphertext.as_bytes())
     }
 }
 
@@ -126,7 +123,7 @@ impl IntoXY<Bls12> for &LeftCiphertext {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use jubjub::curve::JubjubBls12;
+    use jubjub::curve::{JubjubBls12, Unknown};
     use rand::{SeedableRng, XorShiftRng};
 
     #[test]
@@ -145,4 +142,12 @@ mod tests {
         assert_eq!(left_point, left_point2);
         assert_ne!(right_point, left_point2);
     }
+
+    #[test]
+    fn test_decode() {
+        use crate::point::tests::check_decode;
+
+        // The identity is a valid ciphertext component
+        check_decode(|p| LeftCiphertext::try_from(p).unwrap(), true);
+    }
 }
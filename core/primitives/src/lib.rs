@@ -26,6 +26,9 @@ pub mod nonce;
 pub mod g_epoch;
 pub mod right_ciphertext;
 pub mod left_ciphertext;
+pub mod bls_public_key;
+pub mod bls_signature;
+mod point;
 
 pub use self::enc_key::EncKey;
 pub use self::signature::RedjubjubSignature;
@@ -36,6 +39,8 @@ pub use self::nonce::Nonce;
 pub use self::g_epoch::GEpoch;
 pub use self::right_ciphertext::RightCiphertext;
 pub use self::left_ciphertext::LeftCiphertext;
+pub use self::bls_public_key::BlsPublicKey;
+pub use self::bls_signature::BlsSignature;
 
 use lazy_static::lazy_static;
 use jubjub::curve::{JubjubBls12, JubjubEngine};
@@ -4,13 +4,13 @@ use serde::{Serialize, Serializer, Deserialize, Deserializer};
 use substrate_primitives::bytes;
 #[cfg(feature = "std")]
 use substrate_primitives::hexdisplay::AsBytesRef;
-use crate::PARAMS;
 use crate::IntoXY;
 use fixed_hash::construct_fixed_hash;
 use pairing::bls12_381::{Bls12, Fr};
-use jubjub::curve::{edwards, PrimeOrder, Unknown};
+use jubjub::curve::{edwards, PrimeOrder};
 use pairing::io;
 use parity_codec::{Encode, Decode, Input};
+use crate::point::read_non_identity_point;
 use core::convert::TryFrom;
 
 const SIZE: usize = 32;
@@ -35,8 +35,10 @@ impl<'de> Deserialize<'de> for Nonce {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
         where D: Deserializer<'de>
     {
-        bytes::deserialize_check_len(deserializer, bytes::ExpectedLen::Exact(SIZE))
-            .map(|x| Nonce::from_slice(&x))
+        let x = bytes::deserialize_check_len(deserializer, bytes::ExpectedLen::Exact(SIZE))?;
+        read_non_identity_point(&x[..]).map_err(serde::de::Error::custom)?;
+
+        Ok(Nonce::from_slice(&x))
     }
 }
 
@@ -48,7 +50,9 @@ impl Encode for Nonce {
 
 impl Decode for Nonce {
     fn decode<I: Input>(input: &mut I) -> Option<Self> {
-        <[u8; SIZE] as Decode>::decode(input).map(H256)
+        <[u8; SIZE] as Decode>::decode(input)
+            .map(H256)
+            .filter(|x| read_non_identity_point(x.as_bytes()).is_ok())
     }
 }
 
@@ -67,11 +71,7 @@ impl TryFrom<Nonce> for edwards::Point<Bls12, PrimeOrder> {
     type Error = io::Error;
 
     fn try_from(nonce: Nonce) -> Result<Self, io::Error> {
-        let mut bytes = nonce.as_bytes();
-
-        edwards::Point::<Bls12, Unknown>::read(&mut bytes, &PARAMS)?
-            .as_prime_order(&PARAMS)
-            .ok_or(io::Error::NotInField)
+        read_non_identity_point(nonce.as_bytes())
     }
 }
 
@@ -79,11 +79,7 @@ impl TryFrom<&Nonce> for edwards::Point<Bls12, PrimeOrder> {
     type Error = io::Error;
 
     fn try_from(nonce: &Nonce) -> Result<Self, io::Error> {
-        let mut bytes = nonce.as_bytes();
-
-        edwards::Point::<Bls12, Unknown>::read(&mut bytes, &PARAMS)?
-            .as_prime_order(&PARAMS)
-            .ok_or(io::Error::NotInField)
+        read_non_identity_point(nonce.as_bytes())
     }
 }
 
@@ -115,7 +111,7 @@ impl IntoXY<Bls12> for &Nonce {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use jubjub::curve::JubjubBls12;
+    use jubjub::curve::{JubjubBls12, Unknown};
     use rand::{SeedableRng, XorShiftRng};
 
     #[test]
@@ -129,4 +125,11 @@ mod tests {
 
         assert_eq!(point1, point2);
     }
+
+    #[test]
+    fn test_decode() {
+        use crate::point::tests::check_decode;
+
+        check_decode(|p| Nonce::try_from(p).unwrap(), false);
+    }
 }
@@ -0,0 +1,151 @@
+//! Validation shared by the types wrapping an encoded Jubjub point.
+
+use crate::PARAMS;
+use jubjub::curve::{edwards, PrimeOrder, Unknown};
+use pairing::bls12_381::Bls12;
+use pairing::io;
+
+pub(crate) const POINT_SIZE: usize = 32;
+
+/// Reads a point in the prime order subgroup, which may be the identity.
+/// The encoding must be canonical: writing the point back yields the same bytes.
+pub(crate) fn read_point(bytes: &[u8]) -> io::Result<edwards::Point<Bls12, PrimeOrder>> {
+    if bytes.len() != POINT_SIZE {
+        return Err(io::Error::InvalidData);
+    }
+
+    let point = edwards::Point::<Bls12, Unknown>::read(&mut &bytes[..], &PARAMS)?
+        .as_prime_order(&PARAMS)
+        .ok_or(io::Error::NotInField)?;
+
+    let mut canonical = [0u8; POINT_SIZE];
+    point.write(&mut &mut canonical[..])?;
+    if &canonical[..] != bytes {
+        return Err(io::Error::InvalidData);
+    }
+
+    Ok(point)
+}
+
+/// Reads a point of prime order, as `read_point` does, rejecting the identity.
+pub(crate) fn read_non_identity_point(bytes: &[u8]) -> io::Result<edwards::Point<Bls12, PrimeOrder>> {
+    let point = read_point(bytes)?;
+
+    if point == edwards::Point::zero() {
+        return Err(io::Error::PointInfinity);
+    }
+
+    Ok(point)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use pairing::{Field, PrimeField, PrimeFieldRepr, bls12_381::Fr};
+    use parity_codec::{Encode, Decode};
+    use rand::{Rng, SeedableRng, XorShiftRng};
+
+    pub(crate) fn encode<S>(point: &edwards::Point<Bls12, S>) -> [u8; POINT_SIZE] {
+        let mut buf = [0u8; POINT_SIZE];
+        point.write(&mut &mut buf[..]).unwrap();
+        buf
+    }
+
+    pub(crate) fn random_point<R: Rng>(rng: &mut R) -> edwards::Point<Bls12, PrimeOrder> {
+        edwards::Point::<Bls12, Unknown>::rand(rng, &PARAMS).mul_by_cofactor(&PARAMS)
+    }
+
+    /// The encoding of (0, -1), which has order 2.
+    pub(crate) fn small_order_point() -> [u8; POINT_SIZE] {
+        let mut neg_one = Fr::one();
+        neg_one.negate();
+        let point = edwards::Point::<Bls12, Unknown>::get_for_y(neg_one, false, &PARAMS).unwrap();
+        encode(&point)
+    }
+
+    /// The identity (0, 1) with the sign bit of x set.
+    pub(crate) fn non_canonical_identity() -> [u8; POINT_SIZE] {
+        let mut buf = encode(&edwards::Point::<Bls12, PrimeOrder>::zero());
+        buf[POINT_SIZE - 1] |= 0x80;
+        buf
+    }
+
+    /// Check that `T` round-trips the SCALE encoding of random points,
+    /// and that its `Decode` rejects truncated, small order and non-canonical encodings.
+    /// The identity is accepted if and only if `allows_identity`.
+    pub(crate) fn check_decode<T, F>(from_point: F, allows_identity: bool)
+    where
+        T: Encode + Decode + PartialEq + ::std::fmt::Debug,
+        F: Fn(edwards::Point<Bls12, PrimeOrder>) -> T,
+    {
+        let rng = &mut XorShiftRng::from_seed([0x5dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+        for _ in 0..20 {
+            let x = from_point(random_point(rng));
+            let encoded = x.encode();
+            assert_eq!(encoded.len(), POINT_SIZE);
+            assert_eq!(T::decode(&mut &encoded[..]), Some(x));
+            assert!(T::decode(&mut &encoded[..POINT_SIZE - 1]).is_none());
+        }
+
+        assert!(T::decode(&mut &small_order_point()[..]).is_none());
+        assert!(T::decode(&mut &non_canonical_identity()[..]).is_none());
+
+        let identity = encode(&edwards::Point::<Bls12, PrimeOrder>::zero());
+        assert_eq!(T::decode(&mut &identity[..]).is_some(), allows_identity);
+    }
+
+    #[test]
+    fn test_read_point_round_trip() {
+        let rng = &mut XorShiftRng::from_seed([0x5dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+        for _ in 0..100 {
+            let point = random_point(rng);
+            let bytes = encode(&point);
+
+            assert!(read_point(&bytes).unwrap() == point);
+            assert!(read_non_identity_point(&bytes).unwrap() == point);
+        }
+    }
+
+    #[test]
+    fn test_read_point_random_bytes() {
+        let rng = &mut XorShiftRng::from_seed([0x3dbe6258, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+        // Most random strings are rejected; the ones accepted must re-encode to themselves.
+        for _ in 0..1000 {
+            let bytes: [u8; POINT_SIZE] = rng.gen();
+
+            if let Ok(point) = read_point(&bytes) {
+                assert_eq!(encode(&point), bytes);
+            }
+        }
+    }
+
+    #[test]
+    fn test_read_point_rejects_malformed() {
+        let rng = &mut XorShiftRng::from_seed([0x3dbe6258, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let bytes = encode(&random_point(rng));
+
+        // Wrong length
+        assert!(read_point(&bytes[..31]).is_err());
+        assert!(read_point(&[&bytes[..], &[0u8][..]].concat()).is_err());
+
+        // y not in the field
+        let mut modulus = [0u8; POINT_SIZE];
+        Fr::char().write_le(&mut &mut modulus[..]).unwrap();
+        assert!(read_point(&modulus).is_err());
+
+        // Small order and non-canonical encodings
+        assert!(read_point(&small_order_point()).is_err());
+        assert!(read_point(&non_canonical_identity()).is_err());
+
+        // The identity is only accepted where it is allowed
+        let identity = encode(&edwards::Point::<Bls12, PrimeOrder>::zero());
+        assert!(read_point(&identity).is_ok());
+        match read_non_identity_point(&identity) {
+            Err(io::Error::PointInfinity) => {},
+            _ => panic!("identity should be rejected"),
+        }
+    }
+}
@@ -19,7 +19,7 @@ use scrypto::{
     redjubjub::PublicKey,
 };
 use polkadot_rs::Api;
-use zerochain_runtime::{UncheckedExtrinsic, Call, AnonymousBalancesCall};
+use zerochain_runtime::{UncheckedExtrinsic, Hash, Nonce as Index, Call, AnonymousBalancesCall};
 use zprimitives::{
     EncKey as zEncKey,
     LeftCiphertext as zLeftCiphertext,
@@ -43,6 +43,7 @@ use crate::crypto_components::{
     Anonymous,
     CiphertextTrait,
     Submitter,
+    SubmitError,
     Calls,
     Unchecked,Checked,
     ProofContext, convert_to_checked,
@@ -105,6 +106,7 @@ impl<E: JubjubEngine> ProofBuilder<E, Anonymous> for KeyContext<E, Anonymous> {
         enc_keys: MultiEncKeys<E, Anonymous>,
         enc_balances: &[Ciphertext<E>],
         g_epoch: edwards::Point<E, PrimeOrder>,
+        nonce_counter: u32,
         rng: &mut R,
         params: &E::Params,
     ) -> Result<Self::Submitter, SynthesisError> {
@@ -173,6 +175,7 @@ impl<E: JubjubEngine> ProofBuilder<E, Anonymous> for KeyContext<E, Anonymous> {
             enc_balances,
             g_epoch,
             nonce,
+            nonce_counter,
             s_index,
             t_index,
         )
@@ -192,6 +195,7 @@ impl<E: JubjubEngine> ProofContext<E, Unchecked, Anonymous> {
         enc_balances: &[Ciphertext<E>],
         g_epoch: edwards::Point<E, PrimeOrder>,
         nonce: edwards::Point<E, PrimeOrder>,
+        nonce_counter: u32,
         s_index: usize,
         t_index: usize,
     ) -> Self {
@@ -204,6 +208,7 @@ impl<E: JubjubEngine> ProofContext<E, Unchecked, Anonymous> {
             enc_balances: enc_balances.to_vec(),
             g_epoch,
             nonce,
+            nonce_counter,
             s_index: Some(s_index),
             t_index: Some(t_index),
             _marker: PhantomData,
@@ -342,6 +347,7 @@ impl<E: JubjubEngine> ProofContext<E, Checked, Anonymous> {
             left_ciphertexts,
             right_ciphertext,
             nonce,
+            nonce_counter: self.nonce_counter,
             rsk,
             rvk,
         })
@@ -354,12 +360,29 @@ pub struct AnonymousXt {
     pub left_ciphertexts: [[u8; POINT_SIZE]; ANONIMITY_SIZE],
     pub right_ciphertext: [u8; POINT_SIZE],
     pub nonce: [u8; POINT_SIZE],
+    pub nonce_counter: u32,
     pub rsk: [u8; POINT_SIZE],
 	pub rvk: [u8; POINT_SIZE],
 }
 
 impl Submitter for AnonymousXt {
-    fn submit<R: Rng>(&self, calls: Calls, api: &Api, rng: &mut R) {
+    fn submit<R: Rng>(&self, calls: Calls, api: &Api, rng: &mut R) -> Result<(), SubmitError> {
+        use zprimitives::SigVerificationKey;
+
+        let sig_vk = SigVerificationKey::from_slice(&self.rvk[..]);
+        let index = api.get_nonce(&sig_vk)
+            .map_err(|e| SubmitError(format!("Failed to get the nonce of the account: {:?}", e)))?;
+        let checkpoint = api.get_genesis_blockhash()
+            .map_err(|e| SubmitError(format!("Failed to fetch the genesis block hash: {:?}", e)))?;
+
+        let uxt = self.sign(calls, index, checkpoint, rng);
+        let _tx_hash = api.submit_extrinsic(&uxt)
+            .map_err(|e| SubmitError(format!("Failed to submit a extrinsic to zerochain node: {:?}", e)))?;
+
+        Ok(())
+    }
+
+    fn sign<R: Rng>(&self, calls: Calls, index: Index, checkpoint: Hash, rng: &mut R) -> UncheckedExtrinsic {
         use zjubjub::{
             curve::{fs::Fs as zFs, FixedGenerators as zFixedGenerators},
             redjubjub,
@@ -387,9 +410,6 @@ impl Submitter for AnonymousXt {
         let sig_vk = SigVerificationKey::from_slice(&self.rvk[..]);
 
         let era = Era::Immortal;
-        let index = api.get_nonce(&sig_vk).expect("Nonce must be got.");
-        let checkpoint = api.get_genesis_blockhash()
-            .expect("should be fetched the genesis block hash from zerochain node.");
 
         let raw_payload = match calls {
             Calls::AnonymousTransfer => (Compact(index), self.call_transfer(), era, checkpoint),
@@ -398,7 +418,10 @@ impl Submitter for AnonymousXt {
 
         let sig = raw_payload.using_encoded(|payload| {
             let msg = blake2_256(payload);
-            let sig = sig_sk.sign(&msg[..], rng, p_g, &*ZPARAMS);
+            // The nonce is derived deterministically and only hedged with the rng,
+            // so that a weak rng does not leak the signing key.
+            let entropy: [u8; 32] = rng.gen();
+            let sig = sig_sk.sign_deterministic(&msg[..], Some(&entropy), p_g, &*ZPARAMS);
 
             let sig_vk = redjubjub::PublicKey::<zBls12>::try_from(sig_vk)
                 .expect("should be casted to redjubjub::PublicKey<Bls12> type.");
@@ -409,9 +432,7 @@ impl Submitter for AnonymousXt {
 
         let sig_repr = RedjubjubSignature::try_from(sig)
             .expect("shoukd be casted from RedjubjubSignature.");
-        let uxt = UncheckedExtrinsic::new_signed(index, raw_payload.1, sig_vk.into(), sig_repr, era);
-        let _tx_hash = api.submit_extrinsic(&uxt)
-            .expect("Faild to submit a extrinsic to zerochain node.");
+        UncheckedExtrinsic::new_signed(index, raw_payload.1, sig_vk.into(), sig_repr, era)
     }
 }
 
@@ -424,7 +445,8 @@ impl AnonymousXt {
             enc_keys,
             left_ciphertexts,
             zRightCiphertext::from_slice(&self.right_ciphertext[..]),
-            zNonce::from_slice(&self.nonce[..])
+            zNonce::from_slice(&self.nonce[..]),
+            self.nonce_counter
         ))
     }
 }
@@ -481,7 +503,7 @@ mod tests {
             .gen_proof(
                 amount, 0, remaining_balance, s_index, t_index, &spending_key,
                 MultiEncKeys::<Bls12, Anonymous>::new(enc_key_recipient, decoys),
-                &enc_balances, g_epoch,
+                &enc_balances, g_epoch, 0,
                 rng, params
             );
 
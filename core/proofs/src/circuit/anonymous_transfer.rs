@@ -6,7 +6,7 @@
 //! Randomness check: D = r * G
 //! Balance check: \sum s_i * (C_li - C_i) = b_2 * G + sk * (\sum (s_i * C_ri) - D) ,where b_2: remaining balance
 //! Secret key check: sk * G = \sum s_i * y_i
-//! Nonce check: sk * G_epoch = u
+//! Nonce check: sk * G_{epoch, n} = u ,where n: the sender's transaction counter in the epoch
 //! Spend authority: rvk = alpha * G + pgk
 //! s_i \in {0, 1}
 //! t_i \in {0, 1}
@@ -6,6 +6,7 @@
 //! * Validity of encryption for transferred amount
 //! * Validity of encryption for sender's balance
 //! * Spend authority proof
+//! * Validity of the nonce for the epoch generator of the sender's transaction counter
 //! * Some small order checks
 
 use bellman::{
@@ -123,6 +123,9 @@ where
     Ok(())
 }
 
+/// Inputize the epoch generator and the nonce derived from it.
+/// The generator depends on the epoch and the sender's transaction counter in it,
+/// and is checked by the verifier, so the circuit is the same for every counter.
 pub fn g_epoch_nonce_inputize<E, CS>(
     mut cs: CS,
     g_epoch: Option<&edwards::Point<E, PrimeOrder>>,
@@ -22,6 +22,8 @@ use scrypto::{
 use polkadot_rs::Api;
 use zerochain_runtime::{
     UncheckedExtrinsic,
+    Hash,
+    Nonce as Index,
     EncryptedBalancesCall,
     EncryptedAssetsCall,
     AnonymousBalancesCall,
@@ -51,6 +53,7 @@ use crate::crypto_components::{
     Confidential,
     CiphertextTrait,
     Submitter,
+    SubmitError,
     Calls,
     Unchecked,Checked,
     ProofContext, convert_to_checked,
@@ -113,6 +116,7 @@ impl<E: JubjubEngine> ProofBuilder<E, Confidential> for KeyContext<E, Confidenti
         enc_keys: MultiEncKeys<E, Confidential>,
         encrypted_balance: &[Ciphertext<E>],
         g_epoch: edwards::Point<E, PrimeOrder>,
+        nonce_counter: u32,
         rng: &mut R,
         params: &E::Params,
     ) -> Result<Self::Submitter, SynthesisError> {
@@ -164,7 +168,8 @@ impl<E: JubjubEngine> ProofBuilder<E, Confidential> for KeyContext<E, Confidenti
             multi_ciphertexts,
             encrypted_balance[0].clone(), // TODO
             g_epoch,
-            nonce
+            nonce,
+            nonce_counter
         )
         .check_proof(&self.prepared_vk)?
         .gen_xt(&spending_key, alpha)
@@ -188,6 +193,7 @@ impl<E: JubjubEngine> ProofContext<E, Unchecked, Confidential> {
         encrypted_balance: Ciphertext<E>,
         g_epoch: edwards::Point<E, PrimeOrder>,
         nonce: edwards::Point<E, PrimeOrder>,
+        nonce_counter: u32,
     ) -> Self {
         let enc_balances = vec![encrypted_balance];
         ProofContext {
@@ -199,6 +205,7 @@ impl<E: JubjubEngine> ProofContext<E, Unchecked, Confidential> {
             enc_balances,
             g_epoch,
             nonce,
+            nonce_counter,
             s_index: None,
             t_index: None,
             _marker: PhantomData,
@@ -348,6 +355,7 @@ impl<E: JubjubEngine> ProofContext<E, Checked, Confidential> {
 			rsk: rsk_bytes,
 			enc_balance,
 			nonce,
+			nonce_counter: self.nonce_counter,
 		};
 
 		Ok(tx)
@@ -367,10 +375,27 @@ pub struct ConfidentialXt {
 	pub rvk: [u8; POINT_SIZE],
 	pub enc_balance: [u8; CIPHERTEXT_SIZE],
 	pub nonce: [u8; POINT_SIZE],
+	pub nonce_counter: u32,
 }
 
 impl Submitter for ConfidentialXt {
-    fn submit<R: Rng>(&self, calls: Calls, api: &Api, rng: &mut R) {
+    fn submit<R: Rng>(&self, calls: Calls, api: &Api, rng: &mut R) -> Result<(), SubmitError> {
+        use zprimitives::SigVerificationKey;
+
+        let sig_vk = SigVerificationKey::from_slice(&self.rvk[..]);
+        let index = api.get_nonce(&sig_vk)
+            .map_err(|e| SubmitError(format!("Failed to get the nonce of the account: {:?}", e)))?;
+        let checkpoint = api.get_genesis_blockhash()
+            .map_err(|e| SubmitError(format!("Failed to fetch the genesis block hash: {:?}", e)))?;
+
+        let uxt = self.sign(calls, index, checkpoint, rng);
+        let _tx_hash = api.submit_extrinsic(&uxt)
+            .map_err(|e| SubmitError(format!("Failed to submit a extrinsic to zerochain node: {:?}", e)))?;
+
+        Ok(())
+    }
+
+    fn sign<R: Rng>(&self, calls: Calls, index: Index, checkpoint: Hash, rng: &mut R) -> UncheckedExtrinsic {
         use zjubjub::{
             curve::{fs::Fs as zFs, FixedGenerators as zFixedGenerators},
             redjubjub,
@@ -398,9 +423,6 @@ impl Submitter for ConfidentialXt {
         let sig_vk = SigVerificationKey::from_slice(&self.rvk[..]);
 
         let era = Era::Immortal;
-        let index = api.get_nonce(&sig_vk).expect("Nonce must be got.");
-        let checkpoint = api.get_genesis_blockhash()
-            .expect("should be fetched the genesis block hash from zerochain node.");
 
         let raw_payload = match calls {
             Calls::BalanceTransfer => (Compact(index), self.call_transfer(), era, checkpoint),
@@ -413,7 +435,10 @@ impl Submitter for ConfidentialXt {
 
         let sig = raw_payload.using_encoded(|payload| {
             let msg = blake2_256(payload);
-            let sig = sig_sk.sign(&msg[..], rng, p_g, &*ZPARAMS);
+            // The nonce is derived deterministically and only hedged with the rng,
+            // so that a weak rng does not leak the signing key.
+            let entropy: [u8; 32] = rng.gen();
+            let sig = sig_sk.sign_deterministic(&msg[..], Some(&entropy), p_g, &*ZPARAMS);
 
             let sig_vk = redjubjub::PublicKey::<zBls12>::try_from(sig_vk)
                 .expect("should be casted to redjubjub::PublicKey<Bls12> type.");
@@ -424,9 +449,7 @@ impl Submitter for ConfidentialXt {
 
         let sig_repr = RedjubjubSignature::try_from(sig)
             .expect("shoukd be casted from RedjubjubSignature.");
-        let uxt = UncheckedExtrinsic::new_signed(index, raw_payload.1, sig_vk.into(), sig_repr, era);
-        let _tx_hash = api.submit_extrinsic(&uxt)
-            .expect("Faild to submit a extrinsic to zerochain node.");
+        UncheckedExtrinsic::new_signed(index, raw_payload.1, sig_vk.into(), sig_repr, era)
     }
 }
 
@@ -440,7 +463,8 @@ impl ConfidentialXt {
             zLeftCiphertext::from_slice(&self.left_amount_recipient[..]),
             zLeftCiphertext::from_slice(&self.left_fee[..]),
             zRightCiphertext::from_slice(&self.right_randomness[..]),
-            zNonce::from_slice(&self.nonce[..])
+            zNonce::from_slice(&self.nonce[..]),
+            self.nonce_counter
         ))
     }
 
@@ -452,7 +476,8 @@ impl ConfidentialXt {
             zLeftCiphertext::from_slice(&self.left_fee[..]),
             zCiphertext::from_slice(&self.enc_balance[..]),
             zRightCiphertext::from_slice(&self.right_randomness[..]),
-            zNonce::from_slice(&self.nonce[..])
+            zNonce::from_slice(&self.nonce[..]),
+            self.nonce_counter
         ))
     }
 
@@ -466,7 +491,8 @@ impl ConfidentialXt {
             zLeftCiphertext::from_slice(&self.left_amount_recipient[..]),
             zLeftCiphertext::from_slice(&self.left_fee[..]),
             zRightCiphertext::from_slice(&self.right_randomness[..]),
-            zNonce::from_slice(&self.nonce[..])
+            zNonce::from_slice(&self.nonce[..]),
+            self.nonce_counter
         ))
     }
 
@@ -479,7 +505,8 @@ impl ConfidentialXt {
             zLeftCiphertext::from_slice(&self.left_fee[..]),
             zCiphertext::from_slice(&self.enc_balance[..]),
             zRightCiphertext::from_slice(&self.right_randomness[..]),
-            zNonce::from_slice(&self.nonce[..])
+            zNonce::from_slice(&self.nonce[..]),
+            self.nonce_counter
         ))
     }
 
@@ -491,7 +518,8 @@ impl ConfidentialXt {
             zLeftCiphertext::from_slice(&self.left_fee[..]),
             zCiphertext::from_slice(&self.enc_balance[..]),
             zRightCiphertext::from_slice(&self.right_randomness[..]),
-            zNonce::from_slice(&self.nonce[..])
+            zNonce::from_slice(&self.nonce[..]),
+            self.nonce_counter
         ))
     }
 }
@@ -535,7 +563,7 @@ mod tests {
             .gen_proof(
                 amount, fee, remaining_balance, 0, 0, &spending_key,
                 MultiEncKeys::<Bls12, Confidential>::new(enc_key_recipient),
-                &enc_balance, g_epoch,
+                &enc_balance, g_epoch, 0,
                 rng, params
             );
 
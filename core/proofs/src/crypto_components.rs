@@ -18,6 +18,7 @@ use scrypto::{
     redjubjub::PublicKey,
 };
 use polkadot_rs::Api;
+use zerochain_runtime::{UncheckedExtrinsic, Hash, Nonce as Index};
 use crate::{
     elgamal::Ciphertext,
     EncryptionKey,
@@ -28,6 +29,7 @@ use std::{
     io::{self, BufReader, Read},
     path::Path,
     fs::File,
+    fmt,
     marker::PhantomData,
 };
 
@@ -265,8 +267,24 @@ pub enum Calls {
     AnonymousIssue,
 }
 
+/// A failure of the node to serve or accept a transaction while submitting it.
+#[derive(Debug)]
+pub struct SubmitError(pub String);
+
+impl fmt::Display for SubmitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for SubmitError { }
+
 pub trait Submitter {
-    fn submit<R: Rng>(&self, calls: Calls, api: &Api, rng: &mut R);
+    fn submit<R: Rng>(&self, calls: Calls, api: &Api, rng: &mut R) -> Result<(), SubmitError>;
+
+    /// Sign the call with the re-randomized signing key of the proof, without a connection to the node.
+    /// `index` is the account nonce of the signer and `checkpoint` is the genesis hash of the chain.
+    fn sign<R: Rng>(&self, calls: Calls, index: Index, checkpoint: Hash, rng: &mut R) -> UncheckedExtrinsic;
 }
 
 pub trait ProofBuilder<E: JubjubEngine, PC: PrivacyConfing>: Sized {
@@ -289,6 +307,7 @@ pub trait ProofBuilder<E: JubjubEngine, PC: PrivacyConfing>: Sized {
         enc_keys: MultiEncKeys<E, PC>,
         enc_balances: &[Ciphertext<E>],
         g_epoch: edwards::Point<E, PrimeOrder>,
+        nonce_counter: u32,
         rng: &mut R,
         params: &E::Params,
     ) -> Result<Self::Submitter, SynthesisError>;
@@ -345,6 +364,7 @@ pub(crate) struct ProofContext<E: JubjubEngine, IsChecked, PC: PrivacyConfing> {
     pub(crate) enc_balances: Vec<Ciphertext<E>>,
     pub(crate) g_epoch: edwards::Point<E, PrimeOrder>,
     pub(crate) nonce: edwards::Point<E, PrimeOrder>,
+    pub(crate) nonce_counter: u32,
     pub(crate) s_index: Option<usize>,
     pub(crate) t_index: Option<usize>,
     pub(crate) _marker: PhantomData<IsChecked>,
@@ -385,6 +405,7 @@ pub(crate) fn convert_to_checked<E: JubjubEngine, C1, C2, PC: PrivacyConfing>(
         enc_balances: from.enc_balances,
         g_epoch: from.g_epoch,
         nonce: from.nonce,
+        nonce_counter: from.nonce_counter,
         s_index: from.s_index,
         t_index: from.t_index,
         _marker: PhantomData,
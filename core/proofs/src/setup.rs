@@ -1,3 +1,10 @@
+//! Groth16 setup of the transfer circuits, over BLS12-381 only.
+//!
+//! It is not instantiated over BN254 (`zpairing::bn256`). The circuits are built on Jubjub,
+//! whose base field is the BLS12-381 scalar field, and the `bellman` prover used here works with
+//! its own `pairing` crate rather than the zerochain-pairing `Engine`. Over BN254, only the
+//! verification of proofs generated elsewhere is supported, by `bellman-verifier`.
+
 use pairing::bls12_381::Bls12;
 use bellman::groth16::{
     generate_random_parameters,
@@ -4,30 +4,25 @@
 
 // mod utils;
 // use cfg_if::cfg_if;
-// use wasm_bindgen::prelude::*;
+use wasm_bindgen::prelude::*;
 
 // use rand::{ChaChaRng, SeedableRng, Rng, Rand};
 // use keys;
-// use zpairing::{
-//     bls12_381::Bls12 as zBls12,
-//     Field as zField, PrimeField as zPrimeField, PrimeFieldRepr as zPrimeFieldRepr,
-// };
+use zpairing::bls12_381::Bls12 as zBls12;
 // use pairing::{
 //     bls12_381::Bls12, Field,
 // };
-// use zjubjub::{
-//     curve::{JubjubBls12 as zJubjubBls12,
-//         FixedGenerators as zFixedGenerators,
-//         JubjubParams as zJubjubParams,
-//         edwards::Point as zPoint,
-//         fs::Fs as zFs
-//         },
-//     redjubjub::{h_star as zh_star,
-//                 Signature as zSignature,
-//                 PublicKey as zPublicKey,
-//                 write_scalar as zwrite_scalar,
-//                 read_scalar as zread_scalar},
-// };
+use zjubjub::{
+    curve::{JubjubBls12 as zJubjubBls12,
+        FixedGenerators as zFixedGenerators,
+        JubjubParams as zJubjubParams,
+        edwards::Point as zPoint},
+    redjubjub::{h_star as zh_star,
+                Signature as zSignature,
+                PrivateKey as zPrivateKey,
+                PublicKey as zPublicKey,
+                read_scalar as zread_scalar},
+};
 // use scrypto::{
 //     jubjub::{fs::Fs, FixedGenerators, JubjubBls12, JubjubParams},
 // };
@@ -103,75 +98,61 @@
 //     buf
 // }
 
-// #[wasm_bindgen]
-// pub fn sign_wasm(mut sk: &[u8], msg: &[u8], seed_slice: &[u32]) -> Vec<u8> {
-//     let params = &zJubjubBls12::new();
-//     let rng = &mut ChaChaRng::from_seed(seed_slice);
-//     let p_g = zFixedGenerators::Diversifier;
-
-//     let mut ask_repr = zFs::default().into_repr();
-//     ask_repr.read_le(&mut sk).unwrap();
-//     let ask = zFs::from_repr(ask_repr).unwrap();
-
-//     // T = (l_H + 128) bits of randomness
-//     // For H*, l_H = 512 bits
-//     let mut t = [0u8; 80];
-//     rng.fill_bytes(&mut t[..]);
-
-//     // r = H*(T || M)
-//     let r = zh_star::<zBls12>(&t[..], msg);
-
-//     // R = r . P_G
-//     let r_g = params.generator(p_g).mul(r, params);
-//     let mut rbar = [0u8; 32];
-//     r_g.write(&mut &mut rbar[..])
-//         .expect("Jubjub points should serialize to 32 bytes");
-
-//     // S = r + H*(Rbar || M) . sk
-//     let mut s = zh_star::<zBls12>(&rbar[..], msg);
-//     s.mul_assign(&ask);
-//     s.add_assign(&r);
-//     let mut sbar = [0u8; 32];
-//     zwrite_scalar::<zBls12, &mut [u8]>(&s, &mut sbar[..])
-//         .expect("Jubjub scalars should serialize to 32 bytes");
-
-//     let sig = zSignature { rbar, sbar };
-
-//     let mut writer = [0u8; 64];
-//     sig.write(&mut writer[..]).expect("fails to write signature");
-
-//     writer.to_vec()
-// }
-
-// #[wasm_bindgen]
-// pub fn verify_wasm(mut vk: &[u8], msg: &[u8], mut sig: &[u8]) -> bool {
-//     let params = &zJubjubBls12::new();
-//     let p_g = zFixedGenerators::Diversifier;
-
-//     let vk = zPublicKey::<zBls12>::read(&mut vk, params).unwrap();
-//     let sig = zSignature::read(&mut sig).unwrap();
-
-//     // c = H*(Rbar || M)
-//     let c = zh_star::<zBls12>(&sig.rbar[..], msg);
-
-//     // Signature checks:
-//     // R != invalid
-//     let r = match zPoint::read(&mut &sig.rbar[..], params) {
-//         Ok(r) => r,
-//         Err(_) => return false,
-//     };
-//     // S < order(G)
-//     // (E::Fs guarantees its representation is in the field)
-//     let s = match zread_scalar::<zBls12, &[u8]>(&sig.sbar[..]) {
-//         Ok(s) => s,
-//         Err(_) => return false,
-//     };
-//     // 0 = h_G(-S . P_G + R + c . vk)
-//     vk.0.mul(c, params).add(&r, params).add(
-//         &params.generator(p_g).mul(s, params).negate().into(),
-//         params
-//     ).mul_by_cofactor(params).eq(&zPoint::zero())
-// }
+/// Sign with a nonce derived from the key and the message,
+/// so that a weak rng in the browser cannot leak the key.
+/// `entropy` is optional and only hedges the nonce.
+#[wasm_bindgen]
+pub fn sign_wasm(mut sk: &[u8], msg: &[u8], entropy: &[u8]) -> Vec<u8> {
+    let params = &zJubjubBls12::new();
+    let p_g = zFixedGenerators::Diversifier;
+
+    let sk = zPrivateKey::<zBls12>::read(&mut sk).unwrap();
+
+    let mut extra = [0u8; 32];
+    let extra = if entropy.len() == 32 {
+        extra.copy_from_slice(entropy);
+        Some(&extra)
+    } else {
+        None
+    };
+
+    let sig = sk.sign_deterministic(msg, extra, p_g, params);
+
+    let mut writer = [0u8; 64];
+    sig.write(&mut writer[..]).expect("fails to write signature");
+
+    writer.to_vec()
+}
+
+#[wasm_bindgen]
+pub fn verify_wasm(mut vk: &[u8], msg: &[u8], mut sig: &[u8]) -> bool {
+    let params = &zJubjubBls12::new();
+    let p_g = zFixedGenerators::Diversifier;
+
+    let vk = zPublicKey::<zBls12>::read(&mut vk, params).unwrap();
+    let sig = zSignature::read(&mut sig).unwrap();
+
+    // c = H*(Rbar || M)
+    let c = zh_star::<zBls12>(&sig.rbar[..], msg);
+
+    // Signature checks:
+    // R != invalid
+    let r = match zPoint::read(&mut &sig.rbar[..], params) {
+        Ok(r) => r,
+        Err(_) => return false,
+    };
+    // S < order(G)
+    // (E::Fs guarantees its representation is in the field)
+    let s = match zread_scalar::<zBls12, &[u8]>(&sig.sbar[..]) {
+        Ok(s) => s,
+        Err(_) => return false,
+    };
+    // 0 = h_G(-S . P_G + R + c . vk)
+    vk.0.mul(c, params).add(&r, params).add(
+        &params.generator(p_g).mul(s, params).negate().into(),
+        params
+    ).mul_by_cofactor(params).eq(&zPoint::zero())
+}
 
 // #[derive(Serialize)]
 // struct Calls {
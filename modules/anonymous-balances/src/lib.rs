@@ -1,20 +1,107 @@
 //! A module for dealing with anonymous transfer
 #![cfg_attr(not(feature = "std"), no_std)]
 
-use support::{decl_module, decl_storage, decl_event, StorageMap, dispatch::Result, ensure};
+use support::{decl_module, decl_storage, decl_event, StorageValue, StorageMap, dispatch::Result, ensure};
 use rstd::{
     prelude::*,
     result,
+    convert::TryFrom,
 };
 use runtime_primitives::traits::Zero;
-use zprimitives::{EncKey, Proof, Nonce, RightCiphertext, LeftCiphertext, Ciphertext};
+use zprimitives::{EncKey, Proof, Nonce, RightCiphertext, LeftCiphertext, Ciphertext, RedjubjubSignature, PARAMS};
+use keys::EncryptionKey;
+use jubjub::{redjubjub, curve::FixedGenerators};
+use pairing::bls12_381::Bls12;
+use parity_codec::Encode;
 use system::ensure_signed;
 
+/// Domain separator of the message signed to prove the knowledge of a decryption key.
+pub const REGISTER_PERSONALIZATION: &[u8] = b"zerochain_register";
+
 pub trait Trait: system::Trait + zk_system::Trait {
     // The overarching event type.
 	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
 }
 
+/// An error of the anonymous-balances module.
+///
+/// The codes returned by `code` are stable and are reported in the
+/// `TransactionFailed` event. Errors of the zk-system module are offset by `0x100`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum Error {
+    /// The zk proof does not verify against the public input.
+    InvalidZkProof,
+    /// An amount, a fee or the randomness is not a valid ciphertext.
+    InvalidCiphertext,
+    /// A stored balance or pending transfer could not be updated.
+    BalanceUpdateFailed,
+    /// The numbers of encryption keys and ciphertexts differ.
+    LengthMismatch,
+    /// The account has no pending transfer, or was already rolled over in this epoch.
+    NothingToRollOver,
+    /// The encryption key is already in the anonymity set.
+    AlreadyRegistered,
+    /// The signature does not prove the knowledge of the decryption key.
+    InvalidKeyProof,
+    /// An error of the zk-system module.
+    ZkSystem(zk_system::Error),
+}
+
+impl Error {
+    pub fn code(&self) -> u16 {
+        match self {
+            Error::InvalidZkProof => 1,
+            Error::InvalidCiphertext => 2,
+            Error::BalanceUpdateFailed => 3,
+            Error::LengthMismatch => 4,
+            Error::NothingToRollOver => 5,
+            Error::AlreadyRegistered => 6,
+            Error::InvalidKeyProof => 7,
+            Error::ZkSystem(e) => 0x100 + e.code(),
+        }
+    }
+
+    pub fn from_code(code: u16) -> Option<Self> {
+        match code {
+            1 => Some(Error::InvalidZkProof),
+            2 => Some(Error::InvalidCiphertext),
+            3 => Some(Error::BalanceUpdateFailed),
+            4 => Some(Error::LengthMismatch),
+            5 => Some(Error::NothingToRollOver),
+            6 => Some(Error::AlreadyRegistered),
+            7 => Some(Error::InvalidKeyProof),
+            c if c > 0x100 => zk_system::Error::from_code(c - 0x100).map(Error::ZkSystem),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Error::InvalidZkProof => "The zk proof is invalid. Make sure it was built from the current balances.",
+            Error::InvalidCiphertext => "An amount, fee or randomness is not a valid ciphertext.",
+            Error::BalanceUpdateFailed => "Failed to update the encrypted balance.",
+            Error::LengthMismatch => "The numbers of encryption keys and ciphertexts differ.",
+            Error::NothingToRollOver => "Nothing to roll over. Transfers received in this epoch are rolled over in the next one.",
+            Error::AlreadyRegistered => "The encryption key is already in the anonymity set.",
+            Error::InvalidKeyProof => "The key proof is invalid. Sign the registration message with the decryption key.",
+            Error::ZkSystem(e) => e.as_str(),
+        }
+    }
+}
+
+impl From<zk_system::Error> for Error {
+    fn from(e: zk_system::Error) -> Error {
+        Error::ZkSystem(e)
+    }
+}
+
+impl From<Error> for &'static str {
+    fn from(e: Error) -> &'static str {
+        e.as_str()
+    }
+}
+
 decl_module! {
     pub struct Module<T: Trait> for enum Call where origin: T::Origin {
         // Initializing events
@@ -26,59 +113,21 @@ decl_module! {
             enc_keys: Vec<EncKey>,
             left_ciphertexts: Vec<LeftCiphertext>,
             right_ciphertext: RightCiphertext,
-            nonce: Nonce
+            nonce: Nonce,
+            nonce_counter: u32
         ) -> Result {
-            ensure!(enc_keys.len() == left_ciphertexts.len(), "length should be equal");
             let rvk = ensure_signed(origin)?;
 
-            // This function causes a storage mutation, but it's needed before `verify_proof` function is called.
-            // No problem if errors occur after this function because
-            // it just rollover user's own `pending trasfer` to `encrypted balances`.
-            for e in &enc_keys {
-                Self::rollover(e)?;
-            }
-
-            // Veridate the provided nonce isn't included in the nonce pool.
-            assert!(!<zk_system::Module<T>>::nonce_pool().contains(&nonce));
-
-            let mut acc = vec![];
-            for c in &enc_keys {
-                let tmp = Self::encrypted_balance(c).map_or(Ciphertext::zero(), |e| e);
-                acc.push(tmp);
-            }
-
-            // Verify the zk proof
-            if !<zk_system::Module<T>>::verify_anonymous_proof(
-                    &zkproof,
-                    &enc_keys[..],
-                    &left_ciphertexts[..],
-                    &right_ciphertext,
-                    &acc[..],
-                    &rvk,
-                    &nonce
-                )? {
-                    Self::deposit_event(RawEvent::InvalidZkProof());
-                    return Err("Invalid zkproof");
-            }
-
-            // Add a nonce into the nonce pool
-            <zk_system::Module<T>>::nonce_pool().push(nonce);
-
-            for (e, c) in enc_keys.iter().zip(left_ciphertexts.iter()) {
-                Self::add_pending_transfer(e, c, &right_ciphertext)?;
-            }
-
-            Self::deposit_event(
-                RawEvent::AnonymousTransfer(
-                    zkproof,
-                    enc_keys,
-                    left_ciphertexts,
-                    right_ciphertext,
-                    rvk
-                )
-            );
-
-            Ok(())
+            Self::do_anonymous_transfer(
+                rvk.clone(),
+                zkproof,
+                enc_keys,
+                left_ciphertexts,
+                right_ciphertext,
+                nonce,
+                nonce_counter
+            )
+            .map_err(|e| Self::fail(rvk, e))
         }
 
         /// Issue a new class of encrypted fungible assets. There are, and will only ever be, `total`
@@ -92,45 +141,36 @@ decl_module! {
             fee: LeftCiphertext,
             balance: Ciphertext,
             randomness: RightCiphertext,
-            nonce: Nonce
+            nonce: Nonce,
+            nonce_counter: u32
         ) {
             let rvk = ensure_signed(origin)?;
 
-            // Initialize a nonce pool
-            let current_epoch = <zk_system::Module<T>>::get_current_epoch();
-            <zk_system::Module<T>>::init_nonce_pool(current_epoch);
-
-            // Veridate the provided nonce isn't included in the nonce pool.
-            ensure!(!<zk_system::Module<T>>::nonce_pool().contains(&nonce), "Provided nonce is already included in the nonce pool.");
-
-            // Verify a zk proof
-            // 1. Spend authority verification
-            // 2. Range check of issued amount
-            // 3. Encryption integrity
-            if !<zk_system::Module<T>>::verify_confidential_proof(
-                &zkproof,
-                &issuer,
-                &issuer,
-                &total,
-                &total,
-                &balance,
-                &rvk,
-                &fee,
-                &randomness,
-                &nonce
-            )? {
-                Self::deposit_event(RawEvent::InvalidZkProof());
-                return Err("Invalid zkproof");
-            }
+            Self::do_issue(rvk.clone(), zkproof, issuer, total, fee, balance, randomness, nonce, nonce_counter)
+                .map_err(|e| Self::fail(rvk, e))?;
+        }
 
-            // Add a nonce into the nonce pool
-            <zk_system::Module<T>>::nonce_pool().push(nonce);
+        /// Roll the pending transfer of `enc_key` over into its encrypted balance.
+        /// Anyone may call it for any account, because it only merges ciphertexts
+        /// the account already owns. It fails unless there is something to merge,
+        /// so it succeeds at most once per account and epoch.
+        pub fn rollover(origin, enc_key: EncKey) -> Result {
+            let who = ensure_signed(origin)?;
 
-            let total_ciphertext = Ciphertext::from_left_right(total, randomness)
-                .map_err(|_| "Faild to create ciphertext from left and right.")?;
-            <EncryptedBalance<T>>::insert(issuer.clone(), total_ciphertext.clone());
+            Self::do_rollover(&enc_key).map_err(|e| Self::fail(who, e))
+        }
 
-            Self::deposit_event(RawEvent::Issued(issuer, total_ciphertext));
+        /// Add `enc_key` to the anonymity set, so that other accounts can use it as a decoy.
+        /// Keys are registered automatically when they first receive an anonymous balance,
+        /// so this is only needed to join the set before that.
+        /// `key_proof` is a signature of `register_message` by the decryption key,
+        /// so that only keys whose decryption key is known to the sender can be registered.
+        /// It does not limit how many keys one party registers: without transaction fees,
+        /// anyone can still fill the set with keys of their own and control many of the decoys.
+        pub fn register(origin, enc_key: EncKey, key_proof: RedjubjubSignature) -> Result {
+            let who = ensure_signed(origin)?;
+
+            Self::do_register(&who, &enc_key, &key_proof).map_err(|e| Self::fail(who, e))
         }
     }
 }
@@ -143,8 +183,29 @@ decl_storage! {
         pub PendingTransfer get(pending_transfer) : map EncKey => Option<Ciphertext>;
         /// A last epoch for rollover
         pub LastRollOver get(last_rollover) config() : map EncKey => Option<T::BlockNumber>;
-        // TODO: Change to BTreeSet once parity-codec is updated to parity-scale-codec
-        pub EncKeySet get(enc_key_set) config() : Vec<EncKey>;
+
+        /// The number of registered encryption keys, which form the anonymity set.
+        pub EncKeyCount get(enc_key_count) build(|config: &GenesisConfig<T>| {
+            config.enc_key_set.len() as u32
+        }): u32;
+        /// The registered encryption key at each index.
+        /// Wallets page through it with `enc_keys` to sample decoys.
+        pub EncKeyAt get(enc_key_at) build(|config: &GenesisConfig<T>| {
+            config.enc_key_set.iter()
+                .enumerate()
+                .map(|(i, e)| (i as u32, *e))
+                .collect::<Vec<_>>()
+        }): map u32 => Option<EncKey>;
+        /// The index of each registered encryption key.
+        pub EncKeyIndex get(enc_key_index) build(|config: &GenesisConfig<T>| {
+            config.enc_key_set.iter()
+                .enumerate()
+                .map(|(i, e)| (*e, i as u32))
+                .collect::<Vec<_>>()
+        }): map EncKey => Option<u32>;
+    }
+    add_extra_genesis {
+        config(enc_key_set): Vec<EncKey>;
     }
 }
 
@@ -153,11 +214,172 @@ decl_event! (
     pub enum Event<T> where <T as system::Trait>::AccountId {
         AnonymousTransfer(Proof, Vec<EncKey>, Vec<LeftCiphertext>, RightCiphertext, AccountId),
         Issued(EncKey, Ciphertext),
-        InvalidZkProof(),
+        /// The pending transfer of the account was merged into its new encrypted balance.
+        RolledOver(EncKey, Ciphertext),
+        /// The encryption key joined the anonymity set at the given index.
+        Registered(EncKey, u32),
+        /// A transaction of the account failed with the given `Error` code.
+        TransactionFailed(AccountId, u16),
     }
 );
 
 impl<T: Trait> Module<T> {
+    fn do_anonymous_transfer(
+        rvk: T::AccountId,
+        zkproof: Proof,
+        enc_keys: Vec<EncKey>,
+        left_ciphertexts: Vec<LeftCiphertext>,
+        right_ciphertext: RightCiphertext,
+        nonce: Nonce,
+        nonce_counter: u32
+    ) -> result::Result<(), Error> {
+        ensure!(enc_keys.len() == left_ciphertexts.len(), Error::LengthMismatch);
+
+        // This function causes a storage mutation, but it's needed before `verify_proof` function is called.
+        // No problem if errors occur after this function because
+        // it just rollover user's own `pending trasfer` to `encrypted balances`.
+        for e in &enc_keys {
+            Self::apply_rollover(e)?;
+        }
+
+        // Veridate the provided nonce isn't included in the nonce pool.
+        <zk_system::Module<T>>::ensure_nonce_unused(&nonce)?;
+
+        let mut acc = vec![];
+        for c in &enc_keys {
+            let tmp = Self::encrypted_balance(c).map_or(Ciphertext::zero(), |e| e);
+            acc.push(tmp);
+        }
+
+        // Verify the zk proof
+        if !<zk_system::Module<T>>::verify_anonymous_proof(
+                &zkproof,
+                &enc_keys[..],
+                &left_ciphertexts[..],
+                &right_ciphertext,
+                &acc[..],
+                &rvk,
+                &nonce,
+                nonce_counter
+            )? {
+                return Err(Error::InvalidZkProof);
+        }
+
+        // Add a nonce into the nonce pool
+        <zk_system::Module<T>>::insert_nonce(nonce);
+
+        for (e, c) in enc_keys.iter().zip(left_ciphertexts.iter()) {
+            Self::add_pending_transfer(e, c, &right_ciphertext)?;
+        }
+
+        Self::deposit_event(
+            RawEvent::AnonymousTransfer(
+                zkproof,
+                enc_keys,
+                left_ciphertexts,
+                right_ciphertext,
+                rvk
+            )
+        );
+
+        Ok(())
+    }
+
+    fn do_issue(
+        rvk: T::AccountId,
+        zkproof: Proof,
+        issuer: EncKey,
+        total: LeftCiphertext,
+        fee: LeftCiphertext,
+        balance: Ciphertext,
+        randomness: RightCiphertext,
+        nonce: Nonce,
+        nonce_counter: u32
+    ) -> result::Result<(), Error> {
+        // Initialize a nonce pool
+        let current_epoch = <zk_system::Module<T>>::get_current_epoch();
+        <zk_system::Module<T>>::init_nonce_pool(current_epoch);
+
+        // Veridate the provided nonce isn't included in the nonce pool.
+        <zk_system::Module<T>>::ensure_nonce_unused(&nonce)?;
+
+        // Verify a zk proof
+        // 1. Spend authority verification
+        // 2. Range check of issued amount
+        // 3. Encryption integrity
+        if !<zk_system::Module<T>>::verify_confidential_proof(
+            &zkproof,
+            &issuer,
+            &issuer,
+            &total,
+            &total,
+            &balance,
+            &rvk,
+            &fee,
+            &randomness,
+            &nonce,
+            nonce_counter
+        )? {
+            return Err(Error::InvalidZkProof);
+        }
+
+        // Add a nonce into the nonce pool
+        <zk_system::Module<T>>::insert_nonce(nonce);
+
+        let total_ciphertext = Ciphertext::from_left_right(total, randomness)
+            .map_err(|_| Error::InvalidCiphertext)?;
+        <EncryptedBalance<T>>::insert(issuer.clone(), total_ciphertext.clone());
+        Self::register_enc_key(&issuer);
+
+        Self::deposit_event(RawEvent::Issued(issuer, total_ciphertext));
+
+        Ok(())
+    }
+
+    fn do_rollover(enc_key: &EncKey) -> result::Result<(), Error> {
+        ensure!(Self::pending_transfer(enc_key).is_some(), Error::NothingToRollOver);
+        ensure!(Self::apply_rollover(enc_key)?, Error::NothingToRollOver);
+
+        Self::deposit_event(
+            RawEvent::RolledOver(
+                *enc_key,
+                Self::encrypted_balance(enc_key).map_or(Ciphertext::zero(), |e| e)
+            )
+        );
+
+        Ok(())
+    }
+
+    fn do_register(who: &T::AccountId, enc_key: &EncKey, key_proof: &RedjubjubSignature) -> result::Result<(), Error> {
+        // Decode rejected keys out of the prime order subgroup already, so this only fails on a malformed key.
+        let key = EncryptionKey::<Bls12>::try_from(enc_key)
+            .map_err(|_| Error::ZkSystem(zk_system::Error::InvalidEncKey))?;
+
+        // The encryption key is dec_key * G_Diversifier, so it verifies signatures by the decryption key.
+        let sig = redjubjub::Signature::try_from(*key_proof).map_err(|_| Error::InvalidKeyProof)?;
+        let vk = redjubjub::PublicKey::<Bls12>(key.0.into());
+        let msg = Self::register_message(who, enc_key);
+        ensure!(vk.verify(&msg, &sig, FixedGenerators::Diversifier, &*PARAMS), Error::InvalidKeyProof);
+
+        ensure!(Self::register_enc_key(enc_key), Error::AlreadyRegistered);
+        Ok(())
+    }
+
+    /// The message to sign with the decryption key to register `enc_key` from the account `who`.
+    /// It is bound to the account, so that a proof seen in the pool can not be replayed by someone else.
+    pub fn register_message(who: &T::AccountId, enc_key: &EncKey) -> Vec<u8> {
+        let mut msg = REGISTER_PERSONALIZATION.to_vec();
+        msg.extend(enc_key.encode());
+        msg.extend(who.encode());
+        msg
+    }
+
+    /// Report a failed transaction in a `TransactionFailed` event.
+    fn fail(who: T::AccountId, e: Error) -> &'static str {
+        Self::deposit_event(RawEvent::TransactionFailed(who, e.code()));
+        e.into()
+    }
+
     // PUBLIC MUTABLES
 
     /// Rolling over allows us to send transactions asynchronously and protect from front-running attacks.
@@ -166,7 +388,9 @@ impl<T: Trait> Module<T> {
     /// To achieve this, we define a separate (internal) method for rolling over,
     /// and the first thing every other method does is to call this method.
     /// More details in Section 3.1: https://crypto.stanford.edu/~buenz/papers/zether.pdf
-    pub fn rollover(addr: &EncKey) -> result::Result<(), &'static str> {
+    ///
+    /// Returns whether the account was rolled over.
+    pub fn apply_rollover(addr: &EncKey) -> result::Result<bool, Error> {
         let current_epoch = <zk_system::Module<T>>::get_current_epoch();
 
         let last_rollover = Self::last_rollover(addr)
@@ -188,7 +412,7 @@ impl<T: Trait> Module<T> {
 
                 match new_balance {
                     Ok(nb) => *balance = Some(nb),
-                    Err(_) => return Err("Faild to mutate encrypted balance."),
+                    Err(_) => return Err(Error::BalanceUpdateFailed),
                 }
 
                 Ok(())
@@ -202,7 +426,24 @@ impl<T: Trait> Module<T> {
         // Initialize a nonce pool
         <zk_system::Module<T>>::init_nonce_pool(current_epoch);
 
-        Ok(())
+        Ok(last_rollover < current_epoch)
+    }
+
+    /// Add the encryption key to the anonymity set unless it is already there.
+    ///
+    /// Returns whether the key was newly registered.
+    pub fn register_enc_key(enc_key: &EncKey) -> bool {
+        if <EncKeyIndex<T>>::exists(enc_key) {
+            return false;
+        }
+
+        let index = Self::enc_key_count();
+        <EncKeyAt<T>>::insert(index, *enc_key);
+        <EncKeyIndex<T>>::insert(*enc_key, index);
+        <EncKeyCount<T>>::put(index + 1);
+
+        Self::deposit_event(RawEvent::Registered(*enc_key, index));
+        true
     }
 
      /// Adding transferred amount to pending transfer.
@@ -210,9 +451,9 @@ impl<T: Trait> Module<T> {
         address: &EncKey,
         amount: &LeftCiphertext,
         randomness: &RightCiphertext
-    ) -> result::Result<(), &'static str> {
+    ) -> result::Result<(), Error> {
         let enc_amount = Ciphertext::from_left_right(*amount, *randomness)
-            .map_err(|_| "Faild to create amount ciphertext.")?;
+            .map_err(|_| Error::InvalidCiphertext)?;
 
         <PendingTransfer<T>>::mutate(address, |pending_transfer| {
             let new_pending_transfer = match pending_transfer.clone() {
@@ -222,14 +463,26 @@ impl<T: Trait> Module<T> {
 
             match new_pending_transfer {
                 Ok(np) => *pending_transfer = Some(np),
-                Err(_) => return Err("Faild to mutate pending transfer.")
+                Err(_) => return Err(Error::BalanceUpdateFailed)
             }
 
             Ok(())
         })?;
+        Self::register_enc_key(address);
 
         Ok(())
     }
+
+    // PUBLIC IMMUTABLES
+
+    /// Get at most `limit` registered encryption keys, starting from the index `start`.
+    pub fn enc_keys(start: u32, limit: u32) -> Vec<EncKey> {
+        let end = start.saturating_add(limit).min(Self::enc_key_count());
+
+        (start..end)
+            .filter_map(|i| Self::enc_key_at(i))
+            .collect()
+    }
 }
 
 #[cfg(feature = "std")]
@@ -250,7 +503,7 @@ mod tests {
         testing::{Digest, DigestItem, Header}
     };
     use zprimitives::{Ciphertext, SigVerificationKey, PARAMS as ZPARAMS};
-    use keys::EncryptionKey;
+    use keys::{EncryptionKey, ProofGenerationKey};
     use jubjub::{curve::{JubjubBls12, FixedGenerators, fs}};
     use pairing::{Field, bls12_381::Bls12};
     use zcrypto::elgamal;
@@ -476,6 +729,7 @@ mod tests {
                     MultiEncKeys::<tBls12, Anonymous>::new(enc_key_recipient, decoys),
                     &enc_balances,
                     g_epoch,
+                    0,
                     rng,
                     &*PARAMS
                 ).unwrap();
@@ -489,8 +743,82 @@ mod tests {
                 enc_keys,
                 left_ciphertexts,
                 RightCiphertext::from_slice(&tx.right_ciphertext[..]),
-                Nonce::from_slice(&tx.nonce[..])
+                Nonce::from_slice(&tx.nonce[..]),
+                tx.nonce_counter
             ));
         })
     }
+
+    #[test]
+    fn test_register() {
+        with_externalities(&mut new_test_ext(), || {
+            let genesis_keys = init_anonymous_enc_keys();
+            assert_eq!(AnonymousBalances::enc_key_count(), genesis_keys.len() as u32);
+            assert_eq!(AnonymousBalances::enc_key_index(&genesis_keys[1]), Some(1));
+
+            let rng = &mut XorShiftRng::from_seed([0xbc4f6d44, 0xd62f276c, 0xb963afd0, 0x5455863d]);
+            let p_g = FixedGenerators::Diversifier;
+            let seed = b"Carol                           ".to_vec();
+            let dec_key = ProofGenerationKey::<Bls12>::from_seed(&seed[..], &*ZPARAMS).into_decryption_key().unwrap();
+            let enc_key = EncKey::try_from(EncryptionKey::from_decryption_key(&dec_key, &*ZPARAMS)).unwrap();
+            let sk = redjubjub::PrivateKey::<Bls12>(dec_key.0);
+
+            let who = SigVerificationKey::default();
+            let other = SigVerificationKey::from_slice(&[1u8; 32]);
+            let sign = |who: &SigVerificationKey, sk: &redjubjub::PrivateKey<Bls12>, rng: &mut XorShiftRng| {
+                let msg = AnonymousBalances::register_message(who, &enc_key);
+                RedjubjubSignature::try_from(sk.sign(&msg, rng, p_g, &*ZPARAMS)).unwrap()
+            };
+
+            // signed by another key, or for another account
+            let wrong_key = sign(&who, &redjubjub::PrivateKey::<Bls12>(rng.gen()), rng);
+            assert_eq!(
+                AnonymousBalances::register(Origin::signed(who), enc_key, wrong_key),
+                Err(Error::InvalidKeyProof.as_str())
+            );
+            let other_proof = sign(&other, &sk, rng);
+            assert_eq!(
+                AnonymousBalances::register(Origin::signed(who), enc_key, other_proof),
+                Err(Error::InvalidKeyProof.as_str())
+            );
+            assert_eq!(AnonymousBalances::enc_key_count(), 12);
+
+            let key_proof = sign(&who, &sk, rng);
+            assert_ok!(AnonymousBalances::register(Origin::signed(who), enc_key, key_proof));
+            assert_eq!(AnonymousBalances::enc_key_count(), 13);
+            assert_eq!(AnonymousBalances::enc_key_index(&enc_key), Some(12));
+            assert_eq!(
+                AnonymousBalances::register(Origin::signed(who), enc_key, key_proof),
+                Err(Error::AlreadyRegistered.as_str())
+            );
+            assert_eq!(AnonymousBalances::enc_key_count(), 13);
+
+            assert_eq!(AnonymousBalances::enc_keys(0, 2), genesis_keys[..2].to_vec());
+            assert_eq!(AnonymousBalances::enc_keys(12, 5), vec![enc_key]);
+            assert!(AnonymousBalances::enc_keys(13, 5).is_empty());
+        })
+    }
+
+    #[test]
+    fn test_error_codes() {
+        let errors = [
+            Error::InvalidZkProof,
+            Error::InvalidCiphertext,
+            Error::BalanceUpdateFailed,
+            Error::LengthMismatch,
+            Error::NothingToRollOver,
+            Error::AlreadyRegistered,
+            Error::InvalidKeyProof,
+            Error::ZkSystem(zk_system::Error::InvalidProofEncoding),
+            Error::ZkSystem(zk_system::Error::NonceAlreadyUsed),
+        ];
+
+        for e in errors.iter() {
+            assert_eq!(Error::from_code(e.code()), Some(*e));
+        }
+
+        assert_eq!(Error::ZkSystem(zk_system::Error::NonceAlreadyUsed).code(), 0x109);
+        assert_eq!(Error::from_code(0), None);
+        assert_eq!(Error::from_code(0x100), None);
+    }
 }
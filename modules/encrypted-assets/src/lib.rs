@@ -22,6 +22,70 @@ pub trait Trait: system::Trait + encrypted_balances::Trait + zk_system::Trait {
     type AssetId: Parameter + SimpleArithmetic + Default + Copy;
 }
 
+/// An error of the encrypted-assets module.
+///
+/// The codes returned by `code` are stable and are reported in the
+/// `TransactionFailed` event. Errors of the zk-system module are offset by `0x100`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum Error {
+    /// The zk proof does not verify against the public input.
+    InvalidZkProof,
+    /// An amount, a fee or the randomness is not a valid ciphertext.
+    InvalidCiphertext,
+    /// A stored balance or pending transfer could not be updated.
+    BalanceUpdateFailed,
+    /// The account has no pending transfer, or was already rolled over in this epoch.
+    NothingToRollOver,
+    /// An error of the zk-system module.
+    ZkSystem(zk_system::Error),
+}
+
+impl Error {
+    pub fn code(&self) -> u16 {
+        match self {
+            Error::InvalidZkProof => 1,
+            Error::InvalidCiphertext => 2,
+            Error::BalanceUpdateFailed => 3,
+            Error::NothingToRollOver => 4,
+            Error::ZkSystem(e) => 0x100 + e.code(),
+        }
+    }
+
+    pub fn from_code(code: u16) -> Option<Self> {
+        match code {
+            1 => Some(Error::InvalidZkProof),
+            2 => Some(Error::InvalidCiphertext),
+            3 => Some(Error::BalanceUpdateFailed),
+            4 => Some(Error::NothingToRollOver),
+            c if c > 0x100 => zk_system::Error::from_code(c - 0x100).map(Error::ZkSystem),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Error::InvalidZkProof => "The zk proof is invalid. Make sure it was built from the current asset balance.",
+            Error::InvalidCiphertext => "An amount, fee or randomness is not a valid ciphertext.",
+            Error::BalanceUpdateFailed => "Failed to update the encrypted asset balance.",
+            Error::NothingToRollOver => "Nothing to roll over. Transfers received in this epoch are rolled over in the next one.",
+            Error::ZkSystem(e) => e.as_str(),
+        }
+    }
+}
+
+impl From<zk_system::Error> for Error {
+    fn from(e: zk_system::Error) -> Error {
+        Error::ZkSystem(e)
+    }
+}
+
+impl From<Error> for &'static str {
+    fn from(e: Error) -> &'static str {
+        e.as_str()
+    }
+}
+
 decl_module! {
     pub struct Module<T: Trait> for enum Call where origin: T::Origin {
         fn deposit_event<T>() = default;
@@ -37,49 +101,13 @@ decl_module! {
             fee: LeftCiphertext,
             balance: Ciphertext,
             randomness: RightCiphertext,
-            nonce: Nonce
+            nonce: Nonce,
+            nonce_counter: u32
         ) {
             let rvk = ensure_signed(origin)?;
 
-            // Initialize a nonce pool
-            let current_epoch = <zk_system::Module<T>>::get_current_epoch();
-            <zk_system::Module<T>>::init_nonce_pool(current_epoch);
-
-            // Veridate the provided nonce isn't included in the nonce pool.
-            ensure!(!<zk_system::Module<T>>::nonce_pool().contains(&nonce), "Provided nonce is already included in the nonce pool.");
-
-            // Verify a zk proof
-            // 1. Spend authority verification
-            // 2. Range check of issued amount
-            // 3. Encryption integrity
-            if !<zk_system::Module<T>>::verify_confidential_proof(
-                &zkproof,
-                &issuer,
-                &issuer,
-                &total,
-                &total,
-                &balance,
-                &rvk,
-                &fee,
-                &randomness,
-                &nonce
-            )? {
-                Self::deposit_event(RawEvent::InvalidZkProof());
-                return Err("Invalid zkproof");
-            }
-
-            // Add a nonce into the nonce pool
-            <zk_system::Module<T>>::nonce_pool().push(nonce);
-
-            let id = Self::next_asset_id();
-            <NextAssetId<T>>::mutate(|id| *id += One::one());
-
-            let total_ciphertext = Ciphertext::from_left_right(total, randomness)
-                .map_err(|_| "Faild to create ciphertext from left and right.")?;
-            <EncryptedBalance<T>>::insert((id, issuer.clone()), total_ciphertext.clone());
-            <TotalSupply<T>>::insert(id, total_ciphertext.clone());
-
-            Self::deposit_event(RawEvent::Issued(id, issuer, total_ciphertext));
+            Self::do_issue(rvk.clone(), zkproof, issuer, total, fee, balance, randomness, nonce, nonce_counter)
+                .map_err(|e| Self::fail(rvk, e))?;
         }
 
         /// Move some encrypted assets from one holder to another.
@@ -93,74 +121,25 @@ decl_module! {
             amount_recipient: LeftCiphertext,
             fee_sender: LeftCiphertext,
             randomness: RightCiphertext,
-            nonce: Nonce
+            nonce: Nonce,
+            nonce_counter: u32
         ) {
             let rvk = ensure_signed(origin)?;
 
-            // Rollover and get sender's balance.
-            // This function causes a storage mutation, but it's needed before `verify_proof` function is called.
-            // No problem if errors occur after this function because
-            // it just rollover user's own `pending trasfer` to `encrypted balances`.
-            Self::rollover(&address_sender, asset_id)?;
-
-            // Rollover and get recipient's balance
-            // This function causes a storage mutation, but it's needed before `verify_proof` function is called.
-            // No problem if errors occur after this function because
-            // it just rollover user's own `pending trasfer` to `encrypted balances`.
-            Self::rollover(&address_recipient, asset_id)?;
-
-            // Veridate the provided nonce isn't included in the nonce pool.
-            ensure!(!<zk_system::Module<T>>::nonce_pool().contains(&nonce), "Provided nonce is already included in the nonce pool.");
-
-            // Verify the zk proof
-            if !<zk_system::Module<T>>::verify_confidential_proof(
-                &zkproof,
-                &address_sender,
-                &address_recipient,
-                &amount_sender,
-                &amount_recipient,
-                &Self::encrypted_balance((asset_id, address_sender)).map_or(Ciphertext::zero(), |e| e),
-                &rvk,
-                &fee_sender,
-                &randomness,
-                &nonce
-            )? {
-                Self::deposit_event(RawEvent::InvalidZkProof());
-                return Err("Invalid zkproof");
-            }
-
-            // Add a nonce into the nonce pool
-            <zk_system::Module<T>>::nonce_pool().push(nonce);
-
-            // Subtracting transferred amount and fee from the sender's encrypted balances.
-            // This function causes a storage mutation.
-            Self::sub_enc_balance(
-                &address_sender,
-                asset_id,
-                &amount_sender,
-                &fee_sender,
-                &randomness
-            )
-            .map_err(|_| "Faild to subtract amount from sender's balance.")?;
-
-            // Adding transferred amount to the recipient's pending transfer.
-            // This function causes a storage mutation.
-            Self::add_pending_transfer(
-                &address_recipient,
+            Self::do_confidential_transfer(
+                rvk.clone(),
                 asset_id,
-                &amount_recipient,
-                &randomness
+                zkproof,
+                address_sender,
+                address_recipient,
+                amount_sender,
+                amount_recipient,
+                fee_sender,
+                randomness,
+                nonce,
+                nonce_counter
             )
-            .map_err(|_| "Faild to add amount to recipient's pending_transfer.")?;
-
-            Self::deposit_event(
-                RawEvent::ConfidentialAssetTransferred(
-                    asset_id, zkproof, address_sender, address_recipient,
-                    amount_sender, amount_recipient, fee_sender, randomness,
-                    Self::encrypted_balance((asset_id, address_sender)).map_or(Ciphertext::zero(), |e| e),
-                    rvk
-                )
-            );
+            .map_err(|e| Self::fail(rvk, e))?;
         }
 
         /// Destroy any encrypted assets of `id` owned by `owner`.
@@ -173,45 +152,34 @@ decl_module! {
             dummy_fee: LeftCiphertext,
             dummy_balance: Ciphertext,
             randomness: RightCiphertext,
-            nonce: Nonce
+            nonce: Nonce,
+            nonce_counter: u32
         ) {
             let rvk = ensure_signed(origin)?;
 
-            // Initialize a nonce pool
-            let current_epoch = <zk_system::Module<T>>::get_current_epoch();
-            <zk_system::Module<T>>::init_nonce_pool(current_epoch);
-
-            // Veridate the provided nonce isn't included in the nonce pool.
-            ensure!(!<zk_system::Module<T>>::nonce_pool().contains(&nonce), "Provided nonce is already included in the nonce pool.");
-
-            // Verify the zk proof
-            // 1. Spend authority verification
-            if !<zk_system::Module<T>>::verify_confidential_proof(
-                &zkproof,
-                &owner,
-                &owner,
-                &dummy_amount,
-                &dummy_amount,
-                &dummy_balance,
-                &rvk,
-                &dummy_fee,
-                &randomness,
-                &nonce
-            )? {
-                Self::deposit_event(RawEvent::InvalidZkProof());
-                return Err("Invalid zkproof");
-            }
-
-            // Add a nonce into the nonce pool
-            <zk_system::Module<T>>::nonce_pool().push(nonce);
-
-            let balance = <EncryptedBalance<T>>::take((id, owner.clone()))
-                .map_or(Default::default(), |e| e);
+            Self::do_destroy(
+                rvk.clone(),
+                zkproof,
+                owner,
+                id,
+                dummy_amount,
+                dummy_fee,
+                dummy_balance,
+                randomness,
+                nonce,
+                nonce_counter
+            )
+            .map_err(|e| Self::fail(rvk, e))?;
+        }
 
-            let pending_transfer = <PendingTransfer<T>>::take((id, owner.clone()))
-                .map_or(Default::default(), |e| e);
+        /// Roll the pending transfer of `enc_key` in asset `asset_id` over into its
+        /// encrypted balance. Anyone may call it for any account, because it only merges
+        /// ciphertexts the account already owns. It fails unless there is something to
+        /// merge, so it succeeds at most once per account, asset and epoch.
+        fn rollover(origin, asset_id: T::AssetId, enc_key: EncKey) {
+            let who = ensure_signed(origin)?;
 
-            Self::deposit_event(RawEvent::Destroyed(id, owner, balance, pending_transfer));
+            Self::do_rollover(asset_id, &enc_key).map_err(|e| Self::fail(who, e))?;
         }
     }
 }
@@ -231,7 +199,10 @@ decl_event!(
         ),
         /// Some encrypted assets were destroyed.
         Destroyed(AssetId, EncKey, Ciphertext, Ciphertext),
-        InvalidZkProof(),
+        /// The pending transfer of the account was merged into its new encrypted asset balance.
+        RolledOver(AssetId, EncKey, Ciphertext),
+        /// A transaction of the account failed with the given `Error` code.
+        TransactionFailed(AccountId, u16),
     }
 );
 
@@ -255,6 +226,213 @@ decl_storage! {
 }
 
 impl<T: Trait> Module<T> {
+    fn do_issue(
+        rvk: T::AccountId,
+        zkproof: Proof,
+        issuer: EncKey,
+        total: LeftCiphertext,
+        fee: LeftCiphertext,
+        balance: Ciphertext,
+        randomness: RightCiphertext,
+        nonce: Nonce,
+        nonce_counter: u32
+    ) -> result::Result<(), Error> {
+        // Initialize a nonce pool
+        let current_epoch = <zk_system::Module<T>>::get_current_epoch();
+        <zk_system::Module<T>>::init_nonce_pool(current_epoch);
+
+        // Veridate the provided nonce isn't included in the nonce pool.
+        <zk_system::Module<T>>::ensure_nonce_unused(&nonce)?;
+
+        // Verify a zk proof
+        // 1. Spend authority verification
+        // 2. Range check of issued amount
+        // 3. Encryption integrity
+        if !<zk_system::Module<T>>::verify_confidential_proof(
+            &zkproof,
+            &issuer,
+            &issuer,
+            &total,
+            &total,
+            &balance,
+            &rvk,
+            &fee,
+            &randomness,
+            &nonce,
+            nonce_counter
+        )? {
+            return Err(Error::InvalidZkProof);
+        }
+
+        // Add a nonce into the nonce pool
+        <zk_system::Module<T>>::insert_nonce(nonce);
+
+        let id = Self::next_asset_id();
+        <NextAssetId<T>>::mutate(|id| *id += One::one());
+
+        let total_ciphertext = Ciphertext::from_left_right(total, randomness)
+            .map_err(|_| Error::InvalidCiphertext)?;
+        <EncryptedBalance<T>>::insert((id, issuer.clone()), total_ciphertext.clone());
+        <TotalSupply<T>>::insert(id, total_ciphertext.clone());
+
+        Self::deposit_event(RawEvent::Issued(id, issuer, total_ciphertext));
+
+        Ok(())
+    }
+
+    fn do_confidential_transfer(
+        rvk: T::AccountId,
+        asset_id: T::AssetId,
+        zkproof: Proof,
+        address_sender: EncKey,
+        address_recipient: EncKey,
+        amount_sender: LeftCiphertext,
+        amount_recipient: LeftCiphertext,
+        fee_sender: LeftCiphertext,
+        randomness: RightCiphertext,
+        nonce: Nonce,
+        nonce_counter: u32
+    ) -> result::Result<(), Error> {
+        // Rollover and get sender's balance.
+        // This function causes a storage mutation, but it's needed before `verify_proof` function is called.
+        // No problem if errors occur after this function because
+        // it just rollover user's own `pending trasfer` to `encrypted balances`.
+        Self::apply_rollover(&address_sender, asset_id)?;
+
+        // Rollover and get recipient's balance
+        // This function causes a storage mutation, but it's needed before `verify_proof` function is called.
+        // No problem if errors occur after this function because
+        // it just rollover user's own `pending trasfer` to `encrypted balances`.
+        Self::apply_rollover(&address_recipient, asset_id)?;
+
+        // Veridate the provided nonce isn't included in the nonce pool.
+        <zk_system::Module<T>>::ensure_nonce_unused(&nonce)?;
+
+        // Verify the zk proof
+        if !<zk_system::Module<T>>::verify_confidential_proof(
+            &zkproof,
+            &address_sender,
+            &address_recipient,
+            &amount_sender,
+            &amount_recipient,
+            &Self::encrypted_balance((asset_id, address_sender)).map_or(Ciphertext::zero(), |e| e),
+            &rvk,
+            &fee_sender,
+            &randomness,
+            &nonce,
+            nonce_counter
+        )? {
+            return Err(Error::InvalidZkProof);
+        }
+
+        // Add a nonce into the nonce pool
+        <zk_system::Module<T>>::insert_nonce(nonce);
+
+        // Subtracting transferred amount and fee from the sender's encrypted balances.
+        // This function causes a storage mutation.
+        Self::sub_enc_balance(
+            &address_sender,
+            asset_id,
+            &amount_sender,
+            &fee_sender,
+            &randomness
+        )?;
+
+        // Adding transferred amount to the recipient's pending transfer.
+        // This function causes a storage mutation.
+        Self::add_pending_transfer(
+            &address_recipient,
+            asset_id,
+            &amount_recipient,
+            &randomness
+        )?;
+
+        Self::deposit_event(
+            RawEvent::ConfidentialAssetTransferred(
+                asset_id, zkproof, address_sender, address_recipient,
+                amount_sender, amount_recipient, fee_sender, randomness,
+                Self::encrypted_balance((asset_id, address_sender)).map_or(Ciphertext::zero(), |e| e),
+                rvk
+            )
+        );
+
+        Ok(())
+    }
+
+    fn do_destroy(
+        rvk: T::AccountId,
+        zkproof: Proof,
+        owner: EncKey,
+        id: T::AssetId,
+        dummy_amount: LeftCiphertext,
+        dummy_fee: LeftCiphertext,
+        dummy_balance: Ciphertext,
+        randomness: RightCiphertext,
+        nonce: Nonce,
+        nonce_counter: u32
+    ) -> result::Result<(), Error> {
+        // Initialize a nonce pool
+        let current_epoch = <zk_system::Module<T>>::get_current_epoch();
+        <zk_system::Module<T>>::init_nonce_pool(current_epoch);
+
+        // Veridate the provided nonce isn't included in the nonce pool.
+        <zk_system::Module<T>>::ensure_nonce_unused(&nonce)?;
+
+        // Verify the zk proof
+        // 1. Spend authority verification
+        if !<zk_system::Module<T>>::verify_confidential_proof(
+            &zkproof,
+            &owner,
+            &owner,
+            &dummy_amount,
+            &dummy_amount,
+            &dummy_balance,
+            &rvk,
+            &dummy_fee,
+            &randomness,
+            &nonce,
+            nonce_counter
+        )? {
+            return Err(Error::InvalidZkProof);
+        }
+
+        // Add a nonce into the nonce pool
+        <zk_system::Module<T>>::insert_nonce(nonce);
+
+        let balance = <EncryptedBalance<T>>::take((id, owner.clone()))
+            .map_or(Default::default(), |e| e);
+
+        let pending_transfer = <PendingTransfer<T>>::take((id, owner.clone()))
+            .map_or(Default::default(), |e| e);
+
+        Self::deposit_event(RawEvent::Destroyed(id, owner, balance, pending_transfer));
+
+        Ok(())
+    }
+
+    fn do_rollover(asset_id: T::AssetId, enc_key: &EncKey) -> result::Result<(), Error> {
+        let addr_id = (asset_id, *enc_key);
+
+        ensure!(Self::pending_transfer(addr_id).is_some(), Error::NothingToRollOver);
+        ensure!(Self::apply_rollover(enc_key, asset_id)?, Error::NothingToRollOver);
+
+        Self::deposit_event(
+            RawEvent::RolledOver(
+                asset_id,
+                *enc_key,
+                Self::encrypted_balance(addr_id).map_or(Ciphertext::zero(), |e| e)
+            )
+        );
+
+        Ok(())
+    }
+
+    /// Report a failed transaction in a `TransactionFailed` event.
+    fn fail(who: T::AccountId, e: Error) -> &'static str {
+        Self::deposit_event(RawEvent::TransactionFailed(who, e.code()));
+        e.into()
+    }
+
     // PUBLIC MUTABLES
 
     /// Rolling over allows us to send transactions asynchronously and protect from front-running attacks.
@@ -263,7 +441,9 @@ impl<T: Trait> Module<T> {
     /// To achieve this, we define a separate (internal) method for rolling over,
     /// and the first thing every other method does is to call this method.
     /// More details in Section 3.1: https://crypto.stanford.edu/~buenz/papers/zether.pdf
-    pub fn rollover(addr: &EncKey, asset_id: T::AssetId) -> Result<(), &'static str> {
+    ///
+    /// Returns whether the account was rolled over.
+    pub fn apply_rollover(addr: &EncKey, asset_id: T::AssetId) -> result::Result<bool, Error> {
         let current_epoch = <zk_system::Module<T>>::get_current_epoch();
         let addr_id = (asset_id, *addr);
 
@@ -286,7 +466,7 @@ impl<T: Trait> Module<T> {
 
                 match new_balance {
                     Ok(nb) => *balance = Some(nb),
-                    Err(_) => return Err("Faild to mutate encrypted balance."),
+                    Err(_) => return Err(Error::BalanceUpdateFailed),
                 }
 
                 Ok(())
@@ -300,7 +480,7 @@ impl<T: Trait> Module<T> {
         // Initialize a nonce pool
         <zk_system::Module<T>>::init_nonce_pool(current_epoch);
 
-        Ok(())
+        Ok(last_rollover < current_epoch)
     }
 
     // Subtracting transferred amount and fee from encrypted balances.
@@ -310,13 +490,13 @@ impl<T: Trait> Module<T> {
         amount: &LeftCiphertext,
         fee: &LeftCiphertext,
         randomness: &RightCiphertext
-    ) -> result::Result<(), &'static str> {
+    ) -> result::Result<(), Error> {
         let enc_amount = Ciphertext::from_left_right(*amount, *randomness)
-            .map_err(|_| "Faild to create amount ciphertext.")?;
+            .map_err(|_| Error::InvalidCiphertext)?;
         let enc_fee = Ciphertext::from_left_right(*fee, *randomness)
-            .map_err(|_| "Faild to create fee ciphertext.")?;
+            .map_err(|_| Error::InvalidCiphertext)?;
         let amount_plus_fee = enc_amount.add(&enc_fee)
-            .map_err(|_| "Failed to add fee to amount")?;
+            .map_err(|_| Error::InvalidCiphertext)?;
 
         <EncryptedBalance<T>>::mutate((asset_id, *address), |balance| {
             let new_balance = balance.clone()
@@ -336,9 +516,9 @@ impl<T: Trait> Module<T> {
         asset_id: T::AssetId,
         amount: &LeftCiphertext,
         randomness: &RightCiphertext
-    ) -> result::Result<(), &'static str> {
+    ) -> result::Result<(), Error> {
         let enc_amount = Ciphertext::from_left_right(*amount, *randomness)
-            .map_err(|_| "Faild to create amount ciphertext.")?;
+            .map_err(|_| Error::InvalidCiphertext)?;
 
         <PendingTransfer<T>>::mutate((asset_id, *address), |pending_transfer| {
             let new_pending_transfer = match pending_transfer.clone() {
@@ -348,7 +528,7 @@ impl<T: Trait> Module<T> {
 
             match new_pending_transfer {
                 Ok(np) => *pending_transfer = Some(np),
-                Err(_) => return Err("Faild to mutate pending transfer.")
+                Err(_) => return Err(Error::BalanceUpdateFailed)
             }
 
             Ok(())
@@ -552,6 +732,7 @@ mod tests {
                     MultiEncKeys::<tBls12, Confidential>::new(enc_key),
                     &enc_balance,
                     get_g_epoch(),
+                    0,
                     rng,
                     &*PARAMS
                 ).unwrap();
@@ -566,7 +747,8 @@ mod tests {
                 LeftCiphertext::from_slice(&tx.left_fee[..]),
                 Ciphertext::from_slice(&tx.enc_balance[..]),
                 RightCiphertext::from_slice(&tx.right_randomness[..]),
-                Nonce::from_slice(&tx.nonce[..])
+                Nonce::from_slice(&tx.nonce[..]),
+                tx.nonce_counter
             ));
         })
     }
@@ -608,6 +790,7 @@ mod tests {
                     MultiEncKeys::<tBls12, Confidential>::new(recipient_account_id),
                     &enc_alice_bal,
                     get_g_epoch(),
+                    0,
                     rng,
                     &*PARAMS
                 ).unwrap();
@@ -622,7 +805,8 @@ mod tests {
                 LeftCiphertext::from_slice(&tx.left_amount_recipient[..]),
                 LeftCiphertext::from_slice(&tx.left_fee[..]),
                 RightCiphertext::from_slice(&tx.right_randomness[..]),
-                Nonce::from_slice(&tx.nonce[..])
+                Nonce::from_slice(&tx.nonce[..]),
+                tx.nonce_counter
             ));
         })
     }
@@ -655,6 +839,7 @@ mod tests {
                     MultiEncKeys::<tBls12, Confidential>::new(enc_key),
                     &dummy_balance,
                     get_g_epoch(),
+                    0,
                     rng,
                     &*PARAMS
                 ).unwrap();
@@ -668,9 +853,30 @@ mod tests {
                 LeftCiphertext::from_slice(&tx.left_fee[..]),
                 Ciphertext::from_slice(&tx.enc_balance[..]),
                 RightCiphertext::from_slice(&tx.right_randomness[..]),
-                Nonce::from_slice(&tx.nonce[..])
+                Nonce::from_slice(&tx.nonce[..]),
+                tx.nonce_counter
             ));
 
         })
     }
+
+    #[test]
+    fn test_error_codes() {
+        let errors = [
+            Error::InvalidZkProof,
+            Error::InvalidCiphertext,
+            Error::BalanceUpdateFailed,
+            Error::NothingToRollOver,
+            Error::ZkSystem(zk_system::Error::InvalidProofEncoding),
+            Error::ZkSystem(zk_system::Error::NonceAlreadyUsed),
+        ];
+
+        for e in errors.iter() {
+            assert_eq!(Error::from_code(e.code()), Some(*e));
+        }
+
+        assert_eq!(Error::ZkSystem(zk_system::Error::NonceAlreadyUsed).code(), 0x109);
+        assert_eq!(Error::from_code(0), None);
+        assert_eq!(Error::from_code(0x100), None);
+    }
 }
@@ -1,7 +1,7 @@
 //! A module for dealing with confidential transfer
 #![cfg_attr(not(feature = "std"), no_std)]
 
-use support::{decl_module, decl_storage, decl_event, StorageMap, dispatch::Result};
+use support::{decl_module, decl_storage, decl_event, StorageMap, dispatch::Result, ensure};
 use rstd::{
     prelude::*,
     result,
@@ -17,6 +17,70 @@ pub trait Trait: system::Trait + zk_system::Trait {
 
 type FeeAmount = u32;
 
+/// An error of the encrypted-balances module.
+///
+/// The codes returned by `code` are stable and are reported in the
+/// `TransactionFailed` event. Errors of the zk-system module are offset by `0x100`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum Error {
+    /// The zk proof does not verify against the public input.
+    InvalidZkProof,
+    /// An amount, a fee or the randomness is not a valid ciphertext.
+    InvalidCiphertext,
+    /// A stored balance or pending transfer could not be updated.
+    BalanceUpdateFailed,
+    /// The account has no pending transfer, or was already rolled over in this epoch.
+    NothingToRollOver,
+    /// An error of the zk-system module.
+    ZkSystem(zk_system::Error),
+}
+
+impl Error {
+    pub fn code(&self) -> u16 {
+        match self {
+            Error::InvalidZkProof => 1,
+            Error::InvalidCiphertext => 2,
+            Error::BalanceUpdateFailed => 3,
+            Error::NothingToRollOver => 4,
+            Error::ZkSystem(e) => 0x100 + e.code(),
+        }
+    }
+
+    pub fn from_code(code: u16) -> Option<Self> {
+        match code {
+            1 => Some(Error::InvalidZkProof),
+            2 => Some(Error::InvalidCiphertext),
+            3 => Some(Error::BalanceUpdateFailed),
+            4 => Some(Error::NothingToRollOver),
+            c if c > 0x100 => zk_system::Error::from_code(c - 0x100).map(Error::ZkSystem),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Error::InvalidZkProof => "The zk proof is invalid. Make sure it was built from the current balance.",
+            Error::InvalidCiphertext => "An amount, fee or randomness is not a valid ciphertext.",
+            Error::BalanceUpdateFailed => "Failed to update the encrypted balance.",
+            Error::NothingToRollOver => "Nothing to roll over. Transfers received in this epoch are rolled over in the next one.",
+            Error::ZkSystem(e) => e.as_str(),
+        }
+    }
+}
+
+impl From<zk_system::Error> for Error {
+    fn from(e: zk_system::Error) -> Error {
+        Error::ZkSystem(e)
+    }
+}
+
+impl From<Error> for &'static str {
+    fn from(e: Error) -> &'static str {
+        e.as_str()
+    }
+}
+
 decl_module! {
 	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
         // Initializing events
@@ -31,69 +95,35 @@ decl_module! {
             amount_recipient: LeftCiphertext,
             fee_sender: LeftCiphertext,
             randomness: RightCiphertext,
-            nonce: Nonce
+            nonce: Nonce,
+            nonce_counter: u32
         ) -> Result {
 			let rvk = ensure_signed(origin)?;
 
-            // This function causes a storage mutation, but it's needed before `verify_proof` function is called.
-            // No problem if errors occur after this function because
-            // it just rollover user's own `pending trasfer` to `encrypted balances`.
-            Self::rollover(&address_sender)?;
-
-            // This function causes a storage mutation, but it's needed before `verify_proof` function is called.
-            // No problem if errors occur after this function because
-            // it just rollover user's own `pending trasfer` to `encrypted balances`.
-            Self::rollover(&address_recipient)?;
-
-            // Veridate the provided nonce isn't included in the nonce pool.
-            assert!(!<zk_system::Module<T>>::nonce_pool().contains(&nonce));
-
-            // Verify the zk proof
-            if !<zk_system::Module<T>>::verify_confidential_proof(
-                    &zkproof,
-                    &address_sender,
-                    &address_recipient,
-                    &amount_sender,
-                    &amount_recipient,
-                    &Self::encrypted_balance(address_sender).map_or(Ciphertext::zero(), |e| e),
-                    &rvk,
-                    &fee_sender,
-                    &randomness,
-                    &nonce
-                )? {
-                    Self::deposit_event(RawEvent::InvalidZkProof());
-                    return Err("Invalid zkproof");
-            }
+            Self::do_confidential_transfer(
+                rvk.clone(),
+                zkproof,
+                address_sender,
+                address_recipient,
+                amount_sender,
+                amount_recipient,
+                fee_sender,
+                randomness,
+                nonce,
+                nonce_counter
+            )
+            .map_err(|e| Self::fail(rvk, e))
+		}
 
-            // Add a nonce into the nonce pool
-            <zk_system::Module<T>>::nonce_pool().push(nonce);
-
-            // Subtracting transferred amount and fee from the sender's encrypted balances.
-            // This function causes a storage mutation.
-            Self::sub_enc_balance(&address_sender, &amount_sender, &fee_sender, &randomness)
-                .map_err(|_| "Faild to subtract amount from sender's balance.")?;
-
-            // Adding transferred amount to the recipient's pending transfer.
-            // This function causes a storage mutation.
-            Self::add_pending_transfer(&address_recipient, &amount_recipient, &randomness)
-                .map_err(|_| "Faild to add amount to recipient's pending_transfer.")?;
-
-            Self::deposit_event(
-                RawEvent::ConfidentialTransfer(
-                    zkproof,
-                    address_sender,
-                    address_recipient,
-                    amount_sender,
-                    amount_recipient,
-                    fee_sender,
-                    randomness,
-                    Self::encrypted_balance(address_sender).map_or(Ciphertext::zero(), |e| e),
-                    rvk
-                )
-            );
+        /// Roll the pending transfer of `enc_key` over into its encrypted balance.
+        /// Anyone may call it for any account, because it only merges ciphertexts
+        /// the account already owns. It fails unless there is something to merge,
+        /// so it succeeds at most once per account and epoch.
+        pub fn rollover(origin, enc_key: EncKey) -> Result {
+            let who = ensure_signed(origin)?;
 
-            Ok(())
-		}
+            Self::do_rollover(&enc_key).map_err(|e| Self::fail(who, e))
+        }
 	}
 }
 
@@ -117,11 +147,104 @@ decl_event! (
     /// An event in this module.
 	pub enum Event<T> where <T as system::Trait>::AccountId {
 		ConfidentialTransfer(Proof, EncKey, EncKey, LeftCiphertext, LeftCiphertext, LeftCiphertext, RightCiphertext, Ciphertext, AccountId),
-        InvalidZkProof(),
+        /// The pending transfer of the account was merged into its new encrypted balance.
+        RolledOver(EncKey, Ciphertext),
+        /// A transaction of the account failed with the given `Error` code.
+        TransactionFailed(AccountId, u16),
 	}
 );
 
 impl<T: Trait> Module<T> {
+    fn do_confidential_transfer(
+        rvk: T::AccountId,
+        zkproof: Proof,
+        address_sender: EncKey,
+        address_recipient: EncKey,
+        amount_sender: LeftCiphertext,
+        amount_recipient: LeftCiphertext,
+        fee_sender: LeftCiphertext,
+        randomness: RightCiphertext,
+        nonce: Nonce,
+        nonce_counter: u32
+    ) -> result::Result<(), Error> {
+        // This function causes a storage mutation, but it's needed before `verify_proof` function is called.
+        // No problem if errors occur after this function because
+        // it just rollover user's own `pending trasfer` to `encrypted balances`.
+        Self::apply_rollover(&address_sender)?;
+
+        // This function causes a storage mutation, but it's needed before `verify_proof` function is called.
+        // No problem if errors occur after this function because
+        // it just rollover user's own `pending trasfer` to `encrypted balances`.
+        Self::apply_rollover(&address_recipient)?;
+
+        // Veridate the provided nonce isn't included in the nonce pool.
+        <zk_system::Module<T>>::ensure_nonce_unused(&nonce)?;
+
+        // Verify the zk proof
+        if !<zk_system::Module<T>>::verify_confidential_proof(
+                &zkproof,
+                &address_sender,
+                &address_recipient,
+                &amount_sender,
+                &amount_recipient,
+                &Self::encrypted_balance(address_sender).map_or(Ciphertext::zero(), |e| e),
+                &rvk,
+                &fee_sender,
+                &randomness,
+                &nonce,
+                nonce_counter
+            )? {
+                return Err(Error::InvalidZkProof);
+        }
+
+        // Add a nonce into the nonce pool
+        <zk_system::Module<T>>::insert_nonce(nonce);
+
+        // Subtracting transferred amount and fee from the sender's encrypted balances.
+        // This function causes a storage mutation.
+        Self::sub_enc_balance(&address_sender, &amount_sender, &fee_sender, &randomness)?;
+
+        // Adding transferred amount to the recipient's pending transfer.
+        // This function causes a storage mutation.
+        Self::add_pending_transfer(&address_recipient, &amount_recipient, &randomness)?;
+
+        Self::deposit_event(
+            RawEvent::ConfidentialTransfer(
+                zkproof,
+                address_sender,
+                address_recipient,
+                amount_sender,
+                amount_recipient,
+                fee_sender,
+                randomness,
+                Self::encrypted_balance(address_sender).map_or(Ciphertext::zero(), |e| e),
+                rvk
+            )
+        );
+
+        Ok(())
+    }
+
+    fn do_rollover(enc_key: &EncKey) -> result::Result<(), Error> {
+        ensure!(Self::pending_transfer(enc_key).is_some(), Error::NothingToRollOver);
+        ensure!(Self::apply_rollover(enc_key)?, Error::NothingToRollOver);
+
+        Self::deposit_event(
+            RawEvent::RolledOver(
+                *enc_key,
+                Self::encrypted_balance(enc_key).map_or(Ciphertext::zero(), |e| e)
+            )
+        );
+
+        Ok(())
+    }
+
+    /// Report a failed transaction in a `TransactionFailed` event.
+    fn fail(who: T::AccountId, e: Error) -> &'static str {
+        Self::deposit_event(RawEvent::TransactionFailed(who, e.code()));
+        e.into()
+    }
+
     // PUBLIC MUTABLES
 
     /// Rolling over allows us to send transactions asynchronously and protect from front-running attacks.
@@ -130,7 +253,9 @@ impl<T: Trait> Module<T> {
     /// To achieve this, we define a separate (internal) method for rolling over,
     /// and the first thing every other method does is to call this method.
     /// More details in Section 3.1: https://crypto.stanford.edu/~buenz/papers/zether.pdf
-    pub fn rollover(addr: &EncKey) -> result::Result<(), &'static str> {
+    ///
+    /// Returns whether the account was rolled over.
+    pub fn apply_rollover(addr: &EncKey) -> result::Result<bool, Error> {
         let current_epoch = <zk_system::Module<T>>::get_current_epoch();
 
         let last_rollover = Self::last_rollover(addr)
@@ -152,7 +277,7 @@ impl<T: Trait> Module<T> {
 
                 match new_balance {
                     Ok(nb) => *balance = Some(nb),
-                    Err(_) => return Err("Faild to mutate encrypted balance."),
+                    Err(_) => return Err(Error::BalanceUpdateFailed),
                 }
 
                 Ok(())
@@ -166,7 +291,7 @@ impl<T: Trait> Module<T> {
         // Initialize a nonce pool
         <zk_system::Module<T>>::init_nonce_pool(current_epoch);
 
-        Ok(())
+        Ok(last_rollover < current_epoch)
     }
 
     // Subtracting transferred amount and fee from encrypted balances.
@@ -175,13 +300,13 @@ impl<T: Trait> Module<T> {
         amount: &LeftCiphertext,
         fee: &LeftCiphertext,
         randomness: &RightCiphertext
-    ) -> result::Result<(), &'static str> {
+    ) -> result::Result<(), Error> {
         let enc_amount = Ciphertext::from_left_right(*amount, *randomness)
-            .map_err(|_| "Faild to create amount ciphertext.")?;
+            .map_err(|_| Error::InvalidCiphertext)?;
         let enc_fee = Ciphertext::from_left_right(*fee, *randomness)
-            .map_err(|_| "Faild to create fee ciphertext.")?;
+            .map_err(|_| Error::InvalidCiphertext)?;
         let amount_plus_fee = enc_amount.add(&enc_fee)
-            .map_err(|_| "Failed to add fee to amount")?;
+            .map_err(|_| Error::InvalidCiphertext)?;
 
         <EncryptedBalance<T>>::mutate(address, |balance| {
             let new_balance = balance.clone()
@@ -200,9 +325,9 @@ impl<T: Trait> Module<T> {
         address: &EncKey,
         amount: &LeftCiphertext,
         randomness: &RightCiphertext
-    ) -> result::Result<(), &'static str> {
+    ) -> result::Result<(), Error> {
         let enc_amount = Ciphertext::from_left_right(*amount, *randomness)
-            .map_err(|_| "Faild to create amount ciphertext.")?;
+            .map_err(|_| Error::InvalidCiphertext)?;
 
         <PendingTransfer<T>>::mutate(address, |pending_transfer| {
             let new_pending_transfer = match pending_transfer.clone() {
@@ -212,7 +337,7 @@ impl<T: Trait> Module<T> {
 
             match new_pending_transfer {
                 Ok(np) => *pending_transfer = Some(np),
-                Err(_) => return Err("Faild to mutate pending transfer.")
+                Err(_) => return Err(Error::BalanceUpdateFailed)
             }
 
             Ok(())
@@ -417,6 +542,7 @@ pub mod tests {
                     MultiEncKeys::<tBls12, Confidential>::new(recipient_account_id),
                     &enc_alice_bal,
                     g_epoch,
+                    0,
                     rng,
                     &*PARAMS
                 ).unwrap();
@@ -430,11 +556,114 @@ pub mod tests {
                 LeftCiphertext::from_slice(&tx.left_amount_recipient[..]),
                 LeftCiphertext::from_slice(&tx.left_fee[..]),
                 RightCiphertext::from_slice(&tx.right_randomness[..]),
-                Nonce::from_slice(&tx.nonce[..])
+                Nonce::from_slice(&tx.nonce[..]),
+                tx.nonce_counter
             ));
         })
     }
 
+    #[test]
+    fn test_multiple_transfers_in_epoch() {
+        use rand::{SeedableRng, XorShiftRng};
+        use test_pairing::bls12_381::Bls12 as tBls12;
+        use test_proofs::{EncryptionKey as tEncryptionKey, SpendingKey as tSpendingKey,
+            elgamal as telgamal, PARAMS, MultiEncKeys, KeyContext, ProofBuilder, Confidential,
+        };
+        use scrypto::jubjub::edwards as tedwards;
+        use zprimitives::GEpoch;
+
+        with_externalities(&mut new_test_ext(), || {
+            let alice_seed = b"Alice                           ".to_vec();
+            let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+            let bob_addr: [u8; 32] = hex!("45e66da531088b55dcb3b273ca825454d79d2d1d5c4fa2ba4a12c1fa1ccd6389");
+            let recipient_account_id = tEncryptionKey::<tBls12>::read(&mut &bob_addr[..], &PARAMS).unwrap();
+            let spending_key = tSpendingKey::<tBls12>::from_seed(&alice_seed);
+            let (alice_addr, _) = alice_balance_init();
+            let key_context = KeyContext::read_from_path(PK_PATH, VK_PATH).unwrap();
+
+            // Start a new epoch.
+            system::Module::<Test>::set_block_number(2);
+
+            let amount = 8;
+            let fee = 1;
+            let mut remaining_balance = 100;
+            let mut txs = vec![];
+
+            for nonce_counter in 0..2 {
+                remaining_balance -= amount + fee;
+
+                let enc_balance = EncryptedBalances::encrypted_balance(alice_addr).unwrap();
+                let enc_balance = vec![telgamal::Ciphertext::read(&mut enc_balance.as_bytes(), &*PARAMS).unwrap()];
+
+                let g_epoch = GEpoch::group_hash_with_counter(2, nonce_counter).unwrap();
+                let g_epoch = tedwards::Point::read(g_epoch.as_bytes(), &*PARAMS).unwrap().as_prime_order(&*PARAMS).unwrap();
+
+                let tx = key_context.gen_proof(
+                        amount,
+                        fee,
+                        remaining_balance, 0, 0,
+                        &spending_key,
+                        MultiEncKeys::<tBls12, Confidential>::new(recipient_account_id.clone()),
+                        &enc_balance,
+                        g_epoch,
+                        nonce_counter,
+                        rng,
+                        &*PARAMS
+                    ).unwrap();
+
+                assert_ok!(EncryptedBalances::confidential_transfer(
+                    Origin::signed(SigVerificationKey::from_slice(&tx.rvk[..])),
+                    Proof::from_slice(&tx.proof[..]),
+                    EncKey::from_slice(&tx.enc_key_sender[..]),
+                    EncKey::from_slice(&tx.enc_key_recipient[..]),
+                    LeftCiphertext::from_slice(&tx.left_amount_sender[..]),
+                    LeftCiphertext::from_slice(&tx.left_amount_recipient[..]),
+                    LeftCiphertext::from_slice(&tx.left_fee[..]),
+                    RightCiphertext::from_slice(&tx.right_randomness[..]),
+                    Nonce::from_slice(&tx.nonce[..]),
+                    tx.nonce_counter
+                ));
+
+                txs.push(tx);
+            }
+
+            // A replayed transaction is rejected by the nonce pool.
+            let tx = &txs[0];
+            assert!(EncryptedBalances::confidential_transfer(
+                Origin::signed(SigVerificationKey::from_slice(&tx.rvk[..])),
+                Proof::from_slice(&tx.proof[..]),
+                EncKey::from_slice(&tx.enc_key_sender[..]),
+                EncKey::from_slice(&tx.enc_key_recipient[..]),
+                LeftCiphertext::from_slice(&tx.left_amount_sender[..]),
+                LeftCiphertext::from_slice(&tx.left_amount_recipient[..]),
+                LeftCiphertext::from_slice(&tx.left_fee[..]),
+                RightCiphertext::from_slice(&tx.right_randomness[..]),
+                Nonce::from_slice(&tx.nonce[..]),
+                tx.nonce_counter
+            ).is_err());
+        })
+    }
+
+    #[test]
+    fn test_nonce_pool_is_cleared() {
+        with_externalities(&mut new_test_ext(), || {
+            let nonce = Nonce::from_slice(&hex!("c3427a3e3e9f19ff730d45c7c7daa1ee3c96b10a86085d11647fe27d923d654e")[..]);
+
+            zk_system::Module::<Test>::init_nonce_pool(2);
+            zk_system::Module::<Test>::insert_nonce(nonce);
+            assert!(zk_system::Module::<Test>::ensure_nonce_unused(&nonce).is_err());
+            assert_eq!(zk_system::Module::<Test>::nonce_count(), 1);
+            assert_eq!(zk_system::Module::<Test>::nonce_at(0), Some(nonce));
+
+            // The nonces of the last epoch are removed at the start of the next one.
+            zk_system::Module::<Test>::init_nonce_pool(3);
+            assert!(zk_system::Module::<Test>::ensure_nonce_unused(&nonce).is_ok());
+            assert_eq!(zk_system::Module::<Test>::nonce_count(), 0);
+            assert_eq!(zk_system::Module::<Test>::nonce_at(0), None);
+            assert_eq!(zk_system::Module::<Test>::nonce_epoch(nonce), None);
+        })
+    }
+
     #[test]
     #[should_panic]
     fn test_call_with_worng_proof() {
@@ -458,8 +687,54 @@ pub mod tests {
                 LeftCiphertext::from_slice(&enc10_by_bob[..]),
                 LeftCiphertext::from_slice(&enc1_by_alice[..]),
                 RightCiphertext::from_slice(&randomness[..]),
-                Nonce::from_slice(&nonce[..])
+                Nonce::from_slice(&nonce[..]),
+                0
             ));
         })
     }
+
+    #[test]
+    fn test_rollover() {
+        with_externalities(&mut new_test_ext(), || {
+            let (alice_addr, alice_balance) = alice_balance_init();
+            let pending = alice_balance.clone();
+            let caller = Origin::signed(SigVerificationKey::default());
+
+            // Nothing is pending yet.
+            assert!(EncryptedBalances::rollover(caller.clone(), alice_addr).is_err());
+
+            <PendingTransfer<Test>>::insert(alice_addr, pending.clone());
+            system::Module::<Test>::set_block_number(2);
+
+            assert_ok!(EncryptedBalances::rollover(caller.clone(), alice_addr));
+            assert_eq!(EncryptedBalances::encrypted_balance(alice_addr), Some(alice_balance.add(&pending).unwrap()));
+            assert_eq!(EncryptedBalances::pending_transfer(alice_addr), None);
+            assert_eq!(EncryptedBalances::last_rollover(alice_addr), Some(2));
+
+            // A transfer received in the same epoch waits for the next one.
+            <PendingTransfer<Test>>::insert(alice_addr, pending.clone());
+            assert!(EncryptedBalances::rollover(caller, alice_addr).is_err());
+            assert_eq!(EncryptedBalances::pending_transfer(alice_addr), Some(pending));
+        })
+    }
+
+    #[test]
+    fn test_error_codes() {
+        let errors = [
+            Error::InvalidZkProof,
+            Error::InvalidCiphertext,
+            Error::BalanceUpdateFailed,
+            Error::NothingToRollOver,
+            Error::ZkSystem(zk_system::Error::InvalidProofEncoding),
+            Error::ZkSystem(zk_system::Error::NonceAlreadyUsed),
+        ];
+
+        for e in errors.iter() {
+            assert_eq!(Error::from_code(e.code()), Some(*e));
+        }
+
+        assert_eq!(Error::ZkSystem(zk_system::Error::NonceAlreadyUsed).code(), 0x109);
+        assert_eq!(Error::from_code(0), None);
+        assert_eq!(Error::from_code(0x100), None);
+    }
 }
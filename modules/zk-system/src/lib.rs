@@ -1,7 +1,7 @@
 //! A module for dealing with zk-system
 #![cfg_attr(not(feature = "std"), no_std)]
 
-use support::{decl_module, decl_storage, StorageValue, ensure};
+use support::{decl_module, decl_storage, StorageValue, StorageMap, ensure};
 use rstd::{
     prelude::*,
     result,
@@ -19,6 +19,84 @@ mod input_builder;
 
 pub trait Trait: system::Trait { }
 
+/// An error of the zk-system module.
+///
+/// The codes returned by `code` are stable, so that clients can map them back to
+/// an error after it was reported in a failure event.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum Error {
+    /// An encryption key in the public input is not a valid point.
+    InvalidEncKey,
+    /// A ciphertext in the public input is not a valid point.
+    InvalidCiphertext,
+    /// The verification key of the signer is not a valid point.
+    InvalidSigVerificationKey,
+    /// The epoch generator is not a valid point.
+    InvalidGEpoch,
+    /// The nonce is not a valid point.
+    InvalidNonce,
+    /// The public input does not have the length the circuit expects.
+    InputLengthMismatch,
+    /// The zk proof is not a valid encoding.
+    InvalidProofEncoding,
+    /// The stored verifying key does not match the circuit.
+    MalformedVerifyingKey,
+    /// The nonce was already used in the current epoch.
+    NonceAlreadyUsed,
+}
+
+impl Error {
+    pub fn code(&self) -> u16 {
+        match self {
+            Error::InvalidEncKey => 1,
+            Error::InvalidCiphertext => 2,
+            Error::InvalidSigVerificationKey => 3,
+            Error::InvalidGEpoch => 4,
+            Error::InvalidNonce => 5,
+            Error::InputLengthMismatch => 6,
+            Error::InvalidProofEncoding => 7,
+            Error::MalformedVerifyingKey => 8,
+            Error::NonceAlreadyUsed => 9,
+        }
+    }
+
+    pub fn from_code(code: u16) -> Option<Self> {
+        match code {
+            1 => Some(Error::InvalidEncKey),
+            2 => Some(Error::InvalidCiphertext),
+            3 => Some(Error::InvalidSigVerificationKey),
+            4 => Some(Error::InvalidGEpoch),
+            5 => Some(Error::InvalidNonce),
+            6 => Some(Error::InputLengthMismatch),
+            7 => Some(Error::InvalidProofEncoding),
+            8 => Some(Error::MalformedVerifyingKey),
+            9 => Some(Error::NonceAlreadyUsed),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Error::InvalidEncKey => "An encryption key is not a valid point.",
+            Error::InvalidCiphertext => "A ciphertext is not a valid point.",
+            Error::InvalidSigVerificationKey => "The signer's verification key is not a valid point.",
+            Error::InvalidGEpoch => "The epoch generator is not a valid point.",
+            Error::InvalidNonce => "The nonce is not a valid point.",
+            Error::InputLengthMismatch => "The public input does not match the circuit.",
+            Error::InvalidProofEncoding => "The zk proof is not a valid encoding.",
+            Error::MalformedVerifyingKey => "The verifying key does not match the circuit.",
+            Error::NonceAlreadyUsed => "The nonce was already used in this epoch. Submit again with the next nonce counter.",
+        }
+    }
+}
+
+impl From<Error> for &'static str {
+    fn from(e: Error) -> &'static str {
+        e.as_str()
+    }
+}
+
 const CONFIDENTIAL_INPUT_SIZE: usize = 22;
 const ANONIMOUS_INPUT_SIZE: usize = 104;
 
@@ -36,12 +114,29 @@ decl_storage! {
         /// A global last epoch which will be updated in the roll_over function.
         pub LastEpoch get(last_epoch) config() : T::BlockNumber;
 
-        /// An epoch based generator point
+        /// An epoch based generator point. It is the generator of the first nonce of each account in the epoch.
         pub LastGEpoch get(g_epoch) build(|_| GEpoch::try_new().expect("Should init.")) : GEpoch;
 
-        /// A nonce pool. All nonces are erasured at the time of starting each epochs.
-        // TODO: Change to BTreeSet once parity-codec is updated to parity-scale-codec
-        pub NoncePool get(nonce_pool) config() : Vec<Nonce>;
+        /// The epoch in which each nonce was used.
+        /// A nonce is in the nonce pool if it was used in the last epoch,
+        /// so checking it costs a single lookup whatever the size of the pool.
+        pub NonceEpoch get(nonce_epoch) build(|config: &GenesisConfig<T>| {
+            config.nonce_pool.iter()
+                .map(|n| (*n, config.last_epoch.clone()))
+                .collect::<Vec<_>>()
+        }): map Nonce => Option<T::BlockNumber>;
+        /// The number of nonces in the nonce pool. It is reset at the time of starting each epoch.
+        pub NonceCount get(nonce_count) build(|config: &GenesisConfig<T>| {
+            config.nonce_pool.len() as u32
+        }): u32;
+        /// The nonce in the pool at each index, up to `NonceCount`.
+        /// Wallets page through it to find their next unused nonce counter.
+        pub NonceAt get(nonce_at) build(|config: &GenesisConfig<T>| {
+            config.nonce_pool.iter()
+                .enumerate()
+                .map(|(i, n)| (i as u32, *n))
+                .collect::<Vec<_>>()
+        }): map u32 => Option<Nonce>;
 
         /// A verification key of zk proofs of confidential transfer(only readable)
         pub ConfidentialVk get(confidential_vk) config(): PreparedVerifyingKey<Bls12>;
@@ -49,6 +144,9 @@ decl_storage! {
         /// A verification key of zk proofs of anonymous transfer(only readable)
         pub AnonymousVk get(anonymous_vk) config(): PreparedVerifyingKey<Bls12>;
     }
+    add_extra_genesis {
+        config(nonce_pool): Vec<Nonce>;
+    }
 }
 
 impl<T: Trait> Module<T> {
@@ -63,47 +161,48 @@ impl<T: Trait> Module<T> {
         rvk: &T::AccountId,
         fee_sender: &LeftCiphertext,
         randomness: &RightCiphertext,
-        nonce: &Nonce
-    ) -> result::Result<bool, &'static str> {
+        nonce: &Nonce,
+        nonce_counter: u32
+    ) -> result::Result<bool, Error> {
         // Construct public input for circuit
         let mut public_input = PublicInputBuilder::<Bls12>::new(CONFIDENTIAL_INPUT_SIZE);
         public_input.push(Some(address_sender))
-            .map_err(|_| "Faild to get address_sender into xy.")?;
+            .map_err(|_| Error::InvalidEncKey)?;
 
         public_input.push(Some(address_recipient))
-            .map_err(|_| "Faild to get address_recipient into xy.")?;
+            .map_err(|_| Error::InvalidEncKey)?;
 
         public_input.push(Some(amount_sender))
-            .map_err(|_| "Faild to get amount_sender into xy.")?;
+            .map_err(|_| Error::InvalidCiphertext)?;
 
         public_input.push(Some(amount_recipient))
-            .map_err(|_| "Faild to get amount_recipient into xy.")?;
+            .map_err(|_| Error::InvalidCiphertext)?;
 
         public_input.push(Some(randomness))
-            .map_err(|_| "Faild to get randomness into xy.")?;
+            .map_err(|_| Error::InvalidCiphertext)?;
 
         public_input.push(Some(fee_sender))
-            .map_err(|_| "Faild to get fee_sender into xy.")?;
+            .map_err(|_| Error::InvalidCiphertext)?;
 
         public_input.push(balance_sender.left().ok())
-            .map_err(|_| "Faild to get balance_sender's left into xy.")?;
+            .map_err(|_| Error::InvalidCiphertext)?;
 
         public_input.push(balance_sender.right().ok())
-            .map_err(|_| "Faild to get balance_sender's right into xy.")?;
+            .map_err(|_| Error::InvalidCiphertext)?;
 
         public_input.push(Some(rvk.clone()))
-            .map_err(|_| "Faild to get rvk into xy.")?;
+            .map_err(|_| Error::InvalidSigVerificationKey)?;
 
-        public_input.push(Some(Self::g_epoch()))
-            .map_err(|_| "Faild to get g_epoch into xy.")?;
+        public_input.push(Some(Self::g_epoch_at(nonce_counter)?))
+            .map_err(|_| Error::InvalidGEpoch)?;
 
         public_input.push(Some(nonce))
-            .map_err(|_| "Faild to get nonce into xy.")?;
+            .map_err(|_| Error::InvalidNonce)?;
 
-        ensure!(public_input.len() == CONFIDENTIAL_INPUT_SIZE, "Mismatch the length of public input.");
+        ensure!(public_input.len() == CONFIDENTIAL_INPUT_SIZE, Error::InputLengthMismatch);
 
         let proof = bellman_verifier::Proof::<Bls12>::try_from(zkproof)
-            .map_err(|_| "Faild to read zkproof.")?;
+            .map_err(|_| Error::InvalidProofEncoding)?;
 
         // Verify the provided proof
         verify_proof(
@@ -111,7 +210,7 @@ impl<T: Trait> Module<T> {
             &proof,
             public_input.as_slice()
         )
-        .map_err(|_| "Invalid proof.")
+        .map_err(|_| Error::MalformedVerifyingKey)
     }
 
     /// Verify zk proofs of anonymous transfers
@@ -122,38 +221,47 @@ impl<T: Trait> Module<T> {
         right_ciphertext: &RightCiphertext,
         enc_balances: &[Ciphertext],
         rvk: &T::AccountId,
-        nonce: &Nonce
-    ) -> result::Result<bool, &'static str> {
+        nonce: &Nonce,
+        nonce_counter: u32
+    ) -> result::Result<bool, Error> {
         // Construct public input for circuit
         let mut public_input = PublicInputBuilder::<Bls12>::new(ANONIMOUS_INPUT_SIZE);
         public_input.push(enc_keys)
-            .map_err(|_| "Faild to get enc keys into xy.")?;
+            .map_err(|_| Error::InvalidEncKey)?;
 
         public_input.push(left_ciphertexts)
-            .map_err(|_| "Faild to get left ciphertexts into xy.")?;
-
-        public_input.push(enc_balances.iter().map(|e| e.left().unwrap())) // TODO
-            .map_err(|_| "Faild to get left ciphertexts into xy.")?;
-
-        public_input.push(enc_balances.iter().map(|e| e.right().unwrap())) // TODO
-            .map_err(|_| "Faild to get right ciphertexts into xy.")?;
+            .map_err(|_| Error::InvalidCiphertext)?;
+
+        let balances_left = enc_balances.iter()
+            .map(|e| e.left())
+            .collect::<result::Result<Vec<_>, _>>()
+            .map_err(|_| Error::InvalidCiphertext)?;
+        public_input.push(balances_left)
+            .map_err(|_| Error::InvalidCiphertext)?;
+
+        let balances_right = enc_balances.iter()
+            .map(|e| e.right())
+            .collect::<result::Result<Vec<_>, _>>()
+            .map_err(|_| Error::InvalidCiphertext)?;
+        public_input.push(balances_right)
+            .map_err(|_| Error::InvalidCiphertext)?;
 
         public_input.push(Some(right_ciphertext))
-            .map_err(|_| "Faild to get right ciphertexts into xy.")?;
+            .map_err(|_| Error::InvalidCiphertext)?;
 
         public_input.push(Some(rvk.clone()))
-            .map_err(|_| "Faild to get rvk into xy.")?;
+            .map_err(|_| Error::InvalidSigVerificationKey)?;
 
-        public_input.push(Some(Self::g_epoch()))
-            .map_err(|_| "Faild to get g_epoch into xy.")?;
+        public_input.push(Some(Self::g_epoch_at(nonce_counter)?))
+            .map_err(|_| Error::InvalidGEpoch)?;
 
         public_input.push(Some(nonce))
-            .map_err(|_| "Faild to get nonce into xy.")?;
+            .map_err(|_| Error::InvalidNonce)?;
 
-        ensure!(public_input.len() == ANONIMOUS_INPUT_SIZE, "Mismatch the length of public input.");
+        ensure!(public_input.len() == ANONIMOUS_INPUT_SIZE, Error::InputLengthMismatch);
 
         let proof = bellman_verifier::Proof::<Bls12>::try_from(zkproof)
-            .map_err(|_| "Faild to read zkproof.")?;
+            .map_err(|_| Error::InvalidProofEncoding)?;
 
         // Verify the provided proof
         verify_proof(
@@ -161,7 +269,33 @@ impl<T: Trait> Module<T> {
             &proof,
             public_input.as_slice()
         )
-        .map_err(|_| "Error occurred when valifying zkproof.")
+        .map_err(|_| Error::MalformedVerifyingKey)
+    }
+
+    /// Get the generator of the nonce with the given counter in the current epoch.
+    /// An account sends its n-th transaction of an epoch with the nonce `dec_key * g_epoch_at(n)`,
+    /// so it can send several transactions per epoch while each of them is still bound to the epoch.
+    pub fn g_epoch_at(nonce_counter: u32) -> result::Result<GEpoch, Error> {
+        if nonce_counter == 0 {
+            return Ok(Self::g_epoch());
+        }
+
+        GEpoch::group_hash_with_counter(Self::last_epoch().as_() as u32, nonce_counter)
+            .map_err(|_| Error::InvalidGEpoch)
+    }
+
+    /// Ensure the nonce has not been used in the current epoch.
+    pub fn ensure_nonce_unused(nonce: &Nonce) -> result::Result<(), Error> {
+        ensure!(Self::nonce_epoch(nonce) != Some(Self::last_epoch()), Error::NonceAlreadyUsed);
+        Ok(())
+    }
+
+    /// Add a nonce into the nonce pool of the current epoch.
+    pub fn insert_nonce(nonce: Nonce) {
+        let index = Self::nonce_count();
+        <NonceAt<T>>::insert(index, nonce);
+        <NonceCount<T>>::put(index + 1);
+        <NonceEpoch<T>>::insert(nonce, Self::last_epoch());
     }
 
     /// Get current epoch based on current block height.
@@ -172,15 +306,28 @@ impl<T: Trait> Module<T> {
 
     /// Initialize global nonce-related storages
     /// 1. Set last g_epoch to current g_epoch
-    /// 2. Remove all nonces in the pool
+    /// 2. Remove all nonces in the pool if the epoch changed
     /// 3. Set last epoch to current epoch
     pub fn init_nonce_pool(current_epoch: T::BlockNumber) {
         if Self::last_epoch() < current_epoch || current_epoch == T::BlockNumber::zero() {
             let g_epoch = GEpoch::group_hash(current_epoch.as_() as u32).unwrap();
 
             <LastGEpoch<T>>::put(g_epoch);
-            <NoncePool<T>>::kill();
+            if Self::last_epoch() < current_epoch {
+                Self::clear_nonce_pool();
+            }
             <LastEpoch<T>>::put(current_epoch);
         }
     }
+
+    /// Remove the nonces of the last epoch from the storage, so that it does not grow over epochs.
+    /// It costs a lookup and two removals per nonce, once per epoch.
+    fn clear_nonce_pool() {
+        for index in 0..Self::nonce_count() {
+            if let Some(nonce) = <NonceAt<T>>::take(index) {
+                <NonceEpoch<T>>::remove(nonce);
+            }
+        }
+        <NonceCount<T>>::kill();
+    }
 }
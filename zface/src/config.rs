@@ -5,6 +5,7 @@ use std::path::PathBuf;
 
 const APPLICATION_DIRECTORY_NAME: &'static str = "zface";
 const APPLICATION_ENVIRONMENT_ROOT_DIR: &'static str = "ZFACE_ROOT_DIR";
+const APPLICATION_ENVIRONMENT_PROFILE: &'static str = "ZFACE_PROFILE";
 
 pub const DEFAULT_AMOUNT: &str = "10";
 pub const DEFAULT_BALANCE: &str = "100";
@@ -37,6 +38,21 @@ pub(crate) fn global_rootdir_match<'a>(default: &'a PathBuf, matches: &ArgMatche
     }
 }
 
+// profile configuration
+
+pub(crate) fn global_profile_definition<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("PROFILE")
+        .long("profile")
+        .takes_value(true)
+        .global(true)
+        .env(APPLICATION_ENVIRONMENT_PROFILE)
+        .help("the profile of the config file to use, instead of the active one")
+}
+
+pub(crate) fn global_profile_match<'a>(matches: &'a ArgMatches<'a>) -> Option<&'a str> {
+    matches.value_of("PROFILE")
+}
+
 // quiet configuration
 
 pub(crate) fn global_quiet_difinition<'a, 'b>() -> Arg<'a, 'b> {
@@ -72,6 +88,32 @@ pub(crate) fn global_color_option(matches: &ArgMatches) -> term::ColorChoice {
     }
 }
 
+// output configuration
+
+pub(crate) fn global_output_definition<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("OUTPUT")
+        .long("output")
+        .takes_value(true)
+        .default_value("text")
+        .possible_values(&["text", "json"])
+        .global(true)
+        .help("print the results as text, or as a JSON document for scripts")
+}
+
+/// The format given on the command line, or the configured one.
+pub(crate) fn global_output_option(matches: &ArgMatches, configured: Option<term::OutputFormat>) -> term::OutputFormat {
+    if matches.occurrences_of("OUTPUT") == 0 {
+        if let Some(output) = configured {
+            return output;
+        }
+    }
+
+    match matches.value_of("OUTPUT") {
+        Some("json") => term::OutputFormat::Json,
+        _ => term::OutputFormat::Text,
+    }
+}
+
 // verbosity configuration
 
 pub(crate) fn global_verbose_definition<'a, 'b>() -> Arg<'a, 'b> {
@@ -87,10 +129,11 @@ pub(crate) fn global_verbose_option<'a>(matches: &ArgMatches<'a>) -> u64 {
     matches.occurrences_of("VERBOSITY")
 }
 
-pub(crate) fn config_terminal(matches: &ArgMatches) -> term::Config {
+pub(crate) fn config_terminal(matches: &ArgMatches, output: Option<term::OutputFormat>) -> term::Config {
     let quiet = global_quiet_option(matches);
     let color = global_color_option(matches);
     let verbosity = global_verbose_option(matches);
+    let output = global_output_option(matches, output);
 
     if !quiet {
         let log_level = match verbosity {
@@ -108,5 +151,6 @@ pub(crate) fn config_terminal(matches: &ArgMatches) -> term::Config {
     term::Config {
         color,
         quiet,
+        output,
     }
 }
@@ -13,8 +13,10 @@ use std::convert::TryFrom;
 
 mod constants;
 mod components;
+mod path;
 use constants::*;
 pub use components::*;
+pub use path::*;
 
 pub trait Derivation: Sized {
     /// Master key generation:
@@ -29,6 +31,11 @@ pub trait Derivation: Sized {
     /// If an index `i` >= 2^31, the child is a hardended key. If not, the child is a non-hardened key.
     fn derive_child(&self, i: ChildIndex) -> io::Result<Self>;
 
+    /// Derive the descendant key along the path, each index in turn from this key.
+    fn derive_path(&self, path: &DerivationPath) -> io::Result<Self> where Self: Clone {
+        path.0.iter().try_fold(self.clone(), |key, i| key.derive_child(*i))
+    }
+
     fn read<R: Read>(reader: R) -> io::Result<Self>;
 
     fn write<W: Write>(&self, writer: W) -> io::Result<()>;
@@ -303,6 +310,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn derive_path() {
+        let (xsk_m, xpgk_m) = gen_master_key_pairs();
+
+        let path: DerivationPath = "m/3'/5".parse().unwrap();
+        let xsk_h3_n5 = xsk_m.derive_path(&path).unwrap();
+        assert_eq!(
+            xsk_h3_n5,
+            xsk_m.derive_child(ChildIndex::Hardened(3)).unwrap().derive_child(ChildIndex::NonHardened(5)).unwrap()
+        );
+        assert!(xpgk_m.derive_path(&path).is_err());
+
+        let path: DerivationPath = "m/2/7".parse().unwrap();
+        assert_eq!(
+            ExtendedProofGenerationKey::from(&xsk_m.derive_path(&path).unwrap()),
+            xpgk_m.derive_path(&path).unwrap()
+        );
+        assert_eq!(xsk_m.derive_path(&DerivationPath::default()).unwrap(), xsk_m);
+    }
+
     #[test]
     fn read_write() {
         let (xsk_m, xpgk_m) = gen_master_key_pairs();
@@ -0,0 +1,98 @@
+use super::components::ChildIndex;
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
+/// A BIP32-style derivation path such as `m/0'/3'/1`, where `'` marks a hardened index.
+/// `h` and `H` are accepted for `'` as well.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct DerivationPath(pub Vec<ChildIndex>);
+
+impl DerivationPath {
+    /// The path of the account at `index` in the wallet, which is a non-hardened child of the master key.
+    pub fn account(index: u32) -> Self {
+        DerivationPath(vec![ChildIndex::from_index(index)])
+    }
+}
+
+impl FromStr for DerivationPath {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> io::Result<Self> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidInput, msg);
+
+        let mut segments = s.split('/');
+        if segments.next() != Some("m") {
+            return Err(invalid(format!("derivation path '{}' does not start with 'm'", s)));
+        }
+
+        let indexes = segments
+            .map(|segment| {
+                let (number, hardened) = match segment.chars().last() {
+                    Some('\'') | Some('h') | Some('H') => (&segment[..segment.len() - 1], true),
+                    _ => (segment, false),
+                };
+                let i: u32 = number.parse()
+                    .map_err(|_| invalid(format!("invalid index '{}' in derivation path '{}'", segment, s)))?;
+                if i >= (1 << 31) {
+                    return Err(invalid(format!("index '{}' in derivation path '{}' is too large", segment, s)));
+                }
+
+                Ok(if hardened { ChildIndex::Hardened(i) } else { ChildIndex::NonHardened(i) })
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+
+        Ok(DerivationPath(indexes))
+    }
+}
+
+impl fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "m")?;
+        for i in &self.0 {
+            write!(f, "/{}", i)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ChildIndex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ChildIndex::Hardened(i) => write!(f, "{}'", i),
+            ChildIndex::NonHardened(i) => write!(f, "{}", i),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_print() {
+        let path: DerivationPath = "m/0'/3'/1".parse().unwrap();
+        assert_eq!(path, DerivationPath(vec![
+            ChildIndex::Hardened(0),
+            ChildIndex::Hardened(3),
+            ChildIndex::NonHardened(1),
+        ]));
+        assert_eq!(path.to_string(), "m/0'/3'/1");
+
+        let path: DerivationPath = "m/44h/7H".parse().unwrap();
+        assert_eq!(path.to_string(), "m/44'/7'");
+
+        let path: DerivationPath = "m".parse().unwrap();
+        assert_eq!(path, DerivationPath::default());
+        assert_eq!(path.to_string(), "m");
+
+        assert_eq!(DerivationPath::account(2).to_string(), "m/2");
+    }
+
+    #[test]
+    fn parse_invalid() {
+        for s in &["", "0/1", "m/", "m/x", "m/1''", "m/-1", "m/2147483648"] {
+            assert!(s.parse::<DerivationPath>().is_err(), "'{}' should be invalid", s);
+        }
+    }
+}
@@ -1,6 +1,21 @@
 use parity_crypto as crypto;
 use serde_json;
 use std::{error::Error, fmt, io, convert};
+use crate::term::ErrorCode;
+
+/// Exit codes of zface. They are stable, so that scripts can rely on them.
+pub mod exit_code {
+    /// Any other failure, e.g. an I/O error.
+    pub const FAILURE: i32 = 1;
+    /// The input of the command is invalid.
+    pub const INVALID_INPUT: i32 = 2;
+    /// The wallet could not be opened, e.g. because of a wrong password.
+    pub const WALLET: i32 = 3;
+    /// The node could not be reached or returned an unexpected response.
+    pub const NODE: i32 = 4;
+    /// The transaction would be rejected by the chain.
+    pub const REJECTED: i32 = 5;
+}
 
 /// Defined keystore errors
 #[derive(Debug)]
@@ -16,6 +31,21 @@ pub enum KeystoreError {
     InfallibleError(convert::Infallible),
     SynthesisError(bellman::SynthesisError),
     RpcError(ws::Error),
+    TooFewDecoys(u32, usize),
+    InsufficientBalance(u32, u64),
+    EpochTooShort(u64, u64),
+    RpcResponse(String),
+    InvalidTxFile(String),
+    StaleTransaction(u64, u64),
+    InvalidConfig(String),
+    BalanceOutOfRange(u32),
+    UnsupportedKeyfileVersion(u64),
+    Argon2Error(argon2::Error),
+    InvalidArchive(String),
+    ImportConflict(String),
+    Cancelled,
+    InvalidArgument(String),
+    KdfOverLimit(String),
 }
 
 impl From<io::Error> for KeystoreError {
@@ -54,6 +84,18 @@ impl From<ws::Error> for KeystoreError {
     }
 }
 
+impl From<argon2::Error> for KeystoreError {
+    fn from(e: argon2::Error) -> Self {
+        KeystoreError::Argon2Error(e)
+    }
+}
+
+impl From<proofs::crypto_components::SubmitError> for KeystoreError {
+    fn from(e: proofs::crypto_components::SubmitError) -> Self {
+        KeystoreError::RpcResponse(e.0)
+    }
+}
+
 impl From<zpairing::io::Error> for KeystoreError {
     fn from(e: zpairing::io::Error) -> Self {
         KeystoreError::NostdIoError(e)
@@ -74,6 +116,21 @@ impl fmt::Display for KeystoreError {
             KeystoreError::SynthesisError(ref err) => write!(f, "synthesis error: {}", err),
             KeystoreError::RpcError(ref err) => write!(f, "rpc api error: {}", err),
             KeystoreError::NostdIoError(ref err) => write!(f, "No std I/O error: {}", err),
+            KeystoreError::TooFewDecoys(available, required) => write!(f, "Only {} encryption keys can be decoys, but {} are required.", available, required),
+            KeystoreError::InsufficientBalance(balance, required) => write!(f, "Not enough balance: you have {}, but the amount and fee add up to {}.", balance, required),
+            KeystoreError::RpcResponse(ref msg) => write!(f, "rpc response error: {}", msg),
+            KeystoreError::EpochTooShort(epoch_length, proving_blocks) => write!(f, "An epoch lasts {} blocks, but proving needs {}. The proof would be stale before it is included.", epoch_length, proving_blocks),
+            KeystoreError::InvalidTxFile(ref msg) => write!(f, "Invalid transaction file: {}", msg),
+            KeystoreError::StaleTransaction(prepared, current) => write!(f, "The transaction was prepared in epoch {}, but the chain is in epoch {} now. Prepare it again.", prepared, current),
+            KeystoreError::InvalidConfig(ref msg) => write!(f, "Invalid configuration: {}", msg),
+            KeystoreError::BalanceOutOfRange(range) => write!(f, "The balance is not below the decryption range {}. Raise `decryption_range` in the configuration.", range),
+            KeystoreError::UnsupportedKeyfileVersion(version) => write!(f, "Unsupported keyfile version {}. It may have been written by a newer zface.", version),
+            KeystoreError::Argon2Error(ref err) => write!(f, "argon2 error: {}", err),
+            KeystoreError::InvalidArchive(ref msg) => write!(f, "Invalid wallet archive: {}", msg),
+            KeystoreError::ImportConflict(ref msg) => write!(f, "Cannot import the wallet archive: {}", msg),
+            KeystoreError::Cancelled => write!(f, "Cancelled"),
+            KeystoreError::InvalidArgument(ref msg) => write!(f, "Invalid argument: {}", msg),
+            KeystoreError::KdfOverLimit(ref kdf) => write!(f, "The key derivation parameters {} exceed the limits of zface.", kdf),
         }
     }
 }
@@ -92,9 +149,109 @@ impl Error for KeystoreError {
             KeystoreError::SynthesisError(ref err) => err.description(),
             KeystoreError::RpcError(ref err) => err.description(),
             KeystoreError::NostdIoError(ref err) => err.description(),
+            KeystoreError::TooFewDecoys(_, _) => "Too few encryption keys to pick decoys from",
+            KeystoreError::InsufficientBalance(_, _) => "Not enough balance",
+            KeystoreError::EpochTooShort(_, _) => "The epoch is too short to prove a transaction in",
+            KeystoreError::RpcResponse(ref msg) => msg.as_str(),
+            KeystoreError::InvalidTxFile(ref msg) => msg.as_str(),
+            KeystoreError::StaleTransaction(_, _) => "The transaction was prepared in a past epoch",
+            KeystoreError::InvalidConfig(ref msg) => msg.as_str(),
+            KeystoreError::BalanceOutOfRange(_) => "The balance is out of the decryption range",
+            KeystoreError::UnsupportedKeyfileVersion(_) => "Unsupported keyfile version",
+            KeystoreError::Argon2Error(ref err) => err.description(),
+            KeystoreError::InvalidArchive(ref msg) => msg.as_str(),
+            KeystoreError::ImportConflict(ref msg) => msg.as_str(),
+            KeystoreError::Cancelled => "Cancelled",
+            KeystoreError::InvalidArgument(ref msg) => msg.as_str(),
+            KeystoreError::KdfOverLimit(_) => "The key derivation parameters exceed the limits",
+        }
+    }
+}
+
+impl ErrorCode for KeystoreError {
+    fn code(&self) -> &'static str {
+        match *self {
+            KeystoreError::InvalidPassword => "invalid_password",
+            KeystoreError::InvalidKeyfile => "invalid_keyfile",
+            KeystoreError::InvalidPath => "invalid_path",
+            KeystoreError::OverRetries => "over_retries",
+            KeystoreError::IoError(_) => "io_error",
+            KeystoreError::NostdIoError(_) => "io_error",
+            KeystoreError::CryptoError(_) => "crypto_error",
+            KeystoreError::SerdeError(_) => "serde_error",
+            KeystoreError::InfallibleError(_) => "infallible",
+            KeystoreError::SynthesisError(_) => "synthesis_error",
+            KeystoreError::RpcError(_) => "rpc_error",
+            KeystoreError::RpcResponse(_) => "rpc_response",
+            KeystoreError::TooFewDecoys(_, _) => "too_few_decoys",
+            KeystoreError::InsufficientBalance(_, _) => "insufficient_balance",
+            KeystoreError::EpochTooShort(_, _) => "epoch_too_short",
+            KeystoreError::InvalidTxFile(_) => "invalid_tx_file",
+            KeystoreError::StaleTransaction(_, _) => "stale_transaction",
+            KeystoreError::InvalidConfig(_) => "invalid_config",
+            KeystoreError::BalanceOutOfRange(_) => "balance_out_of_range",
+            KeystoreError::UnsupportedKeyfileVersion(_) => "unsupported_keyfile_version",
+            KeystoreError::Argon2Error(_) => "crypto_error",
+            KeystoreError::InvalidArchive(_) => "invalid_archive",
+            KeystoreError::ImportConflict(_) => "import_conflict",
+            KeystoreError::Cancelled => "cancelled",
+            KeystoreError::InvalidArgument(_) => "invalid_argument",
+            KeystoreError::KdfOverLimit(_) => "kdf_over_limit",
+        }
+    }
+
+    fn exit_code(&self) -> i32 {
+        match *self {
+            KeystoreError::InvalidPath
+            | KeystoreError::InvalidArgument(_)
+            | KeystoreError::InvalidTxFile(_)
+            | KeystoreError::InvalidConfig(_)
+            | KeystoreError::InvalidArchive(_) => exit_code::INVALID_INPUT,
+            KeystoreError::InvalidPassword
+            | KeystoreError::InvalidKeyfile
+            | KeystoreError::OverRetries
+            | KeystoreError::UnsupportedKeyfileVersion(_)
+            | KeystoreError::CryptoError(_)
+            | KeystoreError::Argon2Error(_)
+            | KeystoreError::KdfOverLimit(_)
+            | KeystoreError::ImportConflict(_) => exit_code::WALLET,
+            KeystoreError::RpcError(_)
+            | KeystoreError::RpcResponse(_) => exit_code::NODE,
+            KeystoreError::TooFewDecoys(_, _)
+            | KeystoreError::InsufficientBalance(_, _)
+            | KeystoreError::EpochTooShort(_, _)
+            | KeystoreError::StaleTransaction(_, _) => exit_code::REJECTED,
+            _ => exit_code::FAILURE,
         }
     }
 }
 
 /// Alias for keystore operation result
 pub type Result<T> = std::result::Result<T, KeystoreError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_codes() {
+        let e = KeystoreError::InsufficientBalance(10, 5);
+        assert_eq!(e.code(), "insufficient_balance");
+        assert_eq!(e.exit_code(), exit_code::REJECTED);
+
+        let e = KeystoreError::InvalidPassword;
+        assert_eq!(e.code(), "invalid_password");
+        assert_eq!(e.exit_code(), exit_code::WALLET);
+
+        let e = KeystoreError::UnsupportedKeyfileVersion(3);
+        assert_eq!(e.code(), "unsupported_keyfile_version");
+        assert_eq!(e.exit_code(), exit_code::WALLET);
+
+        let e = KeystoreError::InvalidArgument("--amount is required".to_string());
+        assert_eq!(e.code(), "invalid_argument");
+        assert_eq!(e.exit_code(), exit_code::INVALID_INPUT);
+
+        let e = KeystoreError::IoError(io::Error::new(io::ErrorKind::Other, "x"));
+        assert_eq!(e.exit_code(), exit_code::FAILURE);
+    }
+}
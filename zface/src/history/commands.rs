@@ -0,0 +1,72 @@
+use std::path::PathBuf;
+use std::fs;
+use std::io;
+use rand::Rng;
+use crate::term::Term;
+use crate::error::{Result, KeystoreError};
+use crate::wallet::{DirOperations, commands::wallet_keystore_dirs};
+use super::{Account, HistoryStore, Filter, sync};
+use super::export::{self, Format};
+
+/// Show the history of the wallet accounts, after syncing it with the node at `endpoint` if one is given.
+/// `account_names` restricts the records to these accounts, whose amounts are decrypted below `decryption_range`.
+/// The records are written to `output` if given, or printed otherwise.
+/// The wallet password is asked either way, because the stored history is encrypted with it.
+pub fn show_history<R: Rng>(
+    term: &mut Term,
+    root_dir: PathBuf,
+    endpoint: Option<&str>,
+    decryption_range: u32,
+    account_names: Option<Vec<String>>,
+    mut filter: Filter,
+    format: Format,
+    output: Option<PathBuf>,
+    rng: &mut R,
+) -> Result<()> {
+    let (wallet_dir, keystore_dir) = wallet_keystore_dirs(&root_dir)?;
+    let master_keyfile = wallet_dir.load_master()?;
+    let keyfiles = keystore_dir.load_all()?;
+
+    if let Some(names) = account_names {
+        let addresses = names.iter()
+            .map(|name| keyfiles.iter()
+                .find(|k| &k.account_name == name)
+                .map(|k| k.ss58_address.clone())
+                .ok_or(KeystoreError::InvalidKeyfile)
+            )
+            .collect::<Result<Vec<_>>>()?;
+        filter.accounts = Some(addresses);
+    }
+
+    term.info("Enter the wallet password.\n")?;
+    let password = term.passowrd("wallet password")?;
+    master_keyfile.encrypted_key.decrypt(&password[..])?;
+    let store = HistoryStore::open(&root_dir, &password[..], master_keyfile.encrypted_key.kdf, rng)?;
+
+    if let Some(endpoint) = endpoint {
+        let accounts = keyfiles.iter()
+            .filter(|k| filter.accounts.as_ref().map_or(true, |a| a.contains(&k.ss58_address)))
+            .map(|k| Account::new(k.ss58_address.clone(), &k.get_dec_key(&password[..])?, decryption_range))
+            .collect::<Result<Vec<_>>>()?;
+
+        term.simply("Syncing the transaction history...\n")?;
+        let new_records = sync(endpoint, &accounts[..], &store, rng)?;
+        term.simply(&format!("Found {} new transactions.\n", new_records))?;
+    }
+
+    let history = store.load()?;
+    match output {
+        Some(path) => {
+            let mut file = fs::File::create(&path)?;
+            export::write(&mut file, history.filter(&filter), format)?;
+            term.success(&format!("Exported the history to {}\n", path.display()))?;
+        },
+        None if term.is_json() => {
+            let records = history.filter(&filter).collect::<Vec<_>>();
+            term.report(&records, |_| Ok(()))?;
+        },
+        None => export::write(&mut io::stdout(), history.filter(&filter), format)?,
+    }
+
+    Ok(())
+}
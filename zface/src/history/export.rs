@@ -0,0 +1,131 @@
+//! Export of history records to CSV and JSON.
+
+use std::io::Write;
+use crate::error::Result;
+use super::{Record, Kind, Direction};
+
+const CSV_HEADER: &'static str = "account,block,event_index,kind,asset_id,direction,amount,fee,counterparty";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Table,
+    Csv,
+    Json,
+}
+
+impl Kind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Kind::Confidential => "confidential",
+            Kind::Asset => "asset",
+            Kind::Anonymous => "anonymous",
+        }
+    }
+}
+
+impl Direction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Direction::Incoming => "incoming",
+            Direction::Outgoing => "outgoing",
+        }
+    }
+}
+
+/// Write the records in the given format.
+pub fn write<'a, W, I>(writer: &mut W, records: I, format: Format) -> Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = &'a Record>,
+{
+    match format {
+        Format::Table => write_table(writer, records),
+        Format::Csv => write_csv(writer, records),
+        Format::Json => {
+            let records = records.into_iter().collect::<Vec<_>>();
+            serde_json::to_writer_pretty(&mut *writer, &records)?;
+            writeln!(writer)?;
+            Ok(())
+        },
+    }
+}
+
+fn write_table<'a, W: Write, I: IntoIterator<Item = &'a Record>>(writer: &mut W, records: I) -> Result<()> {
+    writeln!(writer, "{:>8} {:<12} {:<9} {:>10} {:>6}  {}", "block", "kind", "direction", "amount", "fee", "counterparty")?;
+    for r in records {
+        let kind = match r.asset_id {
+            Some(id) => format!("{}({})", r.kind.as_str(), id),
+            None => r.kind.as_str().to_string(),
+        };
+        let counterparty = r.counterparty.as_ref().map_or("-", |c| c.as_str());
+
+        writeln!(writer, "{:>8} {:<12} {:<9} {:>10} {:>6}  {}", r.block, kind, r.direction.as_str(), r.amount, r.fee, counterparty)?;
+    }
+
+    Ok(())
+}
+
+fn write_csv<'a, W: Write, I: IntoIterator<Item = &'a Record>>(writer: &mut W, records: I) -> Result<()> {
+    writeln!(writer, "{}", CSV_HEADER)?;
+    for r in records {
+        writeln!(
+            writer,
+            "{},{},{},{},{},{},{},{},{}",
+            csv_field(&r.account),
+            r.block,
+            r.event_index,
+            r.kind.as_str(),
+            r.asset_id.map_or(String::new(), |id| id.to_string()),
+            r.direction.as_str(),
+            r.amount,
+            r.fee,
+            csv_field(r.counterparty.as_ref().map_or("", |c| c.as_str())),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Quote a field if it contains a separator, a quote or a line break.
+fn csv_field(field: &str) -> String {
+    if field.contains(|c| c == ',' || c == '"' || c == '\n' || c == '\r') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::tests::record;
+
+    #[test]
+    fn test_write_csv() {
+        let mut incoming = record("alice", 5, Kind::Confidential, Direction::Incoming);
+        incoming.counterparty = Some("bob".to_string());
+        let outgoing = record("alice,\"x\"", 8, Kind::Asset, Direction::Outgoing);
+
+        let mut buf = vec![];
+        write(&mut buf, &[incoming, outgoing], Format::Csv).unwrap();
+
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            format!(
+                "{}\nalice,5,1,confidential,,incoming,10,1,bob\n\"alice,\"\"x\"\"\",8,1,asset,3,outgoing,10,1,\n",
+                CSV_HEADER
+            )
+        );
+    }
+
+    #[test]
+    fn test_write_json() {
+        let records = vec![record("alice", 5, Kind::Anonymous, Direction::Incoming)];
+
+        let mut buf = vec![];
+        write(&mut buf, &records, Format::Json).unwrap();
+        let decoded: Vec<Record> = serde_json::from_slice(&buf).unwrap();
+
+        assert_eq!(decoded, records);
+    }
+}
@@ -0,0 +1,274 @@
+//! A local history of the transfers of the wallet accounts.
+//!
+//! Blocks are synced from the chain and the transfer events addressed to an account
+//! are decrypted with its decryption key. The history is stored in the zface root directory,
+//! encrypted with the wallet password because it reveals the decrypted amounts and the counterparties.
+
+use std::path::{Path, PathBuf};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{Write, BufReader};
+use rand::Rng;
+use crate::error::{KeystoreError, Result};
+use crate::wallet::{replace_file, KeyCiphertext, DerivedKeys, Kdf};
+
+pub mod commands;
+pub mod export;
+mod rpc;
+mod sync;
+pub use self::sync::{Account, sync};
+
+const HISTORYFILE: &'static str = "history.json";
+
+/// Version of the history file format, bumped on incompatible changes.
+const HISTORY_VERSION: u32 = 1;
+
+/// The module a transfer was made in.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+    Confidential,
+    Asset,
+    Anonymous,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Incoming,
+    Outgoing,
+}
+
+/// A transfer which was made from or to a wallet account.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Record {
+    /// SS58 address of the wallet account
+    pub account: String,
+    pub block: u64,
+    /// Index of the event in the block
+    pub event_index: u32,
+    pub kind: Kind,
+    pub asset_id: Option<u32>,
+    pub direction: Direction,
+    pub amount: u32,
+    pub fee: u32,
+    /// SS58 address of the other party. Unknown for anonymous transfers.
+    pub counterparty: Option<String>,
+}
+
+/// The synced records, and the next block to sync for each account.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct History {
+    pub next_block: BTreeMap<String, u64>,
+    pub records: Vec<Record>,
+}
+
+impl History {
+    /// Add a record unless it is already there.
+    pub fn insert(&mut self, record: Record) {
+        if !self.records.contains(&record) {
+            self.records.push(record);
+        }
+    }
+
+    /// The next block to sync for the account.
+    pub fn next_block(&self, account: &str) -> u64 {
+        self.next_block.get(account).cloned().unwrap_or(0)
+    }
+
+    pub fn filter<'a>(&'a self, filter: &'a Filter) -> impl Iterator<Item = &'a Record> + 'a {
+        self.records.iter().filter(move |r| filter.matches(r))
+    }
+}
+
+/// Criteria to select records. `None` matches any value.
+#[derive(Clone, Debug, Default)]
+pub struct Filter {
+    pub accounts: Option<Vec<String>>,
+    pub kind: Option<Kind>,
+    pub asset_id: Option<u32>,
+    pub direction: Option<Direction>,
+    pub from_block: Option<u64>,
+    pub to_block: Option<u64>,
+}
+
+impl Filter {
+    pub fn matches(&self, record: &Record) -> bool {
+        self.accounts.as_ref().map_or(true, |a| a.contains(&record.account))
+            && self.kind.map_or(true, |k| k == record.kind)
+            && self.asset_id.map_or(true, |id| record.asset_id == Some(id))
+            && self.direction.map_or(true, |d| d == record.direction)
+            && self.from_block.map_or(true, |b| record.block >= b)
+            && self.to_block.map_or(true, |b| record.block <= b)
+    }
+}
+
+/// The history file as stored. The whole history is encrypted, so that the addresses of the accounts are not readable either.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HistoryFile {
+    version: u32,
+    /// The JSON of the `History`, authenticated by the MAC
+    encrypted_history: KeyCiphertext,
+}
+
+/// The history file in the zface root directory, with the keys it is encrypted with.
+pub struct HistoryStore {
+    path: PathBuf,
+    keys: DerivedKeys,
+}
+
+impl HistoryStore {
+    /// Open the history file with the wallet password, which must have been checked against the wallet.
+    /// A new file is encrypted with `kdf`. A file which can not be opened with the password,
+    /// e.g. because the password was changed since, is started over, since the history can always be synced again.
+    pub fn open<P: AsRef<Path>, R: Rng>(root_dir: P, password: &[u8], kdf: Kdf, rng: &mut R) -> Result<Self> {
+        let path = root_dir.as_ref().join(HISTORYFILE);
+        if !path.exists() {
+            let keys = DerivedKeys::new(password, kdf, rng)?;
+            return Ok(HistoryStore { path, keys });
+        }
+
+        if let Some(file) = Self::read_file(&path)? {
+            match DerivedKeys::of(&file.encrypted_history, password) {
+                Ok(keys) => return Ok(HistoryStore { path, keys }),
+                Err(KeystoreError::InvalidPassword) => {},
+                Err(e) => return Err(e),
+            }
+        }
+
+        warn!("The history file can not be opened with the wallet password. The history is synced again from the first block.");
+        let store = HistoryStore { path, keys: DerivedKeys::new(password, kdf, rng)? };
+        store.save(&History::default(), rng)?;
+
+        Ok(store)
+    }
+
+    /// Load the history, or an empty one if nothing was synced yet.
+    pub fn load(&self) -> Result<History> {
+        match Self::read_file(&self.path)? {
+            Some(file) => {
+                let plain = file.encrypted_history.decrypt_with(&self.keys)?;
+                Ok(serde_json::from_slice(&plain[..])?)
+            },
+            None => Ok(History::default()),
+        }
+    }
+
+    pub fn save<R: Rng>(&self, history: &History, rng: &mut R) -> Result<()> {
+        let plain = serde_json::to_vec(history)?;
+        let history_file = HistoryFile {
+            version: HISTORY_VERSION,
+            encrypted_history: KeyCiphertext::encrypt_with(&plain[..], &self.keys, rng)?,
+        };
+
+        let mut file = replace_file(&self.path)?;
+        serde_json::to_writer(&mut file, &history_file)?;
+
+        file.flush()?;
+        file.sync_all()?;
+
+        Ok(())
+    }
+
+    /// Read the history file, if there is one in the current format.
+    fn read_file(path: &Path) -> Result<Option<HistoryFile>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let file = fs::File::open(path)?;
+        let history_file = serde_json::from_reader::<_, HistoryFile>(BufReader::new(file)).ok()
+            .filter(|f| f.version == HISTORY_VERSION);
+
+        Ok(history_file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{XorShiftRng, SeedableRng};
+
+    pub fn record(account: &str, block: u64, kind: Kind, direction: Direction) -> Record {
+        Record {
+            account: account.to_string(),
+            block,
+            event_index: 1,
+            kind,
+            asset_id: if kind == Kind::Asset { Some(3) } else { None },
+            direction,
+            amount: 10,
+            fee: 1,
+            counterparty: None,
+        }
+    }
+
+    #[test]
+    fn test_filter() {
+        let mut history = History::default();
+        history.insert(record("alice", 5, Kind::Confidential, Direction::Incoming));
+        history.insert(record("alice", 8, Kind::Asset, Direction::Outgoing));
+        history.insert(record("bob", 12, Kind::Anonymous, Direction::Incoming));
+        history.insert(record("bob", 12, Kind::Anonymous, Direction::Incoming));
+        assert_eq!(history.records.len(), 3);
+
+        let count = |filter: Filter| history.filter(&filter).count();
+        assert_eq!(count(Filter::default()), 3);
+        assert_eq!(count(Filter { accounts: Some(vec!["alice".to_string()]), ..Default::default() }), 2);
+        assert_eq!(count(Filter { kind: Some(Kind::Asset), asset_id: Some(3), ..Default::default() }), 1);
+        assert_eq!(count(Filter { asset_id: Some(4), ..Default::default() }), 0);
+        assert_eq!(count(Filter { direction: Some(Direction::Incoming), ..Default::default() }), 2);
+        assert_eq!(count(Filter { from_block: Some(6), to_block: Some(12), ..Default::default() }), 2);
+    }
+
+    #[test]
+    fn test_store() {
+        let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let kdf = Kdf::Pbkdf2 { iters: 1 };
+        let dir = tempfile::tempdir().unwrap();
+        let store = HistoryStore::open(dir.path(), b"password", kdf, rng).unwrap();
+
+        assert_eq!(store.load().unwrap(), History::default());
+
+        let mut history = History::default();
+        history.insert(record("alice", 5, Kind::Confidential, Direction::Incoming));
+        history.next_block.insert("alice".to_string(), 6);
+        store.save(&history, rng).unwrap();
+
+        let loaded = HistoryStore::open(dir.path(), b"password", kdf, rng).unwrap().load().unwrap();
+        assert_eq!(loaded, history);
+        assert_eq!(loaded.next_block("alice"), 6);
+        assert_eq!(loaded.next_block("bob"), 0);
+
+        let stored = fs::read_to_string(dir.path().join(HISTORYFILE)).unwrap();
+        assert!(!stored.contains("alice"));
+    }
+
+    #[test]
+    fn test_store_with_another_password() {
+        let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let kdf = Kdf::Pbkdf2 { iters: 1 };
+        let dir = tempfile::tempdir().unwrap();
+
+        let store = HistoryStore::open(dir.path(), b"password", kdf, rng).unwrap();
+        let mut history = History::default();
+        history.insert(record("alice", 5, Kind::Confidential, Direction::Incoming));
+        store.save(&history, rng).unwrap();
+
+        assert_matches!(
+            HistoryStore { path: store.path.clone(), keys: DerivedKeys::new(b"another", kdf, rng).unwrap() }.load(),
+            Err(KeystoreError::InvalidPassword)
+        );
+
+        // started over under the new password
+        let store = HistoryStore::open(dir.path(), b"another", kdf, rng).unwrap();
+        assert_eq!(store.load().unwrap(), History::default());
+
+        fs::write(dir.path().join(HISTORYFILE), b"{\"records\": []}").unwrap();
+        let store = HistoryStore::open(dir.path(), b"another", kdf, rng).unwrap();
+        assert_eq!(store.load().unwrap(), History::default());
+    }
+}
@@ -0,0 +1,118 @@
+//! A minimal JSON-RPC client to read the storage of past blocks,
+//! which is not exposed by `polkadot_rs::Api`.
+
+use std::{sync::mpsc, thread, time::Duration};
+use serde_json::{json, Value};
+use crate::error::{KeystoreError, Result};
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+struct Handler {
+    out: ws::Sender,
+    opened: mpsc::Sender<ws::Sender>,
+    responses: mpsc::Sender<String>,
+}
+
+impl ws::Handler for Handler {
+    fn on_open(&mut self, _: ws::Handshake) -> ws::Result<()> {
+        let _ = self.opened.send(self.out.clone());
+        Ok(())
+    }
+
+    fn on_message(&mut self, msg: ws::Message) -> ws::Result<()> {
+        let _ = self.responses.send(msg.into_text()?);
+        Ok(())
+    }
+}
+
+pub struct RpcClient {
+    out: ws::Sender,
+    responses: mpsc::Receiver<String>,
+    next_id: u64,
+}
+
+impl RpcClient {
+    pub fn connect(endpoint: &str) -> Result<Self> {
+        let (opened_tx, opened_rx) = mpsc::channel();
+        let (responses_tx, responses_rx) = mpsc::channel();
+        let endpoint = endpoint.to_string();
+
+        thread::Builder::new()
+            .name("historyrpc".to_string())
+            .spawn(move || {
+                let res = ws::connect(endpoint, move |out| Handler {
+                    out,
+                    opened: opened_tx.clone(),
+                    responses: responses_tx.clone(),
+                });
+                if let Err(e) = res {
+                    error!("history rpc connection failed: {}", e);
+                }
+            })?;
+
+        let out = opened_rx.recv_timeout(TIMEOUT)
+            .map_err(|_| KeystoreError::RpcResponse("couldn't connect to the node".to_string()))?;
+
+        Ok(RpcClient {
+            out,
+            responses: responses_rx,
+            next_id: 0,
+        })
+    }
+
+    fn request(&mut self, method: &str, params: Value) -> Result<Value> {
+        self.next_id += 1;
+        let body = json!({ "jsonrpc": "2.0", "id": self.next_id, "method": method, "params": params });
+        self.out.send(body.to_string())?;
+
+        loop {
+            let text = self.responses.recv_timeout(TIMEOUT)
+                .map_err(|_| KeystoreError::RpcResponse(format!("no response to {}", method)))?;
+            let mut res: Value = serde_json::from_str(&text)?;
+
+            // Skip anything else, e.g. notifications of a subscription.
+            if res["id"] != json!(self.next_id) {
+                continue;
+            }
+            if let Some(e) = res.get("error") {
+                return Err(KeystoreError::RpcResponse(e.to_string()));
+            }
+
+            return Ok(res["result"].take());
+        }
+    }
+
+    /// Get the number of the best block.
+    pub fn best_number(&mut self) -> Result<u64> {
+        let header = self.request("chain_getHeader", json!([]))?;
+        let number = header["number"].as_str()
+            .ok_or_else(|| KeystoreError::RpcResponse("header without a number".to_string()))?;
+
+        u64::from_str_radix(number.trim_start_matches("0x"), 16)
+            .map_err(|_| KeystoreError::RpcResponse(format!("invalid block number {}", number)))
+    }
+
+    pub fn block_hash(&mut self, number: u64) -> Result<Option<String>> {
+        let hash = self.request("chain_getBlockHash", json!([number]))?;
+        Ok(hash.as_str().map(|h| h.to_string()))
+    }
+
+    /// Get the raw storage value of `key` at the block `hash`.
+    pub fn storage(&mut self, key: &[u8], hash: &str) -> Result<Option<Vec<u8>>> {
+        let key = format!("0x{}", hex::encode(key));
+        let value = self.request("state_getStorage", json!([key, hash]))?;
+
+        match value.as_str() {
+            Some(v) => hex::decode(v.trim_start_matches("0x"))
+                .map(Some)
+                .map_err(|_| KeystoreError::RpcResponse(format!("invalid storage value {}", v))),
+            None => Ok(None),
+        }
+    }
+}
+
+impl Drop for RpcClient {
+    fn drop(&mut self) {
+        let _ = self.out.close(ws::CloseCode::Normal);
+    }
+}
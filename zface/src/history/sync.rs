@@ -0,0 +1,193 @@
+//! Sync of blocks and decryption of the transfer events addressed to the wallet accounts.
+
+use std::convert::TryFrom;
+use rand::Rng;
+use parity_codec::Decode;
+use pairing::bls12_381::Bls12;
+use primitives::{twox_128, crypto::Ss58Codec};
+use proofs::{DecryptionKey, EncryptionKey, PARAMS};
+use zprimitives::{EncKey, Ciphertext, LeftCiphertext, RightCiphertext, PARAMS as ZPARAMS};
+use zcrypto::elgamal as zelgamal;
+use zjubjub::curve::FixedGenerators as zFixedGenerators;
+use zpairing::bls12_381::Bls12 as zBls12;
+use zerochain_runtime::Event;
+use crate::error::{KeystoreError, Result};
+use crate::ss58::EncryptionKeyBytes;
+use super::{History, HistoryStore, Record, Kind, Direction};
+use super::rpc::RpcClient;
+
+/// Save the progress after this many blocks, so an interrupted sync resumes from there.
+const SAVE_INTERVAL: u64 = 100;
+
+/// A wallet account whose transfers are synced.
+pub struct Account {
+    pub address: String,
+    enc_key: EncKey,
+    dec_key: keys::DecryptionKey<zBls12>,
+    /// Amounts are decrypted by brute force below this
+    decryption_range: u32,
+}
+
+impl Account {
+    pub fn new(address: String, dec_key: &DecryptionKey<Bls12>, decryption_range: u32) -> Result<Self> {
+        let mut enc_key = vec![];
+        EncryptionKey::from_decryption_key(dec_key, &PARAMS).write(&mut enc_key)?;
+
+        let mut dec_key_vec = vec![];
+        dec_key.write(&mut dec_key_vec)?;
+
+        Ok(Account {
+            address,
+            enc_key: EncKey::from_slice(&enc_key[..]),
+            dec_key: keys::DecryptionKey::read(&mut &dec_key_vec[..])?,
+            decryption_range,
+        })
+    }
+
+    fn decrypt(&self, left: &LeftCiphertext, right: &RightCiphertext) -> Option<u32> {
+        let ciphertext = Ciphertext::from_left_right(*left, *right).ok()?;
+        self.decrypt_ciphertext(&ciphertext)
+    }
+
+    fn decrypt_ciphertext(&self, ciphertext: &Ciphertext) -> Option<u32> {
+        zelgamal::Ciphertext::<zBls12>::try_from(ciphertext).ok()?
+            .decrypt_with_range(&self.dec_key, zFixedGenerators::Diversifier, &*ZPARAMS, self.decryption_range)
+    }
+
+    /// A record of a confidential transfer if it was made from or to this account.
+    fn confidential_record(
+        &self,
+        sender: &EncKey,
+        recipient: &EncKey,
+        amount_sender: &LeftCiphertext,
+        amount_recipient: &LeftCiphertext,
+        fee_sender: &LeftCiphertext,
+        randomness: &RightCiphertext,
+    ) -> Option<(Direction, u32, u32, String)> {
+        if *sender == self.enc_key {
+            let amount = self.decrypt(amount_sender, randomness)?;
+            let fee = self.decrypt(fee_sender, randomness).unwrap_or(0);
+            Some((Direction::Outgoing, amount, fee, ss58(recipient)))
+        } else if *recipient == self.enc_key {
+            let amount = self.decrypt(amount_recipient, randomness)?;
+            Some((Direction::Incoming, amount, 0, ss58(sender)))
+        } else {
+            None
+        }
+    }
+
+    /// The amount of an anonymous transfer for this account, if it is a real party of it.
+    /// The sender's ciphertext encrypts the negated amount, and decoys receive zero.
+    fn anonymous_amount(&self, left: &LeftCiphertext, right: &RightCiphertext) -> Option<(Direction, u32)> {
+        let ciphertext = Ciphertext::from_left_right(*left, *right).ok()?;
+
+        match self.decrypt_ciphertext(&ciphertext) {
+            Some(0) => None,
+            Some(amount) => Some((Direction::Incoming, amount)),
+            None => {
+                let negated = Ciphertext::zero().sub(&ciphertext).ok()?;
+                self.decrypt_ciphertext(&negated).map(|amount| (Direction::Outgoing, amount))
+            }
+        }
+    }
+
+    /// Records of the transfers made from or to this account in the event.
+    fn records(&self, event: &Event, block: u64, event_index: u32) -> Vec<Record> {
+        let record = |kind, asset_id, direction, amount, fee, counterparty| Record {
+            account: self.address.clone(),
+            block,
+            event_index,
+            kind,
+            asset_id,
+            direction,
+            amount,
+            fee,
+            counterparty,
+        };
+
+        match event {
+            Event::encrypted_balances(encrypted_balances::RawEvent::ConfidentialTransfer(
+                _zkproof, sender, recipient, amount_sender, amount_recipient, fee_sender, randomness, _enc_balance, _sig_vk
+            )) => self.confidential_record(sender, recipient, amount_sender, amount_recipient, fee_sender, randomness)
+                .map(|(direction, amount, fee, counterparty)|
+                    record(Kind::Confidential, None, direction, amount, fee, Some(counterparty))
+                )
+                .into_iter()
+                .collect(),
+            Event::encrypted_assets(encrypted_assets::RawEvent::ConfidentialAssetTransferred(
+                asset_id, _zkproof, sender, recipient, amount_sender, amount_recipient, fee_sender, randomness, _enc_balance, _sig_vk
+            )) => self.confidential_record(sender, recipient, amount_sender, amount_recipient, fee_sender, randomness)
+                .map(|(direction, amount, fee, counterparty)|
+                    record(Kind::Asset, Some(*asset_id), direction, amount, fee, Some(counterparty))
+                )
+                .into_iter()
+                .collect(),
+            Event::anonymous_balances(anonymous_balances::RawEvent::AnonymousTransfer(
+                _zkproof, enc_keys, left_ciphertexts, right_ciphertext, _sig_vk
+            )) => enc_keys.iter()
+                .zip(left_ciphertexts.iter())
+                .filter(|(e, _)| **e == self.enc_key)
+                .filter_map(|(_, c)| self.anonymous_amount(c, right_ciphertext))
+                .map(|(direction, amount)| record(Kind::Anonymous, None, direction, amount, 0, None))
+                .collect(),
+            _ => vec![],
+        }
+    }
+}
+
+fn ss58(enc_key: &EncKey) -> String {
+    let mut bytes = [0u8; 32];
+    bytes.copy_from_slice(enc_key.as_ref());
+    EncryptionKeyBytes(bytes).to_ss58check()
+}
+
+fn events_at(rpc: &mut RpcClient, block: u64) -> Result<Vec<system::EventRecord<Event>>> {
+    let hash = match rpc.block_hash(block)? {
+        Some(h) => h,
+        None => return Ok(vec![]),
+    };
+
+    match rpc.storage(&twox_128(b"System Events")[..], &hash)? {
+        Some(events) => Vec::<system::EventRecord<Event>>::decode(&mut &events[..])
+            .ok_or_else(|| KeystoreError::RpcResponse("couldn't decode event record list".to_string())),
+        None => Ok(vec![]),
+    }
+}
+
+/// Sync the history of the accounts up to the best block, and return the number of new records.
+/// Each account is synced from the block after the last one synced for it.
+pub fn sync<R: Rng>(endpoint: &str, accounts: &[Account], store: &HistoryStore, rng: &mut R) -> Result<usize> {
+    let mut history: History = store.load()?;
+    let mut rpc = RpcClient::connect(endpoint)?;
+
+    let best = rpc.best_number()?;
+    let start = accounts.iter()
+        .map(|a| history.next_block(&a.address))
+        .min()
+        .unwrap_or(best + 1);
+    let len_before = history.records.len();
+
+    for block in start..=best {
+        let pending = accounts.iter()
+            .filter(|a| history.next_block(&a.address) <= block)
+            .collect::<Vec<_>>();
+
+        for (i, event_record) in events_at(&mut rpc, block)?.iter().enumerate() {
+            for account in &pending {
+                for record in account.records(&event_record.event, block, i as u32) {
+                    history.insert(record);
+                }
+            }
+        }
+
+        for account in &pending {
+            history.next_block.insert(account.address.clone(), block + 1);
+        }
+        if block % SAVE_INTERVAL == 0 {
+            store.save(&history, rng)?;
+        }
+    }
+    store.save(&history, rng)?;
+
+    Ok(history.records.len() - len_before)
+}
@@ -4,6 +4,8 @@ pub mod wallet;
 pub mod term;
 pub mod utils;
 pub mod transaction;
+pub mod history;
+pub mod settings;
 pub mod error;
 
 #[macro_use]
@@ -19,13 +19,15 @@ use proofs::{
     };
 use primitives::{hexdisplay::{HexDisplay, AsBytesRef}, crypto::Ss58Codec};
 use pairing::bls12_381::Bls12;
-use polkadot_rs::{Api, Url};
+use polkadot_rs::Api;
 use bip39::{Mnemonic, Language, MnemonicType};
 
 mod utils;
 mod config;
 mod wallet;
 mod transaction;
+mod history;
+mod settings;
 pub mod derive;
 pub mod term;
 pub mod ss58;
@@ -35,6 +37,7 @@ use self::utils::*;
 use self::config::*;
 use self::wallet::commands::*;
 use self::transaction::*;
+use self::settings::Settings;
 
 fn main() {
     let default_root_dir = get_default_root_dir();
@@ -47,139 +50,222 @@ fn main() {
         .arg(global_verbose_definition())
         .arg(global_quiet_difinition())
         .arg(global_color_definition())
+        .arg(global_output_definition())
+        .arg(global_profile_definition())
         .arg(global_rootdir_definition(&default_root_dir))
         .subcommand(snark_commands_definition())
         .subcommand(wallet_commands_definition())
         .subcommand(tx_commands_definition())
+        .subcommand(config_commands_definition())
         .subcommand(debug_commands_definition())
         .get_matches();
 
-    let mut term = term::Term::new(config_terminal(&matches));
     let root_dir = global_rootdir_match(&default_root_dir, &matches);
+    let profile = global_profile_match(&matches);
+    let settings = Settings::load(&root_dir, profile);
+    let mut term = term::Term::new(config_terminal(&matches, settings.as_ref().ok().and_then(|s| s.output)));
     let rng = &mut OsRng::new().expect("should be able to construct RNG");
 
+    // The config commands can fix a configuration which does not load.
+    if let (CONFIG_COMMAND, Some(matches)) = matches.subcommand() {
+        return subcommand_config(term, root_dir, profile, matches);
+    }
+    let settings = settings.unwrap_or_else(|e| term.fail_with(e));
+
     match matches.subcommand() {
-        (SNARK_COMMAND, Some(matches)) => subcommand_snark(term, matches, rng),
-        (WALLET_COMMAND, Some(matches)) => subcommand_wallet(term, root_dir, matches, rng),
-        (TX_COMMAND, Some(matches)) => subcommand_tx(term, root_dir, matches, rng),
-        (DEBUG_COMMAND, Some(matches)) => subcommand_debug(term, matches, rng),
-        _ => {
-            term.error(matches.usage()).unwrap();
-            ::std::process::exit(1);
-        }
+        (SNARK_COMMAND, Some(matches)) => subcommand_snark(term, &settings, matches, rng),
+        (WALLET_COMMAND, Some(matches)) => subcommand_wallet(term, root_dir, &settings, matches, rng),
+        (TX_COMMAND, Some(matches)) => subcommand_tx(term, root_dir, &settings, matches, rng),
+        (DEBUG_COMMAND, Some(matches)) => subcommand_debug(term, &settings, matches, rng),
+        _ => term.fail_with(usage_error(&matches)),
     }
 }
 
+/// The error of a command line without a subcommand, showing the usage.
+fn usage_error(matches: &ArgMatches) -> error::KeystoreError {
+    error::KeystoreError::InvalidArgument(format!("a subcommand is required.\n{}", matches.usage()))
+}
+
+/// The value of a command line argument, which is an error if it is missing.
+fn arg_value<'a>(matches: &'a ArgMatches, name: &str) -> error::Result<&'a str> {
+    matches.value_of(name)
+        .ok_or_else(|| error::KeystoreError::InvalidArgument(format!("--{} is required", name)))
+}
+
+/// Parse the value of a command line argument.
+fn parse_arg<T: std::str::FromStr>(name: &str, value: &str) -> error::Result<T> {
+    value.parse()
+        .map_err(|_| error::KeystoreError::InvalidArgument(format!("invalid --{} '{}'", name, value)))
+}
+
+//
+//  Config Sub Commands
+//
+
+const CONFIG_COMMAND: &'static str = "config";
+
+fn subcommand_config(mut term: term::Term, root_dir: PathBuf, profile: Option<&str>, matches: &ArgMatches) {
+    let res = match matches.subcommand() {
+        ("get", Some(sub_matches)) => {
+            settings::commands::show_config(&mut term, &root_dir, profile, sub_matches.value_of("key"))
+        },
+        ("set", Some(sub_matches)) => {
+            let key = sub_matches.value_of("key").expect("Key is required; qed");
+            let value = sub_matches.value_of("value").expect("Value is required; qed");
+
+            settings::commands::set_config(&mut term, &root_dir, profile, key, Some(value))
+        },
+        ("unset", Some(sub_matches)) => {
+            let key = sub_matches.value_of("key").expect("Key is required; qed");
+
+            settings::commands::set_config(&mut term, &root_dir, profile, key, None)
+        },
+        _ => Err(usage_error(matches)),
+    };
+
+    res.unwrap_or_else(|e| term.fail_with(e))
+}
+
+fn config_commands_definition<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name(CONFIG_COMMAND)
+        .about("Read and write the config file in the root directory. `--profile` selects the profile")
+        .subcommand(SubCommand::with_name("get")
+            .about("Show the configured value of a key, or of all the keys")
+            .arg(Arg::with_name("key")
+                .help("A key of the profile, or `profile` for the active profile")
+                .index(1)
+                .required(false)
+            )
+        )
+        .subcommand(SubCommand::with_name("set")
+            .about("Set a key in a profile, or switch to another profile with the `profile` key")
+            .arg(Arg::with_name("key")
+                .help("One of url, conf_pk_path, conf_vk_path, anony_pk_path, anony_vk_path, default_account, decryption_range, output, profile")
+                .index(1)
+                .required(true)
+            )
+            .arg(Arg::with_name("value")
+                .index(2)
+                .required(true)
+            )
+        )
+        .subcommand(SubCommand::with_name("unset")
+            .about("Unset a key in a profile, so that it falls back to the default")
+            .arg(Arg::with_name("key")
+                .index(1)
+                .required(true)
+            )
+        )
+}
+
 //
 //  Snark Sub Commands
 //
 
 const SNARK_COMMAND: &'static str = "snark";
 
-fn snark_arg_confidential_setup_match<'a, R: Rng>(matches: &ArgMatches<'a>, rng: &mut R) {
-    println!("Performing setup for confidential transfer...");
-    let pk_path = matches.value_of("proving-key-path").unwrap();
-    let vk_path = matches.value_of("verification-key-path").unwrap();
+/// The key files written by a setup, for the JSON output.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SetupReport {
+    circuit: &'static str,
+    proving_key_path: String,
+    verification_key_path: String,
+}
+
+/// The key files given on the command line, or the configured ones.
+/// Their directories are created if needed.
+fn snark_arg_key_paths_match(matches: &ArgMatches, pk_path: &PathBuf, vk_path: &PathBuf) -> error::Result<(PathBuf, PathBuf)> {
+    let pk_path = matches.value_of("proving-key-path").map_or(pk_path.clone(), PathBuf::from);
+    let vk_path = matches.value_of("verification-key-path").map_or(vk_path.clone(), PathBuf::from);
+
+    for path in &[&pk_path, &vk_path] {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+    }
+
+    Ok((pk_path, vk_path))
+}
+
+fn snark_arg_confidential_setup_match<R: Rng>(term: &mut term::Term, settings: &Settings, matches: &ArgMatches, rng: &mut R) -> error::Result<SetupReport> {
+    term.simply("Performing setup for confidential transfer...\n")?;
+    let (pk_path, vk_path) = snark_arg_key_paths_match(matches, &settings.keys.conf_pk, &settings.keys.conf_vk)?;
 
     confidential_setup(rng)
-        .write_to_file(pk_path, vk_path)
-        .unwrap();
+        .write_to_file(&pk_path, &vk_path)?;
 
-    println!("Success! Output >> 'conf_pk.dat' and 'conf_vk.dat'");
+    Ok(SetupReport {
+        circuit: "confidential",
+        proving_key_path: pk_path.display().to_string(),
+        verification_key_path: vk_path.display().to_string(),
+    })
 }
 
-fn snark_arg_anonymous_setup_match<'a, R: Rng>(matches: &ArgMatches<'a>, rng: &mut R) {
-    println!("Performing setup for anonymous transfer...");
-    let pk_path = matches.value_of("proving-key-path").unwrap();
-    let vk_path = matches.value_of("verification-key-path").unwrap();
+fn snark_arg_anonymous_setup_match<R: Rng>(term: &mut term::Term, settings: &Settings, matches: &ArgMatches, rng: &mut R) -> error::Result<SetupReport> {
+    term.simply("Performing setup for anonymous transfer...\n")?;
+    let (pk_path, vk_path) = snark_arg_key_paths_match(matches, &settings.keys.anony_pk, &settings.keys.anony_vk)?;
 
     anonymous_setup(rng)
-        .write_to_file(pk_path, vk_path)
-        .unwrap();
+        .write_to_file(&pk_path, &vk_path)?;
 
-    println!("Success! Output >> 'anony_pk.dat' and 'anony_vk.dat'");
+    Ok(SetupReport {
+        circuit: "anonymous",
+        proving_key_path: pk_path.display().to_string(),
+        verification_key_path: vk_path.display().to_string(),
+    })
 }
 
-fn subcommand_snark<R: Rng>(mut term: term::Term, matches: &ArgMatches, rng: &mut R) {
-    match matches.subcommand() {
+fn subcommand_snark<R: Rng>(mut term: term::Term, settings: &Settings, matches: &ArgMatches, rng: &mut R) {
+    let res = match matches.subcommand() {
         ("setup", Some(matches)) => {
-            snark_arg_confidential_setup_match(matches, rng);
-            snark_arg_anonymous_setup_match(matches, rng);
+            snark_arg_confidential_setup_match(&mut term, settings, matches, rng)
+                .and_then(|conf| Ok(vec![conf, snark_arg_anonymous_setup_match(&mut term, settings, matches, rng)?]))
         },
         ("confidential-setup", Some(matches)) => {
-            snark_arg_confidential_setup_match(matches, rng);
+            snark_arg_confidential_setup_match(&mut term, settings, matches, rng).map(|r| vec![r])
         },
         ("anonymous-setup", Some(matches)) => {
-            snark_arg_anonymous_setup_match(matches, rng);
-        }
-        _ => {
-            term.error(matches.usage()).unwrap();
-            ::std::process::exit(1)
+            snark_arg_anonymous_setup_match(&mut term, settings, matches, rng).map(|r| vec![r])
         }
+        _ => Err(usage_error(matches)),
     };
+
+    res.and_then(|reports| {
+        term.report(&reports, |term| {
+            for r in &reports {
+                term.simply(&format!("Success! Output >> '{}' and '{}'\n", r.proving_key_path, r.verification_key_path))?;
+            }
+            Ok(())
+        })?;
+        Ok(())
+    })
+    .unwrap_or_else(|e| term.fail_with(e))
 }
 
 fn snark_commands_definition<'a, 'b>() -> App<'a, 'b> {
     SubCommand::with_name(SNARK_COMMAND)
         .about("zk-snarks operations")
         .subcommand(SubCommand::with_name("setup")
-            .about("Performs a trusted setup for a given constraint system")
-            .arg(Arg::with_name("proving-key-path")
-                .short("p")
-                .long("proving-key-path")
-                .help("Path of the generated proving key file")
-                .value_name("FILE")
-                .takes_value(true)
-                .required(false)
-                .default_value(CONF_PK_PATH)
-            )
-            .arg(Arg::with_name("verification-key-path")
-                .short("v")
-                .long("verification-key-path")
-                .help("Path of the generated verification key file")
-                .value_name("FILE")
-                .takes_value(true)
-                .required(false)
-                .default_value(CONF_VK_PATH)
-            )
-             .arg(Arg::with_name("proving-key-path")
-                .short("p")
-                .long("proving-key-path")
-                .help("Path of the generated proving key file")
-                .value_name("FILE")
-                .takes_value(true)
-                .required(false)
-                .default_value(ANONY_PK_PATH)
-            )
-            .arg(Arg::with_name("verification-key-path")
-                .short("v")
-                .long("verification-key-path")
-                .help("Path of the generated verification key file")
-                .value_name("FILE")
-                .takes_value(true)
-                .required(false)
-                .default_value(ANONY_VK_PATH)
-            )
+            .about("Performs a trusted setup for both constraint systems, writing the keys to the configured paths")
         )
         .subcommand(SubCommand::with_name("confidential-setup")
             .about("Performs a trusted setup for a given constraint system")
             .arg(Arg::with_name("proving-key-path")
                 .short("p")
                 .long("proving-key-path")
-                .help("Path of the generated proving key file")
+                .help("Path of the generated proving key file. Defaults to `conf_pk_path` in the config")
                 .value_name("FILE")
                 .takes_value(true)
                 .required(false)
-                .default_value(CONF_PK_PATH)
             )
             .arg(Arg::with_name("verification-key-path")
                 .short("v")
                 .long("verification-key-path")
-                .help("Path of the generated verification key file")
+                .help("Path of the generated verification key file. Defaults to `conf_vk_path` in the config")
                 .value_name("FILE")
                 .takes_value(true)
                 .required(false)
-                .default_value(CONF_VK_PATH)
             )
         )
         .subcommand(SubCommand::with_name("anonymous-setup")
@@ -187,20 +273,18 @@ fn snark_commands_definition<'a, 'b>() -> App<'a, 'b> {
             .arg(Arg::with_name("proving-key-path")
                 .short("p")
                 .long("proving-key-path")
-                .help("Path of the generated proving key file")
+                .help("Path of the generated proving key file. Defaults to `anony_pk_path` in the config")
                 .value_name("FILE")
                 .takes_value(true)
                 .required(false)
-                .default_value(ANONY_PK_PATH)
             )
             .arg(Arg::with_name("verification-key-path")
                 .short("v")
                 .long("verification-key-path")
-                .help("Path of the generated verification key file")
+                .help("Path of the generated verification key file. Defaults to `anony_vk_path` in the config")
                 .value_name("FILE")
                 .takes_value(true)
                 .required(false)
-                .default_value(ANONY_VK_PATH)
             )
         )
 }
@@ -211,127 +295,202 @@ fn snark_commands_definition<'a, 'b>() -> App<'a, 'b> {
 
 const WALLET_COMMAND: &'static str = "wallet";
 
-fn wallet_arg_id_match<'a>(matches: &ArgMatches<'a>) -> u32 {
-    let id_str = matches.value_of("asset-id")
-        .expect("Asset id paramter is required; qed");
+fn wallet_arg_id_match<'a>(matches: &ArgMatches<'a>) -> error::Result<u32> {
+    parse_arg("asset-id", arg_value(matches, "asset-id")?)
+}
 
-    let id: u32 = id_str.parse()
-        .expect("should be parsed to u32 number; qed");
+fn wallet_arg_history_filter_match<'a>(matches: &ArgMatches<'a>) -> error::Result<history::Filter> {
+    let block = |name: &str| matches.value_of(name)
+        .map(|b| parse_arg(name, b))
+        .transpose();
+
+    Ok(history::Filter {
+        accounts: None,
+        kind: match matches.value_of("kind") {
+            Some("confidential") => Some(history::Kind::Confidential),
+            Some("asset") => Some(history::Kind::Asset),
+            Some("anonymous") => Some(history::Kind::Anonymous),
+            _ => None,
+        },
+        asset_id: matches.value_of("asset-id")
+            .map(|id| parse_arg("asset-id", id))
+            .transpose()?,
+        direction: match matches.value_of("direction") {
+            Some("in") => Some(history::Direction::Incoming),
+            Some("out") => Some(history::Direction::Outgoing),
+            _ => None,
+        },
+        from_block: block("from-block")?,
+        to_block: block("to-block")?,
+    })
+}
 
-    id
+fn wallet_arg_history_format_match<'a>(matches: &ArgMatches<'a>) -> history::export::Format {
+    match matches.value_of("format") {
+        Some("csv") => history::export::Format::Csv,
+        Some("json") => history::export::Format::Json,
+        _ => history::export::Format::Table,
+    }
 }
 
-fn subcommand_wallet<R: Rng>(mut term: term::Term, root_dir: PathBuf, matches: &ArgMatches, rng: &mut R) {
-    match matches.subcommand() {
-        ("init", Some(_)) => {
+fn wallet_arg_kdf_match<'a>(matches: &ArgMatches<'a>) -> wallet::Kdf {
+    matches.value_of("kdf")
+        .and_then(wallet::Kdf::from_name)
+        .unwrap_or_default()
+}
+
+fn wallet_kdf_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("kdf")
+        .long("kdf")
+        .help("Key derivation function to encrypt the keyfiles with")
+        .takes_value(true)
+        .possible_values(&["scrypt", "argon2id", "pbkdf2"])
+        .default_value("scrypt")
+}
+
+fn subcommand_wallet<R: Rng>(mut term: term::Term, root_dir: PathBuf, settings: &Settings, matches: &ArgMatches, rng: &mut R) {
+    let res = match matches.subcommand() {
+        ("init", Some(sub_matches)) => {
             // Create new wallet
-            new_wallet(&mut term, root_dir, rng)
-                .expect("Invalid operations of creating new wallet.");
+            new_wallet(&mut term, root_dir, wallet_arg_kdf_match(&sub_matches), rng)
         },
         ("list", Some(_)) => {
             // show accounts list
-            show_list(&mut term, root_dir)
-                .expect("Invalid operations of listing accounts.");
+            show_list(&mut term, root_dir, settings.default_account())
         },
         ("add-account", Some(_)) => {
             new_keyfile(&mut term, root_dir, rng)
-                .expect("Invalid operations of creating new account.");
         },
         ("change-account", Some(sub_matches)) => {
             let account_name = sub_matches.value_of("account-name")
                 .expect("Account name is required; qed");
 
             change_default_account(root_dir, account_name)
-                .expect("Change default account failed.");
         },
-        ("recovery", Some(_)) => {
-            recover(&mut term, root_dir, rng)
-                .expect("Invalid mnemonic to recover keystore.");
+        ("recovery", Some(sub_matches)) => {
+            let discovery = if sub_matches.is_present("offline") {
+                None
+            } else {
+                let api = Api::init(tx_arg_settings_match(settings, &sub_matches).node_url());
+                let gap_limit = sub_matches.value_of("gap-limit")
+                    .map_or(Ok(wallet::discovery::DEFAULT_GAP_LIMIT), |n| parse_arg("gap-limit", n))
+                    .unwrap_or_else(|e| term.fail_with(e));
+                Some((api, gap_limit))
+            };
+
+            recover(&mut term, root_dir, wallet_arg_kdf_match(&sub_matches), discovery, rng)
         },
-        ("balance", Some(sub_matches)) => {
-            println!("Getting encrypted balance...");
-            let api = Api::init(tx_arg_url_match(&sub_matches));
-
-            let dec_key = load_dec_key(&mut term, root_dir)
-                .expect("loading decrption key failed.");
+        ("migrate", Some(sub_matches)) => {
+            migrate(&mut term, root_dir, wallet_arg_kdf_match(&sub_matches), rng)
+        },
+        ("change-password", Some(_)) => {
+            change_password(&mut term, root_dir, rng)
+        },
+        ("export", Some(sub_matches)) => {
+            let path = sub_matches.value_of("out-file")
+                .map(PathBuf::from)
+                .expect("Output file is required; qed");
 
-            let balance_query = getter::BalanceQuery::get_encrypted_balance(&dec_key, api)
-                .expect("Falid to get balance data.");
+            export_wallet(&mut term, root_dir, path, wallet_arg_kdf_match(&sub_matches), rng)
+        },
+        ("import", Some(sub_matches)) => {
+            let path = sub_matches.value_of("input")
+                .map(PathBuf::from)
+                .expect("Input file is required; qed");
+            let on_conflict = match sub_matches.value_of("on-conflict") {
+                Some("skip") => wallet::OnConflict::Skip,
+                Some("rename") => wallet::OnConflict::Rename,
+                _ => wallet::OnConflict::Fail,
+            };
+
+            import_wallet(&mut term, root_dir, path, on_conflict, rng)
+        },
+        ("balance", Some(sub_matches)) => {
+            term.simply("Getting encrypted balance...\n").unwrap();
+            let api = Api::init(tx_arg_settings_match(settings, &sub_matches).node_url());
 
-            println!("Decrypted balance: {}", balance_query.decrypted_balance);
-            println!("Encrypted balance: {}", balance_query.encrypted_balance_str);
-            println!("Encrypted pending transfer: {}", balance_query.pending_transfer_str);
+            load_dec_key(&mut term, root_dir, settings.default_account())
+                .and_then(|dec_key| getter::BalanceQuery::get_encrypted_balance(&dec_key, api, settings.decryption_range))
+                .and_then(|balance_query| report_balance(&mut term, &balance_query, None))
         },
         ("asset-balance", Some(sub_matches)) => {
-            println!("Getting encrypted asset...");
-            let api = Api::init(tx_arg_url_match(&sub_matches));
-            let dec_key = load_dec_key(&mut term, root_dir)
-                .expect("loading decrption key failed.");
-            let asset_id = wallet_arg_id_match(&sub_matches);
+            term.simply("Getting encrypted asset...\n").unwrap();
+            let api = Api::init(tx_arg_settings_match(settings, &sub_matches).node_url());
+            let asset_id = wallet_arg_id_match(&sub_matches).unwrap_or_else(|e| term.fail_with(e));
 
-            let balance_query = getter::BalanceQuery::get_encrypted_asset(asset_id, &dec_key, api)
-                .expect("Falid to get balance data.");
-
-            println!("Decrypted balance: {}", balance_query.decrypted_balance);
-            println!("Encrypted balance: {}", balance_query.encrypted_balance_str);
-            println!("Encrypted pending transfer: {}", balance_query.pending_transfer_str);
+            load_dec_key(&mut term, root_dir, settings.default_account())
+                .and_then(|dec_key| getter::BalanceQuery::get_encrypted_asset(asset_id, &dec_key, api, settings.decryption_range))
+                .and_then(|balance_query| report_balance(&mut term, &balance_query, Some(asset_id)))
         },
         ("anonymous-balance", Some(sub_matches)) => {
-            println!("Getting anonymous balance...");
-            let api = Api::init(tx_arg_url_match(&sub_matches));
-            let dec_key = load_dec_key(&mut term, root_dir)
-                .expect("loading decrption key failed.");
-            let balance_query = getter::BalanceQuery::get_anonymous_balance(&dec_key, api)
-                .expect("Falid to get balance data.");
-
-            println!("Decrypted balance: {}", balance_query.decrypted_balance);
-            println!("Encrypted balance: {}", balance_query.encrypted_balance_str);
-            println!("Encrypted pending transfer: {}", balance_query.pending_transfer_str);
-        },
-        ("wallet-test", Some(_)) => {
-            println!("Initialize key components...");
-            println!("Accounts of alice and bob are fixed");
-
-            let alice_seed = seed_to_array(ALICESEED);
-            let bob_seed = seed_to_array(BOBSEED);
-
-            let print_keys_alice = PrintKeys::generate_from_seed(alice_seed);
-            let print_keys_bob = PrintKeys::generate_from_seed(bob_seed);
-            let print_keys_charlie = PrintKeys::generate();
-
-            println!(
-                "
-                \nSeed
-                Alice: 0x{}
-                Bob: 0x{}
-                Charlie: 0x{}
-                \nDecryption Key
-                Alice: 0x{}
-                Bob: 0x{}
-                Charlie: 0x{}
-                \nEncryption Key
-                Alice: 0x{}
-                Bob: 0x{}
-                Charlie: 0x{}
-                ",
-                hex::encode(&alice_seed[..]),
-                hex::encode(&print_keys_bob.seed[..]),
-                hex::encode(&print_keys_charlie.seed[..]),
-                hex::encode(&print_keys_alice.decryption_key[..]),
-                hex::encode(&print_keys_bob.decryption_key[..]),
-                hex::encode(&print_keys_charlie.decryption_key[..]),
-                hex::encode(&print_keys_alice.encryption_key[..]),
-                hex::encode(&print_keys_bob.encryption_key[..]),
-                hex::encode(&print_keys_charlie.encryption_key[..]),
-            );
-        },
-        _ => {
-            term.error(matches.usage()).unwrap();
-            ::std::process::exit(1)
-        }
+            term.simply("Getting anonymous balance...\n").unwrap();
+            let api = Api::init(tx_arg_settings_match(settings, &sub_matches).node_url());
+
+            load_dec_key(&mut term, root_dir, settings.default_account())
+                .and_then(|dec_key| getter::BalanceQuery::get_anonymous_balance(&dec_key, api, settings.decryption_range))
+                .and_then(|balance_query| report_balance(&mut term, &balance_query, None))
+        },
+        ("history", Some(sub_matches)) => {
+            let endpoint = if sub_matches.is_present("offline") {
+                None
+            } else {
+                Some(tx_arg_settings_match(settings, &sub_matches).url)
+            };
+            let account_names = sub_matches.values_of("account")
+                .map(|names| names.map(|n| n.to_string()).collect());
+            let output = sub_matches.value_of("out-file").map(PathBuf::from);
+            let filter = wallet_arg_history_filter_match(&sub_matches).unwrap_or_else(|e| term.fail_with(e));
+
+            history::commands::show_history(
+                &mut term,
+                root_dir,
+                endpoint.as_ref().map(|e| e.as_str()),
+                settings.decryption_range,
+                account_names,
+                filter,
+                wallet_arg_history_format_match(&sub_matches),
+                output,
+                rng
+            )
+        },
+        ("wallet-test", Some(_)) => print_test_accounts(&mut term),
+        _ => Err(usage_error(matches)),
     };
+
+    res.unwrap_or_else(|e| term.fail_with(e))
 }
 
+/// A balance of the default account, for the JSON output.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BalanceReport<'a> {
+    asset_id: Option<u32>,
+    decrypted_balance: u32,
+    encrypted_balance: &'a str,
+    pending_transfer: &'a str,
+}
+
+fn report_balance(term: &mut term::Term, balance_query: &getter::BalanceQuery, asset_id: Option<u32>) -> error::Result<()> {
+    let report = BalanceReport {
+        asset_id,
+        decrypted_balance: balance_query.decrypted_balance,
+        encrypted_balance: &balance_query.encrypted_balance_str,
+        pending_transfer: &balance_query.pending_transfer_str,
+    };
+
+    term.report(&report, |term| {
+        term.simply(&format!(
+            "Decrypted balance: {}\nEncrypted balance: {}\nEncrypted pending transfer: {}\n",
+            report.decrypted_balance,
+            report.encrypted_balance,
+            report.pending_transfer,
+        ))
+    })?;
+
+    Ok(())
+}
+
+
 fn wallet_commands_definition<'a, 'b>() -> App<'a, 'b> {
     SubCommand::with_name(WALLET_COMMAND)
         .about("wallet operations")
@@ -340,6 +499,7 @@ fn wallet_commands_definition<'a, 'b>() -> App<'a, 'b> {
         )
         .subcommand(SubCommand::with_name("init")
             .about("Initialize your wallet")
+            .arg(wallet_kdf_arg())
         )
         .subcommand(SubCommand::with_name("list")
             .about("Show accounts list.")
@@ -358,7 +518,61 @@ fn wallet_commands_definition<'a, 'b>() -> App<'a, 'b> {
             )
         )
         .subcommand(SubCommand::with_name("recovery")
-            .about("Recover keystore from mnemonic.")
+            .about("Recover keystore from mnemonic. The accounts with balances on chain are restored.")
+            .arg(wallet_kdf_arg())
+            .arg(Arg::with_name("url")
+                .short("u")
+                .long("url")
+                .help("Endpoint to connect zerochain nodes")
+                .takes_value(true)
+                .required(false)
+            )
+            .arg(Arg::with_name("gap-limit")
+                .long("gap-limit")
+                .help("Number of unused accounts in a row after which the scan stops (default: 20)")
+                .takes_value(true)
+                .required(false)
+            )
+            .arg(Arg::with_name("offline")
+                .long("offline")
+                .help("Create the first account only, without scanning the accounts on chain")
+            )
+        )
+        .subcommand(SubCommand::with_name("migrate")
+            .about("Re-encrypt old keyfiles with the current keyfile version and the given key derivation function")
+            .arg(wallet_kdf_arg())
+        )
+        .subcommand(SubCommand::with_name("change-password")
+            .about("Change the wallet password")
+        )
+        .subcommand(SubCommand::with_name("export")
+            .about("Export the keyfiles, the account names and the default account to a password-encrypted archive")
+            .arg(Arg::with_name("out-file")
+                .short("o")
+                .long("out-file")
+                .help("Path of the archive")
+                .value_name("FILE")
+                .takes_value(true)
+                .required(true)
+            )
+            .arg(wallet_kdf_arg())
+        )
+        .subcommand(SubCommand::with_name("import")
+            .about("Import a wallet archive")
+            .arg(Arg::with_name("input")
+                .long("input")
+                .help("Path of the archive")
+                .value_name("FILE")
+                .takes_value(true)
+                .required(true)
+            )
+            .arg(Arg::with_name("on-conflict")
+                .long("on-conflict")
+                .help("What to do with an imported account whose name is taken by another account")
+                .takes_value(true)
+                .possible_values(&["fail", "skip", "rename"])
+                .default_value("fail")
+            )
         )
         .subcommand(SubCommand::with_name("balance")
             .about("Get current balance stored in encrypted balances module")
@@ -397,6 +611,80 @@ fn wallet_commands_definition<'a, 'b>() -> App<'a, 'b> {
                 .required(false)
             )
         )
+        .subcommand(SubCommand::with_name("history")
+            .about("Sync and show the transfers of your accounts")
+            .arg(Arg::with_name("url")
+                .short("u")
+                .long("url")
+                .help("Endpoint to connect zerochain nodes")
+                .takes_value(true)
+                .required(false)
+            )
+            .arg(Arg::with_name("offline")
+                .long("offline")
+                .help("Show the stored history without syncing it")
+            )
+            .arg(Arg::with_name("account")
+                .short("n")
+                .long("name")
+                .help("Only show the transfers of this account. Can be repeated.")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .required(false)
+            )
+            .arg(Arg::with_name("kind")
+                .short("k")
+                .long("kind")
+                .help("Only show transfers of this kind")
+                .takes_value(true)
+                .possible_values(&["confidential", "asset", "anonymous"])
+                .required(false)
+            )
+            .arg(Arg::with_name("asset-id")
+                .short("i")
+                .long("id")
+                .help("Only show transfers of this asset")
+                .takes_value(true)
+                .required(false)
+            )
+            .arg(Arg::with_name("direction")
+                .short("d")
+                .long("direction")
+                .help("Only show incoming or outgoing transfers")
+                .takes_value(true)
+                .possible_values(&["in", "out"])
+                .required(false)
+            )
+            .arg(Arg::with_name("from-block")
+                .long("from-block")
+                .help("Only show transfers from this block on")
+                .takes_value(true)
+                .required(false)
+            )
+            .arg(Arg::with_name("to-block")
+                .long("to-block")
+                .help("Only show transfers up to this block")
+                .takes_value(true)
+                .required(false)
+            )
+            .arg(Arg::with_name("format")
+                .short("f")
+                .long("format")
+                .help("Output format")
+                .takes_value(true)
+                .possible_values(&["table", "csv", "json"])
+                .default_value("table")
+            )
+            .arg(Arg::with_name("out-file")
+                .short("o")
+                .long("out-file")
+                .help("Export the history to this file instead of printing it")
+                .value_name("FILE")
+                .takes_value(true)
+                .required(false)
+            )
+        )
 }
 
 //
@@ -405,78 +693,105 @@ fn wallet_commands_definition<'a, 'b>() -> App<'a, 'b> {
 
 const TX_COMMAND: &'static str = "tx";
 
-fn tx_arg_recipient_address_match<'a>(matches: &ArgMatches<'a>) -> [u8; 32] {
-    let recipient_address = matches.value_of("recipient-address")
-        .expect("Recipient's address is required; qed");
+fn tx_arg_recipient_address_match<'a>(matches: &ArgMatches<'a>) -> error::Result<[u8; 32]> {
+    let recipient_address = arg_value(matches, "recipient-address")?;
 
     let recipient_enc_key = EncryptionKeyBytes::from_ss58check(recipient_address)
-        .expect("The string should be a properly encoded SS58Check address.");
+        .map_err(|_| error::KeystoreError::InvalidArgument(
+            format!("'{}' is not a properly encoded SS58Check address", recipient_address)
+        ))?;
 
-    recipient_enc_key.0
+    Ok(recipient_enc_key.0)
 }
 
-fn tx_arg_amount_match<'a>(matches: &ArgMatches<'a>) -> u32 {
-    let amount_str = matches.value_of("amount")
-        .expect("Amount parameter is required; qed");
-
-    let amount: u32 = amount_str.parse()
-        .expect("should be parsed to u32 number; qed");
+fn tx_arg_amount_match<'a>(matches: &ArgMatches<'a>) -> error::Result<u32> {
+    parse_arg("amount", arg_value(matches, "amount")?)
+}
 
-    amount
+fn tx_arg_settings_match<'a>(settings: &Settings, matches: &ArgMatches<'a>) -> Settings {
+    settings.with_url(matches.value_of("url"))
 }
 
-fn tx_arg_url_match<'a>(matches: &ArgMatches<'a>) -> Url {
-    match matches.value_of("url") {
-        Some(u) => Url::Custom(u.to_string()),
-        None => Url::Local,
+fn tx_arg_decoys_match<'a>(matches: &ArgMatches<'a>) -> decoy::Distribution {
+    match matches.value_of("decoys") {
+        Some("recent") => decoy::Distribution::recent(),
+        _ => decoy::Distribution::Uniform,
     }
 }
 
-fn subcommand_tx<R: Rng>(mut term: term::Term, root_dir: PathBuf, matches: &ArgMatches, rng: &mut R) {
+fn tx_arg_kind_match<'a>(matches: &ArgMatches<'a>) -> error::Result<offline::TxKind> {
+    Ok(match matches.value_of("type") {
+        Some("asset-send") => offline::TxKind::AssetTransfer(wallet_arg_id_match(matches)?),
+        Some("anonymous-send") => offline::TxKind::AnonymousTransfer,
+        _ => offline::TxKind::Transfer,
+    })
+}
+
+fn subcommand_tx<R: Rng>(mut term: term::Term, root_dir: PathBuf, settings: &Settings, matches: &ArgMatches, rng: &mut R) {
     let res = match matches.subcommand() {
         ("send", Some(sub_matches)) => {
-            let recipient_enc_key = tx_arg_recipient_address_match(&sub_matches);
-            let amount = tx_arg_amount_match(&sub_matches);
-            let url = tx_arg_url_match(&sub_matches);
+            let recipient_enc_key = tx_arg_recipient_address_match(&sub_matches).unwrap_or_else(|e| term.fail_with(e));
+            let amount = tx_arg_amount_match(&sub_matches).unwrap_or_else(|e| term.fail_with(e));
+            let settings = tx_arg_settings_match(settings, &sub_matches);
 
-            confidential_transfer_tx(&mut term, root_dir, &recipient_enc_key[..], amount, url, rng)
+            confidential_transfer_tx(&mut term, root_dir, &recipient_enc_key[..], amount, &settings, rng)
         },
         ("asset-issue", Some(sub_matches)) => {
-            let amount = tx_arg_amount_match(&sub_matches);
-            let url = tx_arg_url_match(&sub_matches);
+            let amount = tx_arg_amount_match(&sub_matches).unwrap_or_else(|e| term.fail_with(e));
+            let settings = tx_arg_settings_match(settings, &sub_matches);
 
-            asset_issue_tx(&mut term, root_dir, amount, url, rng)
+            asset_issue_tx(&mut term, root_dir, amount, &settings, rng)
         },
         ("asset-send", Some(sub_matches)) => {
-            let recipient_enc_key = tx_arg_recipient_address_match(&sub_matches);
-            let amount = tx_arg_amount_match(&sub_matches);
-            let url = tx_arg_url_match(&sub_matches);
-            let asset_id = wallet_arg_id_match(&sub_matches);
+            let recipient_enc_key = tx_arg_recipient_address_match(&sub_matches).unwrap_or_else(|e| term.fail_with(e));
+            let amount = tx_arg_amount_match(&sub_matches).unwrap_or_else(|e| term.fail_with(e));
+            let settings = tx_arg_settings_match(settings, &sub_matches);
+            let asset_id = wallet_arg_id_match(&sub_matches).unwrap_or_else(|e| term.fail_with(e));
 
-            asset_transfer_tx(&mut term, root_dir, &recipient_enc_key[..], amount, asset_id, url, rng)
+            asset_transfer_tx(&mut term, root_dir, &recipient_enc_key[..], amount, asset_id, &settings, rng)
         },
         ("asset-burn", Some(sub_matches)) => {
-            let url = tx_arg_url_match(&sub_matches);
-            let asset_id = wallet_arg_id_match(&sub_matches);
-            asset_burn_tx(&mut term, root_dir, asset_id, url, rng)
+            let settings = tx_arg_settings_match(settings, &sub_matches);
+            let asset_id = wallet_arg_id_match(&sub_matches).unwrap_or_else(|e| term.fail_with(e));
+            asset_burn_tx(&mut term, root_dir, asset_id, &settings, rng)
         },
         ("anonymous-send", Some(sub_matches)) => {
-            let recipient_enc_key = tx_arg_recipient_address_match(&sub_matches);
-            let amount = tx_arg_amount_match(&sub_matches);
-            let url = tx_arg_url_match(&sub_matches);
+            let recipient_enc_key = tx_arg_recipient_address_match(&sub_matches).unwrap_or_else(|e| term.fail_with(e));
+            let amount = tx_arg_amount_match(&sub_matches).unwrap_or_else(|e| term.fail_with(e));
+            let distribution = tx_arg_decoys_match(&sub_matches);
+            let settings = tx_arg_settings_match(settings, &sub_matches);
 
-            anonymous_transfer_tx(&mut term, root_dir, &recipient_enc_key[..], amount, url, rng)
+            anonymous_transfer_tx(&mut term, root_dir, &recipient_enc_key[..], amount, distribution, &settings, rng)
         },
         ("anonymous-issue", Some(sub_matches)) => {
-            let amount = tx_arg_amount_match(&sub_matches);
-            let url = tx_arg_url_match(&sub_matches);
+            let amount = tx_arg_amount_match(&sub_matches).unwrap_or_else(|e| term.fail_with(e));
+            let settings = tx_arg_settings_match(settings, &sub_matches);
 
-            annonymous_issue_tx(&mut term, root_dir, amount, url, rng)
+            annonymous_issue_tx(&mut term, root_dir, amount, &settings, rng)
         },
-        _ => {
-            term.error(matches.usage()).unwrap();
-            ::std::process::exit(1)
-        }
+        ("prepare", Some(sub_matches)) => {
+            let kind = tx_arg_kind_match(&sub_matches).unwrap_or_else(|e| term.fail_with(e));
+            let recipient_enc_key = tx_arg_recipient_address_match(&sub_matches).unwrap_or_else(|e| term.fail_with(e));
+            let amount = tx_arg_amount_match(&sub_matches).unwrap_or_else(|e| term.fail_with(e));
+            let distribution = tx_arg_decoys_match(&sub_matches);
+            let settings = tx_arg_settings_match(settings, &sub_matches);
+            let output = PathBuf::from(sub_matches.value_of("out-file").unwrap());
+
+            offline::prepare_tx(&mut term, root_dir, kind, &recipient_enc_key[..], amount, distribution, &settings, &output, rng)
+        },
+        ("prove-sign", Some(sub_matches)) => {
+            let input = PathBuf::from(sub_matches.value_of("input").unwrap());
+            let output = PathBuf::from(sub_matches.value_of("out-file").unwrap());
+
+            offline::prove_sign_tx(&mut term, root_dir, settings, &input, &output, sub_matches.is_present("yes"), rng)
+        },
+        ("broadcast", Some(sub_matches)) => {
+            let input = PathBuf::from(sub_matches.value_of("input").unwrap());
+            let settings = tx_arg_settings_match(settings, &sub_matches);
+
+            offline::broadcast_tx(&mut term, &settings, &input)
+        },
+        _ => Err(usage_error(matches)),
     };
 
     res.unwrap_or_else(|e| term.fail_with(e))
@@ -590,6 +905,15 @@ fn tx_commands_definition<'a, 'b>() -> App<'a, 'b> {
                 .takes_value(true)
                 .required(false)
             )
+            .arg(Arg::with_name("decoys")
+                .short("d")
+                .long("decoys")
+                .help("How to pick decoys: uniformly from the anonymity set, or weighted towards recently registered keys")
+                .takes_value(true)
+                .required(false)
+                .possible_values(&["uniform", "recent"])
+                .default_value("uniform")
+            )
             .arg(Arg::with_name("url")
                 .short("u")
                 .long("url")
@@ -614,6 +938,106 @@ fn tx_commands_definition<'a, 'b>() -> App<'a, 'b> {
                 .takes_value(true)
                 .required(false)
             )
+        )        .subcommand(SubCommand::with_name("prepare")
+            .about("Fetch the inputs of a transfer from zerochain nodes into an unsigned transaction file, to be proven and signed offline.")
+            .arg(Arg::with_name("type")
+                .short("t")
+                .long("type")
+                .help("The transfer to prepare")
+                .takes_value(true)
+                .required(false)
+                .possible_values(&["send", "asset-send", "anonymous-send"])
+                .default_value("send")
+            )
+            .arg(Arg::with_name("amount")
+                .short("a")
+                .long("amount")
+                .help("The coin amount for the transfer.")
+                .takes_value(true)
+                .required(true)
+            )
+            .arg(Arg::with_name("recipient-address")
+                .short("to")
+                .long("recipient-address")
+                .help("Recipient's SS58-encoded address")
+                .takes_value(true)
+                .required(true)
+            )
+            .arg(Arg::with_name("asset-id")
+                .short("i")
+                .long("id")
+                .help("Asset id, for an asset-send")
+                .takes_value(true)
+                .required_if("type", "asset-send")
+            )
+            .arg(Arg::with_name("decoys")
+                .short("d")
+                .long("decoys")
+                .help("How to pick decoys for an anonymous-send: uniformly from the anonymity set, or weighted towards recently registered keys")
+                .takes_value(true)
+                .required(false)
+                .possible_values(&["uniform", "recent"])
+                .default_value("uniform")
+            )
+            .arg(Arg::with_name("out-file")
+                .short("o")
+                .long("out-file")
+                .help("Path of the unsigned transaction file")
+                .value_name("FILE")
+                .takes_value(true)
+                .required(false)
+                .default_value("unsigned_tx.json")
+            )
+            .arg(Arg::with_name("url")
+                .short("u")
+                .long("url")
+                .help("Endpoint to connect zerochain nodes")
+                .takes_value(true)
+                .required(false)
+            )
+        )
+        .subcommand(SubCommand::with_name("prove-sign")
+            .about("Prove and sign an unsigned transaction file with the default account, without connecting to zerochain nodes.")
+            .arg(Arg::with_name("input")
+                .long("input")
+                .help("Path of the unsigned transaction file")
+                .value_name("FILE")
+                .takes_value(true)
+                .required(false)
+                .default_value("unsigned_tx.json")
+            )
+            .arg(Arg::with_name("out-file")
+                .short("o")
+                .long("out-file")
+                .help("Path of the signed transaction file")
+                .value_name("FILE")
+                .takes_value(true)
+                .required(false)
+                .default_value("signed_tx.json")
+            )
+            .arg(Arg::with_name("yes")
+                .short("y")
+                .long("yes")
+                .help("Sign without asking for confirmation, e.g. in scripts")
+            )
+        )
+        .subcommand(SubCommand::with_name("broadcast")
+            .about("Submit a signed transaction file to zerochain nodes.")
+            .arg(Arg::with_name("input")
+                .long("input")
+                .help("Path of the signed transaction file")
+                .value_name("FILE")
+                .takes_value(true)
+                .required(false)
+                .default_value("signed_tx.json")
+            )
+            .arg(Arg::with_name("url")
+                .short("u")
+                .long("url")
+                .help("Endpoint to connect zerochain nodes")
+                .takes_value(true)
+                .required(false)
+            )
         )
 }
 
@@ -623,142 +1047,174 @@ fn tx_commands_definition<'a, 'b>() -> App<'a, 'b> {
 
 const DEBUG_COMMAND: &'static str = "debug";
 
-fn debug_arg_seed_match<'a>(matches: &ArgMatches<'a>) -> Vec<u8> {
-    hex::decode(matches.value_of("sender-seed")
-        .expect("Seed parameter is required; qed"))
-        .expect("should be decoded to hex.")
+fn debug_arg_seed_match<'a>(matches: &ArgMatches<'a>) -> error::Result<Vec<u8>> {
+    hex::decode(arg_value(matches, "sender-seed")?)
+        .map_err(|_| error::KeystoreError::InvalidArgument("--sender-seed should be hex-encoded".to_string()))
 }
 
-fn subcommand_debug<R: Rng>(mut term: term::Term, matches: &ArgMatches, rng: &mut R) {
-    match matches.subcommand() {
+fn subcommand_debug<R: Rng>(mut term: term::Term, settings: &Settings, matches: &ArgMatches, rng: &mut R) {
+    let res = match matches.subcommand() {
         ("key-init", Some(_)) => {
             let lang = Language::English;
             // create a new randomly generated mnemonic phrase
             let mnemonic = Mnemonic::new(MnemonicType::Words12, lang);
-            PrintKeys::print_from_phrase(mnemonic.phrase(), None, lang);
+            PrintKeys::print_from_phrase(&mut term, mnemonic.phrase(), None, lang)
         },
         ("send", Some(sub_matches)) => {
-            let seed = debug_arg_seed_match(&sub_matches);
-            let recipient_enc_key = tx_arg_recipient_address_match(&sub_matches);
-            let amount = tx_arg_amount_match(&sub_matches);
-            let url = tx_arg_url_match(&sub_matches);
+            let seed = debug_arg_seed_match(&sub_matches).unwrap_or_else(|e| term.fail_with(e));
+            let recipient_enc_key = tx_arg_recipient_address_match(&sub_matches).unwrap_or_else(|e| term.fail_with(e));
+            let amount = tx_arg_amount_match(&sub_matches).unwrap_or_else(|e| term.fail_with(e));
+            let settings = tx_arg_settings_match(settings, &sub_matches);
 
-            transfer_tx_for_debug(&seed[..], &recipient_enc_key[..], amount, url, rng).unwrap();
+            transfer_tx_for_debug(&mut term, &seed[..], &recipient_enc_key[..], amount, &settings, rng)
         },
         ("anonymous-send", Some(sub_matches)) => {
-            let seed = debug_arg_seed_match(&sub_matches);
-            let recipient_enc_key = tx_arg_recipient_address_match(&sub_matches);
-            let amount = tx_arg_amount_match(&sub_matches);
-            let url = tx_arg_url_match(&sub_matches);
+            let seed = debug_arg_seed_match(&sub_matches).unwrap_or_else(|e| term.fail_with(e));
+            let recipient_enc_key = tx_arg_recipient_address_match(&sub_matches).unwrap_or_else(|e| term.fail_with(e));
+            let amount = tx_arg_amount_match(&sub_matches).unwrap_or_else(|e| term.fail_with(e));
+            let distribution = tx_arg_decoys_match(&sub_matches);
+            let settings = tx_arg_settings_match(settings, &sub_matches);
 
-            anonymous_transfer_tx_for_debug(&seed[..], &recipient_enc_key[..], amount, url, rng).unwrap();
+            anonymous_transfer_tx_for_debug(&mut term, &seed[..], &recipient_enc_key[..], amount, distribution, &settings, rng)
         },
         ("print-tx", Some(sub_matches)) => {
-            println!("Generate transaction...");
-
-            let sender_seed = hex::decode(sub_matches.value_of("sender-privatekey").unwrap()).unwrap();
-            let recipient_seed  = hex::decode(sub_matches.value_of("recipient-privatekey").unwrap()).unwrap();
-
-            let sender_address = getter::address(&sender_seed[..]).unwrap();
-            let recipient_address = getter::address(&recipient_seed[..]).unwrap();
-
-            println!("Private Key(Sender): 0x{}\nAddress(Sender): 0x{}\n",
-                HexDisplay::from(&sender_seed),
-                HexDisplay::from(&sender_address),
-            );
-
-            println!("Private Key(Recipient): 0x{}\nAddress(Recipient): 0x{}\n",
-                HexDisplay::from(&recipient_seed),
-                HexDisplay::from(&recipient_address),
-            );
-
-            let pk_path = sub_matches.value_of("proving-key-path").unwrap();
-            let vk_path = sub_matches.value_of("verification-key-path").unwrap();
-
-            let amount_str = sub_matches.value_of("amount").unwrap();
-            let amount: u32 = amount_str.parse().unwrap();
-            let fee = 1 as u32;
-
-            let balance_str = sub_matches.value_of("balance").unwrap();
-            let balance: u32 = balance_str.parse().unwrap();
-
-            println!("Transaction >>");
-
-            let address_recipient = EncryptionKey::<Bls12>::from_seed(&recipient_seed[..], &PARAMS).unwrap();
-
-            let ciphertext_balance_a = sub_matches.value_of("encrypted-balance").unwrap();
-            let ciphertext_balance_v = hex::decode(ciphertext_balance_a).unwrap();
-            let ciphertext_balance = vec![elgamal::Ciphertext::read(&mut &ciphertext_balance_v[..], &*PARAMS).unwrap()];
-
-            let remaining_balance = balance - amount - fee;
-
-            use scrypto::jubjub::edwards;
-            let g_epoch_vec = hex::decode("0953f47325251a2f479c25527df6d977925bebafde84423b20ae6c903411665a").unwrap();
-            let g_epoch = edwards::Point::read(&g_epoch_vec[..], &*PARAMS).unwrap().as_prime_order(&*PARAMS).unwrap();
-
-            let tx = KeyContext::read_from_path(pk_path, vk_path)
-                .unwrap()
-                .gen_proof(
-                    amount,
-                    fee,
-                    remaining_balance,
-                    0, 0,
-                    &SpendingKey::<Bls12>::from_seed(&sender_seed[..]),
-                    MultiEncKeys::<Bls12, Confidential>::new(address_recipient.clone()),
-                    &ciphertext_balance,
-                    g_epoch,
-                    rng,
-                    &*PARAMS
-                ).expect("fails to generate the tx");
-
-            println!(
-                "
-                \nzkProof(Alice): 0x{}
-                \naddress_sender(Alice): 0x{}
-                \naddress_recipient(Alice): 0x{}
-                \nvalue_sender(Alice): 0x{}
-                \nvalue_recipient(Alice): 0x{}
-                \nrvk(Alice): 0x{}
-                \nrsk(Alice): 0x{}
-                \nEncrypted fee by sender: 0x{}
-                \nright_randomness: 0x{}
-                \nNonce:  0x{}
-                ",
-                HexDisplay::from(&&tx.proof[..] as &dyn AsBytesRef),
-                HexDisplay::from(&tx.enc_key_sender as &dyn AsBytesRef),
-                HexDisplay::from(&tx.enc_key_recipient as &dyn AsBytesRef),
-                HexDisplay::from(&tx.left_amount_sender as &dyn AsBytesRef),
-                HexDisplay::from(&tx.left_amount_recipient as &dyn AsBytesRef),
-                HexDisplay::from(&tx.rvk as &dyn AsBytesRef),
-                HexDisplay::from(&tx.rsk as &dyn AsBytesRef),
-                HexDisplay::from(&tx.left_fee as &dyn AsBytesRef),
-                HexDisplay::from(&tx.right_randomness as &dyn AsBytesRef),
-                HexDisplay::from(&tx.nonce as &dyn AsBytesRef)
-            );
+            debug_print_tx(&mut term, settings, &sub_matches, rng)
         },
         ("balance", Some(sub_matches)) => {
-            println!("Getting encrypted balance...");
-
-            let api = Api::init(tx_arg_url_match(&sub_matches));
-            let decr_key_vec = hex::decode(sub_matches.value_of("decryption-key")
-                .expect("Decryption key parameter is required; qed"))
-                .expect("should be decoded to hex.");
+            term.simply("Getting encrypted balance...\n").unwrap();
+
+            let api = Api::init(tx_arg_settings_match(settings, &sub_matches).node_url());
+            let decr_key_vec = arg_value(&sub_matches, "decryption-key")
+                .and_then(|key| hex::decode(key)
+                    .map_err(|_| error::KeystoreError::InvalidArgument("--decryption-key should be hex-encoded".to_string()))
+                )
+                .unwrap_or_else(|e| term.fail_with(e));
+
+            DecryptionKey::read(&mut &decr_key_vec[..])
+                .map_err(From::from)
+                .and_then(|dec_key| getter::BalanceQuery::get_encrypted_balance(&dec_key, api, settings.decryption_range))
+                .and_then(|balance_query| report_balance(&mut term, &balance_query, None))
+        },
+        _ => Err(usage_error(matches)),
+    };
 
-            let dec_key = DecryptionKey::read(&mut &decr_key_vec[..])
-                .expect("Reading decryption key faild.");
+    res.unwrap_or_else(|e| term.fail_with(e))
+}
 
-            let balance_query = getter::BalanceQuery::get_encrypted_balance(&dec_key, api)
-                .expect("Falid to get balance data.");
+/// The components of a transaction, for the JSON output.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PrintTxReport {
+    sender_private_key: String,
+    sender_address: String,
+    recipient_private_key: String,
+    recipient_address: String,
+    zk_proof: String,
+    address_sender: String,
+    address_recipient: String,
+    value_sender: String,
+    value_recipient: String,
+    rvk: String,
+    rsk: String,
+    fee_sender: String,
+    right_randomness: String,
+    nonce: String,
+}
 
-            println!("Decrypted balance: {}", balance_query.decrypted_balance);
-            println!("Encrypted balance: {}", balance_query.encrypted_balance_str);
-            println!("Encrypted pending transfer: {}", balance_query.pending_transfer_str);
-        },
-        _ => {
-            term.error(matches.usage()).unwrap();
-            ::std::process::exit(1)
-        }
+fn debug_print_tx<'a, R: Rng>(term: &mut term::Term, settings: &Settings, sub_matches: &ArgMatches<'a>, rng: &mut R) -> error::Result<()> {
+    term.simply("Generate transaction...\n")?;
+
+    let sender_seed = hex::decode(sub_matches.value_of("sender-privatekey").unwrap()).unwrap();
+    let recipient_seed  = hex::decode(sub_matches.value_of("recipient-privatekey").unwrap()).unwrap();
+
+    let sender_address = getter::address(&sender_seed[..])?;
+    let recipient_address = getter::address(&recipient_seed[..])?;
+
+    let pk_path = sub_matches.value_of("proving-key-path").map_or(settings.keys.conf_pk.clone(), PathBuf::from);
+    let vk_path = sub_matches.value_of("verification-key-path").map_or(settings.keys.conf_vk.clone(), PathBuf::from);
+
+    let amount_str = sub_matches.value_of("amount").unwrap();
+    let amount: u32 = amount_str.parse().unwrap();
+    let fee = 1 as u32;
+
+    let balance_str = sub_matches.value_of("balance").unwrap();
+    let balance: u32 = balance_str.parse().unwrap();
+
+    let address_recipient = EncryptionKey::<Bls12>::from_seed(&recipient_seed[..], &PARAMS)?;
+
+    let ciphertext_balance_a = sub_matches.value_of("encrypted-balance").unwrap();
+    let ciphertext_balance_v = hex::decode(ciphertext_balance_a).unwrap();
+    let ciphertext_balance = vec![elgamal::Ciphertext::read(&mut &ciphertext_balance_v[..], &*PARAMS)?];
+
+    let remaining_balance = balance - amount - fee;
+
+    use scrypto::jubjub::edwards;
+    let g_epoch_vec = hex::decode("0953f47325251a2f479c25527df6d977925bebafde84423b20ae6c903411665a").unwrap();
+    let g_epoch = edwards::Point::read(&g_epoch_vec[..], &*PARAMS)?.as_prime_order(&*PARAMS).unwrap();
+
+    let tx = KeyContext::read_from_path(pk_path, vk_path)?
+        .gen_proof(
+            amount,
+            fee,
+            remaining_balance,
+            0, 0,
+            &SpendingKey::<Bls12>::from_seed(&sender_seed[..]),
+            MultiEncKeys::<Bls12, Confidential>::new(address_recipient.clone()),
+            &ciphertext_balance,
+            g_epoch,
+            0,
+            rng,
+            &*PARAMS
+        )?;
+
+    let report = PrintTxReport {
+        sender_private_key: format!("0x{}", HexDisplay::from(&sender_seed)),
+        sender_address: format!("0x{}", HexDisplay::from(&sender_address)),
+        recipient_private_key: format!("0x{}", HexDisplay::from(&recipient_seed)),
+        recipient_address: format!("0x{}", HexDisplay::from(&recipient_address)),
+        zk_proof: format!("0x{}", HexDisplay::from(&&tx.proof[..] as &dyn AsBytesRef)),
+        address_sender: format!("0x{}", HexDisplay::from(&tx.enc_key_sender as &dyn AsBytesRef)),
+        address_recipient: format!("0x{}", HexDisplay::from(&tx.enc_key_recipient as &dyn AsBytesRef)),
+        value_sender: format!("0x{}", HexDisplay::from(&tx.left_amount_sender as &dyn AsBytesRef)),
+        value_recipient: format!("0x{}", HexDisplay::from(&tx.left_amount_recipient as &dyn AsBytesRef)),
+        rvk: format!("0x{}", HexDisplay::from(&tx.rvk as &dyn AsBytesRef)),
+        rsk: format!("0x{}", HexDisplay::from(&tx.rsk as &dyn AsBytesRef)),
+        fee_sender: format!("0x{}", HexDisplay::from(&tx.left_fee as &dyn AsBytesRef)),
+        right_randomness: format!("0x{}", HexDisplay::from(&tx.right_randomness as &dyn AsBytesRef)),
+        nonce: format!("0x{}", HexDisplay::from(&tx.nonce as &dyn AsBytesRef)),
     };
+
+    term.report(&report, |term| {
+        term.simply(&format!("Private Key(Sender): {}\nAddress(Sender): {}\n\n", report.sender_private_key, report.sender_address))?;
+        term.simply(&format!("Private Key(Recipient): {}\nAddress(Recipient): {}\n\n", report.recipient_private_key, report.recipient_address))?;
+        term.simply("Transaction >>\n")?;
+        term.simply(&format!(
+            "
+            \nzkProof(Alice): {}
+            \naddress_sender(Alice): {}
+            \naddress_recipient(Alice): {}
+            \nvalue_sender(Alice): {}
+            \nvalue_recipient(Alice): {}
+            \nrvk(Alice): {}
+            \nrsk(Alice): {}
+            \nEncrypted fee by sender: {}
+            \nright_randomness: {}
+            \nNonce:  {}
+            \n",
+            report.zk_proof,
+            report.address_sender,
+            report.address_recipient,
+            report.value_sender,
+            report.value_recipient,
+            report.rvk,
+            report.rsk,
+            report.fee_sender,
+            report.right_randomness,
+            report.nonce,
+        ))
+    })?;
+
+    Ok(())
 }
 
 fn debug_commands_definition<'a, 'b>() -> App<'a, 'b> {
@@ -827,6 +1283,15 @@ fn debug_commands_definition<'a, 'b>() -> App<'a, 'b> {
                 .required(false)
                 .default_value(BOBACCOUNTID)
             )
+            .arg(Arg::with_name("decoys")
+                .short("d")
+                .long("decoys")
+                .help("How to pick decoys: uniformly from the anonymity set, or weighted towards recently registered keys")
+                .takes_value(true)
+                .required(false)
+                .possible_values(&["uniform", "recent"])
+                .default_value("uniform")
+            )
             .arg(Arg::with_name("url")
                 .short("u")
                 .long("url")
@@ -840,20 +1305,18 @@ fn debug_commands_definition<'a, 'b>() -> App<'a, 'b> {
             .arg(Arg::with_name("proving-key-path")
                 .short("p")
                 .long("proving-key-path")
-                .help("Path of the proving key file")
+                .help("Path of the proving key file. Defaults to `conf_pk_path` in the config")
                 .value_name("FILE")
                 .takes_value(true)
                 .required(false)
-                .default_value(CONF_PK_PATH)
             )
             .arg(Arg::with_name("verification-key-path")
                 .short("v")
                 .long("verification-key-path")
-                .help("Path of the generated verification key file")
+                .help("Path of the generated verification key file. Defaults to `conf_vk_path` in the config")
                 .value_name("FILE")
                 .takes_value(true)
                 .required(false)
-                .default_value(CONF_VK_PATH)
             )
             .arg(Arg::with_name("amount")
                 .short("a")
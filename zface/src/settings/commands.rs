@@ -0,0 +1,89 @@
+use std::path::Path;
+use std::collections::BTreeMap;
+use crate::term::Term;
+use crate::error::Result;
+use super::{Settings, ConfigStore, KEYS};
+
+/// The key which selects the active profile, rather than a value in a profile.
+const PROFILE_KEY: &'static str = "profile";
+
+/// The resolved configuration, for the JSON output.
+#[derive(Serialize, Debug)]
+pub struct ConfigReport<'a> {
+    pub profile: &'a str,
+    pub values: BTreeMap<&'a str, Option<String>>,
+}
+
+/// Show the value of `key` in the settings of `profile`, or all the values if no key is given.
+/// The values are the resolved ones, including the defaults and the environment variables.
+pub fn show_config(
+    term: &mut Term,
+    root_dir: &Path,
+    profile: Option<&str>,
+    key: Option<&str>,
+) -> Result<()> {
+    let settings = Settings::load(root_dir, profile)?;
+
+    let mut values = BTreeMap::new();
+    match key {
+        Some(PROFILE_KEY) => {},
+        Some(key) => { values.insert(key, settings.get(key)?); },
+        None => for key in KEYS.iter() {
+            values.insert(*key, settings.get(key)?);
+        },
+    }
+    let report = ConfigReport { profile: &settings.profile, values };
+
+    term.report(&report, |term| {
+        if key.is_none() || key == Some(PROFILE_KEY) {
+            term.info(&format!("profile = {}\n", report.profile))?;
+        }
+        for (key, value) in &report.values {
+            term.simply(&format!("{} = {}\n", key, value.as_ref().map_or("(not set)", |v| v.as_str())))?;
+        }
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+/// Set `key` to `value` in `profile`, or in the active profile if none is given.
+/// No value unsets the key. The `profile` key switches the active profile instead.
+pub fn set_config(
+    term: &mut Term,
+    root_dir: &Path,
+    profile: Option<&str>,
+    key: &str,
+    value: Option<&str>,
+) -> Result<()> {
+    let store = ConfigStore::new(root_dir);
+    let mut config = store.load()?;
+
+    let name = if key == PROFILE_KEY {
+        config.profile = value.map(|v| v.to_string());
+        config.active_profile().to_string()
+    } else {
+        let name = profile.unwrap_or(config.active_profile()).to_string();
+        let values = config.profiles.entry(name.clone()).or_default();
+        match value {
+            Some(value) => values.set(key, value)?,
+            None => values.unset(key)?,
+        }
+        name
+    };
+    store.save(&config)?;
+
+    let mut values = BTreeMap::new();
+    if key != PROFILE_KEY {
+        values.insert(key, value.map(|v| v.to_string()));
+    }
+    let report = ConfigReport { profile: &name, values };
+
+    term.report(&report, |term| match value {
+        Some(value) if key == PROFILE_KEY => term.success(&format!("Switched to the profile '{}'\n", value)),
+        Some(value) => term.success(&format!("Set {} = {} in the profile '{}'\n", key, value, name)),
+        None => term.success(&format!("Unset {} in the profile '{}'\n", key, name)),
+    })?;
+
+    Ok(())
+}
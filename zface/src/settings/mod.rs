@@ -0,0 +1,389 @@
+//! The configuration file of zface, `config.toml` in the zface root directory.
+//!
+//! The file holds named profiles, e.g. one per network, and the name of the active one:
+//!
+//! ```toml
+//! profile = "testnet"
+//!
+//! [profiles.testnet]
+//! url = "ws://testnet.example.com:9944"
+//! default_account = "alice"
+//! decryption_range = 2000000
+//! ```
+//!
+//! A value is looked up in the command line options first, then in the `ZFACE_<KEY>`
+//! environment variables, then in the active profile, and falls back to a built-in default.
+
+use std::path::{Path, PathBuf};
+use std::collections::BTreeMap;
+use std::{env, fs};
+use std::io::Write;
+use polkadot_rs::Url;
+use crate::error::{Result, KeystoreError};
+use crate::term::OutputFormat;
+use crate::transaction::constants::*;
+use crate::wallet::replace_file;
+
+pub mod commands;
+
+const CONFIGFILE: &'static str = "config.toml";
+const PARAMS_DIR: &'static str = "params";
+const ENV_PREFIX: &'static str = "ZFACE_";
+
+pub const DEFAULT_PROFILE: &'static str = "default";
+pub const DEFAULT_URL: &'static str = "ws://127.0.0.1:9944";
+/// Balances are decrypted by brute force up to this amount by default.
+pub const DEFAULT_DECRYPTION_RANGE: u32 = 1_000_000;
+
+/// The keys of a profile, as named in the file and in `zface config get/set`.
+pub const KEYS: [&'static str; 8] = [
+    "url",
+    "conf_pk_path",
+    "conf_vk_path",
+    "anony_pk_path",
+    "anony_vk_path",
+    "default_account",
+    "decryption_range",
+    "output",
+];
+
+/// The values set in a profile. Unset values fall back to the defaults.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conf_pk_path: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conf_vk_path: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub anony_pk_path: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub anony_vk_path: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_account: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decryption_range: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+}
+
+impl Profile {
+    pub fn get(&self, key: &str) -> Result<Option<String>> {
+        let path = |p: &Option<PathBuf>| p.as_ref().map(|p| p.display().to_string());
+
+        Ok(match key {
+            "url" => self.url.clone(),
+            "conf_pk_path" => path(&self.conf_pk_path),
+            "conf_vk_path" => path(&self.conf_vk_path),
+            "anony_pk_path" => path(&self.anony_pk_path),
+            "anony_vk_path" => path(&self.anony_vk_path),
+            "default_account" => self.default_account.clone(),
+            "decryption_range" => self.decryption_range.map(|r| r.to_string()),
+            "output" => self.output.clone(),
+            _ => return Err(unknown_key(key)),
+        })
+    }
+
+    /// Set the value of `key`, checking that it is valid.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        let value = value.to_string();
+
+        match key {
+            "url" => self.url = Some(value),
+            "conf_pk_path" => self.conf_pk_path = Some(PathBuf::from(value)),
+            "conf_vk_path" => self.conf_vk_path = Some(PathBuf::from(value)),
+            "anony_pk_path" => self.anony_pk_path = Some(PathBuf::from(value)),
+            "anony_vk_path" => self.anony_vk_path = Some(PathBuf::from(value)),
+            "default_account" => self.default_account = Some(value),
+            "decryption_range" => {
+                let range = value.parse()
+                    .map_err(|_| KeystoreError::InvalidConfig(format!("decryption_range should be a number, not '{}'", value)))?;
+                self.decryption_range = Some(range);
+            },
+            "output" => {
+                parse_output(&value)?;
+                self.output = Some(value);
+            },
+            _ => return Err(unknown_key(key)),
+        }
+
+        Ok(())
+    }
+
+    /// Clear the value of `key`, so that it falls back to the default.
+    pub fn unset(&mut self, key: &str) -> Result<()> {
+        match key {
+            "url" => self.url = None,
+            "conf_pk_path" => self.conf_pk_path = None,
+            "conf_vk_path" => self.conf_vk_path = None,
+            "anony_pk_path" => self.anony_pk_path = None,
+            "anony_vk_path" => self.anony_vk_path = None,
+            "default_account" => self.default_account = None,
+            "decryption_range" => self.decryption_range = None,
+            "output" => self.output = None,
+            _ => return Err(unknown_key(key)),
+        }
+
+        Ok(())
+    }
+
+    /// The values set in the `ZFACE_<KEY>` environment variables, e.g. `ZFACE_URL`.
+    pub fn from_env() -> Result<Self> {
+        let mut profile = Profile::default();
+        for key in KEYS.iter() {
+            if let Ok(value) = env::var(format!("{}{}", ENV_PREFIX, key.to_uppercase())) {
+                profile.set(key, &value)?;
+            }
+        }
+
+        Ok(profile)
+    }
+
+    /// Take the values of `other` where they are set.
+    pub fn merge(&mut self, other: Profile) {
+        macro_rules! merge {
+            ($($field:ident),*) => { $(if other.$field.is_some() { self.$field = other.$field; })* };
+        }
+        merge!(url, conf_pk_path, conf_vk_path, anony_pk_path, anony_vk_path, default_account, decryption_range, output);
+    }
+}
+
+/// The contents of the configuration file.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigFile {
+    /// Name of the active profile
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+impl ConfigFile {
+    /// Name of the active profile, unless another one is chosen on the command line.
+    pub fn active_profile(&self) -> &str {
+        self.profile.as_ref().map_or(DEFAULT_PROFILE, |p| p.as_str())
+    }
+}
+
+/// The configuration file in the zface root directory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConfigStore(pub PathBuf);
+
+impl ConfigStore {
+    pub fn new<P: AsRef<Path>>(root_dir: P) -> Self {
+        ConfigStore(root_dir.as_ref().join(CONFIGFILE))
+    }
+
+    /// Load the configuration, or an empty one if there is no file.
+    pub fn load(&self) -> Result<ConfigFile> {
+        if !self.0.exists() {
+            return Ok(ConfigFile::default());
+        }
+
+        let contents = fs::read_to_string(&self.0)?;
+        toml::from_str(&contents)
+            .map_err(|e| KeystoreError::InvalidConfig(format!("{}: {}", self.0.display(), e)))
+    }
+
+    pub fn save(&self, config: &ConfigFile) -> Result<()> {
+        let contents = toml::to_string(config)
+            .map_err(|e| KeystoreError::InvalidConfig(e.to_string()))?;
+
+        if let Some(dir) = self.0.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = replace_file(&self.0)?;
+        file.write_all(contents.as_bytes())?;
+
+        file.flush()?;
+        file.sync_all()?;
+
+        Ok(())
+    }
+}
+
+/// Locations of the proving and verifying keys.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyPaths {
+    pub conf_pk: PathBuf,
+    pub conf_vk: PathBuf,
+    pub anony_pk: PathBuf,
+    pub anony_vk: PathBuf,
+}
+
+/// The resolved configuration the commands run with.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Settings {
+    pub profile: String,
+    pub url: String,
+    pub keys: KeyPaths,
+    pub default_account: Option<String>,
+    pub decryption_range: u32,
+    pub output: Option<OutputFormat>,
+}
+
+impl Settings {
+    /// Load the settings of `profile`, or of the active profile of the configuration file.
+    pub fn load(root_dir: &Path, profile: Option<&str>) -> Result<Self> {
+        let config = ConfigStore::new(root_dir).load()?;
+        let name = profile.unwrap_or(config.active_profile()).to_string();
+
+        let mut values = match config.profiles.get(&name) {
+            Some(p) => p.clone(),
+            None if profile.is_some() && name != DEFAULT_PROFILE =>
+                return Err(KeystoreError::InvalidConfig(format!("no profile named '{}'", name))),
+            None => Profile::default(),
+        };
+        values.merge(Profile::from_env()?);
+
+        Self::resolve(root_dir, name, values)
+    }
+
+    /// Fill the values which are not set with the defaults.
+    /// Relative key paths are taken relative to the root directory.
+    pub fn resolve(root_dir: &Path, profile: String, values: Profile) -> Result<Self> {
+        let key_path = |path: Option<PathBuf>, legacy: &str| match path {
+            Some(p) => root_dir.join(p),
+            None => default_key_path(root_dir, legacy),
+        };
+
+        Ok(Settings {
+            profile,
+            url: values.url.unwrap_or_else(|| DEFAULT_URL.to_string()),
+            keys: KeyPaths {
+                conf_pk: key_path(values.conf_pk_path, CONF_PK_PATH),
+                conf_vk: key_path(values.conf_vk_path, CONF_VK_PATH),
+                anony_pk: key_path(values.anony_pk_path, ANONY_PK_PATH),
+                anony_vk: key_path(values.anony_vk_path, ANONY_VK_PATH),
+            },
+            default_account: values.default_account,
+            decryption_range: values.decryption_range.unwrap_or(DEFAULT_DECRYPTION_RANGE),
+            output: values.output.as_ref().map(|o| parse_output(o)).transpose()?,
+        })
+    }
+
+    /// The settings with the node at `url` instead, if one is given on the command line.
+    pub fn with_url(&self, url: Option<&str>) -> Self {
+        let mut settings = self.clone();
+        if let Some(url) = url {
+            settings.url = url.to_string();
+        }
+
+        settings
+    }
+
+    pub fn node_url(&self) -> Url {
+        Url::Custom(self.url.clone())
+    }
+
+    pub fn default_account(&self) -> Option<&str> {
+        self.default_account.as_ref().map(|a| a.as_str())
+    }
+
+    /// The resolved value of `key`, as printed by `zface config get`.
+    pub fn get(&self, key: &str) -> Result<Option<String>> {
+        let path = |p: &PathBuf| Some(p.display().to_string());
+
+        Ok(match key {
+            "url" => Some(self.url.clone()),
+            "conf_pk_path" => path(&self.keys.conf_pk),
+            "conf_vk_path" => path(&self.keys.conf_vk),
+            "anony_pk_path" => path(&self.keys.anony_pk),
+            "anony_vk_path" => path(&self.keys.anony_vk),
+            "default_account" => self.default_account.clone(),
+            "decryption_range" => Some(self.decryption_range.to_string()),
+            "output" => Some(match self.output {
+                Some(OutputFormat::Json) => "json",
+                _ => "text",
+            }.to_string()),
+            _ => return Err(unknown_key(key)),
+        })
+    }
+}
+
+/// The keys live in the `params` directory of the root directory. Keys set up by an older
+/// zface in the `zface/params` directory of the repository are still found there.
+fn default_key_path(root_dir: &Path, legacy: &str) -> PathBuf {
+    let legacy = PathBuf::from(legacy);
+    let path = root_dir.join(PARAMS_DIR).join(legacy.file_name().expect("key paths end with a file name; qed"));
+
+    if !path.exists() && legacy.exists() {
+        legacy
+    } else {
+        path
+    }
+}
+
+fn parse_output(value: &str) -> Result<OutputFormat> {
+    match value {
+        "text" => Ok(OutputFormat::Text),
+        "json" => Ok(OutputFormat::Json),
+        _ => Err(KeystoreError::InvalidConfig(format!("output should be 'text' or 'json', not '{}'", value))),
+    }
+}
+
+fn unknown_key(key: &str) -> KeystoreError {
+    KeystoreError::InvalidConfig(format!("unknown key '{}', expected one of {}", key, KEYS.join(", ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_get_set() {
+        let mut profile = Profile::default();
+        profile.set("url", "ws://example.com:9944").unwrap();
+        profile.set("decryption_range", "2000").unwrap();
+
+        assert_eq!(profile.get("url").unwrap(), Some("ws://example.com:9944".to_string()));
+        assert_eq!(profile.get("decryption_range").unwrap(), Some("2000".to_string()));
+        assert_eq!(profile.get("default_account").unwrap(), None);
+
+        assert_matches!(profile.set("decryption_range", "many"), Err(KeystoreError::InvalidConfig(_)));
+        assert_matches!(profile.set("output", "xml"), Err(KeystoreError::InvalidConfig(_)));
+        assert_matches!(profile.get("colour"), Err(KeystoreError::InvalidConfig(_)));
+
+        profile.unset("url").unwrap();
+        assert_eq!(profile.url, None);
+    }
+
+    #[test]
+    fn test_store() {
+        let mut dir = env::temp_dir();
+        dir.push("zface_config_store");
+        fs::create_dir_all(&dir).unwrap();
+        let store = ConfigStore::new(&dir);
+        let _ = fs::remove_file(&store.0);
+
+        assert_eq!(store.load().unwrap(), ConfigFile::default());
+
+        let mut config = ConfigFile::default();
+        config.profile = Some("testnet".to_string());
+        config.profiles.entry("testnet".to_string()).or_default().set("url", "ws://example.com:9944").unwrap();
+        store.save(&config).unwrap();
+
+        assert_eq!(store.load().unwrap(), config);
+        assert_eq!(store.load().unwrap().active_profile(), "testnet");
+    }
+
+    #[test]
+    fn test_resolve() {
+        let root_dir = PathBuf::from("/zface");
+        let mut values = Profile::default();
+        values.set("conf_pk_path", "keys/conf_pk.dat").unwrap();
+        values.set("anony_pk_path", "/keys/anony_pk.dat").unwrap();
+        values.merge(Profile { decryption_range: Some(10), ..Default::default() });
+
+        let settings = Settings::resolve(&root_dir, DEFAULT_PROFILE.to_string(), values).unwrap();
+
+        assert_eq!(settings.url, DEFAULT_URL);
+        assert_eq!(settings.keys.conf_pk, PathBuf::from("/zface/keys/conf_pk.dat"));
+        assert_eq!(settings.keys.anony_pk, PathBuf::from("/keys/anony_pk.dat"));
+        assert_eq!(settings.decryption_range, 10);
+        assert_eq!(settings.output, None);
+    }
+}
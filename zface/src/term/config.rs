@@ -5,10 +5,20 @@ pub enum ColorChoice {
     Never,
 }
 
+/// Format of the results of the commands.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Free-form text for humans.
+    Text,
+    /// A single JSON document on the standard output, for scripts.
+    Json,
+}
+
 /// Configuration for the output options
 pub struct Config {
     pub color: ColorChoice,
     pub quiet: bool,
+    pub output: OutputFormat,
 }
 
 impl Default for Config {
@@ -16,6 +26,7 @@ impl Default for Config {
         Config {
             color: ColorChoice::Auto,
             quiet: false,
+            output: OutputFormat::Text,
         }
     }
 }
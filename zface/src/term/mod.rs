@@ -1,5 +1,7 @@
 use console;
 use dialoguer;
+use serde::Serialize;
+use serde_json::json;
 use std::{
     error::Error,
     io::{self, Write},
@@ -8,9 +10,20 @@ use std::{
 
 mod config;
 mod style;
-pub use self::config::{ColorChoice, Config};
+pub use self::config::{ColorChoice, Config, OutputFormat};
 pub use self::style::Style;
 
+/// An error with a stable identifier and exit code, so that scripts can tell errors apart.
+pub trait ErrorCode: Error {
+    /// Identifier of the kind of the error, e.g. `invalid_password`.
+    fn code(&self) -> &'static str;
+
+    /// Exit code of the process failing with the error.
+    fn exit_code(&self) -> i32 {
+        1
+    }
+}
+
 pub struct Term {
     pub config: Config,
     pub style: Style,
@@ -23,7 +36,12 @@ impl Term {
             warn!("There might be issue with non user attended terminal.")
         }
 
-        let term = console::Term::stdout();
+        // With JSON output the standard output only carries the result,
+        // so prompts and progress messages go to the standard error.
+        let term = match config.output {
+            OutputFormat::Text => console::Term::stdout(),
+            OutputFormat::Json => console::Term::stderr(),
+        };
         let style = Style::new(&config.color);
 
         Term {
@@ -65,6 +83,14 @@ impl Term {
             .interact()
     }
 
+    /// Ask a yes or no question, which defaults to no.
+    pub fn confirm(&mut self, prompt: &str) -> io::Result<bool> {
+        dialoguer::Confirmation::new()
+            .with_text(prompt)
+            .default(false)
+            .interact()
+    }
+
     pub fn simply(&mut self, msg: &str) -> io::Result<()> {
         write!(self, "{}", msg)
     }
@@ -84,10 +110,50 @@ impl Term {
         write!(&mut self.term, "{}", self.style.error.apply_to(msg))
     }
 
+    pub fn is_json(&self) -> bool {
+        self.config.output == OutputFormat::Json
+    }
+
+    /// Report the result of a command: as a JSON document on the standard output in JSON mode,
+    /// or with `text` otherwise.
+    pub fn report<T, F>(&mut self, result: &T, text: F) -> io::Result<()>
+    where
+        T: Serialize,
+        F: FnOnce(&mut Term) -> io::Result<()>,
+    {
+        match self.config.output {
+            OutputFormat::Text => text(self),
+            OutputFormat::Json => print_json(&json!({
+                "status": "ok",
+                "result": result,
+            })),
+        }
+    }
+
     pub fn fail_with<E>(&mut self, e: E) -> !
     where
-        E: Error,
+        E: ErrorCode,
     {
+        if self.is_json() {
+            let mut causes = vec![];
+            let mut error: &dyn Error = &e;
+            while let Some(err) = error.source() {
+                causes.push(format!("{}", err));
+                error = err;
+            }
+
+            print_json(&json!({
+                "status": "error",
+                "error": {
+                    "code": e.code(),
+                    "message": format!("{}", e),
+                    "causes": causes,
+                },
+            })).unwrap();
+
+            ::std::process::exit(e.exit_code())
+        }
+
         let mut error: &dyn Error = &e;
         let formated = format!("{}", e);
         writeln!(&mut self.term, "{}", self.style.error.apply_to(formated)).unwrap();
@@ -102,10 +168,17 @@ impl Term {
             ).unwrap();
         }
 
-        ::std::process::exit(1)
+        ::std::process::exit(e.exit_code())
     }
 }
 
+fn print_json(value: &serde_json::Value) -> io::Result<()> {
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    serde_json::to_writer(&mut stdout, value)?;
+    writeln!(stdout)
+}
+
 impl io::Write for Term {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
         io::Write::write(&mut self.term, buf)
@@ -8,51 +8,70 @@ use proofs::{
 };
 use pairing::bls12_381::Bls12;
 use parity_codec::Decode;
-use polkadot_rs::{Api, Url, hexstr_to_vec};
+use polkadot_rs::{Api, hexstr_to_vec};
 use scrypto::jubjub::{fs::Fs, FixedGenerators};
-use super::constants::*;
+use super::planner::{self, Plan, Source};
 use crate::{
     error::Result,
     term::Term,
+    settings::Settings,
     wallet::{
         DirOperations,
-        commands::{wallet_keystore_dirs, get_default_keyfile_name}
+        commands::{wallet_keystore_dirs, get_keyfile_name}
     },
     getter,
+    utils::decoy::Distribution,
 };
 
+/// The result of a submitted transaction.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TxReport {
+    pub call: &'static str,
+    pub amount: u32,
+    pub fee: u32,
+    pub asset_id: Option<u32>,
+    /// Balance of the sender after a transfer
+    pub remaining_balance: Option<u32>,
+}
+
 pub fn asset_issue_tx<R: Rng>(
     term: &mut Term,
     root_dir: PathBuf,
     amount: u32,
-    url: Url,
+    settings: &Settings,
     rng: &mut R,
 ) -> Result<()> {
     // user can enter password first.
     let password = prompt_password(term)?;
-    println!("Preparing paramters...");
+    term.simply("Preparing paramters...\n")?;
 
-    let api = Api::init(url);
+    let api = Api::init(settings.node_url());
     let p_g = FixedGenerators::NoteCommitmentRandomness; // 1
 
-    let spending_key = spending_key_from_keystore(root_dir, &password[..])?;
-    let issuer_address = EncryptionKey::<Bls12>::from_spending_key(&spending_key, &PARAMS)?;
+    let spending_key = spending_key_from_keystore(root_dir, settings.default_account(), &password[..])?;
+    let dec_key = ProofGenerationKey::<Bls12>::from_spending_key(&spending_key, &PARAMS)
+        .into_decryption_key()?;
+    let issuer_address = EncryptionKey::<Bls12>::from_decryption_key(&dec_key, &PARAMS);
 
     let enc_amount = vec![elgamal::Ciphertext::encrypt(amount, &Fs::rand(rng), &issuer_address, p_g, &PARAMS)];
     let multi_keys = MultiEncKeys::<Bls12, Confidential>::new(issuer_address.clone());
 
-    println!("Computing zk proof...");
-    subscribe_event(api.clone(), amount);
+    term.simply("Computing zk proof...\n")?;
+    subscribe_event(term, api.clone(), amount);
 
-    println!("Start submitting a transaction to Zerochain...");
-    KeyContext::read_from_path(CONF_PK_PATH, CONF_VK_PATH)?
+    let (g_epoch, nonce_counter) = getter::g_epoch(&api, &dec_key)?;
+
+    term.simply("Start submitting a transaction to Zerochain...\n")?;
+    KeyContext::read_from_path(&settings.keys.conf_pk, &settings.keys.conf_vk)?
         .gen_proof(
             amount,
             0,0,0,0,
             &spending_key,
             multi_keys,
             &enc_amount,
-            getter::g_epoch(&api)?,
+            g_epoch,
+            nonce_counter,
             rng,
             &PARAMS
         )?
@@ -60,7 +79,9 @@ pub fn asset_issue_tx<R: Rng>(
             Calls::AssetIssue,
             &api,
             rng
-        );
+        )?;
+
+    term.report(&TxReport { call: "asset-issue", amount, fee: 0, asset_id: None, remaining_balance: None }, |_| Ok(()))?;
 
     Ok(())
 }
@@ -71,37 +92,35 @@ pub fn asset_transfer_tx<R: Rng>(
     recipient_enc_key: &[u8],
     amount: u32,
     asset_id: u32,
-    url: Url,
+    settings: &Settings,
     rng: &mut R,
 ) -> Result<()> {
     // user can enter password first.
     let password = prompt_password(term)?;
 
-    println!("Preparing paramters...");
+    term.simply("Preparing paramters...\n")?;
 
-    let api = Api::init(url);
-    let spending_key = spending_key_from_keystore(root_dir, &password[..])?;
+    let api = Api::init(settings.node_url());
+    let spending_key = spending_key_from_keystore(root_dir, settings.default_account(), &password[..])?;
     let dec_key = ProofGenerationKey::<Bls12>::from_spending_key(&spending_key, &PARAMS)
         .into_decryption_key()?;
-    let fee = getter::fee(&api)?;
-
-    let balance_query = getter::BalanceQuery::get_encrypted_asset(asset_id, &dec_key, api.clone())?;
-    let remaining_balance = balance_query.decrypted_balance - amount - fee;
-    assert!(balance_query.decrypted_balance >= amount + fee, "Not enough balance you have");
+    let Plan { balance_query, fee, remaining_balance, .. } = planner::plan_transfer(term, &api, &dec_key, Source::Asset(asset_id), amount, settings.decryption_range)?;
 
     let recipient_account_id = EncryptionKey::<Bls12>::read(&mut &recipient_enc_key[..], &PARAMS)?;
     let enc_balance = vec![elgamal::Ciphertext::read(&mut &balance_query.encrypted_balance[..], &*PARAMS)?];
     let multi_keys = MultiEncKeys::<Bls12, Confidential>::new(recipient_account_id.clone());
 
-    println!("Computing zk proof...");
+    term.simply("Computing zk proof...\n")?;
     if recipient_account_id == EncryptionKey::from_decryption_key(&dec_key, &*PARAMS) {
-        subscribe_event(api.clone(), remaining_balance + amount);
+        subscribe_event(term, api.clone(), remaining_balance + amount);
     } else {
-        subscribe_event(api.clone(), remaining_balance);
+        subscribe_event(term, api.clone(), remaining_balance);
     }
 
-    println!("Start submitting a transaction to Zerochain...");
-    KeyContext::read_from_path(CONF_PK_PATH, CONF_VK_PATH)?
+    let (g_epoch, nonce_counter) = getter::g_epoch(&api, &dec_key)?;
+
+    term.simply("Start submitting a transaction to Zerochain...\n")?;
+    KeyContext::read_from_path(&settings.keys.conf_pk, &settings.keys.conf_vk)?
         .gen_proof(
             amount,
             fee,
@@ -111,7 +130,8 @@ pub fn asset_transfer_tx<R: Rng>(
             &spending_key,
             multi_keys,
             &enc_balance,
-            getter::g_epoch(&api)?,
+            g_epoch,
+            nonce_counter,
             rng,
             &PARAMS
         )?
@@ -119,7 +139,9 @@ pub fn asset_transfer_tx<R: Rng>(
             Calls::AssetTransfer(asset_id),
             &api,
             rng
-        );
+        )?;
+
+    term.report(&TxReport { call: "asset-send", amount, fee, asset_id: Some(asset_id), remaining_balance: Some(remaining_balance) }, |_| Ok(()))?;
 
     Ok(())
 }
@@ -128,21 +150,21 @@ pub fn asset_burn_tx<R: Rng>(
     term: &mut Term,
     root_dir: PathBuf,
     asset_id: u32,
-    url: Url,
+    settings: &Settings,
     rng: &mut R,
 ) -> Result<()> {
     // user can enter password first.
-    let password = prompt_password(term).expect("Invalid password");
-    println!("Preparing paramters...");
+    let password = prompt_password(term)?;
+    term.simply("Preparing paramters...\n")?;
 
-    let api = Api::init(url);
+    let api = Api::init(settings.node_url());
     let p_g = FixedGenerators::NoteCommitmentRandomness; // 1
 
     // Validate the asset balance
-    let spending_key = spending_key_from_keystore(root_dir, &password[..])?;
+    let spending_key = spending_key_from_keystore(root_dir, settings.default_account(), &password[..])?;
     let dec_key = ProofGenerationKey::<Bls12>::from_spending_key(&spending_key, &PARAMS)
         .into_decryption_key()?;
-    let balance_query = getter::BalanceQuery::get_encrypted_asset(asset_id, &dec_key, api.clone())?;
+    let balance_query = getter::BalanceQuery::get_encrypted_asset(asset_id, &dec_key, api.clone(), settings.decryption_range)?;
     assert!(balance_query.decrypted_balance != 0, "You don't have the asset. Asset id may be incorrect.");
 
     let amount = 0;
@@ -150,18 +172,21 @@ pub fn asset_burn_tx<R: Rng>(
     let enc_amount =  vec![elgamal::Ciphertext::encrypt(amount, &Fs::rand(rng), &issuer_address, p_g, &PARAMS)];
     let multi_keys = MultiEncKeys::<Bls12, Confidential>::new(issuer_address);
 
-    println!("Computing zk proof...");
-    subscribe_event(api.clone(), amount);
+    term.simply("Computing zk proof...\n")?;
+    subscribe_event(term, api.clone(), amount);
+
+    let (g_epoch, nonce_counter) = getter::g_epoch(&api, &dec_key)?;
 
-    println!("Start submitting a transaction to Zerochain...");
-    KeyContext::read_from_path(CONF_PK_PATH, CONF_VK_PATH)?
+    term.simply("Start submitting a transaction to Zerochain...\n")?;
+    KeyContext::read_from_path(&settings.keys.conf_pk, &settings.keys.conf_vk)?
         .gen_proof(
             amount,
             0, 0, 0, 0,
             &spending_key,
             multi_keys,
             &enc_amount,
-            getter::g_epoch(&api)?,
+            g_epoch,
+            nonce_counter,
             rng,
             &PARAMS
         )?
@@ -169,7 +194,9 @@ pub fn asset_burn_tx<R: Rng>(
             Calls::AssetBurn(asset_id),
             &api,
             rng
-        );
+        )?;
+
+    term.report(&TxReport { call: "asset-burn", amount, fee: 0, asset_id: Some(asset_id), remaining_balance: None }, |_| Ok(()))?;
 
     Ok(())
 }
@@ -179,14 +206,14 @@ pub fn confidential_transfer_tx<R: Rng>(
     root_dir: PathBuf,
     recipient_enc_key: &[u8],
     amount: u32,
-    url: Url,
+    settings: &Settings,
     rng: &mut R,
 ) -> Result<()> {
     // user can enter password first.
     let password = prompt_password(term)?;
-    let spending_key = spending_key_from_keystore(root_dir, &password[..])?;
+    let spending_key = spending_key_from_keystore(root_dir, settings.default_account(), &password[..])?;
 
-    inner_confidential_transfer_tx(spending_key, recipient_enc_key, amount, url, rng)?;
+    inner_confidential_transfer_tx(term, spending_key, recipient_enc_key, amount, settings, rng)?;
 
     Ok(())
 }
@@ -195,34 +222,39 @@ pub fn annonymous_issue_tx<R: Rng>(
     term: &mut Term,
     root_dir: PathBuf,
     amount: u32,
-    url: Url,
+    settings: &Settings,
     rng: &mut R,
 ) -> Result<()> {
     // user can enter password first.
     let password = prompt_password(term)?;
-    println!("Preparing paramters...");
+    term.simply("Preparing paramters...\n")?;
 
-    let api = Api::init(url);
+    let api = Api::init(settings.node_url());
     let p_g = FixedGenerators::NoteCommitmentRandomness; // 1
 
-    let spending_key = spending_key_from_keystore(root_dir, &password[..])?;
-    let issuer_address = EncryptionKey::<Bls12>::from_spending_key(&spending_key, &PARAMS)?;
+    let spending_key = spending_key_from_keystore(root_dir, settings.default_account(), &password[..])?;
+    let dec_key = ProofGenerationKey::<Bls12>::from_spending_key(&spending_key, &PARAMS)
+        .into_decryption_key()?;
+    let issuer_address = EncryptionKey::<Bls12>::from_decryption_key(&dec_key, &PARAMS);
 
     let enc_amount = vec![elgamal::Ciphertext::encrypt(amount, &Fs::rand(rng), &issuer_address, p_g, &PARAMS)];
     let multi_keys = MultiEncKeys::<Bls12, Confidential>::new(issuer_address.clone());
 
-    println!("Computing zk proof...");
-    subscribe_event(api.clone(), amount);
+    term.simply("Computing zk proof...\n")?;
+    subscribe_event(term, api.clone(), amount);
+
+    let (g_epoch, nonce_counter) = getter::g_epoch(&api, &dec_key)?;
 
-    println!("Start submitting a transaction to Zerochain...");
-    KeyContext::read_from_path(CONF_PK_PATH, CONF_VK_PATH)?
+    term.simply("Start submitting a transaction to Zerochain...\n")?;
+    KeyContext::read_from_path(&settings.keys.conf_pk, &settings.keys.conf_vk)?
         .gen_proof(
             amount,
             0,0,0,0,
             &spending_key,
             multi_keys,
             &enc_amount,
-            getter::g_epoch(&api)?,
+            g_epoch,
+            nonce_counter,
             rng,
             &PARAMS
         )?
@@ -230,7 +262,9 @@ pub fn annonymous_issue_tx<R: Rng>(
             Calls::AnonymousIssue,
             &api,
             rng
-        );
+        )?;
+
+    term.report(&TxReport { call: "anonymous-issue", amount, fee: 0, asset_id: None, remaining_balance: None }, |_| Ok(()))?;
 
     Ok(())
 }
@@ -240,75 +274,78 @@ pub fn anonymous_transfer_tx<R: Rng>(
     root_dir: PathBuf,
     recipient_enc_key: &[u8],
     amount: u32,
-    url: Url,
+    distribution: Distribution,
+    settings: &Settings,
     rng: &mut R,
 ) -> Result<()> {
     // user can enter password first.
     let password = prompt_password(term)?;
-    let spending_key = spending_key_from_keystore(root_dir, &password[..])?;
+    let spending_key = spending_key_from_keystore(root_dir, settings.default_account(), &password[..])?;
 
-    inner_anonymous_transfer_tx(spending_key, recipient_enc_key, amount, url, rng)?;
+    inner_anonymous_transfer_tx(term, spending_key, recipient_enc_key, amount, distribution, settings, rng)?;
 
     Ok(())
 }
 
 pub fn transfer_tx_for_debug<R: Rng>(
+    term: &mut Term,
     seed: &[u8],
     recipient_enc_key: &[u8],
     amount: u32,
-    url: Url,
+    settings: &Settings,
     rng: &mut R,
 ) -> Result<()> {
     let spending_key = SpendingKey::from_seed(seed);
-    inner_confidential_transfer_tx(spending_key, recipient_enc_key, amount, url, rng)?;
+    inner_confidential_transfer_tx(term, spending_key, recipient_enc_key, amount, settings, rng)?;
 
     Ok(())
 }
 
 pub fn anonymous_transfer_tx_for_debug<R: Rng>(
+    term: &mut Term,
     seed: &[u8],
     recipient_enc_key: &[u8],
     amount: u32,
-    url: Url,
+    distribution: Distribution,
+    settings: &Settings,
     rng: &mut R,
 ) -> Result<()> {
     let spending_key = SpendingKey::from_seed(seed);
-    inner_anonymous_transfer_tx(spending_key, recipient_enc_key, amount, url, rng)?;
+    inner_anonymous_transfer_tx(term, spending_key, recipient_enc_key, amount, distribution, settings, rng)?;
 
     Ok(())
 }
 
 fn inner_confidential_transfer_tx<R: Rng>(
+    term: &mut Term,
     spending_key: SpendingKey::<Bls12>,
     recipient_enc_key: &[u8],
     amount: u32,
-    url: Url,
+    settings: &Settings,
     rng: &mut R
 ) -> Result<()> {
-    println!("Preparing paramters...");
+    term.simply("Preparing paramters...\n")?;
 
-    let api = Api::init(url);
+    let api = Api::init(settings.node_url());
     let dec_key = ProofGenerationKey::<Bls12>::from_spending_key(&spending_key, &PARAMS)
         .into_decryption_key()?;
-    let fee = getter::fee(&api)?;
-
-    let balance_query = getter::BalanceQuery::get_encrypted_balance(&dec_key, api.clone())?;
-    let remaining_balance = balance_query.decrypted_balance - amount - fee;
-    assert!(balance_query.decrypted_balance >= amount + fee, "Not enough balance you have");
+    let Plan { balance_query, fee, remaining_balance, .. } = planner::plan_transfer(term, &api, &dec_key, Source::Balance, amount, settings.decryption_range)?;
 
     let recipient_account_id = EncryptionKey::<Bls12>::read(&mut &recipient_enc_key[..], &PARAMS)?;
     let multi_keys = MultiEncKeys::<Bls12, Confidential>::new(recipient_account_id.clone());
     let enc_balance = vec![elgamal::Ciphertext::read(&mut &balance_query.encrypted_balance[..], &*PARAMS)?];
 
-    println!("Computing zk proof...");
+    term.simply("Computing zk proof...\n")?;
     if recipient_account_id == EncryptionKey::from_decryption_key(&dec_key, &*PARAMS) {
-        subscribe_event(api.clone(), remaining_balance + amount);
+        subscribe_event(term, api.clone(), remaining_balance + amount);
     } else {
-        subscribe_event(api.clone(), remaining_balance);
+        subscribe_event(term, api.clone(), remaining_balance);
     }
 
-    println!("Start submitting a transaction to Zerochain...");
-    KeyContext::read_from_path(CONF_PK_PATH, CONF_VK_PATH)?
+    let (g_epoch, nonce_counter) = getter::g_epoch(&api, &dec_key)?;
+
+    term.simply("Start submitting a transaction to Zerochain...\n")?;
+    KeyContext::read_from_path(&settings.keys.conf_pk, &settings.keys.conf_vk)?
         .gen_proof(
             amount,
             fee,
@@ -318,7 +355,8 @@ fn inner_confidential_transfer_tx<R: Rng>(
             &spending_key,
             multi_keys,
             &enc_balance,
-            getter::g_epoch(&api)?,
+            g_epoch,
+            nonce_counter,
             rng,
             &PARAMS
         )?
@@ -326,66 +364,53 @@ fn inner_confidential_transfer_tx<R: Rng>(
             Calls::BalanceTransfer,
             &api,
             rng
-        );
+        )?;
+
+    term.report(&TxReport { call: "send", amount, fee, asset_id: None, remaining_balance: Some(remaining_balance) }, |_| Ok(()))?;
 
     Ok(())
 }
 
 fn inner_anonymous_transfer_tx<R: Rng>(
+    term: &mut Term,
     spending_key: SpendingKey::<Bls12>,
     recipient_enc_key: &[u8],
     amount: u32,
-    url: Url,
+    distribution: Distribution,
+    settings: &Settings,
     rng: &mut R
 ) -> Result<()> {
-    println!("Preparing paramters...");
+    term.simply("Preparing paramters...\n")?;
 
-    let api = Api::init(url);
+    let api = Api::init(settings.node_url());
     let dec_key = ProofGenerationKey::<Bls12>::from_spending_key(&spending_key, &PARAMS)
         .into_decryption_key()?;
     let enc_key_sender = EncryptionKey::<Bls12>::from_decryption_key(&dec_key, &PARAMS);
 
-    let balance_query = getter::BalanceQuery::get_anonymous_balance(&dec_key, api.clone())?;
-    let remaining_balance = balance_query.decrypted_balance - amount;
-    assert!(balance_query.decrypted_balance >= amount, "Not enough balance you have");
+    let remaining_balance = planner::plan_transfer(term, &api, &dec_key, Source::Anonymous, amount, settings.decryption_range)?.remaining_balance;
 
-    let s_index: usize = rng.gen_range(0, DECOY_SIZE-1);
-    let mut t_index: usize;
-    loop {
-        t_index = rng.gen_range(0, DECOY_SIZE);
-        if t_index != s_index {
-            break;
-        }
-    }
+    let (s_index, t_index) = party_indices(rng);
 
     let recipient_account_id = EncryptionKey::<Bls12>::read(&mut &recipient_enc_key[..], &PARAMS)?;
-    let decoys = getter::get_enc_keys(&api, rng)?;
+    let parties = [enc_key_sender.clone(), recipient_account_id.clone()];
+    let decoys = getter::get_enc_keys(&api, distribution, &parties[..], rng)?;
     assert_eq!(decoys.len(), DECOY_SIZE);
     let multi_keys = MultiEncKeys::<Bls12, Anonymous>::new(recipient_account_id.clone(), decoys.clone());
 
-    let mut enc_keys = vec![];
-    let mut j = 0;
-    for i in 0..ANONIMITY_SIZE {
-        if i == s_index {
-            enc_keys.push(enc_key_sender.clone());
-        } else if i == t_index {
-            enc_keys.push(recipient_account_id.clone());
-        } else {
-            enc_keys.push(decoys[j].clone());
-            j += 1;
-        }
-    }
+    let enc_keys = anonymity_set(&enc_key_sender, &recipient_account_id, &decoys[..], s_index, t_index);
     let enc_balances = getter::get_enc_balances(&api, &enc_keys[..])?;
 
-    println!("Computing zk proof...");
+    term.simply("Computing zk proof...\n")?;
     if recipient_account_id == EncryptionKey::from_decryption_key(&dec_key, &*PARAMS) {
-        subscribe_event(api.clone(), remaining_balance + amount);
+        subscribe_event(term, api.clone(), remaining_balance + amount);
     } else {
-        subscribe_event(api.clone(), remaining_balance);
+        subscribe_event(term, api.clone(), remaining_balance);
     }
 
-    println!("Start submitting a transaction to Zerochain...");
-    KeyContext::read_from_path(ANONY_PK_PATH, ANONY_VK_PATH)?
+    let (g_epoch, nonce_counter) = getter::g_epoch(&api, &dec_key)?;
+
+    term.simply("Start submitting a transaction to Zerochain...\n")?;
+    KeyContext::read_from_path(&settings.keys.anony_pk, &settings.keys.anony_vk)?
         .gen_proof(
             amount,
             0,
@@ -395,7 +420,8 @@ fn inner_anonymous_transfer_tx<R: Rng>(
             &spending_key,
             multi_keys,
             &enc_balances[..],
-            getter::g_epoch(&api)?,
+            g_epoch,
+            nonce_counter,
             rng,
             &PARAMS
         )?
@@ -403,20 +429,64 @@ fn inner_anonymous_transfer_tx<R: Rng>(
             Calls::AnonymousTransfer,
             &api,
             rng
-        );
+        )?;
+
+    term.report(&TxReport { call: "anonymous-send", amount, fee: 0, asset_id: None, remaining_balance: Some(remaining_balance) }, |_| Ok(()))?;
 
     Ok(())
 }
 
+/// Pick the positions of the sender and the recipient in the anonymity set.
+/// Both are uniform over the whole set and distinct, so that neither position leaks a party.
+pub(super) fn party_indices<R: Rng>(rng: &mut R) -> (usize, usize) {
+    let s_index: usize = rng.gen_range(0, ANONIMITY_SIZE);
+    let mut t_index: usize;
+    loop {
+        t_index = rng.gen_range(0, ANONIMITY_SIZE);
+        if t_index != s_index {
+            break;
+        }
+    }
+
+    (s_index, t_index)
+}
+
+/// The encryption keys of an anonymous transfer in the order the proof takes them:
+/// the decoys, with the sender at `s_index` and the recipient at `t_index`.
+pub(super) fn anonymity_set(
+    sender: &EncryptionKey<Bls12>,
+    recipient: &EncryptionKey<Bls12>,
+    decoys: &[EncryptionKey<Bls12>],
+    s_index: usize,
+    t_index: usize,
+) -> Vec<EncryptionKey<Bls12>> {
+    let mut enc_keys = vec![];
+    let mut j = 0;
+    for i in 0..ANONIMITY_SIZE {
+        if i == s_index {
+            enc_keys.push(sender.clone());
+        } else if i == t_index {
+            enc_keys.push(recipient.clone());
+        } else {
+            enc_keys.push(decoys[j].clone());
+            j += 1;
+        }
+    }
+
+    enc_keys
+}
+
+/// Get the spending key of the account, or of the default account if no name is given.
 pub fn spending_key_from_keystore(
     root_dir: PathBuf,
+    account_name: Option<&str>,
     password: &[u8],
 ) -> Result<SpendingKey<Bls12>>
 {
     let (wallet_dir, keystore_dir) = wallet_keystore_dirs(&root_dir)?;
 
-    let default_keyfile_name = get_default_keyfile_name(&wallet_dir)?;
-    let keyfile = keystore_dir.load(&default_keyfile_name)?;
+    let keyfile_name = get_keyfile_name(&wallet_dir, account_name)?;
+    let keyfile = keystore_dir.load(&keyfile_name)?;
 
     let sk = keyfile.get_current_spending_key(password)?;
 
@@ -430,11 +500,16 @@ pub fn prompt_password(term: &mut Term) -> Result<Vec<u8>> {
     Ok(password)
 }
 
-pub fn subscribe_event(api: Api, remaining_balance: u32) {
+/// Print the outcome of the submitted transaction once its event arrives.
+/// The messages go to the standard error in JSON mode, which keeps the standard output for the result.
+pub fn subscribe_event(term: &Term, api: Api, remaining_balance: u32) {
     use std::sync::mpsc::channel;
     use std::thread;
     use zerochain_runtime::Event;
 
+    let json = term.is_json();
+    let notify = move |msg: String| if json { eprintln!("{}", msg) } else { println!("{}", msg) };
+
     let (tx, rx) = channel();
     let _ = thread::Builder::new()
         .name("eventsubscriber".to_string())
@@ -461,37 +536,43 @@ pub fn subscribe_event(api: Api, remaining_balance: u32) {
                                             _enc_key_sender, _enc_key_recipient,
                                             _amount_sender, _amount_recipient,
                                             _fee_sender,  _randomness, _enc_balances, _sig_vk
-                                        ) => println!("Submitting transaction is completed successfully. \n Remaining balance is {}", remaining_balance),
-                                        encrypted_balances::RawEvent::InvalidZkProof() => {
-                                            println!("Invalid zk proof.");
+                                        ) => notify(format!("Submitting transaction is completed successfully. \n Remaining balance is {}", remaining_balance)),
+                                        encrypted_balances::RawEvent::TransactionFailed(_sig_vk, code) => {
+                                            print_failure(&notify, code, encrypted_balances::Error::from_code(*code).map(|e| e.as_str()));
                                         }
+                                        encrypted_balances::RawEvent::RolledOver(_enc_key, _enc_balance) => {},
                                     }
                                 },
                                 Event::encrypted_assets(enc_assets) => {
                                     match &enc_assets {
                                         encrypted_assets::RawEvent::Issued(
                                             asset_id, _address, _total
-                                        ) => println!("Submitting transaction is completed successfully. \nThe total issued coin is {}. \nThe asset id is {}.", remaining_balance, asset_id),
+                                        ) => notify(format!("Submitting transaction is completed successfully. \nThe total issued coin is {}. \nThe asset id is {}.", remaining_balance, asset_id)),
                                         encrypted_assets::RawEvent::ConfidentialAssetTransferred(
                                             asset_id, _zkproof,
                                             _enc_key_sender, _enc_key_recipient,
                                             _amount_sender, _amount_recipient,
                                             _fee_sender, _randomness, _enc_balances, _sig_vk
-                                        ) => println!("Submitting transaction is completed successfully. \nRemaining balance is {}. \nThe asset id is {}.", remaining_balance, asset_id),
+                                        ) => notify(format!("Submitting transaction is completed successfully. \nRemaining balance is {}. \nThe asset id is {}.", remaining_balance, asset_id)),
                                         encrypted_assets::RawEvent::Destroyed(asset_id, _address, _balance, _pending_transfer)
-                                            => println!("destroyed coins. \nThe asset id is {}.", asset_id),
-                                        encrypted_assets::RawEvent::InvalidZkProof() => println!("Invalid zk proof."),
+                                            => notify(format!("destroyed coins. \nThe asset id is {}.", asset_id)),
+                                        encrypted_assets::RawEvent::TransactionFailed(_sig_vk, code)
+                                            => print_failure(&notify, code, encrypted_assets::Error::from_code(*code).map(|e| e.as_str())),
+                                        encrypted_assets::RawEvent::RolledOver(_asset_id, _enc_key, _enc_balance) => {},
                                     }
                                 },
                                 Event::anonymous_balances(annoy_be) => {
                                     match &annoy_be {
                                         anonymous_balances::RawEvent::Issued(
                                             _enc_key_sender, _total
-                                        ) => println!("Submitting transaction is completed successfully. \nThe total issued coin is {}.", remaining_balance),
+                                        ) => notify(format!("Submitting transaction is completed successfully. \nThe total issued coin is {}.", remaining_balance)),
                                         anonymous_balances::RawEvent::AnonymousTransfer(
                                             _proof, _enc_keys, _left_ciphertexts, _right_ciphertext, _sig_vk,
-                                        ) => println!("Submitting transaction is completed successfully. \n Remaining balance is {}", remaining_balance),
-                                        anonymous_balances::RawEvent::InvalidZkProof() => println!("Invalid zk proof."),
+                                        ) => notify(format!("Submitting transaction is completed successfully. \n Remaining balance is {}", remaining_balance)),
+                                        anonymous_balances::RawEvent::TransactionFailed(_sig_vk, code)
+                                            => print_failure(&notify, code, anonymous_balances::Error::from_code(*code).map(|e| e.as_str())),
+                                        anonymous_balances::RawEvent::RolledOver(_enc_key, _enc_balance) => {},
+                                        anonymous_balances::RawEvent::Registered(_enc_key, _index) => {},
                                     }
                                 }
                                 _ => {},
@@ -503,3 +584,33 @@ pub fn subscribe_event(api: Api, remaining_balance: u32) {
             }
         });
 }
+
+fn print_failure(notify: &impl Fn(String), code: &u16, message: Option<&str>) {
+    match message {
+        Some(m) => notify(format!("Transaction failed (error {}): {}", code, m)),
+        None => notify(format!("Transaction failed with unknown error {}.", code)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{XorShiftRng, SeedableRng};
+
+    #[test]
+    fn test_party_indices_cover_the_set() {
+        let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let mut senders = vec![false; ANONIMITY_SIZE];
+        let mut recipients = vec![false; ANONIMITY_SIZE];
+
+        for _ in 0..1000 {
+            let (s_index, t_index) = party_indices(rng);
+            assert_ne!(s_index, t_index);
+            senders[s_index] = true;
+            recipients[t_index] = true;
+        }
+
+        assert!(senders.iter().all(|b| *b));
+        assert!(recipients.iter().all(|b| *b));
+    }
+}
@@ -1,4 +1,11 @@
+// Locations of the keys in the repository, where zface used to look them up.
+// They are still used if the `params` directory of the root directory has no keys.
 pub const CONF_PK_PATH: &'static str = "zface/params/conf_pk.dat";
 pub const CONF_VK_PATH: &'static str = "zface/params/conf_vk.dat";
 pub const ANONY_PK_PATH: &'static str = "zface/params/anony_pk.dat";
 pub const ANONY_VK_PATH: &'static str = "zface/params/anony_vk.dat";
+
+// Blocks a proof is expected to take from planning to inclusion.
+// A transaction is only planned if at least this many blocks are left in the epoch.
+pub const CONF_PROVING_BLOCKS: u64 = 2;
+pub const ANONY_PROVING_BLOCKS: u64 = 6;
@@ -1,4 +1,6 @@
 pub mod commands;
 pub mod constants;
+pub mod planner;
+pub mod offline;
 pub use self::commands::*;
 pub use self::constants::*;
@@ -0,0 +1,554 @@
+//! Transactions in three steps, so that the spending key can stay on a machine without a connection to a node.
+//!
+//! 1. `prepare_tx` fetches the public inputs of a transfer from a node into an unsigned transaction file.
+//! 2. `prove_sign_tx` computes the proof and signs the extrinsic offline, with the key in the keystore.
+//! 3. `broadcast_tx` submits the signed extrinsic to a node.
+//!
+//! Both files are JSON envelopes with a format version and a checksum of the payload,
+//! so a file damaged on its way between the machines is rejected instead of proven or submitted.
+
+use std::fs;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use rand::Rng;
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::Value;
+use pairing::bls12_381::Bls12;
+use parity_codec::{Encode, Decode};
+use primitives::{blake2_256, crypto::Ss58Codec};
+use polkadot_rs::Api;
+use proofs::{
+    EncryptionKey, ProofGenerationKey, PARAMS, elgamal,
+    crypto_components::{MultiEncKeys, Confidential, Anonymous},
+    crypto_components::{ProofBuilder, KeyContext, Calls, Submitter},
+    constants::{ANONIMITY_SIZE, DECOY_SIZE},
+};
+use zprimitives::Nonce;
+use zerochain_runtime::{Hash, UncheckedExtrinsic};
+use super::commands::{prompt_password, spending_key_from_keystore, party_indices, anonymity_set};
+use super::planner;
+use crate::{
+    error::{KeystoreError, Result},
+    term::Term,
+    settings::Settings,
+    ss58::EncryptionKeyBytes,
+    wallet::{
+        DirOperations,
+        commands::{wallet_keystore_dirs, get_keyfile_name}
+    },
+    getter,
+    utils::decoy::Distribution,
+};
+
+/// Version of the transaction file format, bumped on incompatible changes of the payloads.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// The transfer a transaction file is for.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum TxKind {
+    Transfer,
+    AssetTransfer(u32),
+    AnonymousTransfer,
+}
+
+impl TxKind {
+    fn call(&self) -> Calls {
+        match *self {
+            TxKind::Transfer => Calls::BalanceTransfer,
+            TxKind::AssetTransfer(asset_id) => Calls::AssetTransfer(asset_id),
+            TxKind::AnonymousTransfer => Calls::AnonymousTransfer,
+        }
+    }
+}
+
+/// The public inputs of a transfer, fetched online and proven against offline.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct UnsignedTx {
+    pub kind: TxKind,
+    pub amount: u32,
+    pub fee: u32,
+    /// SS58 address of the sender
+    pub sender: String,
+    /// SS58 address of the recipient
+    pub recipient: String,
+    /// SS58 addresses of the decoys of an anonymous transfer, in the order the proof takes them
+    pub decoys: Vec<String>,
+    /// Position of the sender in the anonymity set
+    pub s_index: u32,
+    /// Position of the recipient in the anonymity set
+    pub t_index: u32,
+    /// Hex-encoded encrypted balances the proof is checked against:
+    /// the sender's for a confidential transfer, and the whole anonymity set's for an anonymous one.
+    pub enc_balances: Vec<String>,
+    /// The epoch the transaction has to be included in
+    pub epoch: u64,
+    /// Hex-encoded nonces used in the epoch so far
+    pub nonce_pool: String,
+    pub genesis_hash: String,
+}
+
+/// A proven and signed extrinsic, ready to be submitted.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SignedTx {
+    pub kind: TxKind,
+    pub epoch: u64,
+    /// Hex-encoded `UncheckedExtrinsic`
+    pub extrinsic: String,
+}
+
+/// The result of a step, for the JSON output.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FileReport {
+    /// The transaction file written or read by the step
+    pub file: String,
+    pub kind: TxKind,
+    pub epoch: u64,
+    pub blocks_until_next_epoch: Option<u64>,
+    pub remaining_balance: Option<u32>,
+}
+
+/// A payload of a transaction file.
+pub trait TxFile: Serialize + DeserializeOwned {
+    /// Tells the payloads apart, so that a file is not passed to the wrong step.
+    const FILE_TYPE: &'static str;
+
+    fn write_to<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let file = fs::File::create(path)?;
+        serde_json::to_writer_pretty(file, &seal(self)?)?;
+
+        Ok(())
+    }
+
+    fn read_from<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = fs::File::open(path)?;
+        let envelope = serde_json::from_reader(BufReader::new(file))?;
+
+        open(envelope)
+    }
+}
+
+impl TxFile for UnsignedTx {
+    const FILE_TYPE: &'static str = "unsignedTx";
+}
+
+impl TxFile for SignedTx {
+    const FILE_TYPE: &'static str = "signedTx";
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct Envelope {
+    version: u32,
+    #[serde(rename = "type")]
+    file_type: String,
+    /// Hex-encoded blake2-256 hash of the JSON payload
+    checksum: String,
+    payload: Value,
+}
+
+fn checksum(payload: &Value) -> Result<String> {
+    Ok(hex::encode(blake2_256(&serde_json::to_vec(payload)?)))
+}
+
+fn seal<T: TxFile>(payload: &T) -> Result<Envelope> {
+    let payload = serde_json::to_value(payload)?;
+
+    Ok(Envelope {
+        version: FORMAT_VERSION,
+        file_type: T::FILE_TYPE.to_string(),
+        checksum: checksum(&payload)?,
+        payload,
+    })
+}
+
+/// Check the envelope and take the payload out of it.
+/// The version is checked first, because the payload of another version may not even parse.
+fn open<T: TxFile>(envelope: Envelope) -> Result<T> {
+    if envelope.version != FORMAT_VERSION {
+        return Err(KeystoreError::InvalidTxFile(
+            format!("version {} is not supported, expected {}", envelope.version, FORMAT_VERSION)
+        ));
+    }
+    if envelope.file_type != T::FILE_TYPE {
+        return Err(KeystoreError::InvalidTxFile(
+            format!("expected a {} file, but got a {} file", T::FILE_TYPE, envelope.file_type)
+        ));
+    }
+    if checksum(&envelope.payload)? != envelope.checksum {
+        return Err(KeystoreError::InvalidTxFile("checksum mismatch, the file is corrupted".to_string()));
+    }
+
+    Ok(serde_json::from_value(envelope.payload)?)
+}
+
+fn invalid(field: &str) -> KeystoreError {
+    KeystoreError::InvalidTxFile(format!("invalid {}", field))
+}
+
+fn hex_field(field: &str, value: &str) -> Result<Vec<u8>> {
+    hex::decode(value).map_err(|_| invalid(field))
+}
+
+fn ss58_of(enc_key: &EncryptionKey<Bls12>) -> Result<String> {
+    let mut bytes = [0u8; 32];
+    enc_key.write(&mut bytes[..])?;
+
+    Ok(EncryptionKeyBytes(bytes).to_ss58check())
+}
+
+fn enc_key_of(field: &str, address: &str) -> Result<EncryptionKey<Bls12>> {
+    let bytes = EncryptionKeyBytes::from_ss58check(address).map_err(|_| invalid(field))?;
+
+    Ok(EncryptionKey::<Bls12>::read(&mut &bytes.0[..], &PARAMS)?)
+}
+
+fn hex_ciphertext(ciphertext: &elgamal::Ciphertext<Bls12>) -> Result<String> {
+    let mut buf = vec![];
+    ciphertext.write(&mut buf)?;
+
+    Ok(hex::encode(buf))
+}
+
+/// Fetch the inputs of a transfer from the default account, and write them to an unsigned transaction file.
+/// Only the address of the account is read from the keystore, so no password is needed.
+pub fn prepare_tx<R: Rng>(
+    term: &mut Term,
+    root_dir: PathBuf,
+    kind: TxKind,
+    recipient_enc_key: &[u8],
+    amount: u32,
+    distribution: Distribution,
+    settings: &Settings,
+    output: &Path,
+    rng: &mut R,
+) -> Result<()> {
+    term.simply("Fetching the inputs of the transaction...\n")?;
+
+    let (wallet_dir, keystore_dir) = wallet_keystore_dirs(&root_dir)?;
+    let keyfile = keystore_dir.load(&get_keyfile_name(&wallet_dir, settings.default_account())?)?;
+    let sender = enc_key_of("sender", &keyfile.ss58_address)?;
+    let recipient = EncryptionKey::<Bls12>::read(&mut &recipient_enc_key[..], &PARAMS)?;
+
+    let api = Api::init(settings.node_url());
+    let epoch_length = getter::epoch_length(&api)?;
+    let height = getter::block_height(&api)?;
+    let genesis_hash = api.get_genesis_blockhash()
+        .map_err(|e| KeystoreError::RpcResponse(format!("failed to fetch the genesis block hash: {:?}", e)))?;
+
+    let mut tx = UnsignedTx {
+        kind,
+        amount,
+        fee: 0,
+        sender: keyfile.ss58_address.clone(),
+        recipient: ss58_of(&recipient)?,
+        decoys: vec![],
+        s_index: 0,
+        t_index: 0,
+        enc_balances: vec![],
+        epoch: height / epoch_length,
+        nonce_pool: hex::encode(getter::nonce_pool(&api)?.encode()),
+        genesis_hash: hex::encode(genesis_hash.encode()),
+    };
+
+    match kind {
+        TxKind::Transfer => {
+            tx.fee = getter::fee(&api)?;
+            tx.enc_balances.push(hex_ciphertext(&getter::get_enc_balance(&api, &sender, None)?)?);
+        },
+        TxKind::AssetTransfer(asset_id) => {
+            tx.fee = getter::fee(&api)?;
+            tx.enc_balances.push(hex_ciphertext(&getter::get_enc_balance(&api, &sender, Some(asset_id))?)?);
+        },
+        // Anonymous transfers do not charge a fee.
+        TxKind::AnonymousTransfer => {
+            let (s_index, t_index) = party_indices(rng);
+            let parties = [sender.clone(), recipient.clone()];
+            let decoys = getter::get_enc_keys(&api, distribution, &parties[..], rng)?;
+            let enc_keys = anonymity_set(&sender, &recipient, &decoys[..], s_index, t_index);
+
+            tx.decoys = decoys.iter().map(ss58_of).collect::<Result<_>>()?;
+            tx.s_index = s_index as u32;
+            tx.t_index = t_index as u32;
+            tx.enc_balances = getter::get_enc_balances(&api, &enc_keys[..])?
+                .iter()
+                .map(hex_ciphertext)
+                .collect::<Result<_>>()?;
+        },
+    }
+
+    tx.write_to(output)?;
+
+    let report = FileReport {
+        file: output.display().to_string(),
+        kind,
+        epoch: tx.epoch,
+        blocks_until_next_epoch: Some(planner::blocks_until_next_epoch(height, epoch_length)),
+        remaining_balance: None,
+    };
+    term.report(&report, |term| {
+        term.success(&format!("Wrote the unsigned transaction to {}\n", report.file))?;
+        term.simply(&format!(
+            "It has to be proven, signed and broadcast in epoch {}, within the next {} blocks.\n",
+            report.epoch,
+            planner::blocks_until_next_epoch(height, epoch_length)
+        ))
+    })?;
+
+    Ok(())
+}
+
+/// Check the positions of the parties in the anonymity set,
+/// which the proof needs to be distinct and within the set.
+fn check_party_indices(tx: &UnsignedTx) -> Result<()> {
+    if tx.kind != TxKind::AnonymousTransfer {
+        return Ok(());
+    }
+    if tx.s_index as usize >= ANONIMITY_SIZE {
+        return Err(invalid("sIndex"));
+    }
+    if tx.t_index as usize >= ANONIMITY_SIZE || tx.t_index == tx.s_index {
+        return Err(invalid("tIndex"));
+    }
+
+    Ok(())
+}
+
+/// Show what the transaction transfers, and ask whether to sign it unless `yes` is set.
+/// The file comes from another machine, so it is not signed blindly.
+fn confirm_tx(term: &mut Term, tx: &UnsignedTx, yes: bool) -> Result<()> {
+    let kind = match tx.kind {
+        TxKind::Transfer => "Confidential transfer".to_string(),
+        TxKind::AssetTransfer(asset_id) => format!("Transfer of asset {}", asset_id),
+        TxKind::AnonymousTransfer => "Anonymous transfer".to_string(),
+    };
+    term.info(&format!(
+        "{} of {} to {}, with a fee of {}, in epoch {}.\n",
+        kind, tx.amount, tx.recipient, tx.fee, tx.epoch
+    ))?;
+
+    if yes || term.confirm("Prove and sign this transaction?")? {
+        Ok(())
+    } else {
+        Err(KeystoreError::Cancelled)
+    }
+}
+
+/// Prove and sign an unsigned transaction with the spending key of the default account,
+/// after confirming its recipient, amount and fee unless `yes` is set.
+/// This does not connect to a node.
+pub fn prove_sign_tx<R: Rng>(
+    term: &mut Term,
+    root_dir: PathBuf,
+    settings: &Settings,
+    input: &Path,
+    output: &Path,
+    yes: bool,
+    rng: &mut R,
+) -> Result<()> {
+    let tx = UnsignedTx::read_from(input)?;
+    check_party_indices(&tx)?;
+    confirm_tx(term, &tx, yes)?;
+
+    let password = prompt_password(term)?;
+    let spending_key = spending_key_from_keystore(root_dir, settings.default_account(), &password[..])?;
+    let dec_key = ProofGenerationKey::<Bls12>::from_spending_key(&spending_key, &PARAMS)
+        .into_decryption_key()?;
+
+    if enc_key_of("sender", &tx.sender)? != EncryptionKey::from_decryption_key(&dec_key, &PARAMS) {
+        return Err(KeystoreError::InvalidTxFile("it was prepared for another account".to_string()));
+    }
+    let recipient = enc_key_of("recipient", &tx.recipient)?;
+
+    let s_index = tx.s_index as usize;
+    let t_index = tx.t_index as usize;
+    let enc_balances = tx.enc_balances.iter()
+        .map(|b| Ok(elgamal::Ciphertext::read(&mut &hex_field("encBalances", b)?[..], &*PARAMS)?))
+        .collect::<Result<Vec<_>>>()?;
+    let sender_balance = tx.enc_balances.get(s_index).ok_or_else(|| invalid("sIndex"))?;
+
+    let balance = getter::decrypt_balance(&hex_field("encBalances", sender_balance)?[..], &dec_key, settings.decryption_range)?
+        .ok_or_else(|| KeystoreError::InvalidTxFile("the balance does not decrypt with the key".to_string()))?;
+    let remaining_balance = planner::remaining_balance(balance, tx.amount, tx.fee)?;
+
+    let nonce_pool = Vec::<Nonce>::decode(&mut &hex_field("noncePool", &tx.nonce_pool)?[..])
+        .ok_or_else(|| invalid("noncePool"))?;
+    let (g_epoch, nonce_counter) = getter::g_epoch_from_pool(tx.epoch, &nonce_pool[..], &dec_key)?;
+    let genesis_hash = Hash::decode(&mut &hex_field("genesisHash", &tx.genesis_hash)?[..])
+        .ok_or_else(|| invalid("genesisHash"))?;
+
+    // The signing key is re-randomized for every proof,
+    // so its account has never sent a transaction and its nonce is zero.
+    let index = 0;
+
+    term.simply("Computing zk proof...\n")?;
+    let uxt = match tx.kind {
+        TxKind::AnonymousTransfer => {
+            let decoys = tx.decoys.iter()
+                .map(|d| enc_key_of("decoys", d))
+                .collect::<Result<Vec<_>>>()?;
+            if decoys.len() != DECOY_SIZE || enc_balances.len() != ANONIMITY_SIZE {
+                return Err(invalid("anonymity set"));
+            }
+            let multi_keys = MultiEncKeys::<Bls12, Anonymous>::new(recipient, decoys);
+
+            KeyContext::read_from_path(&settings.keys.anony_pk, &settings.keys.anony_vk)?
+                .gen_proof(
+                    tx.amount,
+                    0,
+                    remaining_balance,
+                    s_index,
+                    t_index,
+                    &spending_key,
+                    multi_keys,
+                    &enc_balances[..],
+                    g_epoch,
+                    nonce_counter,
+                    rng,
+                    &PARAMS
+                )?
+                .sign(tx.kind.call(), index, genesis_hash, rng)
+        },
+        TxKind::Transfer | TxKind::AssetTransfer(_) => {
+            let multi_keys = MultiEncKeys::<Bls12, Confidential>::new(recipient);
+
+            KeyContext::read_from_path(&settings.keys.conf_pk, &settings.keys.conf_vk)?
+                .gen_proof(
+                    tx.amount,
+                    tx.fee,
+                    remaining_balance,
+                    0,
+                    0,
+                    &spending_key,
+                    multi_keys,
+                    &enc_balances[..],
+                    g_epoch,
+                    nonce_counter,
+                    rng,
+                    &PARAMS
+                )?
+                .sign(tx.kind.call(), index, genesis_hash, rng)
+        },
+    };
+
+    SignedTx {
+        kind: tx.kind,
+        epoch: tx.epoch,
+        extrinsic: hex::encode(uxt.encode()),
+    }.write_to(output)?;
+
+    let report = FileReport {
+        file: output.display().to_string(),
+        kind: tx.kind,
+        epoch: tx.epoch,
+        blocks_until_next_epoch: None,
+        remaining_balance: Some(remaining_balance),
+    };
+    term.report(&report, |term| {
+        term.success(&format!("Wrote the signed transaction to {}\n", report.file))?;
+        term.simply(&format!("Remaining balance after the transfer will be {}.\n", remaining_balance))
+    })?;
+
+    Ok(())
+}
+
+/// Submit a signed transaction, if the chain is still in the epoch it was proven in.
+pub fn broadcast_tx(term: &mut Term, settings: &Settings, input: &Path) -> Result<()> {
+    let tx = SignedTx::read_from(input)?;
+    let uxt = UncheckedExtrinsic::decode(&mut &hex_field("extrinsic", &tx.extrinsic)?[..])
+        .ok_or_else(|| invalid("extrinsic"))?;
+
+    let api = Api::init(settings.node_url());
+    let epoch = getter::current_epoch(&api)?;
+    if epoch != tx.epoch {
+        return Err(KeystoreError::StaleTransaction(tx.epoch, epoch));
+    }
+
+    term.simply("Start submitting a transaction to Zerochain...\n")?;
+    api.submit_extrinsic(&uxt)
+        .map_err(|e| KeystoreError::RpcResponse(format!("failed to submit the extrinsic: {:?}", e)))?;
+
+    let report = FileReport {
+        file: input.display().to_string(),
+        kind: tx.kind,
+        epoch: tx.epoch,
+        blocks_until_next_epoch: None,
+        remaining_balance: None,
+    };
+    term.report(&report, |term| term.success("Submitted the transaction.\n"))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unsigned_tx() -> UnsignedTx {
+        UnsignedTx {
+            kind: TxKind::AssetTransfer(3),
+            amount: 10,
+            fee: 1,
+            sender: "alice".to_string(),
+            recipient: "bob".to_string(),
+            decoys: vec![],
+            s_index: 0,
+            t_index: 0,
+            enc_balances: vec!["00".repeat(64)],
+            epoch: 7,
+            nonce_pool: "00".to_string(),
+            genesis_hash: "11".repeat(32),
+        }
+    }
+
+    #[test]
+    fn test_envelope_round_trip() {
+        let tx = unsigned_tx();
+        let envelope = seal(&tx).unwrap();
+        assert_eq!(envelope.version, FORMAT_VERSION);
+        assert_eq!(envelope.file_type, "unsignedTx");
+
+        let json = serde_json::to_string_pretty(&envelope).unwrap();
+        let decoded: UnsignedTx = open(serde_json::from_str(&json).unwrap()).unwrap();
+        assert_eq!(decoded, tx);
+    }
+
+    #[test]
+    fn test_envelope_rejects_tampering() {
+        let mut envelope = seal(&unsigned_tx()).unwrap();
+        envelope.payload["amount"] = Value::from(1000);
+        assert_matches!(open::<UnsignedTx>(envelope), Err(KeystoreError::InvalidTxFile(_)));
+    }
+
+    #[test]
+    fn test_check_party_indices() {
+        let mut tx = unsigned_tx();
+        tx.t_index = ANONIMITY_SIZE as u32;
+        assert!(check_party_indices(&tx).is_ok());
+
+        tx.kind = TxKind::AnonymousTransfer;
+        assert_matches!(check_party_indices(&tx), Err(KeystoreError::InvalidTxFile(_)));
+
+        tx.t_index = tx.s_index;
+        assert_matches!(check_party_indices(&tx), Err(KeystoreError::InvalidTxFile(_)));
+
+        tx.s_index = ANONIMITY_SIZE as u32;
+        tx.t_index = 0;
+        assert_matches!(check_party_indices(&tx), Err(KeystoreError::InvalidTxFile(_)));
+
+        tx.s_index = ANONIMITY_SIZE as u32 - 1;
+        assert!(check_party_indices(&tx).is_ok());
+    }
+
+    #[test]
+    fn test_envelope_rejects_other_version_and_type() {
+        let mut envelope = seal(&unsigned_tx()).unwrap();
+        envelope.version = FORMAT_VERSION + 1;
+        assert_matches!(open::<UnsignedTx>(envelope), Err(KeystoreError::InvalidTxFile(_)));
+
+        let envelope = seal(&unsigned_tx()).unwrap();
+        assert_matches!(open::<SignedTx>(envelope), Err(KeystoreError::InvalidTxFile(_)));
+    }
+}
@@ -0,0 +1,133 @@
+//! Pre-flight checks of a transfer, run before its proof is computed.
+//!
+//! A proof is bound to the balance and the epoch generator of the epoch it was computed in,
+//! so a transaction which is included after the next epoch boundary is rejected.
+//! The planner waits for the next epoch instead of starting a proof which would go stale.
+
+use std::{thread, time::Duration};
+use pairing::bls12_381::Bls12;
+use polkadot_rs::Api;
+use proofs::DecryptionKey;
+use super::constants::*;
+use crate::{
+    error::{KeystoreError, Result},
+    getter::{self, BalanceQuery},
+    term::Term,
+};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+/// The balance a transfer is paid from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Source {
+    Balance,
+    Asset(u32),
+    Anonymous,
+}
+
+impl Source {
+    fn proving_blocks(&self) -> u64 {
+        match self {
+            Source::Anonymous => ANONY_PROVING_BLOCKS,
+            _ => CONF_PROVING_BLOCKS,
+        }
+    }
+}
+
+/// The on-chain state a transfer is proven against.
+pub struct Plan {
+    pub balance_query: BalanceQuery,
+    pub fee: u32,
+    pub remaining_balance: u32,
+    pub epoch: u64,
+    pub blocks_until_next_epoch: u64,
+}
+
+/// Fetch the state a transfer of `amount` from `source` is proven against, and check that it can succeed.
+/// If the epoch ends before the proof would be included, wait for the next one and fetch again,
+/// because the rollover at the boundary may change the balance.
+pub fn plan_transfer(
+    term: &mut Term,
+    api: &Api,
+    dec_key: &DecryptionKey<Bls12>,
+    source: Source,
+    amount: u32,
+    decryption_range: u32,
+) -> Result<Plan> {
+    let proving_blocks = source.proving_blocks();
+    let epoch_length = getter::epoch_length(api)?;
+    if epoch_length <= proving_blocks {
+        return Err(KeystoreError::EpochTooShort(epoch_length, proving_blocks));
+    }
+
+    loop {
+        let height = getter::block_height(api)?;
+        let blocks_left = blocks_until_next_epoch(height, epoch_length);
+
+        if blocks_left <= proving_blocks {
+            term.simply(&format!("Waiting {} blocks for the next epoch, so that the proof does not go stale...\n", blocks_left))?;
+            wait_for_epoch(api, height / epoch_length + 1, epoch_length)?;
+            continue;
+        }
+
+        let (balance_query, fee) = match source {
+            Source::Balance => (BalanceQuery::get_encrypted_balance(dec_key, api.clone(), decryption_range)?, getter::fee(api)?),
+            Source::Asset(asset_id) => (BalanceQuery::get_encrypted_asset(asset_id, dec_key, api.clone(), decryption_range)?, getter::fee(api)?),
+            // Anonymous transfers do not charge a fee.
+            Source::Anonymous => (BalanceQuery::get_anonymous_balance(dec_key, api.clone(), decryption_range)?, 0),
+        };
+        let remaining_balance = remaining_balance(balance_query.decrypted_balance, amount, fee)?;
+
+        return Ok(Plan {
+            balance_query,
+            fee,
+            remaining_balance,
+            epoch: height / epoch_length,
+            blocks_until_next_epoch: blocks_left,
+        });
+    }
+}
+
+fn wait_for_epoch(api: &Api, epoch: u64, epoch_length: u64) -> Result<()> {
+    while getter::block_height(api)? / epoch_length < epoch {
+        thread::sleep(POLL_INTERVAL);
+    }
+
+    Ok(())
+}
+
+/// The number of blocks from `height` until the first block of the next epoch.
+pub fn blocks_until_next_epoch(height: u64, epoch_length: u64) -> u64 {
+    epoch_length - height % epoch_length
+}
+
+/// The balance left after paying `amount` and `fee`, or an error if `balance` does not cover them.
+pub fn remaining_balance(balance: u32, amount: u32, fee: u32) -> Result<u32> {
+    let required = amount as u64 + fee as u64;
+    if (balance as u64) < required {
+        return Err(KeystoreError::InsufficientBalance(balance, required));
+    }
+
+    Ok(balance - amount - fee)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blocks_until_next_epoch() {
+        assert_eq!(blocks_until_next_epoch(0, 10), 10);
+        assert_eq!(blocks_until_next_epoch(9, 10), 1);
+        assert_eq!(blocks_until_next_epoch(10, 10), 10);
+        assert_eq!(blocks_until_next_epoch(25, 10), 5);
+    }
+
+    #[test]
+    fn test_remaining_balance() {
+        assert_eq!(remaining_balance(100, 10, 1).unwrap(), 89);
+        assert_eq!(remaining_balance(11, 10, 1).unwrap(), 0);
+        assert_matches!(remaining_balance(10, 10, 1), Err(KeystoreError::InsufficientBalance(10, 11)));
+        assert_matches!(remaining_balance(10, u32::max_value(), 1), Err(KeystoreError::InsufficientBalance(10, _)));
+    }
+}
@@ -0,0 +1,179 @@
+//! Selection of decoys for anonymous transfers.
+//!
+//! Decoys are picked by their index in the anonymity set of the anonymous-balances module.
+//! They are picked without replacement and never include the real sender or recipient,
+//! because duplicates and the real parties are visible on-chain and shrink the effective anonymity set.
+
+use rand::Rng;
+use rand::distributions::{Gamma, IndependentSample};
+use std::collections::BTreeSet;
+use crate::error::{KeystoreError, Result};
+
+/// The anonymity set should have this many times more candidates than decoys;
+/// otherwise most of the set ends up in every ring and we warn about it.
+const SMALL_SET_FACTOR: u32 = 4;
+
+/// How many times a biased draw may miss before falling back to a uniform draw.
+const MAX_BIASED_ATTEMPTS: usize = 100;
+
+/// A distribution over the anonymity set to draw decoys from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Distribution {
+    /// Every registered key is equally likely.
+    Uniform,
+    /// The age of a decoy, counted in keys registered after it, follows a gamma distribution,
+    /// so recently registered keys are more likely to be picked.
+    /// `scale` is a fraction of the size of the anonymity set, so that the decoys spread
+    /// over the same share of the set however large it grows.
+    Gamma {
+        shape: f64,
+        scale: f64,
+    },
+}
+
+impl Distribution {
+    /// A gamma distribution weighted towards the most recently registered keys,
+    /// with a mean age of a tenth of the anonymity set.
+    pub fn recent() -> Self {
+        Distribution::Gamma { shape: 2.0, scale: 0.05 }
+    }
+}
+
+impl Default for Distribution {
+    fn default() -> Self {
+        Distribution::Uniform
+    }
+}
+
+/// Selects the indices of decoys in an anonymity set.
+#[derive(Clone, Debug)]
+pub struct DecoySelector {
+    size: usize,
+    distribution: Distribution,
+}
+
+impl DecoySelector {
+    pub fn new(size: usize) -> Self {
+        DecoySelector {
+            size,
+            distribution: Distribution::default(),
+        }
+    }
+
+    pub fn distribution(mut self, distribution: Distribution) -> Self {
+        self.distribution = distribution;
+        self
+    }
+
+    /// Select `size` distinct indices out of `0..set_size`, none of which is in `excluded`.
+    pub fn select<R: Rng>(&self, set_size: u32, excluded: &[u32], rng: &mut R) -> Result<Vec<u32>> {
+        let excluded = excluded.iter()
+            .filter(|i| **i < set_size)
+            .cloned()
+            .collect::<BTreeSet<u32>>();
+        let candidates = set_size - excluded.len() as u32;
+
+        if (candidates as usize) < self.size {
+            return Err(KeystoreError::TooFewDecoys(candidates, self.size));
+        }
+        if candidates < self.size as u32 * SMALL_SET_FACTOR {
+            warn!("Only {} encryption keys can be decoys for {} slots. The anonymity set is small.", candidates, self.size);
+        }
+
+        let mut chosen = BTreeSet::new();
+        let mut acc = Vec::with_capacity(self.size);
+        while acc.len() < self.size {
+            let index = self.draw(set_size, &excluded, &chosen, rng);
+            chosen.insert(index);
+            acc.push(index);
+        }
+
+        Ok(acc)
+    }
+
+    fn draw<R: Rng>(&self, set_size: u32, excluded: &BTreeSet<u32>, chosen: &BTreeSet<u32>, rng: &mut R) -> u32 {
+        let is_free = |i: &u32| !excluded.contains(i) && !chosen.contains(i);
+
+        if let Distribution::Gamma { shape, scale } = self.distribution {
+            let gamma = Gamma::new(shape, scale * set_size as f64);
+            for _ in 0..MAX_BIASED_ATTEMPTS {
+                let age = gamma.ind_sample(rng) as u64;
+                if age < set_size as u64 {
+                    let index = set_size - 1 - age as u32;
+                    if is_free(&index) {
+                        return index;
+                    }
+                }
+            }
+        }
+
+        // There is always a free index because the number of candidates was checked.
+        loop {
+            let index = rng.gen_range(0, set_size);
+            if is_free(&index) {
+                return index;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{XorShiftRng, SeedableRng};
+
+    fn rng() -> XorShiftRng {
+        XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654])
+    }
+
+    #[test]
+    fn test_select_without_replacement() {
+        for distribution in &[Distribution::Uniform, Distribution::recent()] {
+            let selector = DecoySelector::new(10).distribution(*distribution);
+            let decoys = selector.select(12, &[3, 7], &mut rng()).unwrap();
+
+            let unique = decoys.iter().cloned().collect::<BTreeSet<u32>>();
+            assert_eq!(decoys.len(), 10);
+            assert_eq!(unique.len(), 10);
+            assert!(!unique.contains(&3) && !unique.contains(&7));
+            assert!(unique.iter().all(|i| *i < 12));
+        }
+    }
+
+    #[test]
+    fn test_select_is_deterministic() {
+        let selector = DecoySelector::new(10).distribution(Distribution::recent());
+
+        assert_eq!(
+            selector.select(1000, &[0, 1], &mut rng()).unwrap(),
+            selector.select(1000, &[0, 1], &mut rng()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_recent_prefers_new_keys() {
+        let selector = DecoySelector::new(10).distribution(Distribution::recent());
+        let decoys = selector.select(10_000, &[], &mut rng()).unwrap();
+
+        assert!(decoys.iter().all(|i| *i >= 5_000));
+    }
+
+    #[test]
+    fn test_recent_scales_with_the_set() {
+        let selector = DecoySelector::new(10).distribution(Distribution::recent());
+
+        for set_size in &[100, 10_000, 1_000_000] {
+            let decoys = selector.select(*set_size, &[], &mut rng()).unwrap();
+            // not all of them among the newest few percent of the set
+            assert!(decoys.iter().any(|i| *i < set_size - set_size / 50));
+        }
+    }
+
+    #[test]
+    fn test_too_few_candidates() {
+        let selector = DecoySelector::new(10);
+
+        assert_matches!(selector.select(11, &[0, 1], &mut rng()), Err(KeystoreError::TooFewDecoys(9, 10)));
+        assert!(selector.select(12, &[0, 1, 20], &mut rng()).is_ok());
+    }
+}
@@ -3,7 +3,7 @@
 use keys::EncryptionKey as zEncryptionKey;
 use rand::Rng;
 use pairing::bls12_381::Bls12;
-use zprimitives::{EncKey, GEpoch};
+use zprimitives::{EncKey, GEpoch, Nonce};
 use zcrypto::elgamal as zelgamal;
 use polkadot_rs::{Api, hexstr_to_vec, hexstr_to_u64};
 use parity_codec::Encode;
@@ -13,61 +13,65 @@ use zjubjub::curve::FixedGenerators as zFixedGenerators;
 use proofs::{EncryptionKey, DecryptionKey, constants::DECOY_SIZE};
 use zpairing::bls12_381::Bls12 as zBls12;
 use scrypto::jubjub::{edwards, PrimeOrder};
-use crate::error::Result;
+use crate::error::{Result, KeystoreError};
+use super::decoy::{DecoySelector, Distribution};
 use std::convert::TryFrom;
 
 pub fn get_enc_balances(api: &Api, enc_keys: &[EncryptionKey<Bls12>]) -> Result<Vec<elgamal::Ciphertext<Bls12>>> {
     let mut acc = vec![];
     for e in enc_keys {
-        let mut encrypted_balance_str = api.get_storage(
-            "AnonymousBalances",
-            "EncryptedBalance",
-            Some(EncKey::try_from(no_std_e(e)?)?.encode())
-        )?;
-
-        let mut pending_transfer_str = api.get_storage(
-            "AnonymousBalances",
-            "PendingTransfer",
-            Some(EncKey::try_from(no_std_e(e)?)?.encode())
-        )?;
+        acc.push(rolled_over_balance(api, "AnonymousBalances", EncKey::try_from(no_std_e(e)?)?.encode())?);
+    }
 
-        let mut ciphertext = None;
-        let mut p_ciphertext = None;
+    Ok(acc)
+}
 
-        if encrypted_balance_str.as_str() != "0x00" {
-            // TODO: remove unnecessary prefix. If it returns `0x00`, it will be panic.
-            for _ in 0..4 {
-                encrypted_balance_str.remove(2);
-            }
+/// Get the encrypted balance of the encryption key which the next confidential transfer is checked against,
+/// without decrypting it. `asset_id` selects a balance in encrypted-assets module.
+pub fn get_enc_balance(
+    api: &Api,
+    enc_key: &EncryptionKey<Bls12>,
+    asset_id: Option<u32>
+) -> Result<elgamal::Ciphertext<Bls12>> {
+    let account_id = EncKey::try_from(no_std_e(enc_key)?)?;
+
+    match asset_id {
+        Some(id) => rolled_over_balance(api, "EncryptedAssets", (id, account_id).encode()),
+        None => rolled_over_balance(api, "EncryptedBalances", account_id.encode()),
+    }
+}
 
-            let encrypted_balance = hexstr_to_vec(encrypted_balance_str.clone());
-            ciphertext = Some(zelgamal::Ciphertext::<zBls12>::read(&mut &encrypted_balance[..], &ZPARAMS)?);
-        }
+/// The encrypted balance stored under the key in the module,
+/// plus its pending transfer if the next transaction rolls it over.
+fn rolled_over_balance(api: &Api, module: &str, key: Vec<u8>) -> Result<elgamal::Ciphertext<Bls12>> {
+    let encrypted_balance_str = api.get_storage(module, "EncryptedBalance", Some(key.clone()))?;
+    let pending_transfer_str = api.get_storage(module, "PendingTransfer", Some(key.clone()))?;
+    let rolls_over = will_roll_over(api, module, key)?;
 
-        if pending_transfer_str.as_str() != "0x00" {
-            // TODO: remove unnecessary prefix. If it returns `0x00`, it will be panic.
-            for _ in 0..4 {
-                pending_transfer_str.remove(2);
-            }
+    let mut ciphertext = None;
+    let mut p_ciphertext = None;
 
-            let pending_transfer = hexstr_to_vec(pending_transfer_str.clone());
-            p_ciphertext = Some(zelgamal::Ciphertext::<zBls12>::read(&mut &pending_transfer[..], &ZPARAMS)?);
-        }
+    if encrypted_balance_str.as_str() != "0x00" {
+        let encrypted_balance = hexstr_to_vec(encrypted_balance_str.clone());
+        ciphertext = Some(zelgamal::Ciphertext::<zBls12>::read(&mut &encrypted_balance[..], &ZPARAMS)?);
+    }
 
-        let zero = zelgamal::Ciphertext::<zBls12>::zero();
-        let enc_total = ciphertext.unwrap_or(zero.clone()).add(&p_ciphertext.unwrap_or(zero), &*ZPARAMS);
-        let mut buf = vec![0u8; 64];
-        enc_total.write(&mut buf[..])?;
-        let tmp = elgamal::Ciphertext::<Bls12>::read(&mut &buf[..], &*PARAMS)?;
-        acc.push(tmp);
+    if rolls_over && pending_transfer_str.as_str() != "0x00" {
+        let pending_transfer = hexstr_to_vec(pending_transfer_str.clone());
+        p_ciphertext = Some(zelgamal::Ciphertext::<zBls12>::read(&mut &pending_transfer[..], &ZPARAMS)?);
     }
 
-    Ok(acc)
+    let zero = zelgamal::Ciphertext::<zBls12>::zero();
+    let enc_total = ciphertext.unwrap_or(zero.clone()).add(&p_ciphertext.unwrap_or(zero), &*ZPARAMS);
+    let mut buf = vec![0u8; 64];
+    enc_total.write(&mut buf[..])?;
+
+    Ok(elgamal::Ciphertext::<Bls12>::read(&mut &buf[..], &*PARAMS)?)
 }
 
 pub struct BalanceQuery {
     pub decrypted_balance: u32,
-    pub encrypted_balance: Vec<u8>, // total of encrypted balance and pending transfer
+    pub encrypted_balance: Vec<u8>, // balance the next transaction is checked against
     pub encrypted_balance_str: String,
     pub pending_transfer_str: String,
 }
@@ -75,7 +79,7 @@ pub struct BalanceQuery {
 // Temporary code.
 impl BalanceQuery {
     /// Get encrypted and decrypted balance for the decryption key
-    pub fn get_encrypted_balance(dec_key: &DecryptionKey<Bls12>, api: Api) -> Result<Self> {
+    pub fn get_encrypted_balance(dec_key: &DecryptionKey<Bls12>, api: Api, decryption_range: u32) -> Result<Self> {
         let encryption_key = zEncryptionKey::from_decryption_key(&no_std(&dec_key)?, &*ZPARAMS);
         let account_id = EncKey::try_from(encryption_key)?;
 
@@ -91,10 +95,12 @@ impl BalanceQuery {
             Some(account_id.encode())
         )?;
 
-        Self::get_balance_from_decryption_key(encrypted_balance_str, pending_transfer_str, dec_key)
+        let rolls_over = will_roll_over(&api, "EncryptedBalances", account_id.encode())?;
+
+        Self::get_balance_from_decryption_key(encrypted_balance_str, pending_transfer_str, rolls_over, dec_key, decryption_range)
     }
 
-    pub fn get_encrypted_asset(asset_id: u32, dec_key: &DecryptionKey<Bls12>, api: Api) -> Result<Self> {
+    pub fn get_encrypted_asset(asset_id: u32, dec_key: &DecryptionKey<Bls12>, api: Api, decryption_range: u32) -> Result<Self> {
         let encryption_key = zEncryptionKey::from_decryption_key(&no_std(&dec_key)?, &*ZPARAMS);
         let account_id = EncKey::try_from(encryption_key)?;
 
@@ -110,10 +116,12 @@ impl BalanceQuery {
             Some((asset_id, account_id).encode())
         )?;
 
-        Self::get_balance_from_decryption_key(encrypted_asset_str, pending_transfer_str, dec_key)
+        let rolls_over = will_roll_over(&api, "EncryptedAssets", (asset_id, account_id).encode())?;
+
+        Self::get_balance_from_decryption_key(encrypted_asset_str, pending_transfer_str, rolls_over, dec_key, decryption_range)
     }
 
-    pub fn get_anonymous_balance(dec_key: &DecryptionKey<Bls12>, api: Api) -> Result<Self> {
+    pub fn get_anonymous_balance(dec_key: &DecryptionKey<Bls12>, api: Api, decryption_range: u32) -> Result<Self> {
         let encryption_key = zEncryptionKey::from_decryption_key(&no_std(&dec_key)?, &*ZPARAMS);
         let account_id = EncKey::try_from(encryption_key)?;
 
@@ -129,40 +137,37 @@ impl BalanceQuery {
             Some(account_id.encode())
         )?;
 
-        Self::get_balance_from_decryption_key(encrypted_balance_str, pending_transfer_str, dec_key)
+        let rolls_over = will_roll_over(&api, "AnonymousBalances", account_id.encode())?;
+
+        Self::get_balance_from_decryption_key(encrypted_balance_str, pending_transfer_str, rolls_over, dec_key, decryption_range)
     }
 
+    /// The pending transfer only counts if the next transaction rolls it over.
+    /// The balance is decrypted by brute force below `decryption_range`.
     fn get_balance_from_decryption_key(
-        mut encrypted_balance_str: String,
-        mut pending_transfer_str: String,
-        dec_key: &DecryptionKey<Bls12>
+        encrypted_balance_str: String,
+        pending_transfer_str: String,
+        rolls_over: bool,
+        dec_key: &DecryptionKey<Bls12>,
+        decryption_range: u32,
     ) -> Result<Self> {
         let p_g = zFixedGenerators::Diversifier; // 1
         let mut ciphertext = None;
         let mut p_ciphertext = None;
 
         if encrypted_balance_str.as_str() != "0x00" {
-            // TODO: remove unnecessary prefix. If it returns `0x00`, it will be panic.
-            for _ in 0..4 {
-                encrypted_balance_str.remove(2);
-            }
-
             let encrypted_balance = hexstr_to_vec(encrypted_balance_str.clone());
             ciphertext = Some(zelgamal::Ciphertext::<zBls12>::read(&mut &encrypted_balance[..], &ZPARAMS)?);
         }
-        if pending_transfer_str.as_str() != "0x00" {
-            // TODO: remove unnecessary prefix. If it returns `0x00`, it will be panic.
-            for _ in 0..4 {
-                pending_transfer_str.remove(2);
-            }
-
+        if rolls_over && pending_transfer_str.as_str() != "0x00" {
             let pending_transfer = hexstr_to_vec(pending_transfer_str.clone());
             p_ciphertext = Some(zelgamal::Ciphertext::<zBls12>::read(&mut &pending_transfer[..], &ZPARAMS)?);
         }
 
         let zero = zelgamal::Ciphertext::<zBls12>::zero();
         let enc_total = ciphertext.unwrap_or(zero.clone()).add(&p_ciphertext.unwrap_or(zero), &*ZPARAMS);
-        let dec_balance = enc_total.decrypt(&no_std(&dec_key)?, p_g, &ZPARAMS).unwrap();
+        let dec_balance = enc_total.decrypt_with_range(&no_std(&dec_key)?, p_g, &ZPARAMS, decryption_range)
+            .ok_or(KeystoreError::BalanceOutOfRange(decryption_range))?;
         let mut buf = vec![0u8; 64];
         enc_total.write(&mut buf[..])?;
 
@@ -184,17 +189,88 @@ pub fn address(seed: &[u8]) -> Result<Vec<u8>> {
     Ok(address_bytes)
 }
 
-pub fn g_epoch(api: &Api) -> Result<edwards::Point<Bls12, PrimeOrder>> {
+pub fn block_height(api: &Api) -> Result<u64> {
     let current_height_str = api.get_latest_height()?;
+    Ok(hexstr_to_u64(current_height_str))
+}
+
+// Get the epoch length in blocks as `EpochLength` in zk-system module.
+pub fn epoch_length(api: &Api) -> Result<u64> {
     let epoch_length_str = api.get_storage("ZkSystem", "EpochLength", None)?;
-    let current_epoch = hexstr_to_u64(current_height_str) / hexstr_to_u64(epoch_length_str);
-    let g_epoch = GEpoch::group_hash(current_epoch as u32)?; // TODO
+    Ok(hexstr_to_u64(epoch_length_str))
+}
+
+pub fn current_epoch(api: &Api) -> Result<u64> {
+    Ok(block_height(api)? / epoch_length(api)?)
+}
 
-    let point = edwards::Point::<Bls12, _>::read(&mut g_epoch.as_ref(), &PARAMS)?
-            .as_prime_order(&PARAMS)
-            .unwrap();
+/// Whether the next transaction touching the account rolls its pending transfer over,
+/// i.e. the account was not rolled over in the current epoch yet.
+fn will_roll_over(api: &Api, module: &str, key: Vec<u8>) -> Result<bool> {
+    let last_rollover_str = api.get_storage(module, "LastRollOver", Some(key))?;
 
-    Ok(point)
+    Ok(hexstr_to_u64(last_rollover_str) < current_epoch(api)?)
+}
+
+/// Get the nonces used in the current epoch.
+pub fn nonce_pool(api: &Api) -> Result<Vec<Nonce>> {
+    let last_epoch_str = api.get_storage("ZkSystem", "LastEpoch", None)?;
+
+    // The pool is cleared by the first transaction of a new epoch.
+    if hexstr_to_u64(last_epoch_str) < current_epoch(api)? {
+        return Ok(vec![]);
+    }
+
+    let count_str = api.get_storage("ZkSystem", "NonceCount", None)?;
+
+    let mut acc = vec![];
+    for i in 0..hexstr_to_u64(count_str) as u32 {
+        let nonce_str = api.get_storage("ZkSystem", "NonceAt", Some(i.encode()))?;
+        if nonce_str.as_str() != "0x00" {
+            acc.push(Nonce::from_slice(&hexstr_to_vec(nonce_str)[..]));
+        }
+    }
+
+    Ok(acc)
+}
+
+/// Get the epoch generator of the next nonce of the account, and its counter.
+pub fn g_epoch(api: &Api, dec_key: &DecryptionKey<Bls12>) -> Result<(edwards::Point<Bls12, PrimeOrder>, u32)> {
+    g_epoch_from_pool(current_epoch(api)?, &nonce_pool(api)?[..], dec_key)
+}
+
+/// Get the epoch generator of the next nonce of the account in `epoch`, given the nonces used in it.
+/// The n-th transaction of an account in an epoch uses the generator with counter n,
+/// so this is the first counter whose nonce is not in the nonce pool yet.
+pub fn g_epoch_from_pool(
+    epoch: u64,
+    nonce_pool: &[Nonce],
+    dec_key: &DecryptionKey<Bls12>
+) -> Result<(edwards::Point<Bls12, PrimeOrder>, u32)> {
+    let mut nonce_counter = 0;
+    loop {
+        let g_epoch = GEpoch::group_hash_with_counter(epoch as u32, nonce_counter)?;
+        let point = edwards::Point::<Bls12, _>::read(&mut g_epoch.as_ref(), &PARAMS)?
+                .as_prime_order(&PARAMS)
+                .unwrap();
+
+        let mut nonce = [0u8; 32];
+        point.mul(dec_key.0, &PARAMS).write(&mut nonce[..])?;
+
+        if !nonce_pool.contains(&Nonce::from_slice(&nonce[..])) {
+            return Ok((point, nonce_counter));
+        }
+        nonce_counter += 1;
+    }
+}
+
+/// Decrypt an encrypted balance as written in `BalanceQuery::encrypted_balance`.
+/// Returns `None` if it does not decrypt to a balance below `decryption_range` under the key.
+pub fn decrypt_balance(encrypted_balance: &[u8], dec_key: &DecryptionKey<Bls12>, decryption_range: u32) -> Result<Option<u32>> {
+    let p_g = zFixedGenerators::Diversifier; // 1
+    let ciphertext = zelgamal::Ciphertext::<zBls12>::read(&mut &encrypted_balance[..], &ZPARAMS)?;
+
+    Ok(ciphertext.decrypt_with_range(&no_std(&dec_key)?, p_g, &ZPARAMS, decryption_range))
 }
 
 // Get set fee amount as `TransactionBaseFee` in encrypyed-balances module.
@@ -218,25 +294,153 @@ fn no_std_e(enc_key: &EncryptionKey<Bls12>) -> Result<keys::EncryptionKey<zBls12
     Ok(key)
 }
 
-pub fn get_enc_keys<R: Rng>(api: &Api, rng: &mut R) -> Result<Vec<EncryptionKey<Bls12>>> {
-    let mut enc_keys_str = api.get_storage("AnonymousBalances", "EncKeySet", None)?;
-    // TODO: remove unnecessary prefix. If it returns `0x00`, it will be panic.
-    for _ in 0..4 {
-        enc_keys_str.remove(2);
-    }
-    let mut enc_keys_vec = hexstr_to_vec(enc_keys_str);
+/// Get the number of encryption keys in the anonymity set.
+pub fn get_enc_key_count(api: &Api) -> Result<u32> {
+    let count_str = api.get_storage("AnonymousBalances", "EncKeyCount", None)?;
+    Ok(hexstr_to_u64(count_str) as u32)
+}
 
-    assert!(enc_keys_vec.len() % 32 == 0);
-    let mut tmp_acc = vec![];
-    while !enc_keys_vec.is_empty() {
-        let tmp = enc_keys_vec.drain(..32).collect::<Vec<u8>>();
-        tmp_acc.push(EncryptionKey::<Bls12>::read(&mut &tmp[..], &PARAMS)?)
+fn get_enc_key_at(api: &Api, index: u32) -> Result<Option<EncryptionKey<Bls12>>> {
+    let enc_key_str = api.get_storage("AnonymousBalances", "EncKeyAt", Some(index.encode()))?;
+    if enc_key_str.as_str() == "0x00" {
+        return Ok(None);
     }
+
+    let enc_key_vec = hexstr_to_vec(enc_key_str);
+    Ok(Some(EncryptionKey::<Bls12>::read(&mut &enc_key_vec[..], &PARAMS)?))
+}
+
+/// Get at most `limit` encryption keys of the anonymity set, starting from the index `start`.
+pub fn get_enc_keys_page(api: &Api, start: u32, limit: u32) -> Result<Vec<EncryptionKey<Bls12>>> {
+    let end = start.saturating_add(limit).min(get_enc_key_count(api)?);
+
     let mut acc = vec![];
-    for _ in 0..DECOY_SIZE {
-        let random_i = rng.gen_range(0, tmp_acc.len());
-        acc.push(tmp_acc[random_i].clone());
+    for i in start..end {
+        if let Some(enc_key) = get_enc_key_at(api, i)? {
+            acc.push(enc_key);
+        }
     }
 
     Ok(acc)
 }
+
+/// Whether the encryption key has ever received a confidential, an anonymous or an asset transfer,
+/// i.e. an encrypted balance or a pending transfer of it is stored.
+pub fn is_used(api: &Api, enc_key: &EncryptionKey<Bls12>) -> Result<bool> {
+    let account_id = EncKey::try_from(no_std_e(enc_key)?)?;
+    // assets are stored per asset id, and every id below the next one has been issued
+    let next_asset_id = hexstr_to_u64(api.get_storage("EncryptedAssets", "NextAssetId", None)?) as u32;
+
+    is_stored(&account_id, next_asset_id, |module, item, key| Ok(api.get_storage(module, item, Some(key))?))
+}
+
+/// Whether `lookup` finds a balance or a pending transfer of the account in any of the modules,
+/// looking up the assets from `0` to `next_asset_id`.
+fn is_stored<F>(account_id: &EncKey, next_asset_id: u32, mut lookup: F) -> Result<bool>
+where
+    F: FnMut(&str, &str, Vec<u8>) -> Result<String>,
+{
+    let mut keys = vec![
+        ("EncryptedBalances", account_id.encode()),
+        ("AnonymousBalances", account_id.encode()),
+    ];
+    keys.extend((0..next_asset_id).map(|id| ("EncryptedAssets", (id, *account_id).encode())));
+
+    for (module, key) in keys {
+        for item in &["EncryptedBalance", "PendingTransfer"] {
+            if lookup(module, item, key.clone())?.as_str() != "0x00" {
+                return Ok(true);
+            }
+        }
+    }
+
+    Ok(false)
+}
+
+/// Get the index of the encryption key in the anonymity set, if it is registered.
+pub fn get_enc_key_index(api: &Api, enc_key: &EncryptionKey<Bls12>) -> Result<Option<u32>> {
+    let index_str = api.get_storage(
+        "AnonymousBalances",
+        "EncKeyIndex",
+        Some(EncKey::try_from(no_std_e(enc_key)?)?.encode())
+    )?;
+    if index_str.as_str() == "0x00" {
+        return Ok(None);
+    }
+
+    Ok(Some(hexstr_to_u64(index_str) as u32))
+}
+
+/// Pick `DECOY_SIZE` decoys from the anonymity set, excluding the real parties of the transfer.
+pub fn get_enc_keys<R: Rng>(
+    api: &Api,
+    distribution: Distribution,
+    parties: &[EncryptionKey<Bls12>],
+    rng: &mut R
+) -> Result<Vec<EncryptionKey<Bls12>>> {
+    let count = get_enc_key_count(api)?;
+
+    let mut excluded = vec![];
+    for e in parties {
+        if let Some(index) = get_enc_key_index(api, e)? {
+            excluded.push(index);
+        }
+    }
+
+    let selector = DecoySelector::new(DECOY_SIZE).distribution(distribution);
+
+    // Draw again without the indices the node has no key at, until every decoy is found.
+    // The selection fails once too few indices are left.
+    loop {
+        let indices = selector.select(count, &excluded[..], rng)?;
+
+        let mut acc = vec![];
+        for i in indices {
+            match get_enc_key_at(api, i)? {
+                Some(enc_key) => acc.push(enc_key),
+                None => excluded.push(i),
+            }
+        }
+
+        if acc.len() == DECOY_SIZE {
+            return Ok(acc);
+        }
+        warn!("Some encryption keys of the anonymity set are missing on the node. Drawing the decoys again.");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    type Storage = HashMap<(String, String, Vec<u8>), String>;
+
+    fn stored_in(storage: &Storage, account_id: &EncKey, next_asset_id: u32) -> bool {
+        is_stored(account_id, next_asset_id, |module, item, key| {
+            let value = storage.get(&(module.to_string(), item.to_string(), key));
+            Ok(value.map_or("0x00".to_string(), Clone::clone))
+        }).unwrap()
+    }
+
+    fn insert(storage: &mut Storage, module: &str, item: &str, key: Vec<u8>) {
+        storage.insert((module.to_string(), item.to_string(), key), "0x01".to_string());
+    }
+
+    #[test]
+    fn test_is_stored() {
+        let account_id = EncKey::from_slice(&[1u8; 32]);
+        let other_id = EncKey::from_slice(&[2u8; 32]);
+        let mut storage = HashMap::new();
+        assert!(!stored_in(&storage, &account_id, 3));
+
+        // an account which has received an asset only
+        insert(&mut storage, "EncryptedAssets", "PendingTransfer", (2u32, account_id).encode());
+        assert!(stored_in(&storage, &account_id, 3));
+        assert!(!stored_in(&storage, &account_id, 2));
+        assert!(!stored_in(&storage, &other_id, 3));
+
+        insert(&mut storage, "AnonymousBalances", "EncryptedBalance", other_id.encode());
+        assert!(stored_in(&storage, &other_id, 0));
+    }
+}
@@ -1,5 +1,6 @@
 pub mod print_keys;
 pub mod mnemonics;
 pub mod getter;
+pub mod decoy;
 
 pub use self::print_keys::*;
\ No newline at end of file
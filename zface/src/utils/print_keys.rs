@@ -1,12 +1,36 @@
 
 use zprimitives::PARAMS as ZPARAMS;
 use crate::ss58::EncryptionKeyBytes;
+use crate::term::Term;
+use crate::error::Result;
+use crate::config::{ALICESEED, BOBSEED};
 use primitives::crypto::Ss58Codec;
 use zpairing::{bls12_381::Bls12 as zBls12, PrimeField as zPrimeField, PrimeFieldRepr as zPrimeFieldRepr, io};
 use rand::{OsRng, Rng};
 use bip39::{Mnemonic, Language};
 use substrate_bip39::mini_secret_from_entropy;
 
+/// The keys of a phrase, for the JSON output.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct KeysReport<'a> {
+    phrase: &'a str,
+    seed: String,
+    decryption_key: String,
+    encryption_key: String,
+    address: &'a str,
+}
+
+/// The keys of a test account, for the JSON output.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TestAccountReport<'a> {
+    name: &'a str,
+    seed: String,
+    decryption_key: String,
+    encryption_key: String,
+}
+
 pub struct PrintKeys {
     pub phrase: Option<String>,
     pub seed: [u8; 32],
@@ -26,20 +50,71 @@ impl PrintKeys {
         gen_from_seed(seed, None).unwrap()
     }
 
-    pub fn print_from_phrase(phrase: &str, password: Option<&str>, lang: Language) {
+    pub fn print_from_phrase(term: &mut Term, phrase: &str, password: Option<&str>, lang: Language) -> Result<()> {
         let seed = phrase_to_seed(phrase, password, lang);
-        let print_keys = gen_from_seed(seed, Some(phrase)).unwrap();
+        let print_keys = gen_from_seed(seed, Some(phrase))?;
 
-        println!("Phrase `{}` is account:\n Seed: 0x{}\n Decryption key: 0x{}\n Encryption key (hex): 0x{}\n Address (SS58): {}",
+        let report = KeysReport {
             phrase,
-            hex::encode(&print_keys.seed[..]),
-            hex::encode(&print_keys.decryption_key[..]),
-            hex::encode(&print_keys.encryption_key[..]),
-            print_keys.ss58_encryption_key,
-        );
+            seed: format!("0x{}", hex::encode(&print_keys.seed[..])),
+            decryption_key: format!("0x{}", hex::encode(&print_keys.decryption_key[..])),
+            encryption_key: format!("0x{}", hex::encode(&print_keys.encryption_key[..])),
+            address: &print_keys.ss58_encryption_key,
+        };
+
+        term.report(&report, |term| {
+            term.simply(&format!("Phrase `{}` is account:\n Seed: {}\n Decryption key: {}\n Encryption key (hex): {}\n Address (SS58): {}\n",
+                report.phrase,
+                report.seed,
+                report.decryption_key,
+                report.encryption_key,
+                report.address,
+            ))
+        })?;
+
+        Ok(())
     }
 }
 
+/// Print the keys of the test accounts, grouped by kind of key.
+/// Alice and Bob have fixed seeds, and Charlie a random one.
+pub fn print_test_accounts(term: &mut Term) -> Result<()> {
+    term.info("Initialize key components...\n")?;
+    term.info("Accounts of alice and bob are fixed\n")?;
+
+    let accounts = [
+        ("Alice", PrintKeys::generate_from_seed(seed_to_array(ALICESEED))),
+        ("Bob", PrintKeys::generate_from_seed(seed_to_array(BOBSEED))),
+        ("Charlie", PrintKeys::generate()),
+    ];
+    let report: Vec<_> = accounts.iter()
+        .map(|(name, keys)| TestAccountReport {
+            name,
+            seed: format!("0x{}", hex::encode(&keys.seed[..])),
+            decryption_key: format!("0x{}", hex::encode(&keys.decryption_key[..])),
+            encryption_key: format!("0x{}", hex::encode(&keys.encryption_key[..])),
+        })
+        .collect();
+
+    term.report(&report, |term| {
+        term.simply("\nSeed\n")?;
+        for account in &report {
+            term.simply(&format!("{}: {}\n", account.name, account.seed))?;
+        }
+        term.simply("\nDecryption Key\n")?;
+        for account in &report {
+            term.simply(&format!("{}: {}\n", account.name, account.decryption_key))?;
+        }
+        term.simply("\nEncryption Key\n")?;
+        for account in &report {
+            term.simply(&format!("{}: {}\n", account.name, account.encryption_key))?;
+        }
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
 pub fn phrase_to_seed(phrase: &str, password: Option<&str>, lang: Language) -> [u8; 32] {
     mini_secret_from_entropy(
         Mnemonic::from_phrase(phrase, lang)
@@ -0,0 +1,358 @@
+//! Password-encrypted archive of a whole wallet, which backs it up in a single file.
+
+use std::collections::HashSet;
+use std::cmp;
+use std::fs;
+use std::io::{Write, BufReader};
+use std::path::Path;
+use rand::Rng;
+use serde_json::{self, Value};
+use crate::error::{KeystoreError, Result};
+use super::{WalletDirectory, KeystoreDirectory, DirOperations};
+use super::keyfile::{KeyFile, KeyCiphertext, IndexFile, Kdf};
+use super::disk::create_new_file;
+use super::config::VERSION;
+
+/// Version of the archive format, bumped on incompatible changes of the backup.
+pub const ARCHIVE_VERSION: u32 = 1;
+
+/// An archive file. The whole backup is encrypted, so that the account names are not readable either.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Archive {
+    pub version: u32,
+    /// The JSON of the `WalletBackup`, authenticated by the MAC
+    pub encrypted_backup: KeyCiphertext,
+}
+
+impl Archive {
+    pub fn write_to<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let mut file = create_new_file(path.as_ref())?;
+        serde_json::to_writer(&mut file, self)?;
+
+        file.flush()?;
+        file.sync_all()?;
+
+        Ok(())
+    }
+
+    /// Read an archive. The version is checked first, because an archive of another version may not even parse.
+    pub fn read_from<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = fs::File::open(path)?;
+        let value: Value = serde_json::from_reader(BufReader::new(file))?;
+        let version = value.get("version")
+            .and_then(|v| v.as_u64())
+            .ok_or_else(|| KeystoreError::InvalidArchive("no version".to_string()))?;
+
+        if version != ARCHIVE_VERSION as u64 {
+            return Err(KeystoreError::InvalidArchive(
+                format!("version {} is not supported, expected {}", version, ARCHIVE_VERSION)
+            ));
+        }
+
+        Ok(serde_json::from_value(value)?)
+    }
+
+    pub fn open(&self, password: &[u8]) -> Result<WalletBackup> {
+        let plain = self.encrypted_backup.decrypt_bytes(password)?;
+        Ok(serde_json::from_slice(&plain[..])?)
+    }
+}
+
+/// The contents of a wallet. The keyfiles stay encrypted with the wallet password.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct WalletBackup {
+    pub master: KeyFile,
+    pub keyfiles: Vec<KeyFile>,
+    /// Account names, their derivation indexes and the default account
+    pub indexfile: IndexFile,
+}
+
+/// What to do with an imported account whose name is taken by another account.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OnConflict {
+    /// Import nothing
+    Fail,
+    /// Import the other accounts
+    Skip,
+    /// Import it under a free name
+    Rename,
+}
+
+/// The passwords to merge a backup into an existing wallet with.
+#[derive(Clone, Copy)]
+pub struct MergePasswords<'a> {
+    /// The wallet password the keyfiles of the backup are encrypted with
+    pub backup: &'a [u8],
+    /// The password of the existing wallet, which the imported keyfiles are re-encrypted with
+    pub wallet: &'a [u8],
+}
+
+/// The restored accounts, for the JSON output.
+#[derive(Serialize, Debug, Default, PartialEq, Eq)]
+pub struct RestoreReport {
+    pub imported: Vec<String>,
+    /// Accounts which are in the wallet already, or whose name is taken
+    pub skipped: Vec<String>,
+}
+
+impl WalletBackup {
+    pub fn load(wallet_dir: &WalletDirectory, keystore_dir: &KeystoreDirectory) -> Result<Self> {
+        // keyfiles are serialized in the current layout, whichever version they were read from
+        let upgrade = |mut keyfile: KeyFile| {
+            keyfile.version = VERSION;
+            keyfile
+        };
+
+        Ok(WalletBackup {
+            master: upgrade(wallet_dir.load_master()?),
+            keyfiles: keystore_dir.load_all()?.into_iter().map(upgrade).collect(),
+            indexfile: wallet_dir.load_indexfile()?,
+        })
+    }
+
+    pub fn seal<R: Rng>(&self, password: &[u8], kdf: Kdf, rng: &mut R) -> Result<Archive> {
+        let plain = serde_json::to_vec(self)?;
+
+        Ok(Archive {
+            version: ARCHIVE_VERSION,
+            encrypted_backup: KeyCiphertext::encrypt_bytes(&plain[..], password, kdf, rng)?,
+        })
+    }
+
+    /// Restore the backup into a new wallet, or merge its accounts into the existing wallet of the same mnemonic.
+    /// Merged accounts are re-encrypted with the password of the existing wallet, so `passwords` are needed to merge.
+    /// All the conflicts are resolved and the keyfiles re-encrypted before anything is written.
+    pub fn restore<R: Rng>(
+        self,
+        wallet_dir: &WalletDirectory,
+        keystore_dir: &KeystoreDirectory,
+        on_conflict: OnConflict,
+        passwords: Option<MergePasswords>,
+        rng: &mut R,
+    ) -> Result<RestoreReport> {
+        let existing_indexfile = if wallet_dir.get_default_masterfile_path().exists() {
+            let master = wallet_dir.load_master()?;
+            if master.ss58_address != self.master.ss58_address {
+                return Err(KeystoreError::ImportConflict("the archive is a backup of another wallet".to_string()));
+            }
+
+            let passwords = passwords.as_ref()
+                .ok_or_else(|| KeystoreError::ImportConflict("the wallet passwords are needed to merge the archive".to_string()))?;
+            self.master.encrypted_key.decrypt(passwords.backup)?;
+            master.encrypted_key.decrypt(passwords.wallet)?;
+
+            Some(wallet_dir.load_indexfile()?)
+        } else {
+            None
+        };
+
+        let existing_keyfiles = keystore_dir.load_all()?;
+        let addresses: HashSet<&str> = existing_keyfiles.iter().map(|k| k.ss58_address.as_str()).collect();
+        let mut names: HashSet<String> = existing_keyfiles.iter().map(|k| k.account_name.clone()).collect();
+
+        let mut report = RestoreReport::default();
+        let mut accepted = vec![];
+        for mut keyfile in self.keyfiles {
+            let index = self.indexfile.map_account_keyfile
+                .get(&keyfile.account_name)
+                .map(|(_, index)| *index)
+                .ok_or_else(|| KeystoreError::InvalidArchive(format!("no index of the account '{}'", keyfile.account_name)))?;
+            let is_default = keyfile.file_name.as_ref() == Some(&self.indexfile.default_keyfile_name);
+
+            if addresses.contains(keyfile.ss58_address.as_str()) {
+                report.skipped.push(keyfile.account_name);
+                continue;
+            }
+            if names.contains(&keyfile.account_name) {
+                match on_conflict {
+                    OnConflict::Fail => return Err(KeystoreError::ImportConflict(
+                        format!("an account named '{}' exists already", keyfile.account_name)
+                    )),
+                    OnConflict::Skip => {
+                        report.skipped.push(keyfile.account_name);
+                        continue;
+                    },
+                    OnConflict::Rename => keyfile.account_name = free_name(&names, &keyfile.account_name),
+                }
+            }
+
+            if let Some(passwords) = passwords.as_ref().filter(|_| existing_indexfile.is_some()) {
+                keyfile = keyfile.reencrypt(passwords.backup, passwords.wallet, keyfile.encrypted_key.kdf, rng)?;
+            }
+
+            names.insert(keyfile.account_name.clone());
+            accepted.push((keyfile, index, is_default));
+        }
+
+        let is_new = existing_indexfile.is_none();
+        let mut indexfile = existing_indexfile.unwrap_or_default();
+        if is_new {
+            let mut master = self.master;
+            wallet_dir.insert_master(&mut master)?;
+        }
+
+        for (mut keyfile, index, is_default) in accepted {
+            keystore_dir.insert(&mut keyfile, rng)?;
+            let file_name = keyfile.file_name.clone().ok_or(KeystoreError::InvalidKeyfile)?;
+
+            if is_new && is_default {
+                indexfile.default_index = index;
+                indexfile.default_keyfile_name = file_name.clone();
+            }
+            indexfile.map_account_keyfile.insert(keyfile.account_name.clone(), (file_name, index));
+            report.imported.push(keyfile.account_name);
+        }
+        indexfile.max_index = cmp::max(indexfile.max_index, self.indexfile.max_index);
+
+        if is_new {
+            wallet_dir.insert_indexfile(&mut indexfile)?;
+        } else {
+            wallet_dir.update_indexfile(&mut indexfile)?;
+        }
+
+        Ok(report)
+    }
+}
+
+/// Append a number to the name until no account has it.
+fn free_name(names: &HashSet<String>, name: &str) -> String {
+    (2..)
+        .map(|i| format!("{}-{}", name, i))
+        .find(|candidate| !names.contains(candidate))
+        .expect("Some number is free; qed")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{XorShiftRng, SeedableRng};
+    use crate::derive::{ExtendedSpendingKey, Derivation, ChildIndex};
+
+    const KDF: Kdf = Kdf::Pbkdf2 { iters: 1024 };
+
+    /// Create a wallet whose accounts are derived from `first_index` on.
+    fn create_wallet<R: Rng>(
+        dir: &Path,
+        seed: &[u8],
+        accounts: &[&str],
+        first_index: u32,
+        password: &[u8],
+        rng: &mut R,
+    ) -> (WalletDirectory, KeystoreDirectory) {
+        let wallet_dir = WalletDirectory::create(dir).unwrap();
+        let keystore_dir = KeystoreDirectory::create(wallet_dir.get_default_keystore_dir()).unwrap();
+
+        let mut master = KeyFile::create_master("master", password, KDF, rng, seed).unwrap();
+        wallet_dir.insert_master(&mut master).unwrap();
+
+        let mut indexfile = IndexFile::default();
+        for (i, account_name) in accounts.iter().enumerate() {
+            let xsk = ExtendedSpendingKey::master(seed).derive_child(ChildIndex::from_index(first_index + i as u32)).unwrap();
+            let mut keyfile = KeyFile::new(account_name, password, KDF, &xsk, rng).unwrap();
+            keystore_dir.insert(&mut keyfile, rng).unwrap();
+            indexfile = indexfile.next_index(keyfile.file_name.as_ref().unwrap(), account_name);
+        }
+        wallet_dir.insert_indexfile(&mut indexfile).unwrap();
+
+        (wallet_dir, keystore_dir)
+    }
+
+    #[test]
+    fn test_seal_and_open() {
+        let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let seed: [u8; 32] = rng.gen();
+        let dir = tempfile::tempdir().unwrap();
+        let (wallet_dir, keystore_dir) = create_wallet(dir.path(), &seed, &["alice"], 0, b"abcd", rng);
+
+        let backup = WalletBackup::load(&wallet_dir, &keystore_dir).unwrap();
+        let archive = backup.seal(b"archive", KDF, rng).unwrap();
+
+        assert_eq!(archive.open(b"archive").unwrap(), backup);
+        assert_matches!(archive.open(b"wallet"), Err(KeystoreError::InvalidPassword));
+
+        let path = wallet_dir.0.join("backup.json");
+        archive.write_to(&path).unwrap();
+        assert_eq!(Archive::read_from(&path).unwrap(), archive);
+
+        let mut value = serde_json::to_value(&archive).unwrap();
+        value["version"] = (ARCHIVE_VERSION + 1).into();
+        fs::write(&path, serde_json::to_vec(&value).unwrap()).unwrap();
+        assert_matches!(Archive::read_from(&path), Err(KeystoreError::InvalidArchive(_)));
+    }
+
+    #[test]
+    fn test_restore() {
+        let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let seed: [u8; 32] = rng.gen();
+        let source_dir = tempfile::tempdir().unwrap();
+        let (wallet_dir, keystore_dir) = create_wallet(source_dir.path(), &seed, &["alice", "bob"], 0, b"abcd", rng);
+        let backup = WalletBackup::load(&wallet_dir, &keystore_dir).unwrap();
+        let same = MergePasswords { backup: b"abcd", wallet: b"abcd" };
+
+        // into a new wallet
+        let new_dir = tempfile::tempdir().unwrap();
+        let new_wallet_dir = WalletDirectory::create(new_dir.path()).unwrap();
+        let new_keystore_dir = KeystoreDirectory::create(new_wallet_dir.get_default_keystore_dir()).unwrap();
+
+        let report = backup.clone().restore(&new_wallet_dir, &new_keystore_dir, OnConflict::Fail, None, rng).unwrap();
+        assert_eq!(report.imported.len(), 2);
+        assert_eq!(new_wallet_dir.load_master().unwrap().ss58_address, backup.master.ss58_address);
+        let indexfile = new_wallet_dir.load_indexfile().unwrap();
+        assert_eq!(indexfile.max_index, backup.indexfile.max_index);
+        assert_eq!(indexfile.default_index, backup.indexfile.default_index);
+
+        // into a wallet which has the same accounts
+        let report = backup.clone().restore(&new_wallet_dir, &new_keystore_dir, OnConflict::Fail, Some(same), rng).unwrap();
+        assert!(report.imported.is_empty());
+        assert_eq!(report.skipped.len(), 2);
+
+        // into a wallet whose other account has the same name
+        let conflict_dir = tempfile::tempdir().unwrap();
+        let (wallet_dir, keystore_dir) = create_wallet(conflict_dir.path(), &seed, &["alice"], 5, b"abcd", rng);
+        let res = backup.clone().restore(&wallet_dir, &keystore_dir, OnConflict::Fail, Some(same), rng);
+        assert_matches!(res, Err(KeystoreError::ImportConflict(_)));
+        assert_eq!(keystore_dir.load_all().unwrap().len(), 1);
+
+        let report = backup.clone().restore(&wallet_dir, &keystore_dir, OnConflict::Rename, Some(same), rng).unwrap();
+        assert_eq!(report.imported.len(), 2);
+        assert!(report.imported.contains(&"alice-2".to_string()));
+        assert!(wallet_dir.load_indexfile().unwrap().map_account_keyfile.contains_key("alice-2"));
+
+        // into another wallet
+        let other_seed: [u8; 32] = rng.gen();
+        let other_dir = tempfile::tempdir().unwrap();
+        let (wallet_dir, keystore_dir) = create_wallet(other_dir.path(), &other_seed, &["alice"], 0, b"abcd", rng);
+        let res = backup.restore(&wallet_dir, &keystore_dir, OnConflict::Skip, Some(same), rng);
+        assert_matches!(res, Err(KeystoreError::ImportConflict(_)));
+    }
+
+    #[test]
+    fn test_restore_with_another_password() {
+        let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let seed: [u8; 32] = rng.gen();
+        let source_dir = tempfile::tempdir().unwrap();
+        let (wallet_dir, keystore_dir) = create_wallet(source_dir.path(), &seed, &["alice", "bob"], 0, b"abcd", rng);
+        let backup = WalletBackup::load(&wallet_dir, &keystore_dir).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let (wallet_dir, keystore_dir) = create_wallet(dir.path(), &seed, &["carol"], 5, b"wxyz", rng);
+
+        let res = backup.clone().restore(&wallet_dir, &keystore_dir, OnConflict::Fail, None, rng);
+        assert_matches!(res, Err(KeystoreError::ImportConflict(_)));
+        let res = backup.clone().restore(&wallet_dir, &keystore_dir, OnConflict::Fail, Some(MergePasswords { backup: b"wxyz", wallet: b"wxyz" }), rng);
+        assert_matches!(res, Err(KeystoreError::InvalidPassword));
+        let res = backup.clone().restore(&wallet_dir, &keystore_dir, OnConflict::Fail, Some(MergePasswords { backup: b"abcd", wallet: b"abcd" }), rng);
+        assert_matches!(res, Err(KeystoreError::InvalidPassword));
+        assert_eq!(keystore_dir.load_all().unwrap().len(), 1);
+
+        let passwords = MergePasswords { backup: b"abcd", wallet: b"wxyz" };
+        let report = backup.restore(&wallet_dir, &keystore_dir, OnConflict::Fail, Some(passwords), rng).unwrap();
+        assert_eq!(report.imported.len(), 2);
+
+        // the whole wallet opens with its own password
+        for keyfile in keystore_dir.load_all().unwrap() {
+            assert!(keyfile.encrypted_key.decrypt(b"wxyz").is_ok());
+        }
+    }
+}
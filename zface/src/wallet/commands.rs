@@ -1,21 +1,25 @@
 use std::path::PathBuf;
 use std::collections::HashMap;
 use crate::term::Term;
-use crate::derive::ChildIndex;
+use crate::derive::{ExtendedSpendingKey, Derivation, DerivationPath, ChildIndex};
 use crate::utils::mnemonics::*;
 use crate::error::{Result, KeystoreError};
 use super::{WalletDirectory, KeystoreDirectory, DirOperations};
-use super::keyfile::{KeyFile, IndexFile};
+use super::keyfile::{KeyFile, IndexFile, Kdf};
+use super::archive::{Archive, WalletBackup, OnConflict, MergePasswords};
+use super::discovery::discover_accounts;
 use super::config::*;
 use bip39::{Mnemonic, Language, MnemonicType, Seed};
 use rand::Rng;
 use proofs::DecryptionKey;
 use pairing::bls12_381::Bls12;
+use polkadot_rs::Api;
 
-/// Create a new wallet
+/// Create a new wallet whose keyfiles are encrypted with `kdf`.
 pub fn new_wallet<R: Rng>(
     term: &mut Term,
     root_dir: PathBuf,
+    kdf: Kdf,
     rng: &mut R,
 ) -> Result<()> {
     // 1. configure wallet directory
@@ -34,7 +38,7 @@ pub fn new_wallet<R: Rng>(
     // 4. create master keyfile
     let master_seed = Seed::new(&mnemonic, "");
     let master_seed_bytes: &[u8] = master_seed.as_bytes();
-    let mut keyfile_master = KeyFile::create_master(MASTER_ACCOUNTNAME, VERSION, &password[..], ITERS, rng, master_seed_bytes)?;
+    let mut keyfile_master = KeyFile::create_master(MASTER_ACCOUNTNAME, &password[..], kdf, rng, master_seed_bytes)?;
 
     // 5. store master keyfile
     wallet_dir.insert_master(&mut keyfile_master)?;
@@ -47,8 +51,7 @@ pub fn new_wallet<R: Rng>(
     keystore_dir.insert(&mut keyfile, rng)?;
 
     // 8. store new indexfile
-    let file_name = keyfile.file_name.expect("Filename should be set.");
-    new_indexfile(&wallet_dir, &file_name, &keyfile.account_name)?;
+    new_indexfile(&wallet_dir, &[(0, &keyfile)])?;
 
     term.success(&format!(
         "wallet and a new account successfully created.\n
@@ -60,28 +63,56 @@ pub fn new_wallet<R: Rng>(
     Ok(())
 }
 
+/// An account in the wallet, for the JSON output.
+#[derive(Serialize, Debug)]
+pub struct AccountReport<'a> {
+    pub name: &'a str,
+    pub address: &'a str,
+    /// Derivation path from the master key, if the account is in the index file
+    pub path: Option<String>,
+    pub default: bool,
+}
+
+/// Show the accounts in the wallet. `default_account` is marked as the default one if given,
+/// instead of the default account of the wallet.
 pub fn show_list(
     term: &mut Term,
     root_dir: PathBuf,
+    default_account: Option<&str>,
 ) -> Result<()> {
     let (wallet_dir, keystore_dir) = wallet_keystore_dirs(&root_dir)?;
 
     let keyfiles = keystore_dir.load_all()?;
     if keyfiles.len() == 0 {
-        term.warn("Not found accounts\n")?;
+        term.report(&Vec::<AccountReport>::new(), |term| term.warn("Not found accounts\n"))?;
         return Ok(());
     }
 
     let default_index = get_default_index(&wallet_dir)? as usize;
-
-    for (i, keyfile) in keyfiles.iter().enumerate() {
-        let (name, address) = (&*keyfile.account_name, &*keyfile.ss58_address);
-        if i == default_index {
-            term.success(&format!("* {}: {}\n", name, address))?;
-        } else {
-            term.success(&format!("{}: {}\n", name, address))?;
+    let indexfile = wallet_dir.load_indexfile()?;
+    let accounts = keyfiles.iter()
+        .enumerate()
+        .map(|(i, keyfile)| AccountReport {
+            name: &keyfile.account_name,
+            address: &keyfile.ss58_address,
+            path: indexfile.map_account_keyfile
+                .get(&keyfile.account_name)
+                .map(|(_, index)| DerivationPath::account(*index).to_string()),
+            default: default_account.map_or(i == default_index, |name| name == keyfile.account_name),
+        })
+        .collect::<Vec<_>>();
+
+    term.report(&accounts, |term| {
+        for account in &accounts {
+            let path = account.path.as_ref().map_or(String::new(), |path| format!(" ({})", path));
+            if account.default {
+                term.success(&format!("* {}{}: {}\n", account.name, path, account.address))?;
+            } else {
+                term.success(&format!("{}{}: {}\n", account.name, path, account.address))?;
+            }
         }
-    }
+        Ok(())
+    })?;
 
     Ok(())
 }
@@ -118,9 +149,14 @@ pub fn new_keyfile<R: Rng>(
     Ok(())
 }
 
+/// Recover the wallet from the mnemonic. With `discovery`, i.e. a node and a gap limit,
+/// the accounts used on chain are restored, scanning until `gap_limit` unused accounts in a row.
+/// A new account is created at the first index otherwise, or if no account is used.
 pub fn recover<R: Rng>(
     term: &mut Term,
     root_dir: PathBuf,
+    kdf: Kdf,
+    discovery: Option<(Api, u32)>,
     rng: &mut R,
 ) -> Result<()> {
     // 1. configure wallet directory
@@ -128,47 +164,78 @@ pub fn recover<R: Rng>(
 
     // 2. Enter mnemonic
     let mnemonic = input_mnemonic_phrase(MnemonicType::Words12, Language::English);
+    let master_seed = Seed::new(&mnemonic, "");
+    let master_seed_bytes: &[u8] = master_seed.as_bytes();
 
-    // 3. Re-set a new passoword
+    // 3. discover the used accounts, before anything is stored
+    let discovered = match discovery {
+        Some((api, gap_limit)) => {
+            term.info("Scanning the accounts on chain...\n")?;
+            discover_accounts(term, &api, &ExtendedSpendingKey::master(master_seed_bytes), gap_limit)?
+        },
+        None => vec![],
+    };
+
+    // 4. Re-set a new passoword
     term.info("Re-set a new wallet password. This is for local usage only, allows you to protect your cached private key and prevent from creating non desired transactions.\n")?;
     let password = term.new_password("wallet password", "confirm wallet password", "password mismatch")?;
 
-    // 4. create master keyfile
-    let master_seed = Seed::new(&mnemonic, "");
-    let master_seed_bytes: &[u8] = master_seed.as_bytes();
-    let mut keyfile_master = KeyFile::create_master(MASTER_ACCOUNTNAME, VERSION, &password[..], ITERS, rng, master_seed_bytes)?;
+    // 5. create master keyfile
+    let mut keyfile_master = KeyFile::create_master(MASTER_ACCOUNTNAME, &password[..], kdf, rng, master_seed_bytes)?;
 
-    // 5. store master keyfile
+    // 6. store master keyfile
     wallet_dir.insert_master(&mut keyfile_master)?;
 
-    // 6. create a genesis keyfile
-    let child_index = ChildIndex::from_index(0);
-    let mut keyfile = get_new_keyfile(term, rng, &password[..], &wallet_dir, child_index)?;
-
-    // 7. store a genesis keyfile
-    keystore_dir.insert(&mut keyfile, rng)?;
+    // 7. store the discovered accounts, or a genesis keyfile
+    let mut keyfiles = vec![];
+    if discovered.is_empty() {
+        let child_index = ChildIndex::from_index(0);
+        let mut keyfile = get_new_keyfile(term, rng, &password[..], &wallet_dir, child_index)?;
+        keystore_dir.insert(&mut keyfile, rng)?;
+        keyfiles.push((0, keyfile));
+    } else {
+        for (index, xsk) in discovered {
+            let account_name = format!("account-{}", index);
+            let mut keyfile = KeyFile::new(&account_name, &password[..], kdf, &xsk, rng)?;
+            keystore_dir.insert(&mut keyfile, rng)?;
+            keyfiles.push((index, keyfile));
+        }
+    }
 
     // 8. store new indexfile
-    let file_name = keyfile.file_name.expect("Filename should be set.");
-    new_indexfile(&wallet_dir, &file_name, &keyfile.account_name)?;
-
-    term.success(&format!(
-        "Re-generated your wallet from the provided mnemonic successfully.\n
-        {}: {}\n\n",
-        keyfile.account_name,
-        keyfile.ss58_address
-    ))?;
+    let accounts = keyfiles.iter().map(|(index, keyfile)| (*index, keyfile)).collect::<Vec<_>>();
+    new_indexfile(&wallet_dir, &accounts[..])?;
+
+    let report = accounts.iter()
+        .enumerate()
+        .map(|(i, (index, keyfile))| AccountReport {
+            name: &keyfile.account_name,
+            address: &keyfile.ss58_address,
+            path: Some(DerivationPath::account(*index).to_string()),
+            default: i == 0,
+        })
+        .collect::<Vec<_>>();
+
+    term.report(&report, |term| {
+        term.success("Re-generated your wallet from the provided mnemonic successfully.\n")?;
+        for account in &report {
+            term.success(&format!("{} ({}): {}\n", account.name, account.path.as_ref().map_or("", |p| p.as_str()), account.address))?;
+        }
+        Ok(())
+    })?;
 
     Ok(())
 }
 
+/// Load the decryption key of the account, or of the default account if no name is given.
 pub fn load_dec_key(
     term: &mut Term,
     root_dir: PathBuf,
+    account_name: Option<&str>,
 ) -> Result<DecryptionKey<Bls12>> {
     let (wallet_dir, keystore_dir) = wallet_keystore_dirs(&root_dir)?;
-    let default_keyfile_name = get_default_keyfile_name(&wallet_dir)?;
-    let keyfile = keystore_dir.load(default_keyfile_name.as_str())?;
+    let keyfile_name = get_keyfile_name(&wallet_dir, account_name)?;
+    let keyfile = keystore_dir.load(keyfile_name.as_str())?;
 
     // enter password
     term.info("Enter the wallet password.\n")?;
@@ -179,6 +246,185 @@ pub fn load_dec_key(
     Ok(dec_key)
 }
 
+/// The re-encrypted keyfiles, for the JSON output.
+#[derive(Serialize, Debug)]
+pub struct ReencryptReport<'a> {
+    pub version: u32,
+    pub accounts: Vec<&'a str>,
+}
+
+/// Re-encrypt the keyfiles which are older than the current version or are not encrypted with `kdf`.
+pub fn migrate<R: Rng>(
+    term: &mut Term,
+    root_dir: PathBuf,
+    kdf: Kdf,
+    rng: &mut R,
+) -> Result<()> {
+    let (wallet_dir, keystore_dir) = wallet_keystore_dirs(&root_dir)?;
+    let master_keyfile = wallet_dir.load_master()?;
+    let keyfiles = keystore_dir.load_all()?
+        .into_iter()
+        .filter(|keyfile| keyfile.is_outdated(&kdf))
+        .collect::<Vec<_>>();
+
+    if !master_keyfile.is_outdated(&kdf) && keyfiles.is_empty() {
+        let report = ReencryptReport { version: VERSION, accounts: vec![] };
+        term.report(&report, |term| term.success("All keyfiles are up to date.\n"))?;
+        return Ok(());
+    }
+
+    term.info("Enter the wallet password.\n")?;
+    let password = term.passowrd("wallet password")?;
+
+    // re-encrypt all the keyfiles before storing any, so that a wrong password changes nothing
+    let master_keyfile = if master_keyfile.is_outdated(&kdf) {
+        Some(master_keyfile.reencrypt(&password[..], &password[..], kdf, rng)?)
+    } else {
+        None
+    };
+    let keyfiles = keyfiles.iter()
+        .map(|keyfile| keyfile.reencrypt(&password[..], &password[..], kdf, rng))
+        .collect::<Result<Vec<_>>>()?;
+
+    for keyfile in &keyfiles {
+        keystore_dir.update(keyfile)?;
+    }
+    if let Some(ref keyfile) = master_keyfile {
+        wallet_dir.update_master(keyfile)?;
+    }
+
+    let report = ReencryptReport {
+        version: VERSION,
+        accounts: master_keyfile.iter().chain(keyfiles.iter()).map(|keyfile| keyfile.account_name.as_str()).collect(),
+    };
+    term.report(&report, |term| term.success(&format!(
+        "Migrated {} keyfiles to version {} with {}.\n",
+        report.accounts.len(),
+        report.version,
+        kdf.name()
+    )))?;
+
+    Ok(())
+}
+
+/// Re-encrypt all the keyfiles with a new password. Each keyfile keeps its key derivation function.
+pub fn change_password<R: Rng>(
+    term: &mut Term,
+    root_dir: PathBuf,
+    rng: &mut R,
+) -> Result<()> {
+    let (wallet_dir, keystore_dir) = wallet_keystore_dirs(&root_dir)?;
+    let master_keyfile = wallet_dir.load_master()?;
+    let keyfiles = keystore_dir.load_all()?;
+
+    term.info("Enter the current wallet password.\n")?;
+    let password = term.passowrd("current wallet password")?;
+    master_keyfile.encrypted_key.decrypt(&password[..])?;
+
+    term.info("Set a new wallet password.\n")?;
+    let new_password = term.new_password("new wallet password", "confirm new wallet password", "password mismatch")?;
+
+    // Re-encrypt all the keyfiles before storing any, so that a wrong password changes nothing.
+    // A keyfile which opens with the new password only was stored by an interrupted change, and is kept as it is.
+    let master_keyfile = master_keyfile.reencrypt(&password[..], &new_password[..], master_keyfile.encrypted_key.kdf, rng)?;
+    let keyfiles = keyfiles.iter()
+        .map(|keyfile| match keyfile.reencrypt(&password[..], &new_password[..], keyfile.encrypted_key.kdf, rng) {
+            Err(KeystoreError::InvalidPassword) => keyfile.encrypted_key.decrypt(&new_password[..]).map(|_| keyfile.clone()),
+            res => res,
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    // The master keyfile is the last one, so that an interrupted change is still opened with the old password
+    // and can be done again with the same new password.
+    for keyfile in &keyfiles {
+        keystore_dir.update(keyfile)?;
+    }
+    wallet_dir.update_master(&master_keyfile)?;
+
+    let report = ReencryptReport {
+        version: VERSION,
+        accounts: Some(&master_keyfile).into_iter().chain(keyfiles.iter()).map(|keyfile| keyfile.account_name.as_str()).collect(),
+    };
+    term.report(&report, |term| term.success("Changed the wallet password.\n"))?;
+
+    Ok(())
+}
+
+/// The archive written by `wallet export`, for the JSON output.
+#[derive(Serialize, Debug)]
+pub struct ExportReport<'a> {
+    pub file: String,
+    pub accounts: Vec<&'a str>,
+}
+
+/// Export the whole wallet to an archive encrypted with a new archive password.
+pub fn export_wallet<R: Rng>(
+    term: &mut Term,
+    root_dir: PathBuf,
+    path: PathBuf,
+    kdf: Kdf,
+    rng: &mut R,
+) -> Result<()> {
+    let (wallet_dir, keystore_dir) = wallet_keystore_dirs(&root_dir)?;
+    let backup = WalletBackup::load(&wallet_dir, &keystore_dir)?;
+
+    term.info("Set an archive password. It will be needed to import the archive.\n")?;
+    let password = term.new_password("archive password", "confirm archive password", "password mismatch")?;
+
+    backup.seal(&password[..], kdf, rng)?.write_to(&path)?;
+
+    let report = ExportReport {
+        file: path.display().to_string(),
+        accounts: backup.keyfiles.iter().map(|keyfile| keyfile.account_name.as_str()).collect(),
+    };
+    term.report(&report, |term| term.success(&format!(
+        "Exported {} accounts to {}.\n",
+        report.accounts.len(),
+        report.file
+    )))?;
+
+    Ok(())
+}
+
+/// Import an archive into a new wallet, or into the existing wallet of the same mnemonic.
+/// Accounts merged into an existing wallet are re-encrypted with its password.
+pub fn import_wallet<R: Rng>(
+    term: &mut Term,
+    root_dir: PathBuf,
+    path: PathBuf,
+    on_conflict: OnConflict,
+    rng: &mut R,
+) -> Result<()> {
+    let archive = Archive::read_from(&path)?;
+
+    term.info("Enter the archive password.\n")?;
+    let password = term.passowrd("archive password")?;
+    let backup = archive.open(&password[..])?;
+
+    let (wallet_dir, keystore_dir) = wallet_keystore_dirs(&root_dir)?;
+    let report = if wallet_dir.get_default_masterfile_path().exists() {
+        term.info("Enter the wallet password the archive was exported with.\n")?;
+        let backup_password = term.passowrd("exported wallet password")?;
+        term.info("Enter the current wallet password.\n")?;
+        let wallet_password = term.passowrd("wallet password")?;
+
+        let passwords = MergePasswords { backup: &backup_password[..], wallet: &wallet_password[..] };
+        backup.restore(&wallet_dir, &keystore_dir, on_conflict, Some(passwords), rng)?
+    } else {
+        backup.restore(&wallet_dir, &keystore_dir, on_conflict, None, rng)?
+    };
+
+    term.report(&report, |term| {
+        term.success(&format!("Imported {} accounts.\n", report.imported.len()))?;
+        for account_name in &report.skipped {
+            term.warn(&format!("Skipped {}: it is in the wallet already, or the name is taken.\n", account_name))?;
+        }
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
 pub fn change_default_account(
     root_dir: PathBuf,
     account_name: &str,
@@ -212,12 +458,11 @@ fn get_new_keyfile<R: Rng>(
     term.info("Enter a new account name.\n")?;
     let account_name = term.account_name("new account name")?;
 
-    // create new keyfile
+    // create new keyfile, encrypted in the same way as the master keyfile
     let keyfile = KeyFile::new(
         account_name.as_str(),
-        VERSION,
         password,
-        ITERS,
+        master_keyfile.encrypted_key.kdf,
         &xsk_child,
         rng
     )?;
@@ -225,15 +470,20 @@ fn get_new_keyfile<R: Rng>(
     Ok(keyfile)
 }
 
-/// Create a new index file in wallet directory.
-fn new_indexfile(wallet_dir: &WalletDirectory, keyfile_name: &str, account_name: &str) -> Result<()> {
+/// Create a new index file in wallet directory with the stored keyfiles and their indexes.
+/// The first account is the default one.
+fn new_indexfile(wallet_dir: &WalletDirectory, accounts: &[(u32, &KeyFile)]) -> Result<()> {
     let mut map_account_keyfile = HashMap::new();
-    map_account_keyfile.insert(account_name.to_string(), (keyfile_name.to_string(), 0));
+    for (index, keyfile) in accounts {
+        let keyfile_name = keyfile.file_name.clone().ok_or(KeystoreError::InvalidKeyfile)?;
+        map_account_keyfile.insert(keyfile.account_name.clone(), (keyfile_name, *index));
+    }
 
+    let (default_index, default_keyfile) = accounts.first().ok_or(KeystoreError::InvalidKeyfile)?;
     let mut indexfile = IndexFile {
-        default_index: 0,
-        max_index: 0,
-        default_keyfile_name: keyfile_name.to_string(),
+        default_index: *default_index,
+        max_index: accounts.iter().map(|(index, _)| *index).max().unwrap_or(0),
+        default_keyfile_name: default_keyfile.file_name.clone().ok_or(KeystoreError::InvalidKeyfile)?,
         map_account_keyfile,
     };
     wallet_dir.insert_indexfile(&mut indexfile)
@@ -261,6 +511,20 @@ pub fn get_default_keyfile_name(wallet_dir: &WalletDirectory) -> Result<String>
     Ok(indexfile.default_keyfile_name)
 }
 
+/// Get the keyfile name of the account, or of the default account if no name is given.
+pub fn get_keyfile_name(wallet_dir: &WalletDirectory, account_name: Option<&str>) -> Result<String> {
+    match account_name {
+        Some(name) => {
+            let indexfile = wallet_dir.load_indexfile()?;
+            indexfile.map_account_keyfile
+                .get(name)
+                .map(|(keyfile_name, _)| keyfile_name.clone())
+                .ok_or(KeystoreError::InvalidKeyfile)
+        },
+        None => get_default_keyfile_name(wallet_dir),
+    }
+}
+
 pub fn wallet_keystore_dirs(root_dir: &PathBuf) -> Result<(WalletDirectory, KeystoreDirectory)> {
     // configure wallet directory
     let wallet_dir = WalletDirectory::create(&root_dir)?;
@@ -2,5 +2,20 @@ pub const KEYSTORE_DIR: &'static str = "keystore";
 pub const MASTER_KEYFILE: &'static str = "master.json";
 pub const MASTER_ACCOUNTNAME: &'static str = "master";
 pub const INDEXFILE: &'static str = "index.json";
-pub const VERSION: u32 = 1;
+pub const VERSION: u32 = 2;
 pub const ITERS: u32 = 1024;
+pub const SCRYPT_N: u32 = 32768;
+pub const SCRYPT_R: u32 = 8;
+pub const SCRYPT_P: u32 = 1;
+/// Memory of Argon2id in KiB
+pub const ARGON2_MEMORY: u32 = 65536;
+pub const ARGON2_ITERATIONS: u32 = 3;
+pub const ARGON2_LANES: u32 = 1;
+/// Limits of the key derivation parameters read from keyfiles and archives,
+/// so that a crafted file can not exhaust the memory or hang zface.
+pub const MAX_PBKDF2_ITERS: u32 = 10_000_000;
+/// Memory of scrypt and Argon2id in KiB
+pub const MAX_KDF_MEMORY: u32 = 1 << 20;
+pub const MAX_SCRYPT_P: u32 = 16;
+pub const MAX_ARGON2_ITERATIONS: u32 = 16;
+pub const MAX_ARGON2_LANES: u32 = 16;
@@ -0,0 +1,94 @@
+//! Discovery of the accounts of a recovered wallet by their balances on chain.
+
+use polkadot_rs::Api;
+use proofs::{EncryptionKey, PARAMS};
+use pairing::bls12_381::Bls12;
+use crate::derive::{ExtendedSpendingKey, Derivation, DerivationPath};
+use crate::error::Result;
+use crate::term::Term;
+use crate::utils::getter;
+
+/// Number of unused accounts in a row after which the scan stops, as in BIP44.
+pub const DEFAULT_GAP_LIMIT: u32 = 20;
+
+/// Derive the accounts of the master key from the first index on,
+/// and keep the ones which are used on chain with their indexes.
+pub fn discover_accounts(
+    term: &mut Term,
+    api: &Api,
+    xsk_master: &ExtendedSpendingKey,
+    gap_limit: u32,
+) -> Result<Vec<(u32, ExtendedSpendingKey)>> {
+    scan(gap_limit, |index| {
+        let path = DerivationPath::account(index);
+        let xsk = xsk_master.derive_path(&path)?;
+        let enc_key = EncryptionKey::<Bls12>::from_spending_key(&xsk.spending_key, &PARAMS)?;
+
+        if getter::is_used(api, &enc_key)? {
+            term.info(&format!("Found a used account at {}\n", path))?;
+            Ok(Some(xsk))
+        } else {
+            Ok(None)
+        }
+    })
+}
+
+/// Look up the accounts from the first index on, until `gap_limit` of them in a row are not found.
+fn scan<T, F>(gap_limit: u32, mut lookup: F) -> Result<Vec<(u32, T)>>
+where
+    F: FnMut(u32) -> Result<Option<T>>,
+{
+    let mut found = vec![];
+    let mut gap = 0;
+    let mut index = 0;
+
+    // non-hardened indexes only, as the accounts are derived
+    while gap < gap_limit && index < (1 << 31) {
+        match lookup(index)? {
+            Some(account) => {
+                found.push((index, account));
+                gap = 0;
+            },
+            None => gap += 1,
+        }
+        index += 1;
+    }
+
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::KeystoreError;
+
+    #[test]
+    fn test_scan_until_gap() {
+        let used = [0, 2, 5, 9];
+        let mut looked_up = vec![];
+
+        let found = scan(3, |index| {
+            looked_up.push(index);
+            Ok(if used.contains(&index) { Some(index * 10) } else { None })
+        }).unwrap();
+
+        // 9 is beyond the gap of 6, 7 and 8
+        assert_eq!(found, vec![(0, 0), (2, 20), (5, 50)]);
+        assert_eq!(looked_up, (0..9).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_scan_nothing_used() {
+        let found = scan(DEFAULT_GAP_LIMIT, |_| Ok(None::<()>)).unwrap();
+        assert!(found.is_empty());
+
+        let found = scan(0, |_| Ok(Some(()))).unwrap();
+        assert!(found.is_empty());
+    }
+
+    #[test]
+    fn test_scan_error() {
+        let res = scan(5, |index| if index == 2 { Err(KeystoreError::InvalidKeyfile) } else { Ok(Some(())) });
+        assert_matches!(res, Err(KeystoreError::InvalidKeyfile));
+    }
+}
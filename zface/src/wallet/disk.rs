@@ -27,6 +27,12 @@ impl WalletDirectory {
         save_keyfile(MASTER_KEYFILE.to_string(), &keyfile_path, keyfile)
     }
 
+    /// Overwrite the master keyfile, e.g. after re-encrypting it.
+    pub fn update_master(&self, keyfile: &KeyFile) -> Result<()> {
+        let keyfile_path = self.get_default_masterfile_path();
+        overwrite_keyfile(&keyfile_path, keyfile)
+    }
+
     pub fn load_master(&self) -> Result<KeyFile> {
         let path_master = self.get_default_masterfile_path();
         load_keyfile(&path_master)
@@ -98,6 +104,11 @@ impl DirOperations for KeystoreDirectory{
         Ok(self.get_keyfile(keyfile_name)?)
     }
 
+    fn update(&self, keyfile: &KeyFile) -> Result<()> {
+        let filename = keyfile.file_name.as_ref().ok_or(KeystoreError::InvalidKeyfile)?;
+        overwrite_keyfile(&self.0.join(filename), keyfile)
+    }
+
     fn remove(&self, keyfile: &mut KeyFile) -> Result<()> {
         let removed_file = self.get_all_keyfiles()?
             .into_iter()
@@ -132,26 +143,24 @@ impl KeystoreDirectory {
         let path = self.0.join(keyfile_name);
         let file = fs::File::open(path)?;
         let reader = BufReader::new(file);
-        let keyfile = serde_json::from_reader(reader)?;
+        let keyfile = KeyFile::read(reader)?;
 
         Ok(keyfile)
     }
 
     fn get_all_keyfiles(&self) -> Result<BTreeMap<PathBuf, KeyFile>> {
-        Ok(fs::read_dir(&self.0)?
-            .flat_map(|entry| {
-                let path = entry?.path();
-                fs::File::open(path.clone())
-                    .map(|file| {
-                        let reader = BufReader::new(file);
-                        let keyfile = serde_json::from_reader(reader)
-                            .expect("Should deserialize from json file.");
-
-                        (path, keyfile)
-                    })
-            })
-            .collect()
-        )
+        let mut keyfiles = BTreeMap::new();
+        for entry in fs::read_dir(&self.0)? {
+            let path = entry?.path();
+            if !path.is_file() || is_hidden(&path) {
+                continue;
+            }
+
+            let reader = BufReader::new(fs::File::open(&path)?);
+            keyfiles.insert(path, KeyFile::read(reader)?);
+        }
+
+        Ok(keyfiles)
     }
 }
 
@@ -167,12 +176,34 @@ fn save_keyfile(filename: String, keyfile_path: &PathBuf, keyfile: &mut KeyFile)
     Ok(())
 }
 
+/// Overwrite a keyfile through a hidden temporary file,
+/// so that an interruption leaves either the old or the new keyfile.
+fn overwrite_keyfile(keyfile_path: &Path, keyfile: &KeyFile) -> Result<()> {
+    let filename = keyfile_path.file_name().ok_or(KeystoreError::InvalidPath)?;
+    let tmp_path = keyfile_path.with_file_name(format!(".{}.new", filename.to_string_lossy()));
+
+    let mut file = replace_file(&tmp_path)?;
+    serde_json::to_writer(&mut file, keyfile)?;
+
+    file.flush()?;
+    file.sync_all()?;
+    fs::rename(&tmp_path, keyfile_path)?;
+
+    Ok(())
+}
+
+fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .map(|name| name.to_string_lossy().starts_with('.'))
+        .unwrap_or(false)
+}
+
 // TODO: make it abstract
 fn load_keyfile(keyfile_path: &PathBuf) -> Result<KeyFile> {
     let file = fs::File::open(keyfile_path)?;
 
     let reader = BufReader::new(file);
-    let keyfile = serde_json::from_reader(reader)?;
+    let keyfile = KeyFile::read(reader)?;
 
     Ok(keyfile)
 }
@@ -260,6 +291,7 @@ mod tests {
     use std::env;
     use rand::{XorShiftRng, SeedableRng};
     use crate::derive::{ExtendedSpendingKey, Derivation};
+    use super::super::keyfile::Kdf;
 
     #[test]
     fn test_manage_keyfile() {
@@ -269,12 +301,11 @@ mod tests {
 
         let seed: [u8; 32] = rng.gen();
         let xsk_master = ExtendedSpendingKey::master(&seed);
-        let iters = 1024;
+        let kdf = Kdf::Pbkdf2 { iters: 1024 };
         let password = b"abcd";
-        let version = 1;
 
         let directory = KeystoreDirectory::create(dir.clone()).unwrap();
-        let mut keyfile = KeyFile::new("Test", version, password, iters, &xsk_master, rng).unwrap();
+        let mut keyfile = KeyFile::new("Test", password, kdf, &xsk_master, rng).unwrap();
 
         // create
         let res_create = directory.insert(&mut keyfile, rng);
@@ -287,6 +318,13 @@ mod tests {
 
         assert_eq!(*keyfile_loaded, keyfile);
 
+        // update
+        let keyfile_updated = keyfile.reencrypt(password, b"wxyz", kdf, rng).unwrap();
+        let res_update = directory.update(&keyfile_updated);
+
+        assert!(res_update.is_ok(), "Should update keyfile successfully.");
+        assert_eq!(directory.load_all().unwrap(), vec![keyfile_updated]);
+
         // remove
         let res_remove = directory.remove(keyfile_loaded);
 
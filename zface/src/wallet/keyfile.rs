@@ -8,10 +8,42 @@ use proofs::{SpendingKey, ProofGenerationKey, DecryptionKey, PARAMS};
 use pairing::bls12_381::Bls12;
 use std::convert::TryInto;
 use std::collections::HashMap;
+use std::io;
 use super::SerdeBytes;
+use super::config::*;
 use crate::error::{KeystoreError, Result};
 use crate::derive::{ExtendedSpendingKey, Derivation, ChildIndex};
 
+/// The encryption key and the MAC key derived from a password,
+/// to encrypt data more than once without running the KDF each time.
+pub struct DerivedKeys {
+    kdf: Kdf,
+    salt: Vec<u8>,
+    left: Vec<u8>,
+    right: Vec<u8>,
+}
+
+impl DerivedKeys {
+    /// Derive the keys under a new salt.
+    pub fn new<R: Rng>(password: &[u8], kdf: Kdf, rng: &mut R) -> Result<Self> {
+        let salt: [u8; 32] = rng.gen();
+        Self::with_salt(password, kdf, salt.to_vec())
+    }
+
+    /// Derive the keys the ciphertext was encrypted with, checking the password against its MAC.
+    pub fn of(ciphertext: &KeyCiphertext, password: &[u8]) -> Result<Self> {
+        let keys = Self::with_salt(password, ciphertext.kdf, ciphertext.salt.0.clone())?;
+        ciphertext.decrypt_with(&keys)?;
+
+        Ok(keys)
+    }
+
+    fn with_salt(password: &[u8], kdf: Kdf, salt: Vec<u8>) -> Result<Self> {
+        let (left, right) = kdf.derive_keys(password, &salt[..])?;
+        Ok(DerivedKeys { kdf, salt, left, right })
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct KeyFile {
@@ -35,46 +67,86 @@ pub struct KeyFile {
 impl KeyFile {
     pub fn new<R: Rng>(
         account_name: &str,
-        version: u32,
         password: &[u8],
-        iters: u32,
+        kdf: Kdf,
         xsk: &ExtendedSpendingKey,
         rng: &mut R,
     ) -> Result<Self> {
-        let encrypted_key = KeyCiphertext::encrypt(xsk, password, iters, rng)?;
+        let encrypted_key = KeyCiphertext::encrypt(xsk, password, kdf, rng)?;
         let ss58_address = xsk.try_into()?;
 
         Ok(KeyFile {
             file_name: None,
             account_name: account_name.to_string(),
             ss58_address,
-            version,
+            version: VERSION,
             encrypted_key,
         })
     }
 
     pub fn create_master<R: Rng>(
         account_name: &str,
-        version: u32,
         password: &[u8],
-        iters: u32,
+        kdf: Kdf,
         rng: &mut R,
         seed: &[u8],
     ) -> Result<Self> {
         let xsk_master = ExtendedSpendingKey::master(seed);
 
-        let encrypted_key = KeyCiphertext::encrypt(&xsk_master, password, iters, rng)?;
+        let encrypted_key = KeyCiphertext::encrypt(&xsk_master, password, kdf, rng)?;
         let ss58_master_addr = (&xsk_master).try_into()?;
 
         Ok(KeyFile {
             file_name: None,
             account_name: account_name.to_string(),
             ss58_address: ss58_master_addr,
-            version,
+            version: VERSION,
             encrypted_key,
         })
     }
 
+    /// Read a keyfile of any supported version.
+    /// Version 1 keyfiles are read as they are, so they keep the version until they are migrated.
+    pub fn read<R: io::Read>(reader: R) -> Result<Self> {
+        let value: serde_json::Value = serde_json::from_reader(reader)?;
+        let version = value.get("version")
+            .and_then(|v| v.as_u64())
+            .ok_or(KeystoreError::InvalidKeyfile)?;
+
+        match version {
+            1 => {
+                let keyfile: KeyFileV1 = serde_json::from_value(value)?;
+                Ok(keyfile.into())
+            },
+            v if v == VERSION as u64 => Ok(serde_json::from_value(value)?),
+            v => Err(KeystoreError::UnsupportedKeyfileVersion(v)),
+        }
+    }
+
+    /// Re-encrypt the private key with `new_password` and `kdf`,
+    /// which upgrades the keyfile to the current version.
+    pub fn reencrypt<R: Rng>(
+        &self,
+        password: &[u8],
+        new_password: &[u8],
+        kdf: Kdf,
+        rng: &mut R,
+    ) -> Result<Self> {
+        let xsk = self.encrypted_key.decrypt(password)?;
+        let encrypted_key = KeyCiphertext::encrypt(&xsk, new_password, kdf, rng)?;
+
+        Ok(KeyFile {
+            version: VERSION,
+            encrypted_key,
+            ..self.clone()
+        })
+    }
+
+    /// Whether migrating to `kdf` would change this keyfile.
+    pub fn is_outdated(&self, kdf: &Kdf) -> bool {
+        self.version < VERSION || self.encrypted_key.kdf.name() != kdf.name()
+    }
+
     pub fn get_child_xsk(&self, password: &[u8], index: ChildIndex) -> Result<ExtendedSpendingKey> {
         let xsk = self.encrypted_key.decrypt(password)?;
         let xsk_child = xsk.derive_child(index)?;
@@ -96,13 +168,101 @@ impl KeyFile {
     }
 }
 
+/// Key derivation function which derives the encryption key and the MAC key from the password.
+/// The parameters are stored in the keyfile, so that they can be raised for new keyfiles.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone, Copy)]
+#[serde(tag = "function", rename_all = "lowercase")]
+pub enum Kdf {
+    /// PBKDF2-HMAC-SHA256, which is the only function of version 1 keyfiles.
+    Pbkdf2 { iters: u32 },
+    /// scrypt, whose cost `n` is a power of two.
+    Scrypt { n: u32, r: u32, p: u32 },
+    /// Argon2id, whose `memory` is in KiB.
+    Argon2id { memory: u32, iterations: u32, lanes: u32 },
+}
+
+impl Default for Kdf {
+    fn default() -> Self {
+        Kdf::Scrypt { n: SCRYPT_N, r: SCRYPT_R, p: SCRYPT_P }
+    }
+}
+
+impl Kdf {
+    /// The function with its default parameters, by its name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "pbkdf2" => Some(Kdf::Pbkdf2 { iters: ITERS }),
+            "scrypt" => Some(Kdf::default()),
+            "argon2id" => Some(Kdf::Argon2id { memory: ARGON2_MEMORY, iterations: ARGON2_ITERATIONS, lanes: ARGON2_LANES }),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Kdf::Pbkdf2 { .. } => "pbkdf2",
+            Kdf::Scrypt { .. } => "scrypt",
+            Kdf::Argon2id { .. } => "argon2id",
+        }
+    }
+
+    /// Whether the parameters are within the limits, which bound the memory and the time of a derivation.
+    pub fn is_within_limits(&self) -> bool {
+        match *self {
+            Kdf::Pbkdf2 { iters } => iters <= MAX_PBKDF2_ITERS,
+            // scrypt takes 128 * r * n bytes
+            Kdf::Scrypt { n, r, p } => n as u64 * r as u64 / 8 <= MAX_KDF_MEMORY as u64 && p <= MAX_SCRYPT_P,
+            Kdf::Argon2id { memory, iterations, lanes } => {
+                memory <= MAX_KDF_MEMORY && iterations <= MAX_ARGON2_ITERATIONS && lanes <= MAX_ARGON2_LANES
+            },
+        }
+    }
+
+    /// Derive the AES-128 key and the MAC key.
+    fn derive_keys(&self, password: &[u8], salt: &[u8]) -> Result<(Vec<u8>, Vec<u8>)> {
+        if !self.is_within_limits() {
+            return Err(KeystoreError::KdfOverLimit(format!("{:?}", self)));
+        }
+
+        match *self {
+            Kdf::Pbkdf2 { iters } => {
+                if iters == 0 {
+                    return Err(KeystoreError::InvalidKeyfile);
+                }
+                let (derived_left, derived_right) = crypto::derive_key_iterations(password, salt, iters);
+                Ok((derived_left.to_vec(), derived_right.to_vec()))
+            },
+            Kdf::Scrypt { n, r, p } => {
+                let derived = crypto::scrypt::derive_key(password, salt, n, p, r).map_err(crypto::Error::from)?;
+                Ok(derived)
+            },
+            Kdf::Argon2id { memory, iterations, lanes } => {
+                let config = argon2::Config {
+                    variant: argon2::Variant::Argon2id,
+                    version: argon2::Version::Version13,
+                    mem_cost: memory,
+                    time_cost: iterations,
+                    lanes,
+                    thread_mode: argon2::ThreadMode::Sequential,
+                    secret: &[],
+                    ad: &[],
+                    hash_length: 32,
+                };
+                let mut derived_left = argon2::hash_raw(password, salt, &config)?;
+                let derived_right = derived_left.split_off(16);
+                Ok((derived_left, derived_right))
+            },
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone)]
 pub struct KeyCiphertext {
     pub ciphertext: SerdeBytes,
     pub mac: SerdeBytes,
     pub salt: SerdeBytes,
     pub iv: SerdeBytes,
-    pub iters: u32,
+    pub kdf: Kdf,
 }
 
 impl KeyCiphertext {
@@ -111,36 +271,60 @@ impl KeyCiphertext {
     pub fn encrypt<R: Rng>(
         xsk: &ExtendedSpendingKey,
         password: &[u8],
-        iters: u32,
+        kdf: Kdf,
         rng: &mut R,
     ) -> Result<Self>
     {
-        assert!(iters != 0);
-        let salt: [u8; 32] = rng.gen();
-        let iv: [u8; 16] = rng.gen();
+        let xsk_bytes: Vec<u8> = xsk.try_into()?;
+        Self::encrypt_bytes(&xsk_bytes[..], password, kdf, rng)
+    }
 
-        let (derived_left, derived_right) = crypto::derive_key_iterations(password, &salt, iters);
+    pub fn decrypt(&self, password: &[u8]) -> Result<ExtendedSpendingKey> {
+        let plain = self.decrypt_bytes(password)?;
+        let xsk = ExtendedSpendingKey::read(&mut &plain[..])?;
 
-        let xsk_bytes: Vec<u8> = xsk.try_into()?;
+        Ok(xsk)
+    }
 
-        let mut ciphertext: SmallVec<[u8; 32]> = SmallVec::from_vec(vec![0; xsk_bytes.len()]);
+    /// Encrypt any bytes, authenticated by the MAC.
+    pub fn encrypt_bytes<R: Rng>(
+        plain: &[u8],
+        password: &[u8],
+        kdf: Kdf,
+        rng: &mut R,
+    ) -> Result<Self>
+    {
+        let keys = DerivedKeys::new(password, kdf, rng)?;
+        Self::encrypt_with(plain, &keys, rng)
+    }
+
+    /// Encrypt with keys derived before, under a new IV.
+    pub fn encrypt_with<R: Rng>(plain: &[u8], keys: &DerivedKeys, rng: &mut R) -> Result<Self> {
+        let iv: [u8; 16] = rng.gen();
 
-        crypto::aes::encrypt_128_ctr(&derived_left, &iv, &xsk_bytes[..], &mut *ciphertext).map_err(crypto::Error::from)?;
+        let mut ciphertext: SmallVec<[u8; 32]> = SmallVec::from_vec(vec![0; plain.len()]);
 
-        let mac = crypto::derive_mac(&derived_right, &*ciphertext).keccak256();
+        crypto::aes::encrypt_128_ctr(&keys.left, &iv, plain, &mut *ciphertext).map_err(crypto::Error::from)?;
+
+        let mac = crypto::derive_mac(&keys.right, &*ciphertext).keccak256();
 
         Ok(KeyCiphertext {
             ciphertext: ciphertext.into(),
             mac: mac.into(),
-            salt: salt.into(),
+            salt: keys.salt.clone().into(),
             iv: iv.into(),
-            iters,
+            kdf: keys.kdf,
         })
     }
 
-    pub fn decrypt(&self, password: &[u8]) -> Result<ExtendedSpendingKey> {
-        let (derived_left, derived_right) = crypto::derive_key_iterations(password, &self.salt.0[..], self.iters);
-        let mac = crypto::derive_mac(&derived_right, &self.ciphertext.0).keccak256();
+    pub fn decrypt_bytes(&self, password: &[u8]) -> Result<Vec<u8>> {
+        let keys = DerivedKeys::with_salt(password, self.kdf, self.salt.0.clone())?;
+        self.decrypt_with(&keys)
+    }
+
+    /// Decrypt with keys derived before. Keys of another password or salt fail the MAC.
+    pub fn decrypt_with(&self, keys: &DerivedKeys) -> Result<Vec<u8>> {
+        let mac = crypto::derive_mac(&keys.right, &self.ciphertext.0).keccak256();
 
         if !crypto::is_equal(&mac, &self.mac.0) {
             return Err(KeystoreError::InvalidPassword)
@@ -148,12 +332,50 @@ impl KeyCiphertext {
 
         let mut plain: SmallVec<[u8; 32]> = SmallVec::from_vec(vec![0; self.ciphertext.0.len()]);
 
-        crypto::aes::decrypt_128_ctr(&derived_left, &self.iv.0, &self.ciphertext.0, &mut plain)
+        crypto::aes::decrypt_128_ctr(&keys.left, &self.iv.0, &self.ciphertext.0, &mut plain)
             .map_err(crypto::Error::from)?;
 
-        let xsk = ExtendedSpendingKey::read(&mut &plain.to_vec()[..])?;
+        Ok(plain.to_vec())
+    }
+}
 
-        Ok(xsk)
+/// Version 1 keyfile, whose key is always derived with PBKDF2.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KeyFileV1 {
+    file_name: Option<String>,
+    account_name: String,
+    ss58_address: String,
+    version: u32,
+    encrypted_key: KeyCiphertextV1,
+}
+
+#[derive(Deserialize)]
+struct KeyCiphertextV1 {
+    ciphertext: SerdeBytes,
+    mac: SerdeBytes,
+    salt: SerdeBytes,
+    iv: SerdeBytes,
+    iters: u32,
+}
+
+impl From<KeyFileV1> for KeyFile {
+    fn from(v1: KeyFileV1) -> Self {
+        let encrypted_key = v1.encrypted_key;
+
+        KeyFile {
+            file_name: v1.file_name,
+            account_name: v1.account_name,
+            ss58_address: v1.ss58_address,
+            version: v1.version,
+            encrypted_key: KeyCiphertext {
+                ciphertext: encrypted_key.ciphertext,
+                mac: encrypted_key.mac,
+                salt: encrypted_key.salt,
+                iv: encrypted_key.iv,
+                kdf: Kdf::Pbkdf2 { iters: encrypted_key.iters },
+            },
+        }
     }
 }
 
@@ -215,9 +437,9 @@ mod tests {
         let xsk_master = ExtendedSpendingKey::master(&seed);
 
         let password = b"abcd";
-        let iters = 1024;
+        let kdf = Kdf::Pbkdf2 { iters: 1024 };
 
-        let ciphertext = KeyCiphertext::encrypt(&xsk_master, password, iters, rng).unwrap();
+        let ciphertext = KeyCiphertext::encrypt(&xsk_master, password, kdf, rng).unwrap();
         let decrypted = ciphertext.decrypt(password).unwrap();
 
         assert_eq!(decrypted, xsk_master);
@@ -231,11 +453,105 @@ mod tests {
 
         let password_enc = b"abcd";
         let password_dec = b"wxyz";
-        let iters = 1024;
+        let kdf = Kdf::Pbkdf2 { iters: 1024 };
 
-        let ciphertext = KeyCiphertext::encrypt(&xsk_master, password_enc, iters, rng).unwrap();
+        let ciphertext = KeyCiphertext::encrypt(&xsk_master, password_enc, kdf, rng).unwrap();
         let decrypted = ciphertext.decrypt(password_dec);
 
         assert_matches!(decrypted, Err(KeystoreError::InvalidPassword));
     }
+
+    #[test]
+    fn test_scrypt_and_argon2id() {
+        let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let seed: [u8; 32] = rng.gen();
+        let xsk_master = ExtendedSpendingKey::master(&seed);
+
+        let kdfs = [
+            Kdf::Scrypt { n: 1024, r: 8, p: 1 },
+            Kdf::Argon2id { memory: 256, iterations: 1, lanes: 1 },
+        ];
+
+        for kdf in kdfs.iter() {
+            let ciphertext = KeyCiphertext::encrypt(&xsk_master, b"abcd", *kdf, rng).unwrap();
+
+            assert_eq!(ciphertext.decrypt(b"abcd").unwrap(), xsk_master);
+            assert_matches!(ciphertext.decrypt(b"wxyz"), Err(KeystoreError::InvalidPassword));
+        }
+    }
+
+    #[test]
+    fn test_kdf_limits() {
+        let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+        for name in &["pbkdf2", "scrypt", "argon2id"] {
+            assert!(Kdf::from_name(name).unwrap().is_within_limits());
+        }
+
+        let kdfs = [
+            Kdf::Pbkdf2 { iters: MAX_PBKDF2_ITERS + 1 },
+            Kdf::Scrypt { n: 1 << 30, r: 8, p: 1 },
+            Kdf::Scrypt { n: 1024, r: 8, p: MAX_SCRYPT_P + 1 },
+            Kdf::Argon2id { memory: MAX_KDF_MEMORY + 1, iterations: 1, lanes: 1 },
+            Kdf::Argon2id { memory: 256, iterations: u32::max_value(), lanes: 1 },
+            Kdf::Argon2id { memory: 256, iterations: 1, lanes: MAX_ARGON2_LANES + 1 },
+        ];
+
+        for kdf in kdfs.iter() {
+            assert!(!kdf.is_within_limits());
+            assert_matches!(KeyCiphertext::encrypt_bytes(b"plain", b"abcd", *kdf, rng), Err(KeystoreError::KdfOverLimit(_)));
+        }
+
+        // a keyfile read from disk is checked before its key is derived
+        let mut ciphertext = KeyCiphertext::encrypt_bytes(b"plain", b"abcd", Kdf::Pbkdf2 { iters: 1 }, rng).unwrap();
+        ciphertext.kdf = Kdf::Scrypt { n: 1 << 30, r: 8, p: 1 };
+        assert_matches!(ciphertext.decrypt_bytes(b"abcd"), Err(KeystoreError::KdfOverLimit(_)));
+    }
+
+    #[test]
+    fn test_read_keyfile_versions() {
+        let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let seed: [u8; 32] = rng.gen();
+        let xsk_master = ExtendedSpendingKey::master(&seed);
+        let keyfile = KeyFile::new("Test", b"abcd", Kdf::Pbkdf2 { iters: 1024 }, &xsk_master, rng).unwrap();
+
+        // current version
+        let json = serde_json::to_vec(&keyfile).unwrap();
+        assert_eq!(KeyFile::read(&json[..]).unwrap(), keyfile);
+
+        // version 1, whose ciphertext has bare iterations
+        let mut value = serde_json::to_value(&keyfile).unwrap();
+        value["version"] = 1.into();
+        let encrypted_key = value["encryptedKey"].as_object_mut().unwrap();
+        encrypted_key.remove("kdf");
+        encrypted_key.insert("iters".to_string(), 1024.into());
+
+        let json = serde_json::to_vec(&value).unwrap();
+        let keyfile_v1 = KeyFile::read(&json[..]).unwrap();
+        assert_eq!(keyfile_v1.version, 1);
+        assert_eq!(keyfile_v1.encrypted_key, keyfile.encrypted_key);
+        assert!(keyfile_v1.is_outdated(&Kdf::Pbkdf2 { iters: 1024 }));
+
+        // unsupported version
+        value["version"] = 3.into();
+        let json = serde_json::to_vec(&value).unwrap();
+        assert_matches!(KeyFile::read(&json[..]), Err(KeystoreError::UnsupportedKeyfileVersion(3)));
+    }
+
+    #[test]
+    fn test_reencrypt() {
+        let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let seed: [u8; 32] = rng.gen();
+        let xsk_master = ExtendedSpendingKey::master(&seed);
+        let keyfile = KeyFile::new("Test", b"abcd", Kdf::Pbkdf2 { iters: 1024 }, &xsk_master, rng).unwrap();
+
+        let kdf = Kdf::Scrypt { n: 1024, r: 8, p: 1 };
+        let reencrypted = keyfile.reencrypt(b"abcd", b"wxyz", kdf, rng).unwrap();
+
+        assert_eq!(reencrypted.ss58_address, keyfile.ss58_address);
+        assert_eq!(reencrypted.encrypted_key.kdf, kdf);
+        assert_eq!(reencrypted.encrypted_key.decrypt(b"wxyz").unwrap(), xsk_master);
+        assert_matches!(reencrypted.encrypted_key.decrypt(b"abcd"), Err(KeystoreError::InvalidPassword));
+        assert_matches!(keyfile.reencrypt(b"wxyz", b"abcd", kdf, rng), Err(KeystoreError::InvalidPassword));
+    }
 }
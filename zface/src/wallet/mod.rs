@@ -5,8 +5,12 @@ pub mod commands;
 mod config;
 mod keyfile;
 mod disk;
-pub use self::keyfile::KeyFile;
+mod archive;
+pub mod discovery;
+pub use self::keyfile::{KeyFile, KeyCiphertext, DerivedKeys, Kdf};
+pub use self::archive::OnConflict;
 pub use self::disk::{KeystoreDirectory, WalletDirectory};
+pub(crate) use self::disk::replace_file;
 use crate::error::Result;
 
 /// Operations in a keystore directory
@@ -20,6 +24,9 @@ pub trait DirOperations {
     /// Load all keyfiles in this keystore directory.
     fn load_all(&self) -> Result<Vec<KeyFile>>;
 
+    /// Overwrite a stored keyfile.
+    fn update(&self, keyfile: &KeyFile) -> Result<()>;
+
     /// Remove a keyfile from this keystore directory.
     fn remove(&self, keyfile: &mut KeyFile) -> Result<()>;
 }